@@ -0,0 +1,97 @@
+//! Benchmark gate for collecting the keep set of many paths (`cargo bench --bench flatten`).
+//!
+//! Unions the nodes of generated paths with `flatten_into_hashset` and compares it against the
+//! reduction by set unions it replaces on 2k paths, the unions take too long for more. Exits
+//! with an error if it is slower than that by more than `TRIM_GRAPH_BENCH_TOLERANCE` (default
+//! 0.1, i.e. 10%). The time for 1M paths is only reported.
+
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use trim_graph::trim::flatten_into_hashset;
+
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+const PATHS: usize = 1_000_000;
+const COMPARED_PATHS: usize = 2_000;
+const STEPS: usize = 8;
+const NODES: usize = 500_000;
+const ROUNDS: usize = 3;
+
+/// Nodes of short paths spread over the whole graph, overlapping like haplotypes of a region
+fn generate(paths: usize) -> Vec<Vec<String>> {
+    (0..paths)
+        .map(|p| {
+            let start = p * 7919 % NODES;
+            (0..STEPS)
+                .map(|s| ((start + s) % NODES).to_string())
+                .collect()
+        })
+        .collect()
+}
+
+/// The reduction `flatten_into_hashset` did before, building a new set on every union
+fn flatten_union(v: Vec<Vec<String>>) -> HashSet<String> {
+    let union = |acc: HashSet<String>, set: HashSet<String>| acc.union(&set).cloned().collect();
+    #[cfg(feature = "parallel")]
+    return v
+        .into_par_iter()
+        .map(|row| HashSet::from_iter(row.iter().cloned()))
+        .reduce(HashSet::new, union);
+    #[cfg(not(feature = "parallel"))]
+    return v
+        .into_iter()
+        .map(|row| HashSet::from_iter(row.iter().cloned()))
+        .fold(HashSet::new(), union);
+}
+
+/// Fastest of several runs, without the time to copy the input
+fn time(f: impl Fn(Vec<Vec<String>>) -> HashSet<String>, paths: &[Vec<String>]) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let input = paths.to_vec();
+            let start = Instant::now();
+            black_box(f(input));
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() -> ExitCode {
+    let tolerance = std::env::var("TRIM_GRAPH_BENCH_TOLERANCE")
+        .ok()
+        .and_then(|t| t.parse::<f64>().ok())
+        .unwrap_or(0.1);
+    let paths = generate(COMPARED_PATHS);
+    assert_eq!(
+        flatten_into_hashset(paths.clone()),
+        flatten_union(paths.clone())
+    );
+    let flatten = time(flatten_into_hashset, &paths);
+    let union = time(flatten_union, &paths);
+    let ratio = flatten.as_secs_f64() / union.as_secs_f64();
+    println!(
+        "flatten {:>7} paths   fold {:>8.1} ms   union {:>8.1} ms   ratio {:.2}",
+        COMPARED_PATHS,
+        flatten.as_secs_f64() * 1e3,
+        union.as_secs_f64() * 1e3,
+        ratio
+    );
+
+    let paths = generate(PATHS);
+    let flatten = time(flatten_into_hashset, &paths);
+    println!(
+        "flatten {:>7} paths   fold {:>8.1} ms",
+        PATHS,
+        flatten.as_secs_f64() * 1e3
+    );
+
+    if ratio > 1.0 + tolerance {
+        eprintln!("flatten_into_hashset is slower than the union of sets");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
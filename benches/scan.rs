@@ -0,0 +1,121 @@
+//! Benchmark gate for line classification and field splitting (`cargo bench --bench scan`).
+//!
+//! Classifies a generated graph with `GfaLines::classify` and splits its lines with
+//! `scan::fields`, and compares both against the `str::lines`/`split('\t')` code they replace.
+//! Exits with an error if either is slower than the `std` version by more than
+//! `TRIM_GRAPH_BENCH_TOLERANCE` (default 0.1, i.e. 10%).
+
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use trim_graph::scan;
+use trim_graph::trim::GfaLines;
+
+const SEGMENTS: usize = 200_000;
+const PATHS: usize = 16;
+const ROUNDS: usize = 15;
+
+/// Graph with a chain of segments of 1 to 64 bp and a bubble every 10 segments, with paths over
+/// the chain
+fn generate() -> String {
+    let mut gfa = String::from("H\tVN:Z:1.0\n");
+    for i in 1..=SEGMENTS {
+        let length = 1 + i * 7919 % 64;
+        gfa.push_str(&format!(
+            "S\t{}\t{}\n",
+            i,
+            "ACGT".repeat(16)[..length].to_owned()
+        ));
+    }
+    for i in 1..SEGMENTS {
+        gfa.push_str(&format!("L\t{}\t+\t{}\t+\t0M\n", i, i + 1));
+        if i % 10 == 0 && i + 2 <= SEGMENTS {
+            gfa.push_str(&format!("L\t{}\t+\t{}\t+\t0M\n", i, i + 2));
+        }
+    }
+    let steps = (1..=SEGMENTS)
+        .map(|i| format!("{}+", i))
+        .collect::<Vec<_>>()
+        .join(",");
+    for p in 0..PATHS {
+        gfa.push_str(&format!("P\tsample{}#0#chr1\t{}\t*\n", p, steps));
+    }
+    gfa
+}
+
+/// The classification `GfaLines::classify` did before using `scan`
+fn classify_std(content: &str) -> GfaLines<'_> {
+    let mut lines = GfaLines::default();
+    for line in content.lines() {
+        if line.starts_with('S') {
+            lines.segments.push(line);
+        } else if line.starts_with('L') {
+            lines.links.push(line);
+        } else if line.starts_with('P') {
+            lines.paths.push(line);
+        } else if line.starts_with('W') {
+            lines.walks.push(line);
+        } else if line.starts_with('J') {
+            lines.jumps.push(line);
+        } else if line.starts_with('H') {
+            lines.headers.push(line);
+        } else {
+            lines.others.push(line);
+        }
+    }
+    lines
+}
+
+/// Fastest of several runs
+fn time<T>(mut f: impl FnMut() -> T) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() -> ExitCode {
+    let tolerance = std::env::var("TRIM_GRAPH_BENCH_TOLERANCE")
+        .ok()
+        .and_then(|t| t.parse::<f64>().ok())
+        .unwrap_or(0.1);
+    let gfa = generate();
+    let lines = gfa.lines().collect::<Vec<_>>();
+    let mb = gfa.len() as f64 / 1e6;
+
+    let benchmarks = [
+        (
+            "classify",
+            time(|| GfaLines::classify(&gfa).line_count()),
+            time(|| classify_std(&gfa).line_count()),
+        ),
+        (
+            "fields",
+            time(|| lines.iter().map(|l| scan::fields(l).count()).sum::<usize>()),
+            time(|| lines.iter().map(|l| l.split('\t').count()).sum::<usize>()),
+        ),
+    ];
+    let mut passed = true;
+    for (name, scan, std) in benchmarks {
+        let ratio = scan.as_secs_f64() / std.as_secs_f64();
+        println!(
+            "{:<10} scan {:>8.1} MB/s   std {:>8.1} MB/s   ratio {:.2}",
+            name,
+            mb / scan.as_secs_f64(),
+            mb / std.as_secs_f64(),
+            ratio
+        );
+        if ratio > 1.0 + tolerance {
+            eprintln!("{} is slower than the std version", name);
+            passed = false;
+        }
+    }
+    match passed {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
@@ -0,0 +1,37 @@
+/*
+ * C API of trim-graph, link against the cdylib (libtrim_graph.so / .dylib).
+ *
+ * All functions returning int return 0 on success and -1 on failure.
+ */
+#ifndef TRIM_GRAPH_H
+#define TRIM_GRAPH_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct TrimGraph TrimGraph;
+
+/* Reads the GFA file at path, returns NULL if it cannot be read. */
+TrimGraph *trim_graph_open(const char *path);
+
+/* Sets the names of the paths to keep, NULL names keeps all paths (the default). */
+int trim_graph_set_selection(TrimGraph *graph, const char *const *names, size_t count);
+
+/* Non-zero values keep all segment/link/jump lines, like -S/-L/-J. */
+int trim_graph_set_options(TrimGraph *graph, int ignore_segments, int ignore_links,
+                           int ignore_jumps);
+
+/* Trims the graph with the current selection and writes it as GFA to out_path. */
+int trim_graph_trim_to_file(const TrimGraph *graph, const char *out_path);
+
+/* Frees a graph returned by trim_graph_open. */
+void trim_graph_free(TrimGraph *graph);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
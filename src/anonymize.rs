@@ -0,0 +1,133 @@
+//! Anonymization of the sample names of paths and walks (`--anonymize-paths`).
+//!
+//! Samples are replaced by pseudonyms `sample1`, `sample2`, ... in order of appearance. The
+//! mapping is kept in a TSV file (`original<TAB>pseudonym`): samples already in it keep their
+//! pseudonym, so graphs anonymized with the same file stay comparable. P lines in PanSN form
+//! (`sample#hap#contig`) only have the sample replaced, other P lines the whole name.
+
+use crate::graph::split_pansn;
+use crate::trim::Trimmed;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Pseudonyms {
+    pseudonyms: HashMap<String, String>,
+    /// Original names in the order they were added
+    originals: Vec<String>,
+    used: HashSet<String>,
+}
+
+impl Pseudonyms {
+    /// Reads a mapping written by [`Pseudonyms::write`]
+    pub fn read<R: BufRead>(input: R) -> io::Result<Self> {
+        let mut pseudonyms = Pseudonyms::default();
+        for line in input.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let (original, pseudonym) = line.split_once('\t').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid pseudonym mapping {}", line),
+                )
+            })?;
+            pseudonyms.insert(original.to_string(), pseudonym.to_string());
+        }
+        Ok(pseudonyms)
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for original in &self.originals {
+            writeln!(out, "{}\t{}", original, self.pseudonyms[original])?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, original: String, pseudonym: String) {
+        self.used.insert(pseudonym.clone());
+        self.originals.push(original.clone());
+        self.pseudonyms.insert(original, pseudonym);
+    }
+
+    /// Pseudonym of the name, a new one if the name was not seen before
+    pub fn pseudonym(&mut self, original: &str) -> String {
+        if let Some(pseudonym) = self.pseudonyms.get(original) {
+            return pseudonym.clone();
+        }
+        let pseudonym = (self.originals.len() + 1..)
+            .map(|i| format!("sample{}", i))
+            .find(|p| !self.used.contains(p))
+            .expect("There is always an unused pseudonym");
+        self.insert(original.to_string(), pseudonym.clone());
+        pseudonym
+    }
+
+    fn anonymize_path(&mut self, line: &str) -> String {
+        let mut fields = line.split('\t').collect::<Vec<_>>();
+        let name = match split_pansn(fields[1]) {
+            Some((sample, hap, contig)) => {
+                format!("{}#{}#{}", self.pseudonym(sample), hap, contig)
+            }
+            None => self.pseudonym(fields[1]),
+        };
+        fields[1] = &name;
+        fields.join("\t")
+    }
+
+    fn anonymize_walk(&mut self, line: &str) -> String {
+        let mut fields = line.split('\t').collect::<Vec<_>>();
+        let sample = self.pseudonym(fields[1]);
+        fields[1] = &sample;
+        fields.join("\t")
+    }
+
+    /// Replaces the sample names of all paths and walks of the graph
+    pub fn anonymize(&mut self, trimmed: &mut Trimmed) {
+        trimmed.paths = trimmed
+            .paths
+            .iter()
+            .map(|p| self.anonymize_path(p))
+            .collect();
+        trimmed.walks = trimmed
+            .walks
+            .iter()
+            .map(|w| self.anonymize_walk(w))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anonymize() {
+        let mut pseudonyms = Pseudonyms::read("HG002\tsample1\n".as_bytes()).unwrap();
+        let mut trimmed = Trimmed {
+            paths: vec![
+                "P\tHG003#1#chr1\t1+\t*".to_string(),
+                "P\tHG002#2#chr1\t1+\t*".to_string(),
+                "P\tchr1\t1+\t*".to_string(),
+            ],
+            walks: vec!["W\tHG003\t2\tchr1\t0\t1\t>1".to_string()],
+            ..Trimmed::default()
+        };
+        pseudonyms.anonymize(&mut trimmed);
+        assert_eq!(
+            trimmed.paths,
+            vec![
+                "P\tsample2#1#chr1\t1+\t*",
+                "P\tsample1#2#chr1\t1+\t*",
+                "P\tsample3\t1+\t*"
+            ]
+        );
+        assert_eq!(trimmed.walks, vec!["W\tsample2\t2\tchr1\t0\t1\t>1"]);
+
+        let mut out = Vec::new();
+        pseudonyms.write(&mut out).unwrap();
+        assert_eq!(out, b"HG002\tsample1\nHG003\tsample2\nchr1\tsample3\n");
+        assert_eq!(Pseudonyms::read(out.as_slice()).unwrap(), pseudonyms);
+    }
+}
@@ -0,0 +1,111 @@
+//! Segments between two anchors of a reference (`trim-graph between`).
+//!
+//! All segments on any route through the graph from the first anchor to the last one are kept,
+//! as long as the route is at most `--max-detour-bp` longer than the reference between them.
+//! Unlike coordinate extraction, this captures complete insertions and structural variants
+//! whose sequence never touches the reference inside the region.
+
+use crate::handle::{Handle, HandleGraph};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Shortest bp from the given handle to every handle, excluding both ends, following edges
+/// to the left if `go_left` is set. Unreachable handles get `usize::MAX`.
+fn distances(graph: &HandleGraph, from: Handle, go_left: bool) -> Vec<usize> {
+    // Indexed by the packed handle, 2 * rank + is_reverse
+    let index = |handle: Handle| 2 * handle.rank() + handle.is_reverse() as usize;
+    let mut distances = vec![usize::MAX; 2 * graph.node_count()];
+    let mut queue = BinaryHeap::new();
+    for next in graph.follow_edges(from, go_left) {
+        distances[index(next)] = 0;
+        queue.push(Reverse((0, next)));
+    }
+    while let Some(Reverse((distance, handle))) = queue.pop() {
+        if distance > distances[index(handle)] {
+            continue;
+        }
+        let through = distance + graph.get_length(handle);
+        for next in graph.follow_edges(handle, go_left) {
+            if through < distances[index(next)] {
+                distances[index(next)] = through;
+                queue.push(Reverse((through, next)));
+            }
+        }
+    }
+    distances
+}
+
+/// Ranks of the segments on a route from `from` to `to` with at most `max_bp` between the two
+/// anchors, sorted, `None` if `to` cannot be reached that way
+pub fn nodes_between(
+    graph: &HandleGraph,
+    from: Handle,
+    to: Handle,
+    max_bp: usize,
+) -> Option<Vec<usize>> {
+    if from == to {
+        return Some(vec![from.rank()]);
+    }
+    let after = distances(graph, from, false);
+    let before = distances(graph, to, true);
+    let index = |handle: Handle| 2 * handle.rank() + handle.is_reverse() as usize;
+    if after[index(to)] > max_bp {
+        return None;
+    }
+    let mut ranks = graph
+        .handles()
+        .flat_map(|h| [h, h.flip()])
+        .filter(|h| {
+            let (after, before) = (after[index(*h)], before[index(*h)]);
+            after != usize::MAX
+                && before != usize::MAX
+                && after + graph.get_length(*h) + before <= max_bp
+        })
+        .map(|h| h.rank())
+        .chain([from.rank(), to.rank()])
+        .collect::<Vec<_>>();
+    ranks.sort_unstable();
+    ranks.dedup();
+    Some(ranks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    #[test]
+    fn test_nodes_between() {
+        // Reference 1 2 4 5, 3 is an insertion of 6 bp between 1 and 4 that skips 2, 6 hangs
+        // off 3 and 7 is a long detour from 1 to 5
+        let graph = HandleGraph::new(
+            Graph::from_gfa(
+                "S\t1\tA\nS\t2\tCC\nS\t3\tGGGGGG\nS\t4\tT\nS\t5\tA\nS\t6\tC\nS\t7\tTTTTTTTTTT\n\
+             L\t1\t+\t2\t+\t0M\nL\t2\t+\t4\t+\t0M\nL\t1\t+\t3\t-\t0M\nL\t3\t-\t4\t+\t0M\n\
+             L\t4\t+\t5\t+\t0M\nL\t3\t+\t6\t+\t0M\nL\t1\t+\t7\t+\t0M\nL\t7\t+\t5\t+\t0M\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let from = graph.parse_handle("1+").unwrap();
+        let to = graph.parse_handle("5+").unwrap();
+        let names = |ranks: Vec<usize>| {
+            ranks
+                .into_iter()
+                .map(|r| graph.get_name(Handle::new(r, false)).to_string())
+                .collect::<Vec<_>>()
+        };
+        // The reference has 3 bp between the anchors
+        assert_eq!(
+            names(nodes_between(&graph, from, to, 3).unwrap()),
+            vec!["1", "2", "4", "5"]
+        );
+        assert_eq!(
+            names(nodes_between(&graph, from, to, 7).unwrap()),
+            vec!["1", "2", "3", "4", "5"]
+        );
+        assert_eq!(names(nodes_between(&graph, from, to, 10).unwrap()).len(), 6);
+        assert_eq!(nodes_between(&graph, from, to, 2), None);
+        assert_eq!(nodes_between(&graph, to, from, 100), None);
+    }
+}
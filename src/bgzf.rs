@@ -0,0 +1,154 @@
+//! Blocked gzip (BGZF) output with an optional `.gzi` index of the block offsets.
+
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::{self, Write};
+
+/// Maximum number of uncompressed bytes per block, chosen like htslib so that the
+/// compressed block always fits into the 64 KiB limit of BGZF
+const BLOCK_SIZE: usize = 0xff00;
+
+/// Empty block marking the end of a BGZF file
+const EOF_BLOCK: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Compressed and uncompressed offsets of the blocks of a BGZF file, written as `.gzi` by
+/// `bgzip -i`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GziIndex {
+    blocks: Vec<(u64, u64)>,
+}
+
+impl GziIndex {
+    /// Writes the index in the format of htslib: the number of entries followed by the
+    /// offsets of all blocks but the first one, as little-endian 64-bit integers
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let blocks = self.blocks.get(1..).unwrap_or_default();
+        out.write_all(&(blocks.len() as u64).to_le_bytes())?;
+        for (compressed, uncompressed) in blocks {
+            out.write_all(&compressed.to_le_bytes())?;
+            out.write_all(&uncompressed.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writer producing blocked gzip (BGZF) output as used by htslib and vg
+pub struct BgzfWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    index: GziIndex,
+    compressed_offset: u64,
+    uncompressed_offset: u64,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(inner: W) -> Self {
+        BgzfWriter {
+            inner,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            index: GziIndex::default(),
+            compressed_offset: 0,
+            uncompressed_offset: 0,
+        }
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.buffer)?;
+        let compressed = encoder.finish()?;
+        let mut crc = Crc::new();
+        crc.update(&self.buffer);
+
+        // header (18 bytes) + compressed data + crc32 + isize
+        let block_size = 18 + compressed.len() + 8;
+        let mut header = [
+            0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43,
+            0x02, 0x00, 0x00, 0x00,
+        ];
+        header[16..18].copy_from_slice(&((block_size - 1) as u16).to_le_bytes());
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&compressed)?;
+        self.inner.write_all(&crc.sum().to_le_bytes())?;
+        self.inner
+            .write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+        self.index
+            .blocks
+            .push((self.compressed_offset, self.uncompressed_offset));
+        self.compressed_offset += block_size as u64;
+        self.uncompressed_offset += self.buffer.len() as u64;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Writes all remaining data and the EOF marker, returning the inner writer
+    pub fn finish(self) -> io::Result<W> {
+        self.finish_with_index().map(|(inner, _)| inner)
+    }
+
+    /// Same as [`BgzfWriter::finish`], also returning the offsets of the written blocks
+    pub fn finish_with_index(mut self) -> io::Result<(W, GziIndex)> {
+        if !self.buffer.is_empty() {
+            self.write_block()?;
+        }
+        self.inner.write_all(&EOF_BLOCK)?;
+        self.inner.flush()?;
+        Ok((self.inner, self.index))
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(BLOCK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() == BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_bgzf_roundtrip() {
+        let data = (0..200_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut writer = BgzfWriter::new(Vec::new());
+        writer.write_all(&data).unwrap();
+        let compressed = writer.finish().unwrap();
+        assert!(compressed.ends_with(&EOF_BLOCK));
+        let mut decompressed = Vec::new();
+        MultiGzDecoder::new(&compressed[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn test_gzi() {
+        let data = vec![b'A'; BLOCK_SIZE * 2 + 10];
+        let mut writer = BgzfWriter::new(Vec::new());
+        writer.write_all(&data).unwrap();
+        let (compressed, index) = writer.finish_with_index().unwrap();
+        assert_eq!(index.blocks.len(), 3);
+        assert_eq!(index.blocks[1].1, BLOCK_SIZE as u64);
+        // the second block starts right after the first one, whose size is in its header
+        let first_size = u16::from_le_bytes([compressed[16], compressed[17]]) as u64 + 1;
+        assert_eq!(index.blocks[1].0, first_size);
+
+        let mut gzi = Vec::new();
+        index.write(&mut gzi).unwrap();
+        assert_eq!(gzi.len(), 8 + 2 * 16);
+        assert_eq!(gzi[..8], 2u64.to_le_bytes());
+    }
+}
@@ -0,0 +1,74 @@
+//! Bloom filter for approximate keep sets (`--bloom-false-keep-rate`).
+//!
+//! The nodes and edges of the kept paths are only remembered as bits, so the memory needed
+//! does not grow with the length of their names. Membership tests never miss an inserted item,
+//! but report other items as present at about the configured rate, so trimming with a filter
+//! may keep a few extra segments and edges, but never drops one that has to stay.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// Filter for up to `items` items with the given false positive rate
+    pub fn new(items: usize, rate: f64) -> Self {
+        let items = items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-items * rate.ln() / (ln2 * ln2)).ceil().max(64.0) as usize;
+        let hashes = ((bits as f64 / items) * ln2).round().clamp(1.0, 16.0) as u32;
+        BloomFilter {
+            bits: vec![0; bits.div_ceil(64)],
+            hashes,
+        }
+    }
+
+    /// Size of the filter in bytes
+    pub fn size(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    /// Bit positions of an item, by double hashing
+    fn positions<T: Hash + ?Sized>(&self, item: &T) -> impl Iterator<Item = usize> {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+        let (first, step) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        let bits = self.bits.len() as u64 * 64;
+        (0..self.hashes as u64)
+            .map(move |i| (first.wrapping_add(i.wrapping_mul(step)) % bits) as usize)
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for position in self.positions(item).collect::<Vec<_>>() {
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.positions(item)
+            .all(|position| self.bits[position / 64] & (1 << (position % 64)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut filter = BloomFilter::new(1000, 0.01);
+        for i in 0..1000 {
+            filter.insert(&i.to_string());
+        }
+        assert!((0..1000).all(|i| filter.contains(&i.to_string())));
+        let false_positives = (1000..11000)
+            .filter(|i| filter.contains(&i.to_string()))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+}
@@ -0,0 +1,182 @@
+//! Shrinking the trimmed graph to a size budget (`--target-bp`, `--target-nodes`).
+//!
+//! Segments are dropped greedily in the order of their coverage, the number of walk steps
+//! visiting them, lowest first. Walks through a dropped segment are dropped as well, which
+//! lowers the coverage of the other segments they visit. Segments of kept paths (P lines) are
+//! never dropped, so the budget cannot be reached if they alone exceed it.
+
+use crate::graph::{path_line_steps, segment_length, walk_line_steps, EdgeRecord};
+use crate::trim::Trimmed;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Total sequence length in bp
+    Bp(usize),
+    /// Number of segments
+    Nodes(usize),
+}
+
+impl Budget {
+    fn target(&self) -> usize {
+        match self {
+            Budget::Bp(bp) => *bp,
+            Budget::Nodes(nodes) => *nodes,
+        }
+    }
+
+    fn cost(&self, length: usize) -> usize {
+        match self {
+            Budget::Bp(_) => length,
+            Budget::Nodes(_) => 1,
+        }
+    }
+}
+
+/// Drops the segments with the lowest coverage until the graph fits `budget`, returns whether
+/// it fits. Fails on a path or walk with invalid steps or on a malformed link or jump.
+pub fn fit_budget(trimmed: &mut Trimmed, budget: Budget) -> Result<bool, String> {
+    let mut ranks = HashMap::new();
+    let mut lengths = Vec::new();
+    for line in &trimmed.segments {
+        let name = line.split('\t').nth(1).expect("All nodes should have ids");
+        ranks.insert(name, lengths.len());
+        lengths.push(segment_length(line));
+    }
+    let mut protected = HashSet::new();
+    for path in &trimmed.paths {
+        let steps = path_line_steps(path)?;
+        protected.extend(
+            steps
+                .iter()
+                .filter_map(|(node, _)| ranks.get(node).copied()),
+        );
+    }
+    let walk_nodes = trimmed
+        .walks
+        .iter()
+        .map(|w| {
+            Ok(walk_line_steps(w)?
+                .iter()
+                .filter_map(|(node, _)| ranks.get(node).copied())
+                .collect::<Vec<_>>())
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let mut coverage = vec![0; lengths.len()];
+    let mut node_walks = vec![Vec::new(); lengths.len()];
+    for (walk, nodes) in walk_nodes.iter().enumerate() {
+        for node in nodes {
+            coverage[*node] += 1;
+            node_walks[*node].push(walk);
+        }
+    }
+
+    let mut size = lengths.iter().map(|l| budget.cost(*l)).sum::<usize>();
+    let mut candidates = (0..lengths.len())
+        .filter(|r| !protected.contains(r))
+        .map(|r| (coverage[r], r))
+        .collect::<BTreeSet<_>>();
+    let mut dropped = vec![false; lengths.len()];
+    let mut dropped_walks = vec![false; walk_nodes.len()];
+    while size > budget.target() {
+        let Some((_, node)) = candidates.pop_first() else {
+            break;
+        };
+        dropped[node] = true;
+        size -= budget.cost(lengths[node]);
+        for walk in &node_walks[node] {
+            if std::mem::replace(&mut dropped_walks[*walk], true) {
+                continue;
+            }
+            for other in &walk_nodes[*walk] {
+                if candidates.remove(&(coverage[*other], *other)) {
+                    coverage[*other] -= 1;
+                    candidates.insert((coverage[*other], *other));
+                } else {
+                    coverage[*other] -= 1;
+                }
+            }
+        }
+    }
+
+    let kept = |node: &str| ranks.get(node).is_none_or(|r| !dropped[*r]);
+    let [kept_links, kept_jumps] = [&trimmed.links, &trimmed.jumps].map(|edges| {
+        edges
+            .iter()
+            .map(|l| {
+                let edge = EdgeRecord::parse(l)?.edge;
+                Ok(kept(edge.from) && kept(edge.to))
+            })
+            .collect::<Result<Vec<_>, String>>()
+    });
+    let (kept_links, kept_jumps) = (kept_links?, kept_jumps?);
+    let segments = trimmed.segments.len();
+    trimmed
+        .segments
+        .retain(|s| kept(s.split('\t').nth(1).expect("All nodes should have ids")));
+    for (edges, kept_edges) in [
+        (&mut trimmed.links, kept_links),
+        (&mut trimmed.jumps, kept_jumps),
+    ] {
+        let mut kept_edges = kept_edges.into_iter();
+        edges.retain(|_| kept_edges.next() == Some(true));
+    }
+    let walks = trimmed.walks.len();
+    let mut walk = 0;
+    trimmed.walks.retain(|_| {
+        walk += 1;
+        !dropped_walks[walk - 1]
+    });
+    log::info!(
+        "Dropped {} segments and {} walks to fit the budget",
+        segments - trimmed.segments.len(),
+        walks - trimmed.walks.len()
+    );
+    Ok(size <= budget.target())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_fit_budget() {
+        // A bubble visited by two walks, one visited by a single walk, and the reference
+        let content = "S\t1\tAAAA\nS\t2\tCC\nS\t3\tGGG\nS\t4\tTTTT\nS\t5\tA\nS\t6\tCCCC\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t4\t+\t0M\nL\t1\t+\t4\t+\t0M\n\
+                       L\t1\t+\t3\t+\t0M\nL\t3\t+\t4\t+\t0M\nL\t4\t+\t5\t+\t0M\n\
+                       L\t5\t+\t6\t+\t0M\nL\t4\t+\t6\t+\t0M\n\
+                       P\tref\t1+,4+,6+\t*\n\
+                       W\ts\t1\tc\t0\t11\t>1>2>4>6\nW\tt\t1\tc\t0\t11\t>1>2>4>6\n\
+                       W\tu\t1\tc\t0\t13\t>1>3>4>5>6\n";
+        let lines = GfaLines::classify(content);
+        let mut trimmed = trim_gfa(lines.clone(), None, &TrimOptions::default()).unwrap();
+        assert!(fit_budget(&mut trimmed, Budget::Bp(14)).unwrap());
+        assert_eq!(trimmed.segments.len(), 4);
+        assert!(!trimmed.segments.contains(&"S\t3\tGGG"));
+        assert!(!trimmed.segments.contains(&"S\t5\tA"));
+        assert_eq!(trimmed.links.len(), 4);
+        assert_eq!(trimmed.walks.len(), 2);
+
+        let mut trimmed = trim_gfa(lines, None, &TrimOptions::default()).unwrap();
+        assert!(!fit_budget(&mut trimmed, Budget::Nodes(2)).unwrap());
+        assert_eq!(trimmed.segments.len(), 3);
+        assert!(trimmed.walks.is_empty());
+        assert_eq!(trimmed.paths.len(), 1);
+    }
+
+    #[test]
+    fn test_fit_budget_malformed_link() {
+        let lines = GfaLines::classify("S\t1\tA\nS\t2\tC\nL\t1\t+\n");
+        let mut trimmed = Trimmed {
+            segments: lines.segments,
+            links: lines.links,
+            ..Default::default()
+        };
+        assert_eq!(
+            fit_budget(&mut trimmed, Budget::Nodes(1)).unwrap_err(),
+            "Link with 3 instead of at least 5 columns: L\t1\t+"
+        );
+    }
+}
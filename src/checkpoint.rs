@@ -0,0 +1,183 @@
+//! Checkpoints of long trimming runs (`--checkpoint DIR`, `--resume`).
+//!
+//! The inputs of a run and its completed phases are recorded in `DIR/checkpoint.json`, the
+//! result of each phase is stored next to it. A resumed run with the same inputs loads the
+//! results of completed phases instead of computing them again.
+
+use crate::index::FileStamp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+const MANIFEST: &str = "checkpoint.json";
+
+/// Everything a checkpoint depends on, a checkpoint is only resumed for the same inputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inputs {
+    pub graph: String,
+    pub stamp: FileStamp,
+    pub paths_to_keep: Option<Vec<String>>,
+    /// Sorted names of the walks to keep
+    #[serde(default)]
+    pub walks_to_keep: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Nodes and edges covered by the kept paths and walks
+    KeepSet,
+}
+
+impl Phase {
+    fn file_name(&self) -> &'static str {
+        match self {
+            Phase::KeepSet => "keep-set.bin",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    inputs: Inputs,
+    completed: Vec<Phase>,
+}
+
+pub struct Checkpoint {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Writes the file through a temporary file, so that an interruption never leaves a
+/// partially written file behind
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    write(&mut out)?;
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(tmp, path)
+}
+
+impl Checkpoint {
+    /// Starts a new checkpoint in `dir`, or continues the one in it if `resume` is set
+    pub fn open(dir: &Path, inputs: Inputs, resume: bool) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        if resume {
+            match fs::read(dir.join(MANIFEST)) {
+                Ok(content) => {
+                    let manifest: Manifest = serde_json::from_slice(&content)?;
+                    if manifest.inputs != inputs {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Checkpoint in {} was made for other inputs", dir.display()),
+                        ));
+                    }
+                    log::info!("Resuming after phases {:?}", manifest.completed);
+                    return Ok(Checkpoint {
+                        dir: dir.to_path_buf(),
+                        manifest,
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    log::warn!("No checkpoint in {}, starting over", dir.display())
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let checkpoint = Checkpoint {
+            dir: dir.to_path_buf(),
+            manifest: Manifest {
+                inputs,
+                completed: Vec::new(),
+            },
+        };
+        checkpoint.write_manifest()?;
+        Ok(checkpoint)
+    }
+
+    fn write_manifest(&self) -> io::Result<()> {
+        write_atomically(&self.dir.join(MANIFEST), |out| {
+            serde_json::to_writer_pretty(out, &self.manifest).map_err(io::Error::from)
+        })
+    }
+
+    pub fn is_completed(&self, phase: Phase) -> bool {
+        self.manifest.completed.contains(&phase)
+    }
+
+    /// Result of the phase, loaded if the phase was completed before, otherwise computed and
+    /// recorded
+    pub fn run<T: Serialize + DeserializeOwned>(
+        &mut self,
+        phase: Phase,
+        compute: impl FnOnce() -> T,
+    ) -> io::Result<T> {
+        self.try_run(phase, || Ok(compute()))
+    }
+
+    /// Same as [`Checkpoint::run`] for a phase that can fail, which is then not completed
+    pub fn try_run<T: Serialize + DeserializeOwned, E: From<io::Error>>(
+        &mut self,
+        phase: Phase,
+        compute: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let path = self.dir.join(phase.file_name());
+        if self.is_completed(phase) {
+            log::info!("Loading {:?} from {}", phase, path.display());
+            let input = BufReader::new(File::open(path)?);
+            return Ok(bincode::deserialize_from(input).map_err(invalid_data)?);
+        }
+        let result = compute()?;
+        write_atomically(&path, |out| {
+            bincode::serialize_into(out, &result).map_err(invalid_data)
+        })?;
+        self.manifest.completed.push(phase);
+        self.write_manifest()?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume() {
+        let dir =
+            std::env::temp_dir().join(format!("trim-graph-checkpoint-{}", std::process::id()));
+        let inputs = Inputs {
+            graph: "graph.gfa".to_string(),
+            stamp: FileStamp::default(),
+            paths_to_keep: Some(vec!["p1".to_string()]),
+            walks_to_keep: None,
+        };
+        let mut checkpoint = Checkpoint::open(&dir, inputs.clone(), false).unwrap();
+        assert!(!checkpoint.is_completed(Phase::KeepSet));
+        assert_eq!(
+            checkpoint.run(Phase::KeepSet, || vec![1, 2]).unwrap(),
+            vec![1, 2]
+        );
+
+        let mut resumed = Checkpoint::open(&dir, inputs.clone(), true).unwrap();
+        let result: Vec<i32> = resumed
+            .run(Phase::KeepSet, || panic!("Phase should not run again"))
+            .unwrap();
+        assert_eq!(result, vec![1, 2]);
+
+        let other = Inputs {
+            paths_to_keep: None,
+            ..inputs
+        };
+        assert!(Checkpoint::open(&dir, other, true).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+//! Merging of segments with identical sequences (`--collapse-identical-segments`).
+//!
+//! All segments with the same sequence are merged into the first of them. With
+//! `--collapse-reverse-complements`, segments whose sequence is the reverse complement of an
+//! earlier segment are merged into it as well, in reverse orientation. Links, jumps, paths and
+//! walks are redirected to it, with the orientation flipped for reverse merges, and links or
+//! jumps that become duplicates (also in reverse) are written once. Segments without sequence
+//! (`*`) are never merged.
+
+use crate::graph::{reverse_complement, tokenize_path, walk_line_steps};
+use crate::trim::Trimmed;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Collapse {
+    /// Merged segments with the segment they were merged into, and whether they are its
+    /// reverse complement
+    pub merged: HashMap<String, (String, bool)>,
+    /// Sequence length of the merged segments
+    pub bp: usize,
+}
+
+impl Collapse {
+    /// Finds the segments with the same sequence as an earlier segment, or with its reverse
+    /// complement if `reverse` is set
+    pub fn identical(segments: &[&str], reverse: bool) -> Self {
+        let mut first = HashMap::<&str, &str>::new();
+        let mut collapse = Collapse::default();
+        for line in segments {
+            let mut fields = line.split('\t').skip(1);
+            let name = fields.next().expect("All nodes should have ids");
+            let Some(sequence) = fields.next().filter(|s| !s.is_empty() && *s != "*") else {
+                continue;
+            };
+            let kept = match first.get(sequence) {
+                Some(kept) => Some((kept.to_string(), false)),
+                None if reverse => first
+                    .get(reverse_complement(sequence).as_str())
+                    .map(|kept| (kept.to_string(), true)),
+                None => None,
+            };
+            match kept {
+                Some(kept) => {
+                    collapse.merged.insert(name.to_string(), kept);
+                    collapse.bp += sequence.len();
+                }
+                None => {
+                    first.insert(sequence, name);
+                }
+            }
+        }
+        collapse
+    }
+
+    /// Segment a step on `name` is redirected to, and the orientation of the step on it
+    fn target<'a>(&'a self, name: &'a str, forward: bool) -> (&'a str, bool) {
+        match self.merged.get(name) {
+            Some((kept, reverse)) => (kept.as_str(), forward != *reverse),
+            None => (name, forward),
+        }
+    }
+
+    /// Removes the merged segments and redirects the paths and walks, fails on a path or walk
+    /// with invalid steps
+    pub fn apply(&self, trimmed: &mut Trimmed) -> Result<(), String> {
+        if self.merged.is_empty() {
+            return Ok(());
+        }
+        trimmed.segments.retain(|s| {
+            !self
+                .merged
+                .contains_key(s.split('\t').nth(1).expect("All nodes should have ids"))
+        });
+        redirect_steps(trimmed, |name, forward| {
+            let (name, forward) = self.target(name, forward);
+            (name.to_string(), forward)
+        })
+    }
+
+    /// Links or jumps redirected to the remaining segments, without duplicates
+    pub fn edges(&self, edges: &[&str]) -> Vec<String> {
+        let orientation = |forward: bool| if forward { "+" } else { "-" };
+        let mut seen = HashSet::new();
+        edges
+            .iter()
+            .filter_map(|line| {
+                let mut fields = line.split('\t').collect::<Vec<_>>();
+                if fields.len() < 5 {
+                    return Some(line.to_string());
+                }
+                let (from, from_forward) = self.target(fields[1], fields[2] != "-");
+                let (to, to_forward) = self.target(fields[3], fields[4] != "-");
+                // a+ -> b+ is the same edge as b- -> a-
+                let edge = (from, from_forward, to, to_forward);
+                let reversed = (to, !to_forward, from, !from_forward);
+                let key = (edge.min(reversed), fields[5..].join("\t"));
+                if !seen.insert(key) {
+                    return None;
+                }
+                fields[1] = from;
+                fields[2] = orientation(from_forward);
+                fields[3] = to;
+                fields[4] = orientation(to_forward);
+                Some(fields.join("\t"))
+            })
+            .collect()
+    }
+}
+
+/// Replaces the steps of the paths and walks by the segment and orientation `target` gives
+/// for them, fails on a path or walk with invalid steps. The steps column of paths is written
+/// anew, without the whitespace around steps.
+pub(crate) fn redirect_steps<F: Fn(&str, bool) -> (String, bool)>(
+    trimmed: &mut Trimmed,
+    target: F,
+) -> Result<(), String> {
+    for path in trimmed.paths.iter_mut() {
+        let mut fields = path.split('\t').collect::<Vec<_>>();
+        let Some(steps) = fields.get(2) else {
+            continue;
+        };
+        let tokens = tokenize_path(steps)
+            .map_err(|e| format!("Invalid steps of path {}: {}", fields[1], e))?;
+        // Steps keep the separator following them
+        let steps = tokens
+            .iter()
+            .map(|token| {
+                let (name, forward) = target(token.name, token.forward);
+                let separator = match token.jump {
+                    Some(true) => ";",
+                    Some(false) => ",",
+                    None => "",
+                };
+                format!("{}{}{}", name, if forward { '+' } else { '-' }, separator)
+            })
+            .collect::<String>();
+        fields[2] = &steps;
+        *path = fields.join("\t");
+    }
+    for walk in trimmed.walks.iter_mut() {
+        let mut fields = walk.split('\t').collect::<Vec<_>>();
+        if fields.len() < 7 {
+            continue;
+        }
+        let steps = walk_line_steps(walk)?
+            .into_iter()
+            .map(|(name, forward)| {
+                let (name, forward) = target(name, forward);
+                format!("{}{}", if forward { '>' } else { '<' }, name)
+            })
+            .collect::<String>();
+        fields[6] = &steps;
+        *walk = fields.join("\t");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_collapse_identical() {
+        // Segments 2 and 3 are the same bubble branch
+        let content = "S\t1\tACGT\nS\t2\tGG\nS\t3\tGG\nS\t4\tT\nS\t5\t*\nS\t6\t*\n\
+                       L\t1\t+\t2\t+\t0M\nL\t1\t+\t3\t+\t0M\nL\t2\t+\t4\t+\t0M\n\
+                       L\t3\t+\t4\t+\t0M\nL\t4\t+\t5\t+\t0M\nL\t4\t+\t6\t+\t0M\n\
+                       P\tref\t1+,2+,4+,5+\t*\nW\ts\t1\tc\t0\t7\t>1>3>4>6\n";
+        let mut trimmed =
+            trim_gfa(GfaLines::classify(content), None, &TrimOptions::default()).unwrap();
+        let collapse = Collapse::identical(&trimmed.segments, false);
+        assert_eq!(collapse.merged.len(), 1);
+        assert_eq!(collapse.bp, 2);
+        collapse.apply(&mut trimmed).unwrap();
+        assert_eq!(trimmed.segments.len(), 5);
+        assert_eq!(trimmed.paths, vec!["P\tref\t1+,2+,4+,5+\t*"]);
+        assert_eq!(trimmed.walks, vec!["W\ts\t1\tc\t0\t7\t>1>2>4>6"]);
+        assert_eq!(
+            collapse.edges(&trimmed.links),
+            vec![
+                "L\t1\t+\t2\t+\t0M",
+                "L\t2\t+\t4\t+\t0M",
+                "L\t4\t+\t5\t+\t0M",
+                "L\t4\t+\t6\t+\t0M"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_reverse_complements() {
+        // Segment 3 is the reverse complement of segment 2
+        let content = "S\t1\tACGT\nS\t2\tGGA\nS\t3\tTCC\nS\t4\tT\n\
+                       L\t1\t+\t2\t+\t0M\nL\t1\t+\t3\t-\t0M\nL\t2\t+\t4\t+\t0M\n\
+                       L\t4\t-\t3\t+\t0M\n\
+                       P\tref\t1+,2+,4+\t*\nW\ts\t1\tc\t0\t8\t>1<3>4\nW\tt\t1\tc\t0\t8\t<4>3<1\n";
+        let mut trimmed =
+            trim_gfa(GfaLines::classify(content), None, &TrimOptions::default()).unwrap();
+        assert!(Collapse::identical(&trimmed.segments, false)
+            .merged
+            .is_empty());
+        let collapse = Collapse::identical(&trimmed.segments, true);
+        assert_eq!(collapse.merged["3"], ("2".to_string(), true));
+        collapse.apply(&mut trimmed).unwrap();
+        assert_eq!(
+            trimmed.walks,
+            vec!["W\ts\t1\tc\t0\t8\t>1>2>4", "W\tt\t1\tc\t0\t8\t<4<2<1"]
+        );
+        assert_eq!(
+            collapse.edges(&trimmed.links),
+            vec!["L\t1\t+\t2\t+\t0M", "L\t2\t+\t4\t+\t0M"]
+        );
+    }
+
+    #[test]
+    fn test_redirect_steps() {
+        let mut trimmed = Trimmed {
+            paths: vec!["P\tp\t1+, 2-;3+\t*".to_string()],
+            ..Default::default()
+        };
+        let double = |name: &str, forward: bool| (name.repeat(2), !forward);
+        redirect_steps(&mut trimmed, double).unwrap();
+        assert_eq!(trimmed.paths, ["P\tp\t11-,22+;33-\t*"]);
+        // An empty step is an error, not a panic
+        trimmed.paths = vec!["P\tq\t1+,,2+\t*".to_string()];
+        let error = redirect_steps(&mut trimmed, double).unwrap_err();
+        assert!(error.starts_with("Invalid steps of path q"));
+    }
+}
@@ -0,0 +1,215 @@
+//! The graph removed by trimming (`--complement-output`).
+//!
+//! The complement consists of the removed segments, the removed links and jumps between two
+//! removed segments, and the fragments of the removed paths and walks running through removed
+//! segments. Path fragments are named `name:start-end` after the interval of the original path
+//! they cover (like the fragments of the server), walk fragments get the interval as their
+//! start/end columns.
+
+use crate::extract::split_fragment;
+use crate::graph::{format_path_tokens, segment_length, tokenize_path, EdgeRecord, WalkRecord};
+use crate::scan;
+use crate::trim::{GfaLines, Trimmed};
+use std::collections::{HashMap, HashSet};
+
+/// Runs of consecutive steps on segments in `nodes` as (first step, end step, start offset,
+/// end offset)
+fn runs(
+    steps: &[&str],
+    lengths: &HashMap<&str, usize>,
+    nodes: &HashSet<&str>,
+) -> Vec<(usize, usize, usize, usize)> {
+    let mut runs = Vec::new();
+    let mut run: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for (i, node) in steps.iter().enumerate() {
+        match (nodes.contains(node), run) {
+            (true, None) => run = Some((i, offset)),
+            (false, Some((first, start))) => {
+                runs.push((first, i, start, offset));
+                run = None;
+            }
+            _ => {}
+        }
+        offset += lengths.get(node).copied().unwrap_or(0);
+    }
+    if let Some((first, start)) = run {
+        runs.push((first, steps.len(), start, offset));
+    }
+    runs
+}
+
+/// Fragments of a P line running through segments in `nodes`, named `name:start-end`. The
+/// fragments of a path that is a fragment itself are named after the original path, with
+/// coordinates on it. Fails on a path without name or with invalid steps.
+pub(crate) fn clip_path(
+    line: &str,
+    lengths: &HashMap<&str, usize>,
+    nodes: &HashSet<&str>,
+) -> Result<Vec<String>, String> {
+    let name = scan::field(line, 1).ok_or_else(|| format!("Path without name: {}", line))?;
+    let tokens = tokenize_path(scan::field(line, 2).unwrap_or(""))
+        .map_err(|e| format!("Invalid steps of path {}: {}", name, e))?;
+    let names = tokens.iter().map(|t| t.name).collect::<Vec<_>>();
+    let (original, base) = split_fragment(name);
+    Ok(runs(&names, lengths, nodes)
+        .into_iter()
+        .map(|(first, end, start, stop)| {
+            format!(
+                "P\t{}:{}-{}\t{}\t*",
+                original,
+                base + start,
+                base + stop,
+                format_path_tokens(&tokens[first..end])
+            )
+        })
+        .collect())
+}
+
+/// Fragments of a W line running through segments in `nodes`, with their interval as start
+/// and end. Fails on an invalid walk.
+pub(crate) fn clip_walk(
+    line: &str,
+    lengths: &HashMap<&str, usize>,
+    nodes: &HashSet<&str>,
+) -> Result<Vec<String>, String> {
+    let walk = WalkRecord::parse(line)?;
+    let steps = walk.steps;
+    let names = steps.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    // The sample, haplotype and sequence columns are kept as written
+    let fields = line.split('\t').collect::<Vec<_>>();
+    let walk_start = walk.seq_start.unwrap_or(0);
+    let runs = runs(&names, lengths, nodes);
+    Ok(runs
+        .into_iter()
+        .map(|(first, end, start, stop)| {
+            let steps = steps[first..end]
+                .iter()
+                .map(|(name, forward)| format!("{}{}", if *forward { '>' } else { '<' }, name))
+                .collect::<String>();
+            format!(
+                "W\t{}\t{}\t{}\t{}\t{}\t{}",
+                fields[1],
+                fields[2],
+                fields[3],
+                walk_start + start,
+                walk_start + stop,
+                steps
+            )
+        })
+        .collect())
+}
+
+/// Lines of `lines` removed in `trimmed`, with the removed paths and walks clipped to the
+/// removed segments. Fails on an invalid removed path, walk, link or jump.
+pub fn complement<'a>(lines: &GfaLines<'a>, trimmed: &Trimmed<'a>) -> Result<Trimmed<'a>, String> {
+    let name = |line: &&'a str| line.split('\t').nth(1).expect("All nodes should have ids");
+    let kept = trimmed.segments.iter().map(name).collect::<HashSet<_>>();
+    let segments = lines
+        .segments
+        .iter()
+        .filter(|s| !kept.contains(name(s)))
+        .copied()
+        .collect::<Vec<_>>();
+    let removed = segments.iter().map(name).collect::<HashSet<_>>();
+    let lengths = lines
+        .segments
+        .iter()
+        .map(|s| (name(s), segment_length(s)))
+        .collect::<HashMap<_, _>>();
+    let between_removed = |edges: &[&'a str]| {
+        let mut between = Vec::new();
+        for line in edges {
+            let edge = EdgeRecord::parse(line)?.edge;
+            if removed.contains(edge.from) && removed.contains(edge.to) {
+                between.push(*line);
+            }
+        }
+        Ok::<_, String>(between)
+    };
+
+    let kept_paths = trimmed
+        .paths
+        .iter()
+        .map(|p| p.as_str())
+        .collect::<HashSet<_>>();
+    let paths = lines
+        .paths
+        .iter()
+        .filter(|p| !kept_paths.contains(*p))
+        .map(|p| clip_path(p, &lengths, &removed))
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+    let kept_walks = trimmed
+        .walks
+        .iter()
+        .map(|w| w.as_str())
+        .collect::<HashSet<_>>();
+    let mut walks = Vec::new();
+    for walk in lines.walks.iter().filter(|w| !kept_walks.contains(*w)) {
+        walks.extend(clip_walk(walk, &lengths, &removed)?);
+    }
+
+    Ok(Trimmed {
+        headers: lines.headers.clone(),
+        links: between_removed(&lines.links)?,
+        jumps: between_removed(&lines.jumps)?,
+        segments,
+        paths,
+        walks,
+        others: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, TrimOptions};
+
+    #[test]
+    fn test_complement() {
+        let content = "S\t1\tACGT\nS\t2\tAA\nS\t3\tC\nS\t4\tGG\nS\t5\tT\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t1\t+\t3\t+\t0M\n\
+                       L\t3\t+\t4\t+\t0M\nL\t3\t+\t5\t-\t0M\nL\t4\t+\t5\t-\t0M\nL\t2\t+\t5\t-\t0M\n\
+                       P\tref\t1+,3+,4+\t*\nP\talt\t1+,2+,3+;5-\t*\n\
+                       W\ts\t1\tchr1\t10\t19\t>1>2>3<5\n";
+        let lines = GfaLines::classify(content);
+        let options = TrimOptions {
+            walks_to_keep: Some(HashSet::new()),
+            ..TrimOptions::default()
+        };
+        let trimmed = trim_gfa(lines.clone(), Some(vec!["ref".to_string()]), &options).unwrap();
+        let removed = complement(&lines, &trimmed).unwrap();
+        assert_eq!(removed.segments, vec!["S\t2\tAA", "S\t5\tT"]);
+        assert_eq!(removed.links, vec!["L\t2\t+\t5\t-\t0M"]);
+        assert_eq!(
+            removed.paths,
+            vec!["P\talt:4-6\t2+\t*", "P\talt:7-8\t5-\t*"]
+        );
+        assert_eq!(
+            removed.walks,
+            vec!["W\ts\t1\tchr1\t14\t16\t>2", "W\ts\t1\tchr1\t17\t18\t<5"]
+        );
+
+        // Truncated lines are errors, not panics
+        let mut truncated = lines.clone();
+        truncated.links.push("L\t2\t+");
+        assert!(complement(&truncated, &trimmed).is_err());
+        let mut truncated = lines.clone();
+        truncated.walks.push("W\ts\t2\tchr1");
+        assert!(complement(&truncated, &trimmed).is_err());
+    }
+
+    #[test]
+    fn test_clip_path() {
+        let lengths = HashMap::from([("1", 4), ("2", 2), ("3", 1)]);
+        let nodes = HashSet::from(["2", "3"]);
+        // Fragments of fragments keep the coordinates on the original path
+        assert_eq!(
+            clip_path("P\tref:10-17\t1+, 2-;3+\t*", &lengths, &nodes).unwrap(),
+            ["P\tref:14-17\t2-;3+\t*"]
+        );
+        assert!(clip_path("P\tref\t1+,,2+\t*", &lengths, &nodes).is_err());
+        assert!(clip_path("P", &lengths, &nodes).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Trimming of graphs consisting of many connected components, e.g. one per reference contig in
+//! whole-genome graphs (`--by-component`).
+//!
+//! The segments are grouped into the connected components of the links and jumps, components
+//! joined by a path or walk without an edge are merged. Each component with its paths and walks
+//! is trimmed on its own, all of them in parallel, which keeps the sets of nodes and edges to
+//! keep small. Reading and writing are not split: the components are cut out of the lines of
+//! the whole graph and the trimmed ones are merged back into a single [`Trimmed`], whose lines
+//! are in input order as without splitting.
+
+use crate::graph::{path_line_steps, walk_line_steps};
+use crate::par::{IntoParallelRefIterator, ParallelIterator};
+use crate::trim::{trim_gfa, GfaLines, TrimOptions, Trimmed};
+use std::collections::{HashMap, HashSet};
+
+/// Union-find over the segment ranks
+pub(crate) struct Components {
+    parents: Vec<usize>,
+}
+
+impl Components {
+    pub(crate) fn new(size: usize) -> Self {
+        Components {
+            parents: (0..size).collect(),
+        }
+    }
+
+    pub(crate) fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        // The smaller rank becomes the root, so components are numbered by their first segment
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+/// Splits the lines into the connected components of the graph. Headers and other lines are
+/// left out, lines not touching any segment go to the first component. Fails on the first path or
+/// walk with invalid steps.
+pub fn split_components<'a>(lines: &GfaLines<'a>) -> Result<Vec<GfaLines<'a>>, String> {
+    let ranks = lines
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.split('\t').nth(1).expect("All nodes should have ids"), i))
+        .collect::<HashMap<_, _>>();
+    let edge_ends = |line: &str| {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        [1, 3]
+            .into_iter()
+            .filter_map(|i| fields.get(i).and_then(|n| ranks.get(n)).copied())
+            .collect::<Vec<_>>()
+    };
+    let mut components = Components::new(lines.segments.len());
+    for line in lines.links.iter().chain(&lines.jumps) {
+        if let [from, to] = edge_ends(line)[..] {
+            components.union(from, to);
+        }
+    }
+
+    let path_ranks = |steps: &[(&str, bool)]| {
+        steps
+            .iter()
+            .filter_map(|(node, _)| ranks.get(node).copied())
+            .collect::<Vec<_>>()
+    };
+    let path_steps = lines
+        .paths
+        .par_iter()
+        .map(|p| Ok(path_ranks(&path_line_steps(p)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let walk_steps = lines
+        .walks
+        .par_iter()
+        .map(|w| Ok(path_ranks(&walk_line_steps(w)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    for steps in path_steps.iter().chain(&walk_steps) {
+        for pair in steps.windows(2) {
+            components.union(pair[0], pair[1]);
+        }
+    }
+
+    let roots = (0..lines.segments.len())
+        .map(|i| components.find(i))
+        .collect::<Vec<_>>();
+    let mut numbers = HashMap::new();
+    for root in &roots {
+        let next = numbers.len();
+        numbers.entry(*root).or_insert(next);
+    }
+    let mut split = vec![GfaLines::default(); numbers.len().max(1)];
+    let component = |ranks: &[usize]| ranks.first().map_or(0, |r| numbers[&roots[*r]]);
+    for (segment, root) in lines.segments.iter().zip(&roots) {
+        split[numbers[root]].segments.push(segment);
+    }
+    for link in &lines.links {
+        split[component(&edge_ends(link))].links.push(link);
+    }
+    for jump in &lines.jumps {
+        split[component(&edge_ends(jump))].jumps.push(jump);
+    }
+    for (path, steps) in lines.paths.iter().zip(&path_steps) {
+        split[component(steps)].paths.push(path);
+    }
+    for (walk, steps) in lines.walks.iter().zip(&walk_steps) {
+        split[component(steps)].walks.push(walk);
+    }
+    Ok(split)
+}
+
+/// Same as [`trim_gfa`], but trims the connected components of the graph in parallel
+pub fn trim_by_component<'a>(
+    lines: GfaLines<'a>,
+    paths_to_keep: Option<Vec<String>>,
+    options: &TrimOptions,
+) -> Result<Trimmed<'a>, String> {
+    let components = split_components(&lines)?;
+    log::info!("Trimming {} components", components.len());
+    let trimmed = components
+        .par_iter()
+        .map(|component| trim_gfa(component.clone(), paths_to_keep.clone(), options))
+        .collect::<Result<Vec<_>, _>>()?;
+    let pairs = || components.iter().zip(&trimmed);
+    let to_strings = |lines: Vec<&str>| lines.into_iter().map(str::to_string).collect();
+    Ok(Trimmed {
+        segments: in_input_order(
+            &lines.segments,
+            pairs().map(|(c, t)| (&c.segments[..], &t.segments[..])),
+        ),
+        paths: to_strings(in_input_order(
+            &lines.paths,
+            pairs().map(|(c, t)| (&c.paths[..], &t.paths[..])),
+        )),
+        walks: to_strings(in_input_order(
+            &lines.walks,
+            pairs().map(|(c, t)| (&c.walks[..], &t.walks[..])),
+        )),
+        links: in_input_order(
+            &lines.links,
+            pairs().map(|(c, t)| (&c.links[..], &t.links[..])),
+        ),
+        jumps: in_input_order(
+            &lines.jumps,
+            pairs().map(|(c, t)| (&c.jumps[..], &t.jumps[..])),
+        ),
+        headers: lines.headers,
+        others: lines.others,
+    })
+}
+
+/// The lines of `lines` kept in any component, in input order. Trimming a component keeps a
+/// subsequence of its lines, which is matched against them in step to find the kept ones.
+fn in_input_order<'a: 'b, 'b, S: AsRef<str> + 'b>(
+    lines: &[&'a str],
+    components: impl Iterator<Item = (&'b [&'a str], &'b [S])>,
+) -> Vec<&'a str> {
+    let mut kept = HashSet::new();
+    for (input, trimmed) in components {
+        let mut trimmed = trimmed.iter().peekable();
+        for line in input {
+            if trimmed.next_if(|t| t.as_ref() == *line).is_some() {
+                kept.insert(line.as_ptr());
+            }
+        }
+    }
+    lines
+        .iter()
+        .filter(|l| kept.contains(&l.as_ptr()))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_by_component() {
+        let content = "H\tVN:Z:1.1\nS\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nS\t5\tA\n\
+                       L\t1\t+\t2\t+\t0M\nL\t3\t+\t4\t+\t0M\nL\t4\t+\t5\t+\t0M\nL\t3\t+\t5\t+\t0M\n\
+                       P\tchr1\t1+,2+\t*\nP\tchr2\t3+,4+,5+\t*\nW\ts\t1\tchr2\t0\t2\t>3>5\n";
+        let lines = GfaLines::classify(content);
+        let components = split_components(&lines).unwrap();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].paths, vec!["P\tchr1\t1+,2+\t*"]);
+        assert_eq!(components[1].links.len(), 3);
+        assert_eq!(components[1].walks.len(), 1);
+
+        let options = TrimOptions {
+            walks_to_keep: Some(Default::default()),
+            ..TrimOptions::default()
+        };
+        let trimmed = trim_by_component(lines, Some(vec!["chr1".to_string()]), &options).unwrap();
+        let expected = trim_gfa(
+            GfaLines::classify(content),
+            Some(vec!["chr1".to_string()]),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(trimmed.headers, expected.headers);
+        assert_eq!(trimmed.segments, expected.segments);
+        assert_eq!(trimmed.links, expected.links);
+        assert_eq!(trimmed.paths, expected.paths);
+        assert!(trimmed.walks.is_empty());
+    }
+
+    #[test]
+    fn test_trim_by_component_order() {
+        let content = "S\t1\tA\nS\t3\tG\nS\t2\tC\nS\t4\tT\n\
+                       L\t3\t+\t4\t+\t0M\nL\t1\t+\t2\t+\t0M\n\
+                       P\tb\t3+,4+\t*\nP\ta\t1+,2+\t*\n";
+        let options = TrimOptions::default();
+        let trimmed = trim_by_component(GfaLines::classify(content), None, &options).unwrap();
+        let expected = trim_gfa(GfaLines::classify(content), None, &options).unwrap();
+        assert_eq!(
+            trimmed.segments,
+            vec!["S\t1\tA", "S\t3\tG", "S\t2\tC", "S\t4\tT"]
+        );
+        assert_eq!(trimmed.segments, expected.segments);
+        assert_eq!(trimmed.links, expected.links);
+        assert_eq!(trimmed.paths, expected.paths);
+    }
+}
@@ -0,0 +1,179 @@
+//! Compressed graphs: gzip, bgzip (BGZF) and, with the `zstd` feature, zstd.
+//!
+//! Compressed input is recognized by its magic bytes, so no file extension is needed.
+
+use crate::bgzf::{BgzfWriter, GziIndex};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    /// Blocked gzip as written by `bgzip`, readable by any gzip decoder
+    Bgzf,
+    Zstd,
+}
+
+impl Compression {
+    /// Compression of data starting with the given bytes
+    pub fn detect(start: &[u8]) -> Self {
+        // BGZF blocks are gzip members with a "BC" extra subfield
+        if start.starts_with(GZIP_MAGIC) && start.len() >= 14 && start[3] & 4 != 0 {
+            match &start[12..14] {
+                b"BC" => Compression::Bgzf,
+                _ => Compression::Gzip,
+            }
+        } else if start.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if start.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Built without the zstd feature, cannot handle zstd-compressed graphs",
+    )
+}
+
+/// Decompresses the data if it is compressed, otherwise returns it unchanged
+pub fn decompress(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    match Compression::detect(&data) {
+        Compression::None => return Ok(data),
+        Compression::Gzip | Compression::Bgzf => {
+            MultiGzDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => {
+            zstd::stream::read::Decoder::new(data.as_slice())?.read_to_end(&mut decompressed)?;
+        }
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => return Err(zstd_unsupported()),
+    }
+    Ok(decompressed)
+}
+
+/// Writer compressing its output, [`Encoder::finish`] has to be called to complete it
+pub enum Encoder<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    Bgzf(BgzfWriter<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Compresses with the default level of the format if `level` is `None` (BGZF always uses
+    /// the default level). zstd compresses on `threads` additional threads (0 to compress on
+    /// the calling thread only).
+    pub fn new(
+        out: W,
+        compression: Compression,
+        level: Option<u32>,
+        threads: u32,
+    ) -> io::Result<Self> {
+        Ok(match compression {
+            Compression::None => Encoder::Plain(out),
+            Compression::Gzip => {
+                let level = level.map_or(flate2::Compression::default(), |l| {
+                    flate2::Compression::new(l.min(9))
+                });
+                Encoder::Gzip(GzEncoder::new(out, level))
+            }
+            Compression::Bgzf => Encoder::Bgzf(BgzfWriter::new(out)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let level = level.map_or(zstd::DEFAULT_COMPRESSION_LEVEL, |l| l as i32);
+                let mut encoder = zstd::stream::write::Encoder::new(out, level)?;
+                encoder.multithread(threads)?;
+                Encoder::Zstd(encoder)
+            }
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => {
+                let _ = threads;
+                return Err(zstd_unsupported());
+            }
+        })
+    }
+
+    /// Writes the end of the compressed stream and returns the inner writer
+    pub fn finish(self) -> io::Result<W> {
+        self.finish_with_index().map(|(out, _)| out)
+    }
+
+    /// Same as [`Encoder::finish`], also returning the block index of BGZF output
+    pub fn finish_with_index(self) -> io::Result<(W, Option<GziIndex>)> {
+        match self {
+            Encoder::Plain(out) => Ok((out, None)),
+            Encoder::Gzip(encoder) => Ok((encoder.finish()?, None)),
+            Encoder::Bgzf(writer) => {
+                let (out, index) = writer.finish_with_index()?;
+                Ok((out, Some(index)))
+            }
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => Ok((encoder.finish()?, None)),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Plain(out) => out.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Bgzf(writer) => writer.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(out) => out.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Bgzf(writer) => writer.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(compression: Compression) {
+        let gfa = b"S\t1\tACGT\nS\t2\tTT\n".repeat(100);
+        let mut encoder = Encoder::new(Vec::new(), compression, None, 0).unwrap();
+        encoder.write_all(&gfa).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(Compression::detect(&compressed), compression);
+        assert_eq!(decompress(compressed).unwrap(), gfa);
+    }
+
+    #[test]
+    fn test_gzip() {
+        round_trip(Compression::None);
+        round_trip(Compression::Gzip);
+        round_trip(Compression::Bgzf);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        round_trip(Compression::Zstd);
+    }
+}
@@ -0,0 +1,131 @@
+//! Trim recipes in TOML files (`--config trim.toml`).
+//!
+//! Every `[[job]]` table is one run of trim-graph, its keys are the long options of the
+//! command line (`paths-to-keep = "keep.txt"`, `drop = ["P", "W"]`, `prune-complex = true`)
+//! plus `graph` for the graph file. Keys outside of the job tables are defaults for all jobs.
+//! A file without job tables describes a single job.
+
+use toml::{Table, Value};
+
+/// Command line arguments of the option given as a TOML key and value
+fn option_args(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    let option = format!("--{}", key.replace('_', "-"));
+    Ok(match value {
+        Value::String(s) => vec![option, s.clone()],
+        Value::Integer(i) => vec![option, i.to_string()],
+        Value::Float(f) => vec![option, f.to_string()],
+        Value::Boolean(true) => vec![option],
+        Value::Boolean(false) => Vec::new(),
+        // Repeated, since the items may contain commas (e.g. rename rules)
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => Ok([option.clone(), s.clone()]),
+                Value::Integer(i) => Ok([option.clone(), i.to_string()]),
+                _ => Err(format!("Unsupported list value for {}", key)),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .concat(),
+        _ => return Err(format!("Unsupported value for {}", key)),
+    })
+}
+
+fn job_args(job: &Table) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut graph = None;
+    for (key, value) in job {
+        match key.as_str() {
+            "graph" => match value {
+                Value::String(s) => graph = Some(s.clone()),
+                _ => return Err("graph has to be a file name".to_string()),
+            },
+            "config" => return Err("Configs cannot include other configs".to_string()),
+            _ => args.extend(option_args(key, value)?),
+        }
+    }
+    if let Some(graph) = graph {
+        args.extend(["--".to_string(), graph]);
+    }
+    Ok(args)
+}
+
+/// Command line arguments (without the program name) of every job of the config
+pub fn parse_jobs(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut defaults = content
+        .parse::<Table>()
+        .map_err(|e| format!("Invalid config: {}", e))?;
+    let jobs = match defaults.remove("job") {
+        None => vec![Table::new()],
+        Some(Value::Array(jobs)) => jobs
+            .into_iter()
+            .map(|job| match job {
+                Value::Table(job) => Ok(job),
+                _ => Err("Jobs have to be tables ([[job]])".to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => return Err("Jobs have to be tables ([[job]])".to_string()),
+    };
+    jobs.into_iter()
+        .map(|job| {
+            let mut merged = defaults.clone();
+            merged.extend(job);
+            job_args(&merged)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jobs() {
+        let config = r#"
+            graph = "graph.gfa"
+            threads = 8
+
+            [[job]]
+            paths-to-keep = "chr1.txt"
+            output = "chr1.gfa"
+
+            [[job]]
+            graph = "other.gfa"
+            drop = ["P", "W"]
+            prune_complex = true
+            checksum = false
+        "#;
+        let jobs = parse_jobs(config).unwrap();
+        assert_eq!(
+            jobs,
+            vec![
+                vec![
+                    "--output",
+                    "chr1.gfa",
+                    "--paths-to-keep",
+                    "chr1.txt",
+                    "--threads",
+                    "8",
+                    "--",
+                    "graph.gfa"
+                ],
+                vec![
+                    "--drop",
+                    "P",
+                    "--drop",
+                    "W",
+                    "--prune-complex",
+                    "--threads",
+                    "8",
+                    "--",
+                    "other.gfa"
+                ]
+            ]
+        );
+        assert_eq!(
+            parse_jobs("graph = \"g.gfa\"").unwrap(),
+            vec![vec!["--", "g.gfa"]]
+        );
+        assert!(parse_jobs("job = 1").is_err());
+        assert!(parse_jobs("[[job]]\nconfig = \"a.toml\"").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Reachability between listed segments (`trim-graph connected`), to check that trimming kept
+//! loci of interest connected.
+//!
+//! Two segments are reachable from each other if they are in the same connected component of
+//! the links and jumps, regardless of orientation. Components are numbered from 1 in the order
+//! of their first segment in the graph.
+
+use crate::components::Components;
+use crate::trim::GfaLines;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Component of each of the nodes, `None` for nodes that are not segments of the graph
+pub fn node_components(lines: &GfaLines, nodes: &[String]) -> Vec<Option<usize>> {
+    let ranks = lines
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.split('\t').nth(1).expect("All nodes should have ids"), i))
+        .collect::<HashMap<_, _>>();
+    let mut components = Components::new(lines.segments.len());
+    for line in lines.links.iter().chain(&lines.jumps) {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if let (Some(from), Some(to)) = (
+            fields.get(1).and_then(|n| ranks.get(n)),
+            fields.get(3).and_then(|n| ranks.get(n)),
+        ) {
+            components.union(*from, *to);
+        }
+    }
+    // The root of a component is its first segment
+    let mut numbers = vec![0; lines.segments.len()];
+    let mut count = 0;
+    for i in 0..lines.segments.len() {
+        let root = components.find(i);
+        numbers[i] = match root == i {
+            true => {
+                count += 1;
+                count
+            }
+            false => numbers[root],
+        };
+    }
+    nodes
+        .iter()
+        .map(|node| ranks.get(node.as_str()).map(|rank| numbers[*rank]))
+        .collect()
+}
+
+/// Writes one line `node component reachable` per node, with a header. `reachable` lists the
+/// other nodes in the same component, `*` stands for none and for the component of nodes
+/// missing from the graph.
+pub fn write_connectivity<W: Write>(
+    nodes: &[String],
+    components: &[Option<usize>],
+    out: &mut W,
+) -> io::Result<()> {
+    writeln!(out, "node\tcomponent\treachable")?;
+    for (node, component) in nodes.iter().zip(components) {
+        let Some(component) = component else {
+            writeln!(out, "{}\t*\t*", node)?;
+            continue;
+        };
+        let reachable = nodes
+            .iter()
+            .zip(components)
+            .filter(|(other, c)| *other != node && *c == &Some(*component))
+            .map(|(other, _)| other.as_str())
+            .collect::<Vec<_>>();
+        let reachable = match reachable.is_empty() {
+            true => "*".to_string(),
+            false => reachable.join(","),
+        };
+        writeln!(out, "{}\t{}\t{}", node, component, reachable)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connectivity() {
+        // 1-2-3 and 4-5 (through a jump) are connected, 6 is on its own
+        let content = "S\t1\tA\nS\t4\tC\nS\t2\tG\nS\t3\tT\nS\t5\tA\nS\t6\tC\n\
+                       L\t1\t+\t2\t+\t0M\nL\t3\t-\t2\t-\t0M\nJ\t4\t+\t5\t-\t*\n";
+        let nodes = ["3", "5", "1", "6", "7", "4"]
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>();
+        let components = node_components(&GfaLines::classify(content), &nodes);
+        assert_eq!(
+            components,
+            vec![Some(1), Some(2), Some(1), Some(3), None, Some(2)]
+        );
+        let mut out = Vec::new();
+        write_connectivity(&nodes, &components, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "node\tcomponent\treachable\n3\t1\t1\n5\t2\t4\n1\t1\t3\n6\t3\t*\n7\t*\t*\n4\t2\t5\n"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Haplotype coverage along a reference path (`--coverage-bedgraph`), as bedGraph.
+//!
+//! The coverage of a segment is the number of paths and walks visiting it, the reference
+//! included. Each visit of the reference projects the coverage of its segment onto the
+//! reference interval of the visit, adjacent intervals with the same coverage are merged.
+//! Segments off the reference have no interval of their own and do not show up in the track.
+
+use crate::graph::{path_line_steps, segment_length, walk_line_steps};
+use crate::removed::find_reference;
+use crate::trim::Trimmed;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Number of paths and walks visiting each segment, fails on a path or walk with invalid steps
+pub fn path_counts(trimmed: &Trimmed) -> Result<HashMap<String, usize>, String> {
+    let mut counts = HashMap::new();
+    let mut count = |steps: Vec<(&str, bool)>| {
+        for node in steps
+            .into_iter()
+            .map(|(node, _)| node)
+            .collect::<HashSet<_>>()
+        {
+            *counts.entry(node.to_string()).or_insert(0) += 1;
+        }
+    };
+    for path in &trimmed.paths {
+        count(path_line_steps(path)?);
+    }
+    for walk in &trimmed.walks {
+        count(walk_line_steps(walk)?);
+    }
+    Ok(counts)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageTrack {
+    /// Contig of the reference, the sample and haplotype of PanSN names are left out
+    pub contig: String,
+    /// Start, end and coverage, in reference order
+    pub intervals: Vec<(usize, usize, usize)>,
+}
+
+impl CoverageTrack {
+    /// Projects the coverage of the segments of `trimmed` onto the reference, a path name or
+    /// the PanSN name of a walk
+    pub fn new(trimmed: &Trimmed, reference: &str) -> Result<Self, String> {
+        let (steps, contig, start) = find_reference(&trimmed.paths, &trimmed.walks, reference)?;
+        let lengths = trimmed
+            .segments
+            .iter()
+            .map(|s| {
+                let name = s.split('\t').nth(1).expect("All nodes should have ids");
+                (name, segment_length(s))
+            })
+            .collect::<HashMap<_, _>>();
+        let counts = path_counts(trimmed)?;
+        let mut intervals: Vec<(usize, usize, usize)> = Vec::new();
+        let mut offset = start;
+        for node in &steps {
+            let end = offset + lengths.get(node.as_str()).copied().unwrap_or(0);
+            let coverage = counts.get(node).copied().unwrap_or(0);
+            match intervals.last_mut() {
+                _ if end == offset => {}
+                Some(last) if last.2 == coverage => last.1 = end,
+                _ => intervals.push((offset, end, coverage)),
+            }
+            offset = end;
+        }
+        Ok(CoverageTrack { contig, intervals })
+    }
+
+    /// Writes one bedGraph line `contig start end coverage` per interval
+    pub fn write_bedgraph<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (start, end, coverage) in &self.intervals {
+            writeln!(out, "{}\t{}\t{}\t{}", self.contig, start, end, coverage)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage_track() {
+        // Both walks skip 3, one visits 1 twice, 5 has no sequence
+        let trimmed = Trimmed {
+            segments: vec!["S\t1\tACGT", "S\t2\tAA", "S\t3\tCCC", "S\t4\tGG", "S\t5\t*"],
+            paths: vec!["P\tGRCh38#0#chr1\t1+,2+,3+,5+,4+\t*".to_string()],
+            walks: vec![
+                "W\tHG002\t1\tchr1\t0\t14\t>1>2>1>2>4".to_string(),
+                "W\tHG002\t2\tchr1\t0\t8\t>1>2>4".to_string(),
+            ],
+            ..Trimmed::default()
+        };
+        assert_eq!(path_counts(&trimmed).unwrap()["1"], 3);
+        let track = CoverageTrack::new(&trimmed, "GRCh38#0#chr1").unwrap();
+        let mut out = Vec::new();
+        track.write_bedgraph(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "chr1\t0\t6\t3\nchr1\t6\t9\t1\nchr1\t9\t11\t3\n"
+        );
+    }
+}
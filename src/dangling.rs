@@ -0,0 +1,198 @@
+//! Records left dangling by `--ignore-segments`, `--ignore-links` and `--ignore-jumps`.
+//!
+//! Keeping all lines of one record type while trimming the others can produce a graph that
+//! refers to records it does not contain: with `-L` or `-J` the kept links and jumps may
+//! connect removed segments, and with `-S` segments stay that no kept path, walk, link or jump
+//! touches any more. [`Consistency`] counts such records in the trimmed graph and gives a few
+//! examples, to warn about them with a hint which option causes them.
+
+use crate::graph::{path_line_steps, walk_line_steps};
+use crate::trim::{walk_name, TrimOptions, Trimmed};
+use std::collections::{BTreeSet, HashSet};
+
+/// Examples given per kind of dangling record
+const EXAMPLES: usize = 3;
+
+/// Number of dangling records of one kind and the first few of them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dangling {
+    pub count: usize,
+    pub examples: Vec<String>,
+    /// Segments the records refer to that are missing from the graph
+    pub missing: BTreeSet<String>,
+}
+
+impl Dangling {
+    fn add(&mut self, example: impl FnOnce() -> String) {
+        self.count += 1;
+        if self.examples.len() < EXAMPLES {
+            self.examples.push(example());
+        }
+    }
+
+    fn describe(&self) -> String {
+        let mut missing = self
+            .missing
+            .iter()
+            .take(EXAMPLES)
+            .cloned()
+            .collect::<Vec<_>>();
+        if self.missing.len() > EXAMPLES {
+            missing.push("...".to_string());
+        }
+        format!(
+            "{} removed segments (e.g. {}: {})",
+            self.missing.len(),
+            self.examples.join(", "),
+            missing.join(", ")
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Consistency {
+    /// L lines with a segment missing from the graph
+    pub links: Dangling,
+    /// J lines with a segment missing from the graph
+    pub jumps: Dangling,
+    /// P and W lines stepping through a segment missing from the graph
+    pub paths: Dangling,
+    /// Segments on no path, walk, link or jump
+    pub isolated: Dangling,
+}
+
+impl Consistency {
+    /// Checks the trimmed graph for records referring to missing segments and for isolated
+    /// segments, fails on a path or walk with invalid steps
+    pub fn of(trimmed: &Trimmed) -> Result<Self, String> {
+        let segments = trimmed
+            .segments
+            .iter()
+            .map(|s| s.split('\t').nth(1).expect("All nodes should have ids"))
+            .collect::<HashSet<_>>();
+        let mut consistency = Consistency::default();
+        let mut touched = HashSet::new();
+        for (edges, dangling) in [
+            (&trimmed.links, &mut consistency.links),
+            (&trimmed.jumps, &mut consistency.jumps),
+        ] {
+            for line in edges {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                let ends = [1, 3].map(|i| fields.get(i).copied().unwrap_or_default());
+                touched.extend(ends);
+                let missing = ends.iter().filter(|end| !segments.contains(*end));
+                let missing = missing.map(|end| end.to_string()).collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    dangling.add(|| fields[..fields.len().min(5)].join(" "));
+                    dangling.missing.extend(missing);
+                }
+            }
+        }
+        for line in trimmed.paths.iter().chain(&trimmed.walks) {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let (name, steps) = match fields[0] {
+                "W" => (walk_name(line), walk_line_steps(line)?),
+                _ => (
+                    fields.get(1).unwrap_or(&"").to_string(),
+                    path_line_steps(line)?,
+                ),
+            };
+            let mut missing = Vec::new();
+            for (node, _) in steps {
+                match segments.get(node) {
+                    Some(segment) => {
+                        touched.insert(*segment);
+                    }
+                    None => missing.push(node.to_string()),
+                }
+            }
+            if !missing.is_empty() {
+                consistency.paths.add(|| name);
+                consistency.paths.missing.extend(missing);
+            }
+        }
+        for segment in &trimmed.segments {
+            let name = segment.split('\t').nth(1).unwrap_or_default();
+            if !touched.contains(name) {
+                consistency.isolated.add(|| name.to_string());
+            }
+        }
+        Ok(consistency)
+    }
+
+    /// Warnings about the dangling records, with the options that cause them. Isolated
+    /// segments are only reported with `--ignore-segments`, graphs may have them anyway.
+    pub fn warnings(&self, options: &TrimOptions) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (dangling, records, option) in [
+            (&self.links, "links", "-L/--ignore-links"),
+            (&self.jumps, "jumps", "-J/--ignore-jumps"),
+        ] {
+            if dangling.count > 0 {
+                warnings.push(format!(
+                    "{} kept {} connect {}; they are kept by {}, drop it to trim them as well \
+                     or add -S/--ignore-segments to keep all segments",
+                    dangling.count,
+                    records,
+                    dangling.describe(),
+                    option
+                ));
+            }
+        }
+        if self.paths.count > 0 {
+            warnings.push(format!(
+                "{} kept paths and walks step through {}; remove -S/-L/-J or add the segments",
+                self.paths.count,
+                self.paths.describe()
+            ));
+        }
+        if options.ignore_segments && self.isolated.count > 0 {
+            warnings.push(format!(
+                "{} segments kept by -S/--ignore-segments are on no kept path, walk, link or \
+                 jump (e.g. {}); add -L and -J to keep their edges",
+                self.isolated.count,
+                self.isolated.examples.join(", ")
+            ));
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines};
+
+    #[test]
+    fn test_dangling() {
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t1\t+\t3\t+\t0M\n\
+                       J\t3\t+\t4\t+\t10\tSC:i:1\nP\tp\t1+,3+\t*\n";
+        let trim = |options: TrimOptions| {
+            let trimmed = trim_gfa(GfaLines::classify(content), None, &options).unwrap();
+            (Consistency::of(&trimmed).unwrap(), options)
+        };
+
+        let (consistency, options) = trim(TrimOptions {
+            ignore_links: true,
+            ignore_jumps: true,
+            ..Default::default()
+        });
+        assert_eq!(consistency.links.count, 2);
+        assert_eq!(consistency.links.examples, ["L 1 + 2 +", "L 2 + 3 +"]);
+        assert_eq!(consistency.jumps.missing, BTreeSet::from(["4".to_string()]));
+        assert_eq!(consistency.paths, Dangling::default());
+        let warnings = consistency.warnings(&options);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0]
+            .starts_with("2 kept links connect 1 removed segments (e.g. L 1 + 2 +, L 2 + 3 +: 2)"));
+
+        let (consistency, options) = trim(TrimOptions {
+            ignore_segments: true,
+            ..Default::default()
+        });
+        assert_eq!(consistency.isolated.examples, ["2", "4"]);
+        assert_eq!(consistency.warnings(&options).len(), 1);
+        assert!(consistency.warnings(&TrimOptions::default()).is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+//! Checksums of trimmed graphs (`--checksum`).
+//!
+//! Segments get an `SH:Z` tag with the SHA-1 of their sequence, and the whole graph gets a
+//! digest written as `H SH:Z` header. The digest is the SHA-1 of the sorted records without
+//! their tags, so it does not depend on the order of the lines or on tags added later, and
+//! two graphs with the same content have the same digest.
+
+use crate::par::{IntoParallelRefIterator, ParallelIterator};
+use crate::trim::Trimmed;
+use itertools::Itertools;
+use sha1::{Digest, Sha1};
+
+pub(crate) const SEGMENT_TAG: &str = "SH:Z:";
+/// Header line carrying the digest, without the digest itself
+pub const DIGEST_HEADER: &str = "H\tSH:Z:";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn hex_sha1(data: &[u8]) -> String {
+    hex(&Sha1::digest(data))
+}
+
+/// Segment lines with the `SH:Z` tag of their sequence, replacing an existing one
+pub fn tag_segments(segments: &[&str]) -> Vec<String> {
+    segments
+        .par_iter()
+        .map(|line| {
+            let sequence = line.split('\t').nth(2).unwrap_or("*");
+            let fields = line
+                .split('\t')
+                .filter(|field| !field.starts_with(SEGMENT_TAG))
+                .join("\t");
+            format!(
+                "{}\t{}{}",
+                fields,
+                SEGMENT_TAG,
+                hex_sha1(sequence.as_bytes())
+            )
+        })
+        .collect()
+}
+
+/// Required fields of a line, i.e. the line without its tags
+fn without_tags(line: &str, fields: usize) -> String {
+    line.split('\t').take(fields).join("\t")
+}
+
+/// Digest of the segments, links, jumps, paths and walks of the graph
+pub fn graph_digest(trimmed: &Trimmed) -> String {
+    let records = |lines: &[&str], fields: usize| {
+        lines
+            .par_iter()
+            .map(|line| without_tags(line, fields))
+            .collect::<Vec<_>>()
+    };
+    let paths = trimmed.paths.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+    let walks = trimmed.walks.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+    let mut records = [
+        records(&trimmed.segments, 3),
+        records(&trimmed.links, 6),
+        records(&trimmed.jumps, 6),
+        records(&paths, 4),
+        records(&walks, 7),
+    ]
+    .concat();
+    records.sort_unstable();
+    let mut hasher = Sha1::new();
+    for record in records {
+        hasher.update(record.as_bytes());
+        hasher.update(b"\n");
+    }
+    hex(&hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_segments() {
+        let tagged = tag_segments(&["S\t1\tACGT\tSH:Z:old\tLN:i:4"]);
+        assert_eq!(
+            tagged,
+            vec![format!("S\t1\tACGT\tLN:i:4\tSH:Z:{}", hex_sha1(b"ACGT"))]
+        );
+        assert_eq!(hex_sha1(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[test]
+    fn test_graph_digest() {
+        let graph = Trimmed {
+            segments: vec!["S\t1\tA", "S\t2\tC\tSH:Z:x"],
+            links: vec!["L\t1\t+\t2\t+\t0M"],
+            ..Trimmed::default()
+        };
+        let reordered = Trimmed {
+            segments: vec!["S\t2\tC", "S\t1\tA\tLN:i:1"],
+            ..graph.clone()
+        };
+        assert_eq!(graph_digest(&graph), graph_digest(&reordered));
+        let other = Trimmed {
+            links: Vec::new(),
+            ..graph.clone()
+        };
+        assert_ne!(graph_digest(&graph), graph_digest(&other));
+    }
+}
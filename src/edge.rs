@@ -0,0 +1,168 @@
+//! Edges between oriented segments, equal in both directions.
+//!
+//! The L line `a + b -` connects the end of `a` to the end of `b`, which a path can traverse
+//! as `a+,b-` or, on the other strand, as `b+,a-`. An [`Edge`] therefore compares, hashes and
+//! orders by its canonical direction, the smaller of the two tuples `(from, from_forward, to,
+//! to_forward)`, so that the keep sets of paths and walks, the L/J lines they are matched
+//! against and the edges counted from reads agree however each of them reads the edge.
+
+use crate::graph::Link;
+use crate::scan;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// Edge from one oriented segment to another. Segments are named by `S`, e.g. `&str` for edges
+/// borrowed from a line.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Edge<S = String> {
+    pub from: S,
+    pub from_forward: bool,
+    pub to: S,
+    pub to_forward: bool,
+}
+
+impl<S> Edge<S> {
+    pub fn new(from: S, from_forward: bool, to: S, to_forward: bool) -> Self {
+        Edge {
+            from,
+            from_forward,
+            to,
+            to_forward,
+        }
+    }
+
+    /// The same edge read from the other strand, `b+ -> a-` for `a+ -> b-`
+    pub fn reverse(self) -> Self {
+        Edge {
+            from: self.to,
+            from_forward: !self.to_forward,
+            to: self.from,
+            to_forward: !self.from_forward,
+        }
+    }
+}
+
+impl<S: Ord> Edge<S> {
+    /// Both segments with their orientation, in canonical direction
+    fn key(&self) -> (&S, bool, &S, bool) {
+        let forward = (&self.from, self.from_forward, &self.to, self.to_forward);
+        let reverse = (&self.to, !self.to_forward, &self.from, !self.from_forward);
+        forward.min(reverse)
+    }
+
+    /// Whether the edge is stored in its canonical direction
+    pub fn is_canonical(&self) -> bool {
+        (&self.from, self.from_forward, &self.to, self.to_forward)
+            <= (&self.to, !self.to_forward, &self.from, !self.from_forward)
+    }
+
+    /// The edge in its canonical direction
+    pub fn canonical(self) -> Self {
+        match self.is_canonical() {
+            true => self,
+            false => self.reverse(),
+        }
+    }
+}
+
+impl<'a> Edge<&'a str> {
+    /// Edge of an L or J line, `None` if it has less than 5 columns
+    pub fn from_line(line: &'a str) -> Option<Self> {
+        let mut fields = scan::fields(line).skip(1);
+        let (from, from_orientation) = (fields.next()?, fields.next()?);
+        let (to, to_orientation) = (fields.next()?, fields.next()?);
+        Some(Edge::new(
+            from,
+            from_orientation.contains('+'),
+            to,
+            to_orientation.contains('+'),
+        ))
+    }
+
+    pub fn into_owned(self) -> Edge {
+        Edge::new(
+            self.from.to_string(),
+            self.from_forward,
+            self.to.to_string(),
+            self.to_forward,
+        )
+    }
+}
+
+impl From<&Link> for Edge {
+    fn from(link: &Link) -> Self {
+        Edge::new(
+            link.from.clone(),
+            link.from_forward,
+            link.to.clone(),
+            link.to_forward,
+        )
+    }
+}
+
+impl<S: Ord> PartialEq for Edge<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<S: Ord> Eq for Edge<S> {}
+
+/// Hashes the canonical direction, `Edge<&str>` and `Edge<String>` hash the same
+impl<S: Ord + Hash> Hash for Edge<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl<S: Ord> PartialOrd for Edge<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: Ord> Ord for Edge<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_edge_equality() {
+        let edge = Edge::new("a", true, "b", false);
+        assert_eq!(edge, Edge::new("b", true, "a", false));
+        assert_eq!(edge, edge.reverse());
+        assert_ne!(edge, Edge::new("b", false, "a", true));
+        assert_ne!(edge, Edge::new("a", true, "b", true));
+        // Loops over both ends of a segment are their own reverse
+        let loop_edge = Edge::new("a", true, "a", false);
+        assert!(loop_edge.is_canonical() && loop_edge.reverse().is_canonical());
+        let set = HashSet::from([edge.into_owned(), edge.reverse().into_owned()]);
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(&Edge::from_line("L\tb\t+\ta\t-\t0M").unwrap().into_owned()));
+    }
+
+    #[test]
+    fn test_canonical() {
+        let edge = Edge::new("b", false, "a", false);
+        assert!(!edge.is_canonical());
+        let canonical = edge.canonical();
+        assert_eq!(
+            (
+                canonical.from,
+                canonical.from_forward,
+                canonical.to,
+                canonical.to_forward
+            ),
+            ("a", true, "b", true)
+        );
+        assert!(canonical.is_canonical());
+        assert_eq!(Edge::from_line("L\t1\t+\t2"), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Equivalence of graphs up to segment renaming and line order (`trim-graph equal`).
+//!
+//! Segments are identified by a hash of their sequence, refined a few times with the hashes of
+//! their neighbours, like in the Weisfeiler-Lehman test. Links, jumps, paths and walks are
+//! described by the hashes of the segments they connect or visit. Two graphs are equivalent if
+//! all of these agree as multisets, regardless of segment names, tags and line order. Graphs
+//! that differ only in how identical, symmetric parts are named are not told apart.
+
+use crate::edge::Edge;
+use crate::graph::{Graph, Link};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Rounds of refining the segment hashes with their neighbourhood
+const ROUNDS: usize = 3;
+
+fn hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashed content of a graph, all parts sorted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canonical {
+    pub segments: Vec<u64>,
+    pub links: Vec<u64>,
+    pub jumps: Vec<u64>,
+    /// Paths by name with their hashed steps
+    pub paths: Vec<(String, u64)>,
+    /// Walks by name and interval with their hashed steps
+    pub walks: Vec<(String, u64)>,
+}
+
+/// Hash of an edge, the same for both of its directions
+fn edge_hash(labels: &HashMap<&str, u64>, link: &Link) -> u64 {
+    hash(&Edge::new(
+        labels[link.from.as_str()],
+        link.from_forward,
+        labels[link.to.as_str()],
+        link.to_forward,
+    ))
+}
+
+fn sorted<T: Ord>(mut v: Vec<T>) -> Vec<T> {
+    v.sort_unstable();
+    v
+}
+
+fn steps_hash(labels: &HashMap<&str, u64>, steps: &[(String, bool)]) -> u64 {
+    hash(
+        &steps
+            .iter()
+            .map(|(node, forward)| (labels.get(node.as_str()).copied(), *forward))
+            .collect::<Vec<_>>(),
+    )
+}
+
+impl Canonical {
+    pub fn of(graph: &Graph) -> Self {
+        let mut labels = graph
+            .segments
+            .iter()
+            .map(|s| {
+                let sequence = s.sequence.as_ref().map(|s| s.to_ascii_uppercase());
+                (s.name.as_str(), hash(&sequence))
+            })
+            .collect::<HashMap<_, _>>();
+        for _ in 0..ROUNDS {
+            let mut neighbours = labels
+                .keys()
+                .map(|name| (*name, Vec::new()))
+                .collect::<HashMap<_, _>>();
+            for (kind, edges) in [(0u8, &graph.links), (1u8, &graph.jumps)] {
+                for link in edges {
+                    let (Some(from), Some(to)) =
+                        (labels.get(link.from.as_str()), labels.get(link.to.as_str()))
+                    else {
+                        continue;
+                    };
+                    // Sides: the right side of a forward segment is its end
+                    neighbours.entry(link.from.as_str()).or_default().push((
+                        kind,
+                        link.from_forward,
+                        *to,
+                        link.to_forward,
+                    ));
+                    neighbours.entry(link.to.as_str()).or_default().push((
+                        kind,
+                        !link.to_forward,
+                        *from,
+                        !link.from_forward,
+                    ));
+                }
+            }
+            labels = neighbours
+                .into_iter()
+                .map(|(name, mut adjacent)| {
+                    adjacent.sort_unstable();
+                    (name, hash(&(labels[name], adjacent)))
+                })
+                .collect();
+        }
+
+        let known = |link: &&Link| {
+            labels.contains_key(link.from.as_str()) && labels.contains_key(link.to.as_str())
+        };
+        Canonical {
+            segments: sorted(labels.values().copied().collect()),
+            links: sorted(
+                graph
+                    .links
+                    .iter()
+                    .filter(known)
+                    .map(|l| edge_hash(&labels, l))
+                    .collect(),
+            ),
+            jumps: sorted(
+                graph
+                    .jumps
+                    .iter()
+                    .filter(known)
+                    .map(|j| edge_hash(&labels, j))
+                    .collect(),
+            ),
+            paths: sorted(
+                graph
+                    .paths
+                    .iter()
+                    .map(|p| (p.name.clone(), steps_hash(&labels, &p.steps)))
+                    .collect(),
+            ),
+            walks: sorted(
+                graph
+                    .walks
+                    .iter()
+                    .map(|w| {
+                        let name = format!("{}:{}-{}", w.name(), w.seq_start, w.seq_end);
+                        (name, steps_hash(&labels, &w.steps))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Descriptions of the parts in which the graphs differ, empty if they are equivalent
+    pub fn differences(&self, other: &Canonical) -> Vec<String> {
+        let mut differences = Vec::new();
+        for (part, a, b) in [
+            ("segments", &self.segments, &other.segments),
+            ("links", &self.links, &other.links),
+            ("jumps", &self.jumps, &other.jumps),
+        ] {
+            if a != b {
+                differences.push(format!("{} differ ({} vs {})", part, a.len(), b.len()));
+            }
+        }
+        for (part, a, b) in [
+            ("Path", &self.paths, &other.paths),
+            ("Walk", &self.walks, &other.walks),
+        ] {
+            let b_steps = b.iter().cloned().collect::<HashMap<_, _>>();
+            let a_names = a.iter().map(|(name, _)| name).collect::<Vec<_>>();
+            for (name, steps) in a {
+                match b_steps.get(name) {
+                    None => {
+                        differences.push(format!("{} {} is only in the first graph", part, name))
+                    }
+                    Some(other) if other != steps => {
+                        differences.push(format!("{} {} visits other segments", part, name))
+                    }
+                    Some(_) => {}
+                }
+            }
+            for (name, _) in b {
+                if !a_names.contains(&name) {
+                    differences.push(format!("{} {} is only in the second graph", part, name));
+                }
+            }
+        }
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equivalence() {
+        let a = Graph::from_gfa(
+            "S\t1\tACG\nS\t2\tT\nS\t3\tGA\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t-\t0M\n\
+             P\tref\t1+,2+,3-\t*\nW\ts\t1\tchr1\t0\t4\t>1>2\n",
+        )
+        .unwrap();
+        // Renamed, reordered and with a link written in its other direction
+        let b = Graph::from_gfa(
+            "S\tc\tGA\nS\ta\tACG\nS\tb\tT\nL\tc\t+\tb\t-\t0M\nL\ta\t+\tb\t+\t0M\n\
+             W\ts\t1\tchr1\t0\t4\t>a>b\nP\tref\ta+,b+,c-\t*\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Canonical::of(&a).differences(&Canonical::of(&b)),
+            Vec::<String>::new()
+        );
+
+        let c = Graph::from_gfa(
+            "S\t1\tACG\nS\t2\tT\nS\t3\tGA\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\n\
+             P\tref\t1+,2+,3+\t*\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Canonical::of(&a).differences(&Canonical::of(&c)),
+            vec![
+                "segments differ (3 vs 3)",
+                "links differ (2 vs 2)",
+                "Path ref visits other segments",
+                "Walk s#1#chr1:0-4 is only in the first graph"
+            ]
+        );
+    }
+}
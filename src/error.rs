@@ -0,0 +1,97 @@
+//! Errors of trim-graph runs and the exit codes they map to.
+//!
+//! | Code | Meaning |
+//! |------|---------|
+//! | 0    | Success |
+//! | 1    | The graphs compared by `trim-graph equal` differ |
+//! | 2    | Invalid input: malformed graph, keep list or arguments |
+//! | 3    | Trimming removed nothing (only with `--fail-if-unchanged`) |
+//! | 4    | The selection matched no path or walk, `shortest-path` found no path or `between` no route |
+//! | 5    | I/O error, e.g. a missing file or a failed download |
+//! | 6    | Validation failure: an index or checkpoint does not match the graph, or `--byte-exact` output differs from the input |
+
+use std::fmt;
+use std::io;
+
+pub const EXIT_DIFFERENT: u8 = 1;
+pub const EXIT_INVALID_INPUT: u8 = 2;
+pub const EXIT_UNCHANGED: u8 = 3;
+pub const EXIT_NOTHING_SELECTED: u8 = 4;
+pub const EXIT_IO: u8 = 5;
+pub const EXIT_VALIDATION: u8 = 6;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidInput(String),
+    NothingSelected(String),
+    Io(io::Error),
+    Validation(String),
+    /// Trimming removed nothing and `--fail-if-unchanged` is set
+    Unchanged(String),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::InvalidInput(_) => EXIT_INVALID_INPUT,
+            Error::NothingSelected(_) => EXIT_NOTHING_SELECTED,
+            Error::Io(_) => EXIT_IO,
+            Error::Validation(_) => EXIT_VALIDATION,
+            Error::Unchanged(_) => EXIT_UNCHANGED,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput(message)
+            | Error::NothingSelected(message)
+            | Error::Validation(message)
+            | Error::Unchanged(message) => write!(f, "{}", message),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    /// Data that cannot be read (bad UTF-8, corrupt compression or index) is invalid input,
+    /// everything else an I/O error
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => {
+                Error::InvalidInput(e.to_string())
+            }
+            _ => Error::Io(e),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::InvalidInput(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let missing = io::Error::new(io::ErrorKind::NotFound, "graph.gfa");
+        assert_eq!(Error::from(missing).exit_code(), EXIT_IO);
+        let corrupt = io::Error::new(io::ErrorKind::InvalidData, "stream did not contain UTF-8");
+        assert_eq!(Error::from(corrupt).exit_code(), EXIT_INVALID_INPUT);
+        assert_eq!(
+            Error::from("Invalid path line".to_string()).exit_code(),
+            EXIT_INVALID_INPUT
+        );
+        assert_eq!(
+            Error::Unchanged("Trimming removed nothing".to_string()).exit_code(),
+            EXIT_UNCHANGED
+        );
+    }
+}
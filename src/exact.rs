@@ -0,0 +1,83 @@
+//! Byte-exact trimming (`--byte-exact`).
+//!
+//! Trimming writes the kept lines as they are, unless options like `--uppercase` or
+//! `--rename-paths` rewrite them. For users who treat trimming as a line filter, `--byte-exact`
+//! refuses these options and, before anything is written, looks up the checksum of every
+//! output line among the checksums of the input lines. The output is then guaranteed to
+//! consist of lines of the input only, in the grouping of [`Trimmed::write_gfa`].
+
+use crate::scan;
+use crate::trim::Trimmed;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+fn checksum(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Checksums of the lines of a graph, without their line terminator
+#[derive(Debug, Clone, Default)]
+pub struct LineChecksums {
+    checksums: HashSet<u64>,
+}
+
+impl LineChecksums {
+    pub fn new<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        LineChecksums {
+            checksums: lines.into_iter().map(checksum).collect(),
+        }
+    }
+
+    /// Checksums of all lines of the content. Fails on CRLF line endings, which are written as
+    /// LF.
+    pub fn of(content: &str) -> Result<Self, String> {
+        if content.contains("\r\n") {
+            return Err("--byte-exact cannot keep the CRLF line endings of the graph".to_string());
+        }
+        Ok(Self::new(scan::lines(content)))
+    }
+
+    pub fn contains(&self, line: &str) -> bool {
+        self.checksums.contains(&checksum(line))
+    }
+
+    /// Checks that every line to write is a line of the input, returns the number of lines
+    pub fn verify(&self, trimmed: &Trimmed) -> Result<usize, String> {
+        let mut verified = 0;
+        for line in trimmed.lines() {
+            if !self.contains(line) {
+                return Err(format!(
+                    "--byte-exact: output line {} is not a line of the input: {}",
+                    verified + 1,
+                    line
+                ));
+            }
+            verified += 1;
+        }
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_verify() {
+        let content = "H\tVN:Z:1.0\nS\t1\tacg\tRC:i:3\nS\t2\tT\nL\t1\t+\t2\t+\t0M\nP\tp\t1+\t*\n";
+        let checksums = LineChecksums::of(content).unwrap();
+        let lines = GfaLines::classify(content);
+        let mut trimmed = trim_gfa(lines, None, &TrimOptions::default()).unwrap();
+        assert_eq!(checksums.verify(&trimmed), Ok(3));
+        trimmed.segments[0] = "S\t1\tACG\tRC:i:3";
+        assert_eq!(
+            checksums.verify(&trimmed).unwrap_err(),
+            "--byte-exact: output line 2 is not a line of the input: S\t1\tACG\tRC:i:3"
+        );
+        assert!(LineChecksums::of("S\t1\tA\r\n").is_err());
+    }
+}
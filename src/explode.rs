@@ -0,0 +1,84 @@
+//! One graph per path or walk (`trim-graph explode-paths`).
+//!
+//! Tools that cannot handle graphs with several haplotypes get one small GFA per path or walk,
+//! holding only that path, the segments it visits and the links and jumps between its
+//! consecutive steps. Every graph is trimmed from the same classified lines, so the graph is
+//! read once however many paths it has.
+
+use crate::trim::{trim_gfa, walk_name, GfaLines, TrimOptions, Trimmed};
+use std::collections::HashSet;
+
+/// A path (P line) or walk (W line, by its PanSN name) of the graph
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PathName {
+    pub name: String,
+    pub is_walk: bool,
+}
+
+impl PathName {
+    /// File to write the graph of the path to, `/` is replaced so that it stays in the directory
+    pub fn file_name(&self) -> String {
+        format!("{}.gfa", self.name.replace('/', "_"))
+    }
+}
+
+/// The paths and then the walks of the graph, in input order
+pub fn path_names(lines: &GfaLines) -> Vec<PathName> {
+    let paths = lines.path_names().into_iter().map(|name| PathName {
+        name,
+        is_walk: false,
+    });
+    let walks = lines.walks.iter().map(|walk| PathName {
+        name: walk_name(walk),
+        is_walk: true,
+    });
+    paths.chain(walks).collect()
+}
+
+/// The graph of a single path or walk. Headers and other lines are kept, steps without edge in
+/// the input are only listed as missing edges.
+pub fn path_graph<'a>(lines: &GfaLines<'a>, path: &PathName) -> Result<Trimmed<'a>, String> {
+    let (paths, walks) = match path.is_walk {
+        false => (vec![path.name.clone()], HashSet::new()),
+        true => (Vec::new(), HashSet::from([path.name.clone()])),
+    };
+    let options = TrimOptions {
+        walks_to_keep: Some(walks),
+        ..Default::default()
+    };
+    trim_gfa(lines.clone(), Some(paths), &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_graph() {
+        let gfa = "H\tVN:Z:1.0\nS\t1\tA\nS\t2\tC\nS\t3\tG\nL\t1\t+\t2\t+\t0M\n\
+                   L\t1\t+\t3\t+\t0M\nL\t2\t+\t3\t+\t0M\nP\tx/1\t1+,3+\t*\n\
+                   W\ts\t0\tc\t0\t2\t>1>2\n";
+        let lines = GfaLines::classify(gfa);
+        let names = path_names(&lines);
+        assert_eq!(
+            names.iter().map(|p| p.file_name()).collect::<Vec<_>>(),
+            ["x_1.gfa", "s#0#c.gfa"]
+        );
+
+        let path = path_graph(&lines, &names[0]).unwrap();
+        assert_eq!(
+            path.lines().collect::<Vec<_>>(),
+            [
+                "H\tVN:Z:1.0",
+                "S\t1\tA",
+                "S\t3\tG",
+                "P\tx/1\t1+,3+\t*",
+                "L\t1\t+\t3\t+\t0M"
+            ]
+        );
+        let walk = path_graph(&lines, &names[1]).unwrap();
+        assert_eq!(walk.segments, ["S\t1\tA", "S\t2\tC"]);
+        assert_eq!((walk.paths.len(), walk.walks.len()), (0, 1));
+        assert_eq!(walk.links, ["L\t1\t+\t2\t+\t0M"]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Sequences stored outside of the graph (`--resolve-sequences`).
+//!
+//! S lines without sequence (`*`) may point to a FASTA file holding it with a `UR:Z:` tag, a
+//! local path (also as `file://` URI) or a URL. The sequence is taken from the record named like
+//! the segment, or from the only record of the file. A file that cannot be read, e.g. a URL
+//! while offline, leaves its segments without sequence with a warning. A file that lacks the
+//! record or whose sequence contradicts the segment's LN tag is invalid input.
+
+use std::collections::HashMap;
+use std::io;
+
+/// Segment lines with the external sequences filled in
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resolved {
+    pub segments: Vec<String>,
+    /// Number of segments whose sequence was filled in
+    pub resolved: usize,
+    /// Number of segments whose file could not be read
+    pub unavailable: usize,
+}
+
+/// Records of a FASTA file by name (up to the first whitespace), in file order
+pub(crate) fn parse_fasta(content: &str) -> Vec<(String, String)> {
+    let mut records = Vec::new();
+    for line in content.lines() {
+        match line.strip_prefix('>') {
+            Some(header) => {
+                let name = header.split_whitespace().next().unwrap_or("");
+                records.push((name.to_string(), String::new()));
+            }
+            None => {
+                if let Some((_, sequence)) = records.last_mut() {
+                    sequence.push_str(line.trim());
+                }
+            }
+        }
+    }
+    records
+}
+
+/// Fills in the sequences of the segments with `*` and a UR tag, reading the files with `read`
+pub fn resolve_sequences<F: Fn(&str) -> io::Result<String>>(
+    segments: &[&str],
+    read: F,
+) -> io::Result<Resolved> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut files = HashMap::<&str, Option<Vec<(String, String)>>>::new();
+    let mut resolved = Resolved::default();
+    for line in segments {
+        let uri = line
+            .split('\t')
+            .skip(3)
+            .find_map(|tag| tag.strip_prefix("UR:Z:"));
+        let mut fields = line.split('\t').collect::<Vec<_>>();
+        let (Some("*"), Some(uri)) = (fields.get(2).copied(), uri) else {
+            resolved.segments.push(line.to_string());
+            continue;
+        };
+        let records = files.entry(uri).or_insert_with(|| {
+            match read(uri.strip_prefix("file://").unwrap_or(uri)) {
+                Ok(content) => Some(parse_fasta(&content)),
+                Err(e) => {
+                    log::warn!(
+                        "Cannot read {}, keeping its segments without sequence: {}",
+                        uri,
+                        e
+                    );
+                    None
+                }
+            }
+        });
+        let Some(records) = records else {
+            resolved.unavailable += 1;
+            resolved.segments.push(line.to_string());
+            continue;
+        };
+        let name = fields[1];
+        let sequence = match records.iter().find(|(n, _)| n == name) {
+            Some((_, sequence)) => sequence,
+            None if records.len() == 1 => &records[0].1,
+            None => {
+                return Err(invalid(format!(
+                    "{} has no record for segment {}",
+                    uri, name
+                )))
+            }
+        };
+        let length = fields
+            .iter()
+            .find_map(|tag| tag.strip_prefix("LN:i:"))
+            .and_then(|length| length.parse::<usize>().ok());
+        if length.is_some_and(|length| length != sequence.len()) {
+            return Err(invalid(format!(
+                "Sequence of segment {} in {} has {} bp, its LN tag says {}",
+                name,
+                uri,
+                sequence.len(),
+                length.unwrap_or_default()
+            )));
+        }
+        fields[2] = sequence;
+        resolved.segments.push(fields.join("\t"));
+        resolved.resolved += 1;
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_sequences() {
+        let read = |path: &str| match path {
+            "/data/seqs.fa" => Ok(">1 first\nAC\nGT\n>2\nGG\n".to_string()),
+            "single.fa" => Ok(">chr1\nTTT\n".to_string()),
+            _ => Err(io::Error::new(io::ErrorKind::NotFound, path)),
+        };
+        let segments = [
+            "S\t1\t*\tLN:i:4\tUR:Z:file:///data/seqs.fa",
+            "S\t2\t*\tUR:Z:/data/seqs.fa",
+            "S\t3\t*\tUR:Z:single.fa",
+            "S\t4\t*\tUR:Z:https://example.org/4.fa",
+            "S\t5\tCA",
+        ];
+        let resolved = resolve_sequences(&segments, read).unwrap();
+        assert_eq!(
+            resolved.segments,
+            vec![
+                "S\t1\tACGT\tLN:i:4\tUR:Z:file:///data/seqs.fa",
+                "S\t2\tGG\tUR:Z:/data/seqs.fa",
+                "S\t3\tTTT\tUR:Z:single.fa",
+                "S\t4\t*\tUR:Z:https://example.org/4.fa",
+                "S\t5\tCA"
+            ]
+        );
+        assert_eq!((resolved.resolved, resolved.unavailable), (3, 1));
+        assert!(resolve_sequences(&["S\t1\t*\tLN:i:5\tUR:Z:single.fa"], read).is_err());
+        assert!(resolve_sequences(&["S\t7\t*\tUR:Z:/data/seqs.fa"], read).is_err());
+    }
+}
@@ -0,0 +1,716 @@
+//! Extraction of the subgraph around an interval of a reference path or walk.
+//!
+//! The segments overlapping the interval on the reference are kept together with the parts
+//! of all selected paths/walks passing through them. A path may leave the region between two
+//! visits (e.g. through an insertion) for at most as many bp as the region is long, but at
+//! least [`MIN_MAX_DETOUR`] bp, otherwise the visits become separate fragments.
+//!
+//! Path fragments are named `name:start-end` after the interval of the original path they
+//! cover, walk fragments get the interval as their start and end columns. A path that is
+//! itself a fragment (its name ends in `:start-end`) keeps the coordinates of the original
+//! path, so fragments of repeated extractions can still be stitched together.
+//!
+//! [`Extractor::extract_exact`] splits the segments at the ends of the region at the
+//! boundaries instead, so the reference fragment starts and ends exactly at the requested bases.
+//! Pieces of rGFA segments keep their stable sequence and rank, with the offset moved to the
+//! start of the piece.
+
+use crate::graph::{split_pansn, Graph, Link, Path, Segment, Stable, Walk};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+
+/// Detours shorter than this are always kept, regardless of the length of the region
+pub const MIN_MAX_DETOUR: usize = 100_000;
+
+/// Interval on a path or walk, 0-based and half-open
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub path: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    /// Parses `name:start-end`, the name may itself contain ':'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid region {}, expected name:start-end", s);
+        let (path, interval) = s.rsplit_once(':').ok_or_else(invalid)?;
+        let (start, end) = interval.split_once('-').ok_or_else(invalid)?;
+        let start = start.parse::<usize>().map_err(|_| invalid())?;
+        let end = end.parse::<usize>().map_err(|_| invalid())?;
+        if path.is_empty() || start >= end {
+            return Err(invalid());
+        }
+        Ok(Region {
+            path: path.to_string(),
+            start,
+            end,
+        })
+    }
+}
+
+/// Name of the original path and the start of the fragment on it, paths that are no
+/// fragments start at 0. Any name ending in `:start-end` with `start < end` is taken for a
+/// fragment, also a path that was named like this in the input (e.g. `chr1:100-200` of an
+/// assembly), its coordinates then count from `start`. Names like `chr1:200-100` are no
+/// fragments.
+pub(crate) fn split_fragment(name: &str) -> (&str, usize) {
+    match name.parse::<Region>() {
+        Ok(region) => (&name[..region.path.len()], region.start),
+        Err(_) => (name, 0),
+    }
+}
+
+/// A path or walk of the graph
+#[derive(Clone, Copy)]
+enum Sequence<'a> {
+    Path(&'a Path),
+    Walk(&'a Walk),
+}
+
+impl<'a> Sequence<'a> {
+    fn name(&self) -> String {
+        match self {
+            Sequence::Path(p) => p.name.clone(),
+            Sequence::Walk(w) => w.name(),
+        }
+    }
+
+    fn sample(&self) -> &'a str {
+        match self {
+            Sequence::Path(p) => split_pansn(&p.name).map_or(p.name.as_str(), |(s, _, _)| s),
+            Sequence::Walk(w) => &w.sample,
+        }
+    }
+
+    fn contig(&self) -> &'a str {
+        match self {
+            Sequence::Path(p) => split_pansn(&p.name).map_or(p.name.as_str(), |(_, _, c)| c),
+            Sequence::Walk(w) => &w.seq_id,
+        }
+    }
+
+    fn steps(&self) -> &'a [(String, bool)] {
+        match self {
+            Sequence::Path(p) => &p.steps,
+            Sequence::Walk(w) => &w.steps,
+        }
+    }
+
+    /// Coordinate of the first base, walks and path fragments may start at an offset
+    fn base(&self) -> usize {
+        match self {
+            Sequence::Path(p) => split_fragment(&p.name).1,
+            Sequence::Walk(w) => w.seq_start.parse().unwrap_or(0),
+        }
+    }
+}
+
+/// Oriented segment as the reference visits it
+pub type Anchor<'a> = (&'a str, bool);
+
+/// Graph loaded once and queried for multiple regions
+pub struct Extractor {
+    graph: Graph,
+    lengths: HashMap<String, usize>,
+    /// First numeric id not used by a segment, for the pieces of split segments
+    next_id: u64,
+}
+
+impl Extractor {
+    pub fn new(graph: Graph) -> Self {
+        let lengths = graph
+            .segments
+            .iter()
+            .map(|s| (s.name.clone(), s.length))
+            .collect();
+        let next_id = graph
+            .segments
+            .iter()
+            .filter_map(|s| s.name.parse::<u64>().ok())
+            .max()
+            .map_or(1, |id| id + 1);
+        Extractor {
+            graph,
+            lengths,
+            next_id,
+        }
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    fn sequences(&self) -> impl Iterator<Item = Sequence<'_>> {
+        self.graph
+            .paths
+            .iter()
+            .map(Sequence::Path)
+            .chain(self.graph.walks.iter().map(Sequence::Walk))
+    }
+
+    /// Names of all paths and walks (walks in PanSN form)
+    pub fn path_names(&self) -> Vec<String> {
+        self.sequences().map(|s| s.name()).collect()
+    }
+
+    /// Path or walk with the given name, or the single one on the given contig
+    fn reference(&self, name: &str) -> Result<Sequence<'_>, String> {
+        if let Some(sequence) = self.sequences().find(|s| s.name() == name) {
+            return Ok(sequence);
+        }
+        let candidates = self
+            .sequences()
+            .filter(|s| s.contig() == name)
+            .collect::<Vec<_>>();
+        match candidates.len() {
+            0 => Err(format!("No path or walk named {}", name)),
+            1 => Ok(candidates[0]),
+            n => Err(format!(
+                "{} paths/walks are on contig {}, use the full name",
+                n, name
+            )),
+        }
+    }
+
+    fn length(&self, node: &str) -> usize {
+        self.lengths.get(node).copied().unwrap_or(0)
+    }
+
+    /// Offsets of all steps relative to the start of the path/walk
+    fn offsets(&self, steps: &[(String, bool)]) -> Vec<usize> {
+        let mut offset = 0;
+        steps
+            .iter()
+            .map(|(node, _)| {
+                let current = offset;
+                offset += self.length(node);
+                current
+            })
+            .collect()
+    }
+
+    /// Ranges of steps (inclusive) visiting the region nodes, merged if the detour between
+    /// two visits is at most `max_detour` bp
+    fn fragments(
+        &self,
+        steps: &[(String, bool)],
+        nodes: &HashSet<&str>,
+        max_detour: usize,
+    ) -> Vec<(usize, usize)> {
+        let mut fragments: Vec<(usize, usize)> = Vec::new();
+        let mut detour = 0;
+        for (i, (node, _)) in steps.iter().enumerate() {
+            if !nodes.contains(node.as_str()) {
+                detour += self.length(node);
+                continue;
+            }
+            match fragments.last_mut() {
+                Some((_, end)) if detour <= max_detour => *end = i,
+                _ => fragments.push((i, i)),
+            }
+            detour = 0;
+        }
+        fragments
+    }
+
+    /// Subgraph around the region, containing the fragments of the reference and of the
+    /// paths/walks of the given samples (all samples if `None`)
+    pub fn extract(
+        &self,
+        region: &Region,
+        samples: Option<&HashSet<String>>,
+    ) -> Result<Graph, String> {
+        let reference = self.reference(&region.path)?;
+        let reference_name = reference.name();
+        let base = reference.base();
+        let steps = reference.steps();
+        let region_nodes = steps
+            .iter()
+            .zip(self.offsets(steps))
+            .filter(|((node, _), offset)| {
+                let start = base + offset;
+                start < region.end && start + self.length(node) > region.start
+            })
+            .map(|((node, _), _)| node.as_str())
+            .collect::<HashSet<_>>();
+        let max_detour = (region.end - region.start).max(MIN_MAX_DETOUR);
+        Ok(self.subgraph(&reference_name, &region_nodes, max_detour, samples))
+    }
+
+    /// Steps of the reference holding the first and the last base of the region, with the bp
+    /// of the reference between them
+    pub fn anchors(&self, region: &Region) -> Result<(Anchor<'_>, Anchor<'_>, usize), String> {
+        let reference = self.reference(&region.path)?;
+        let base = reference.base();
+        let steps = reference.steps();
+        let offsets = self.offsets(steps);
+        let holding = |position: usize| {
+            steps
+                .iter()
+                .zip(&offsets)
+                .position(|((node, _), offset)| {
+                    base + offset <= position && position < base + offset + self.length(node)
+                })
+                .ok_or_else(|| format!("{} is not on {}", position, reference.name()))
+        };
+        let first = holding(region.start)?;
+        let last = holding(region.end - 1)?;
+        if last < first {
+            return Err(format!(
+                "{} visits the end of the region first",
+                reference.name()
+            ));
+        }
+        let anchor = |i: usize| (steps[i].0.as_str(), steps[i].1);
+        let between = offsets[last].saturating_sub(offsets[first] + self.length(&steps[first].0));
+        Ok((anchor(first), anchor(last), between))
+    }
+
+    /// Subgraph of the given segments (e.g. of [`crate::between::nodes_between`]) with the
+    /// fragments of the reference and of the paths/walks of the given samples through them
+    pub fn extract_nodes(
+        &self,
+        region: &Region,
+        nodes: &HashSet<&str>,
+        samples: Option<&HashSet<String>>,
+    ) -> Result<Graph, String> {
+        let reference_name = self.reference(&region.path)?.name();
+        Ok(self.subgraph(&reference_name, nodes, 0, samples))
+    }
+
+    /// Subgraph of the given segments with the fragments of all paths/walks through them
+    pub fn extract_fragments(&self, nodes: &HashSet<&str>) -> Graph {
+        self.subgraph("", nodes, 0, None)
+    }
+
+    /// Subgraph of the fragments of the reference and of the paths/walks of the given samples
+    /// visiting `region_nodes`, with detours of at most `max_detour` bp
+    fn subgraph(
+        &self,
+        reference_name: &str,
+        region_nodes: &HashSet<&str>,
+        max_detour: usize,
+        samples: Option<&HashSet<String>>,
+    ) -> Graph {
+        let mut nodes = region_nodes.clone();
+        let mut paths = Vec::new();
+        let mut walks = Vec::new();
+        for sequence in self.sequences() {
+            let name = sequence.name();
+            if name != reference_name && samples.is_some_and(|s| !s.contains(sequence.sample())) {
+                continue;
+            }
+            let steps = sequence.steps();
+            let offsets = self.offsets(steps);
+            let fragments = self.fragments(steps, region_nodes, max_detour);
+            for (first, last) in fragments {
+                nodes.extend(steps[first..=last].iter().map(|(node, _)| node.as_str()));
+                let fragment = steps[first..=last].to_vec();
+                let start = offsets[first];
+                let end = offsets[last] + self.length(&steps[last].0);
+                match sequence {
+                    Sequence::Path(path) => {
+                        let (original, base) = split_fragment(&path.name);
+                        paths.push(Path {
+                            name: format!("{}:{}-{}", original, base + start, base + end),
+                            steps: fragment,
+                        });
+                    }
+                    Sequence::Walk(walk) => {
+                        let (seq_start, seq_end) = match walk.seq_start.parse::<usize>() {
+                            Ok(base) => ((base + start).to_string(), (base + end).to_string()),
+                            Err(_) => (walk.seq_start.clone(), walk.seq_end.clone()),
+                        };
+                        walks.push(Walk {
+                            seq_start,
+                            seq_end,
+                            steps: fragment,
+                            ..walk.clone()
+                        });
+                    }
+                }
+            }
+        }
+
+        let keep_link =
+            |l: &&Link| nodes.contains(l.from.as_str()) && nodes.contains(l.to.as_str());
+        Graph {
+            segments: self
+                .graph
+                .segments
+                .iter()
+                .filter(|s| nodes.contains(s.name.as_str()))
+                .cloned()
+                .collect(),
+            links: self.graph.links.iter().filter(keep_link).cloned().collect(),
+            jumps: self.graph.jumps.iter().filter(keep_link).cloned().collect(),
+            paths,
+            walks,
+        }
+    }
+
+    /// Like [`Extractor::extract`], but segments reaching over the ends of the region on the
+    /// reference are split there into pieces with new numeric ids. Pieces outside the region
+    /// are cut from the ends of all fragments and only kept where a fragment passes through
+    /// them.
+    pub fn extract_exact(
+        &self,
+        region: &Region,
+        samples: Option<&HashSet<String>>,
+    ) -> Result<Graph, String> {
+        let graph = self.extract(region, samples)?;
+        let reference = self.reference(&region.path)?;
+        let base = reference.base();
+        let steps = reference.steps();
+        let offsets = self.offsets(steps);
+
+        // Cut positions within each boundary segment, in its forward orientation
+        let mut cuts = HashMap::<&str, BTreeSet<usize>>::new();
+        for ((node, forward), offset) in steps.iter().zip(&offsets) {
+            let start = base + offset;
+            let length = self.length(node);
+            for cut in [region.start, region.end] {
+                if start < cut && cut < start + length {
+                    let cut = if *forward {
+                        cut - start
+                    } else {
+                        start + length - cut
+                    };
+                    cuts.entry(node.as_str()).or_default().insert(cut);
+                }
+            }
+        }
+        if cuts.is_empty() {
+            return Ok(graph);
+        }
+
+        // Pieces (name, start, end) of every split segment, in forward orientation
+        let mut next_id = self.next_id;
+        let mut pieces = HashMap::<&str, Vec<(String, usize, usize)>>::new();
+        let mut names = cuts.keys().copied().collect::<Vec<_>>();
+        names.sort_unstable();
+        for node in names {
+            let mut bounds = vec![0];
+            bounds.extend(&cuts[node]);
+            bounds.push(self.length(node));
+            let split = bounds
+                .windows(2)
+                .map(|w| {
+                    next_id += 1;
+                    ((next_id - 1).to_string(), w[0], w[1])
+                })
+                .collect();
+            pieces.insert(node, split);
+        }
+
+        // Pieces overlapping the region on the reference
+        let mut inside = HashSet::new();
+        for ((node, forward), offset) in steps.iter().zip(&offsets) {
+            let Some(split) = pieces.get(node.as_str()) else {
+                continue;
+            };
+            let start = base + offset;
+            let length = self.length(node);
+            for (name, from, to) in split {
+                let (from, to) = if *forward {
+                    (start + from, start + to)
+                } else {
+                    (start + length - to, start + length - from)
+                };
+                if from < region.end && to > region.start {
+                    inside.insert(name.as_str());
+                }
+            }
+        }
+        let piece_lengths = pieces
+            .values()
+            .flatten()
+            .map(|(name, from, to)| (name.as_str(), to - from))
+            .collect::<HashMap<_, _>>();
+
+        // Replaces split segments by their pieces and cuts outside pieces from the ends,
+        // returns the number of bp cut from the start and from the end
+        let split_steps = |steps: &[(String, bool)]| {
+            let mut split = Vec::new();
+            for (node, forward) in steps {
+                match pieces.get(node.as_str()) {
+                    Some(p) if *forward => {
+                        split.extend(p.iter().map(|(n, _, _)| (n.clone(), true)))
+                    }
+                    Some(p) => split.extend(p.iter().rev().map(|(n, _, _)| (n.clone(), false))),
+                    None => split.push((node.clone(), *forward)),
+                }
+            }
+            let outside = |(node, _): &(String, bool)| {
+                piece_lengths
+                    .get(node.as_str())
+                    .filter(|_| !inside.contains(node.as_str()))
+                    .copied()
+            };
+            let first = split.iter().position(|s| outside(s).is_none());
+            let last = split.iter().rposition(|s| outside(s).is_none());
+            let (Some(first), Some(last)) = (first, last) else {
+                return (Vec::new(), 0, 0);
+            };
+            let cut_start = split[..first].iter().filter_map(outside).sum::<usize>();
+            let cut_end = split[last + 1..].iter().filter_map(outside).sum::<usize>();
+            (split[first..=last].to_vec(), cut_start, cut_end)
+        };
+
+        let mut paths = Vec::new();
+        for path in graph.paths {
+            let (steps, cut_start, cut_end) = split_steps(&path.steps);
+            if steps.is_empty() {
+                continue;
+            }
+            let name = match path.name.parse::<Region>() {
+                Ok(r) => format!("{}:{}-{}", r.path, r.start + cut_start, r.end - cut_end),
+                Err(_) => path.name,
+            };
+            paths.push(Path { name, steps });
+        }
+        let mut walks = Vec::new();
+        for walk in graph.walks {
+            let (steps, cut_start, cut_end) = split_steps(&walk.steps);
+            if steps.is_empty() {
+                continue;
+            }
+            let (seq_start, seq_end) = match (
+                walk.seq_start.parse::<usize>(),
+                walk.seq_end.parse::<usize>(),
+            ) {
+                (Ok(start), Ok(end)) => {
+                    ((start + cut_start).to_string(), (end - cut_end).to_string())
+                }
+                _ => (walk.seq_start.clone(), walk.seq_end.clone()),
+            };
+            walks.push(Walk {
+                seq_start,
+                seq_end,
+                steps,
+                ..walk
+            });
+        }
+
+        let used = paths
+            .iter()
+            .flat_map(|p| &p.steps)
+            .chain(walks.iter().flat_map(|w| &w.steps))
+            .map(|(node, _)| node.as_str())
+            .collect::<HashSet<_>>();
+        let mut segments = Vec::new();
+        for segment in graph.segments {
+            let Some(split) = pieces.get(segment.name.as_str()) else {
+                segments.push(segment);
+                continue;
+            };
+            for (name, from, to) in split.iter().filter(|(n, _, _)| used.contains(n.as_str())) {
+                let sequence = segment.sequence.as_deref().map(|sequence| match sequence {
+                    "*" => "*".to_string(),
+                    sequence => sequence[*from..*to].to_string(),
+                });
+                // Segments of rGFA graphs are forward on their stable sequence
+                let stable = segment.stable.as_ref().map(|stable| Stable {
+                    offset: stable.offset + from,
+                    ..stable.clone()
+                });
+                segments.push(Segment {
+                    name: name.clone(),
+                    sequence,
+                    length: to - from,
+                    stable,
+                    tags: String::new(),
+                });
+            }
+        }
+
+        // Edges at the start of a split segment attach to its first piece, at the end to its
+        // last one, consecutive pieces are linked
+        let kept = segments
+            .iter()
+            .map(|s| s.name.clone())
+            .collect::<HashSet<_>>();
+        let end_piece = |node: &str, at_end: bool| match pieces.get(node) {
+            Some(split) if at_end => split[split.len() - 1].0.clone(),
+            Some(split) => split[0].0.clone(),
+            None => node.to_string(),
+        };
+        let reattach = |edges: Vec<Link>| {
+            edges
+                .into_iter()
+                .map(|l| Link {
+                    from: end_piece(&l.from, l.from_forward),
+                    to: end_piece(&l.to, !l.to_forward),
+                    ..l
+                })
+                .filter(|l| kept.contains(&l.from) && kept.contains(&l.to))
+                .collect::<Vec<_>>()
+        };
+        let mut links = reattach(graph.links);
+        links.extend(
+            pieces
+                .values()
+                .flat_map(|split| split.windows(2))
+                .filter(|w| kept.contains(&w[0].0) && kept.contains(&w[1].0))
+                .map(|w| Link::new(w[0].0.clone(), true, w[1].0.clone(), true)),
+        );
+        Ok(Graph {
+            segments,
+            links,
+            jumps: reattach(graph.jumps),
+            paths,
+            walks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Extractor {
+        // ref: 1 (0-4) 2 (4-6) 4 (6-10) 5 (10-14), HG002 takes the insertion 3 instead of 2
+        Extractor::new(
+            Graph::from_gfa(
+                "S\t1\tAAAA\nS\t2\tCC\nS\t3\tGGGGGG\nS\t4\tTTTT\nS\t5\tAAAA\n\
+             L\t1\t+\t2\t+\t0M\nL\t2\t+\t4\t+\t0M\nL\t1\t+\t3\t+\t0M\nL\t3\t+\t4\t+\t0M\n\
+             L\t4\t+\t5\t+\t0M\n\
+             P\tGRCh38#0#chr1\t1+,2+,4+,5+\t*\n\
+             W\tHG002\t1\tchr1\t100\t118\t>1>3>4>5\n\
+             W\tHG003\t1\tchr1\t0\t14\t>1>2>4>5\n",
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(
+            "GRCh38#0#chr1:10-20".parse::<Region>(),
+            Ok(Region {
+                path: "GRCh38#0#chr1".to_string(),
+                start: 10,
+                end: 20
+            })
+        );
+        assert!("chr1:20-10".parse::<Region>().is_err());
+        assert!("chr1".parse::<Region>().is_err());
+        assert_eq!(split_fragment("chr1:10-20"), ("chr1", 10));
+        assert_eq!(split_fragment("chr1:20-10"), ("chr1:20-10", 0));
+        assert_eq!(split_fragment("chr1:10-10"), ("chr1:10-10", 0));
+    }
+
+    #[test]
+    fn test_extract() {
+        let extractor = example();
+        let region = "GRCh38#0#chr1:3-7".parse::<Region>().unwrap();
+        let samples = HashSet::from(["HG002".to_string()]);
+        let graph = extractor.extract(&region, Some(&samples)).unwrap();
+        let names = graph
+            .segments
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["1", "2", "3", "4"]);
+        assert_eq!(graph.links.len(), 4);
+        assert_eq!(graph.paths[0].steps.len(), 3);
+        assert_eq!(graph.paths[0].name, "GRCh38#0#chr1:0-10");
+        assert_eq!(graph.walks.len(), 1);
+        assert_eq!(graph.walks[0].seq_start, "100");
+        assert_eq!(graph.walks[0].seq_end, "114");
+    }
+
+    #[test]
+    fn test_extract_fragment() {
+        let extractor = example();
+        let region = "GRCh38#0#chr1:5-12".parse::<Region>().unwrap();
+        let graph = extractor.extract(&region, None).unwrap();
+        assert_eq!(graph.paths[0].name, "GRCh38#0#chr1:4-14");
+
+        // Extracting again from the fragment keeps the coordinates of the original path
+        let extractor = Extractor::new(graph);
+        let region = "GRCh38#0#chr1:4-14:10-12".parse::<Region>().unwrap();
+        let graph = extractor.extract(&region, None).unwrap();
+        assert_eq!(graph.paths[0].name, "GRCh38#0#chr1:10-14");
+    }
+
+    #[test]
+    fn test_extract_exact() {
+        let extractor = example();
+        let region = "GRCh38#0#chr1:3-7".parse::<Region>().unwrap();
+        let graph = extractor.extract_exact(&region, None).unwrap();
+        let segments = graph
+            .segments
+            .iter()
+            .map(|s| (s.name.as_str(), s.loaded_sequence().unwrap()))
+            .collect::<Vec<_>>();
+        // 1 is split into 6 (AAA) and 7 (A), 4 into 8 (T) and 9 (TTT)
+        assert_eq!(
+            segments,
+            vec![("7", "A"), ("2", "CC"), ("3", "GGGGGG"), ("8", "T")]
+        );
+        assert_eq!(graph.paths[0].name, "GRCh38#0#chr1:3-7");
+        assert_eq!(
+            crate::graph::format_path_steps(&graph.paths[0].steps),
+            "7+,2+,8+"
+        );
+        assert_eq!(graph.walks[0].seq_start, "103");
+        assert_eq!(graph.walks[0].seq_end, "111");
+        assert_eq!(graph.walks[1].seq_end, "7");
+        assert_eq!(graph.links.len(), 4);
+        assert!(graph.links.iter().all(|l| l.from != "1" && l.to != "4"));
+
+        let extractor = Extractor::new(
+            Graph::from_gfa("S\t1\tACGT\tSN:Z:chr1\tSO:i:10\tSR:i:0\nP\tchr1\t1+\t*\n").unwrap(),
+        );
+        let region = "chr1:1-3".parse::<Region>().unwrap();
+        let graph = extractor.extract_exact(&region, None).unwrap();
+        assert_eq!(graph.segments.len(), 1);
+        assert_eq!(graph.segments[0].loaded_sequence(), Ok("CG"));
+        assert_eq!(graph.segments[0].stable.as_ref().unwrap().offset, 11);
+        assert_eq!(graph.paths[0].name, "chr1:1-3");
+    }
+
+    #[test]
+    fn test_anchors() {
+        let extractor = example();
+        let region = "GRCh38#0#chr1:3-11".parse::<Region>().unwrap();
+        assert_eq!(
+            extractor.anchors(&region),
+            Ok((("1", true), ("5", true), 6))
+        );
+        let region = "GRCh38#0#chr1:4-6".parse::<Region>().unwrap();
+        assert_eq!(
+            extractor.anchors(&region),
+            Ok((("2", true), ("2", true), 0))
+        );
+        let nodes = HashSet::from(["1", "3", "4"]);
+        let graph = extractor.extract_nodes(&region, &nodes, None).unwrap();
+        assert_eq!(graph.paths[0].name, "GRCh38#0#chr1:0-4");
+        assert_eq!(graph.walks[0].steps.len(), 3);
+        assert!("GRCh38#0#chr1:12-20"
+            .parse::<Region>()
+            .is_ok_and(|r| extractor.anchors(&r).is_err()));
+    }
+
+    #[test]
+    fn test_reference_by_contig() {
+        let extractor = example();
+        assert!(extractor.reference("chr1").is_err());
+        assert!(extractor.reference("HG002#1#chr1").is_ok());
+        assert!(extractor.reference("chr2").is_err());
+    }
+
+    #[test]
+    fn test_fragments() {
+        let extractor = example();
+        let steps = ["1", "3", "1", "3", "3", "1"]
+            .map(|node| (node.to_string(), true))
+            .to_vec();
+        let nodes = HashSet::from(["1"]);
+        assert_eq!(extractor.fragments(&steps, &nodes, 6), vec![(0, 2), (5, 5)]);
+    }
+}
@@ -0,0 +1,205 @@
+//! C API for calling trim-graph in-process, see `include/trim_graph.h`.
+//!
+//! All functions return 0 on success and -1 on failure (e.g. null or non-UTF-8 arguments,
+//! unreadable files), failures are reported through the `log` crate. Panics do not unwind into
+//! the caller, they are failures as well.
+
+use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+use std::ffi::{c_char, c_int, CStr};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Opened graph together with the selection to apply
+pub struct TrimGraph {
+    content: String,
+    paths_to_keep: Option<Vec<String>>,
+    options: TrimOptions,
+}
+
+/// Runs `f`, returning `failed` if it panics
+fn guard<T>(failed: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        log::error!("trim-graph panicked");
+        failed
+    })
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Reads the GFA file at `path`, returns null if it cannot be read.
+///
+/// # Safety
+/// `path` must be null or a valid null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn trim_graph_open(path: *const c_char) -> *mut TrimGraph {
+    guard(std::ptr::null_mut(), || {
+        let Some(path) = to_str(path) else {
+            return std::ptr::null_mut();
+        };
+        match fs::read_to_string(path) {
+            Ok(content) => Box::into_raw(Box::new(TrimGraph {
+                content,
+                paths_to_keep: None,
+                options: TrimOptions::default(),
+            })),
+            Err(e) => {
+                log::error!("Could not read {}: {}", path, e);
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Sets the paths to keep, replacing any previous selection. Passing a null `names` resets
+/// the selection to keeping all paths.
+///
+/// # Safety
+/// `graph` must come from `trim_graph_open`, `names` must be null or point to `count` valid
+/// null-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn trim_graph_set_selection(
+    graph: *mut TrimGraph,
+    names: *const *const c_char,
+    count: usize,
+) -> c_int {
+    guard(-1, || {
+        let Some(graph) = graph.as_mut() else {
+            return -1;
+        };
+        if names.is_null() {
+            graph.paths_to_keep = None;
+            return 0;
+        }
+        let names = std::slice::from_raw_parts(names, count)
+            .iter()
+            .map(|name| to_str(*name).map(|s| s.to_string()))
+            .collect::<Option<Vec<_>>>();
+        match names {
+            Some(names) => {
+                graph.paths_to_keep = Some(names);
+                0
+            }
+            None => -1,
+        }
+    })
+}
+
+/// Sets which record types are left untouched (non-zero means ignore), like -S/-L/-J
+///
+/// # Safety
+/// `graph` must come from `trim_graph_open`.
+#[no_mangle]
+pub unsafe extern "C" fn trim_graph_set_options(
+    graph: *mut TrimGraph,
+    ignore_segments: c_int,
+    ignore_links: c_int,
+    ignore_jumps: c_int,
+) -> c_int {
+    guard(-1, || {
+        let Some(graph) = graph.as_mut() else {
+            return -1;
+        };
+        graph.options.ignore_segments = ignore_segments != 0;
+        graph.options.ignore_links = ignore_links != 0;
+        graph.options.ignore_jumps = ignore_jumps != 0;
+        0
+    })
+}
+
+/// Trims the graph with the current selection and writes it as GFA to `out_path`
+///
+/// # Safety
+/// `graph` must come from `trim_graph_open`, `out_path` must be a valid null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn trim_graph_trim_to_file(
+    graph: *const TrimGraph,
+    out_path: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        let (Some(graph), Some(out_path)) = (graph.as_ref(), to_str(out_path)) else {
+            return -1;
+        };
+        let trimmed = match trim_gfa(
+            GfaLines::classify(&graph.content),
+            graph.paths_to_keep.clone(),
+            &graph.options,
+        ) {
+            Ok(trimmed) => trimmed,
+            Err(e) => {
+                log::error!("Could not trim the graph: {}", e);
+                return -1;
+            }
+        };
+        let result = fs::File::create(out_path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            trimmed.write_gfa(&mut out)?;
+            out.flush()
+        });
+        match result {
+            Ok(()) => 0,
+            Err(e) => {
+                log::error!("Could not write {}: {}", out_path, e);
+                -1
+            }
+        }
+    })
+}
+
+/// Frees a graph returned by `trim_graph_open`, null is ignored
+///
+/// # Safety
+/// `graph` must come from `trim_graph_open` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn trim_graph_free(graph: *mut TrimGraph) {
+    guard((), || {
+        if !graph.is_null() {
+            drop(Box::from_raw(graph));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_c_api() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("trim_graph_ffi_{}.gfa", std::process::id()));
+        let output = dir.join(format!("trim_graph_ffi_{}.out.gfa", std::process::id()));
+        fs::write(
+            &input,
+            "S\t1\tA\nS\t2\tC\nS\t3\tG\nL\t1\t+\t2\t+\t0M\nL\t1\t+\t3\t+\t0M\n\
+             P\tp1\t1+,2+\t*\nP\tp2\t1+,3+\t*\n",
+        )
+        .unwrap();
+        let input_c = CString::new(input.to_str().unwrap()).unwrap();
+        let output_c = CString::new(output.to_str().unwrap()).unwrap();
+        let p2 = CString::new("p2").unwrap();
+        unsafe {
+            let graph = trim_graph_open(input_c.as_ptr());
+            assert!(!graph.is_null());
+            assert_eq!(
+                trim_graph_set_selection(graph, [p2.as_ptr()].as_ptr(), 1),
+                0
+            );
+            assert_eq!(trim_graph_trim_to_file(graph, output_c.as_ptr()), 0);
+            trim_graph_free(graph);
+            assert!(trim_graph_open(std::ptr::null()).is_null());
+        }
+        assert_eq!(guard(-1, || panic!("in the closure")), -1);
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "S\t1\tA\nS\t3\tG\nP\tp2\t1+,3+\t*\nL\t1\t+\t3\t+\t0M\n"
+        );
+        fs::remove_file(input).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+}
@@ -0,0 +1,304 @@
+//! Custom trimming criteria for library users, composed into a [`Pipeline`].
+//!
+//! A [`Filter`] decides about single segments, links and jumps, and paths and walks, e.g.
+//! segments with a tag or paths listed in a database; closures can be added directly with
+//! [`Pipeline::nodes`], [`Pipeline::edges`] and [`Pipeline::paths`]. A record is kept if every
+//! filter keeps it. Before trimming, the paths and walks the filters reject are deselected,
+//! together with those visiting a rejected segment or traversing a rejected edge, so that the
+//! kept paths stay contiguous. The selection is then trimmed as usual, which drops what only
+//! the deselected paths covered, and rejected records kept otherwise (e.g. with
+//! [`TrimOptions::ignore_segments`]) are removed afterwards.
+
+use crate::edge::Edge;
+use crate::graph::{tokenize_path, tokenize_walk};
+use crate::scan;
+use crate::trim::{trim_gfa, walk_name, GfaLines, TrimOptions, Trimmed};
+use std::collections::HashSet;
+
+/// Criterion for the records to keep, every record is kept unless a method says otherwise
+pub trait Filter {
+    /// Whether to keep the segment of the S line
+    fn keep_segment(&self, _line: &str) -> bool {
+        true
+    }
+
+    /// Whether to keep the edge of the L or J line
+    fn keep_edge(&self, _line: &str) -> bool {
+        true
+    }
+
+    /// Whether to keep the path or walk, by its name (PanSN for walks)
+    fn keep_path(&self, _name: &str) -> bool {
+        true
+    }
+}
+
+struct NodeFilter<F>(F);
+
+impl<F: Fn(&str) -> bool> Filter for NodeFilter<F> {
+    fn keep_segment(&self, line: &str) -> bool {
+        (self.0)(line)
+    }
+}
+
+struct EdgeFilter<F>(F);
+
+impl<F: Fn(&str) -> bool> Filter for EdgeFilter<F> {
+    fn keep_edge(&self, line: &str) -> bool {
+        (self.0)(line)
+    }
+}
+
+struct PathFilter<F>(F);
+
+impl<F: Fn(&str) -> bool> Filter for PathFilter<F> {
+    fn keep_path(&self, name: &str) -> bool {
+        (self.0)(name)
+    }
+}
+
+/// Records of the input rejected by the filters of a [`Pipeline`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filtered {
+    pub segments: usize,
+    /// Links and jumps
+    pub edges: usize,
+    /// Selected paths and walks that were deselected
+    pub paths: usize,
+}
+
+/// Filters applied together when trimming
+#[derive(Default)]
+pub struct Pipeline {
+    filters: Vec<Box<dyn Filter>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a filter of any records
+    pub fn filter(mut self, filter: impl Filter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Adds a filter of segments, called with the S line
+    pub fn nodes(self, keep: impl Fn(&str) -> bool + 'static) -> Self {
+        self.filter(NodeFilter(keep))
+    }
+
+    /// Adds a filter of links and jumps, called with the L or J line
+    pub fn edges(self, keep: impl Fn(&str) -> bool + 'static) -> Self {
+        self.filter(EdgeFilter(keep))
+    }
+
+    /// Adds a filter of paths and walks, called with the name (PanSN for walks)
+    pub fn paths(self, keep: impl Fn(&str) -> bool + 'static) -> Self {
+        self.filter(PathFilter(keep))
+    }
+
+    fn keep_segment(&self, line: &str) -> bool {
+        self.filters.iter().all(|f| f.keep_segment(line))
+    }
+
+    fn keep_edge(&self, line: &str) -> bool {
+        self.filters.iter().all(|f| f.keep_edge(line))
+    }
+
+    fn keep_path(&self, name: &str) -> bool {
+        self.filters.iter().all(|f| f.keep_path(name))
+    }
+
+    /// Trims the graph like [`trim_gfa`] to the selected paths and walks the filters keep, and
+    /// removes the rejected segments and edges. Fails on a path or walk with invalid steps.
+    pub fn trim<'a>(
+        &self,
+        lines: GfaLines<'a>,
+        paths_to_keep: Option<Vec<String>>,
+        options: &TrimOptions,
+    ) -> Result<(Trimmed<'a>, Filtered), String> {
+        let removed_nodes = lines
+            .segments
+            .iter()
+            .filter(|line| !self.keep_segment(line))
+            .filter_map(|line| scan::field(line, 1))
+            .collect::<HashSet<_>>();
+        let removed_edges = |edges: &[&'a str]| {
+            edges
+                .iter()
+                .filter(|line| !self.keep_edge(line))
+                .filter_map(|line| Edge::from_line(line))
+                .collect::<HashSet<_>>()
+        };
+        let removed_links = removed_edges(&lines.links);
+        let removed_jumps = removed_edges(&lines.jumps);
+        // Steps with whether a jump (`;` in P lines) leads to the next one
+        let is_blocked = |steps: &[(&str, bool, bool)]| {
+            steps
+                .iter()
+                .any(|(node, _, _)| removed_nodes.contains(node))
+                || steps.windows(2).any(|pair| {
+                    let edge = Edge::new(pair[0].0, pair[0].1, pair[1].0, pair[1].1);
+                    match pair[0].2 {
+                        true => removed_jumps.contains(&edge),
+                        false => removed_links.contains(&edge),
+                    }
+                })
+        };
+
+        let selected_paths = paths_to_keep.map(|p| p.into_iter().collect::<HashSet<_>>());
+        let mut filtered = Filtered {
+            segments: removed_nodes.len(),
+            edges: removed_links.len() + removed_jumps.len(),
+            paths: 0,
+        };
+        let mut paths = Vec::new();
+        for line in &lines.paths {
+            let name = scan::field(line, 1).unwrap_or("");
+            if selected_paths.as_ref().is_some_and(|s| !s.contains(name)) {
+                continue;
+            }
+            let steps = tokenize_path(scan::field(line, 2).unwrap_or(""))
+                .map_err(|e| format!("Invalid steps of path {}: {}", name, e))?
+                .iter()
+                .map(|t| (t.name, t.forward, t.jump == Some(true)))
+                .collect::<Vec<_>>();
+            match self.keep_path(name) && !is_blocked(&steps) {
+                true => paths.push(name.to_string()),
+                false => filtered.paths += 1,
+            }
+        }
+        let mut walks = HashSet::new();
+        for line in &lines.walks {
+            let name = walk_name(line);
+            if options
+                .walks_to_keep
+                .as_ref()
+                .is_some_and(|keep| !keep.contains(&name))
+            {
+                continue;
+            }
+            let steps = tokenize_walk(scan::field(line, 6).unwrap_or(""))
+                .map_err(|e| format!("Invalid steps of walk {}: {}", name, e))?
+                .into_iter()
+                .map(|(node, forward)| (node, forward, false))
+                .collect::<Vec<_>>();
+            match self.keep_path(&name) && !is_blocked(&steps) {
+                true => {
+                    walks.insert(name);
+                }
+                false => filtered.paths += 1,
+            }
+        }
+
+        let options = TrimOptions {
+            walks_to_keep: Some(walks),
+            ..options.clone()
+        };
+        let mut trimmed = trim_gfa(lines, Some(paths), &options)?;
+        trimmed
+            .segments
+            .retain(|line| scan::field(line, 1).is_none_or(|name| !removed_nodes.contains(name)));
+        let keep_edge = |removed: &HashSet<Edge<&str>>, line: &str| match Edge::from_line(line) {
+            Some(edge) => {
+                !removed.contains(&edge)
+                    && !removed_nodes.contains(edge.from)
+                    && !removed_nodes.contains(edge.to)
+            }
+            None => true,
+        };
+        trimmed.links.retain(|line| keep_edge(&removed_links, line));
+        trimmed.jumps.retain(|line| keep_edge(&removed_jumps, line));
+        Ok((trimmed, filtered))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keeps the segments with a tag
+    struct HasTag(&'static str);
+
+    impl Filter for HasTag {
+        fn keep_segment(&self, line: &str) -> bool {
+            scan::fields(line)
+                .skip(3)
+                .any(|tag| tag.starts_with(self.0))
+        }
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let content = "S\t1\tA\tok:i:1\nS\t2\tC\tok:i:1\nS\t3\tG\nS\t4\tT\tok:i:1\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t2\t+\t4\t+\t0M\n\
+                       L\t1\t+\t4\t+\t0M\n\
+                       P\ta\t1+,2+,4+\t*\nP\tb\t1+,2+,3+\t*\nP\tc\t1+,4+\t*\n\
+                       W\ts\t1\tc\t0\t2\t>1>2\nW\tt\t1\tc\t0\t2\t>1>2\n";
+        let pipeline = Pipeline::new()
+            .filter(HasTag("ok:"))
+            .edges(|line| !line.starts_with("L\t1\t+\t4"))
+            .paths(|name| !name.starts_with('t'));
+        let (trimmed, filtered) = pipeline
+            .trim(GfaLines::classify(content), None, &TrimOptions::default())
+            .unwrap();
+        assert_eq!(
+            filtered,
+            Filtered {
+                segments: 1,
+                edges: 1,
+                paths: 3
+            }
+        );
+        assert_eq!(trimmed.paths, ["P\ta\t1+,2+,4+\t*"]);
+        assert_eq!(trimmed.walks, ["W\ts\t1\tc\t0\t2\t>1>2"]);
+        assert_eq!(
+            trimmed.segments,
+            ["S\t1\tA\tok:i:1", "S\t2\tC\tok:i:1", "S\t4\tT\tok:i:1"]
+        );
+        assert_eq!(trimmed.links, ["L\t1\t+\t2\t+\t0M", "L\t2\t+\t4\t+\t0M"]);
+
+        // Rejected segments go even if trimming keeps all segments
+        let options = TrimOptions {
+            ignore_segments: true,
+            ..Default::default()
+        };
+        let (trimmed, _) = Pipeline::new()
+            .nodes(|line| !line.starts_with("S\t3\t"))
+            .trim(
+                GfaLines::classify(content),
+                Some(vec!["a".to_string()]),
+                &options,
+            )
+            .unwrap();
+        assert_eq!(trimmed.segments.len(), 3);
+        assert_eq!(trimmed.paths.len(), 1);
+
+        let error = Pipeline::new()
+            .trim(
+                GfaLines::classify("S\t1\tA\nP\ta\t1+,,1+\t*\n"),
+                None,
+                &TrimOptions::default(),
+            )
+            .unwrap_err();
+        assert!(error.starts_with("Invalid steps of path a"));
+    }
+
+    #[test]
+    fn test_pipeline_jumps() {
+        // The rejected jump runs parallel to a link, only the path using the jump is blocked
+        let content = "S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nJ\t1\t+\t2\t+\t10\n\
+                       P\ta\t1+,2+\t*\nP\tb\t1+;2+\t*\n";
+        let (trimmed, filtered) = Pipeline::new()
+            .edges(|line| !line.starts_with('J'))
+            .trim(GfaLines::classify(content), None, &TrimOptions::default())
+            .unwrap();
+        assert_eq!(filtered.edges, 1);
+        assert_eq!(filtered.paths, 1);
+        assert_eq!(trimmed.paths, ["P\ta\t1+,2+\t*"]);
+        assert_eq!(trimmed.links, ["L\t1\t+\t2\t+\t0M"]);
+        assert!(trimmed.jumps.is_empty());
+    }
+}
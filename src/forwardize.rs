@@ -0,0 +1,134 @@
+//! Orientation of the segments along the reference (`--forwardize`).
+//!
+//! Segments the reference traverses in reverse more often than forward are flipped: their
+//! sequence is replaced by its reverse complement, and the orientation of every link, jump and
+//! step on them is inverted, so the graph describes the same sequences and the reference reads
+//! left to right in viewers. The overlaps of links stay valid, since a link joins the same
+//! segment ends after flipping.
+
+use crate::collapse::redirect_steps;
+use crate::graph::{path_line_steps, reverse_complement, walk_line_steps};
+use crate::scan;
+use crate::trim::{walk_name, Trimmed};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Forwardize {
+    /// Segments to flip
+    pub flipped: HashSet<String>,
+}
+
+impl Forwardize {
+    /// Finds the segments visited more often in reverse than forward by the reference, a path
+    /// name or the PanSN name of a walk
+    pub fn along(trimmed: &Trimmed, reference: &str) -> Result<Self, String> {
+        let path = trimmed
+            .paths
+            .iter()
+            .find(|l| scan::field(l, 1) == Some(reference));
+        let steps = match path {
+            Some(line) => path_line_steps(line)?,
+            None => {
+                let walk = trimmed
+                    .walks
+                    .iter()
+                    .find(|l| walk_name(l) == reference)
+                    .ok_or_else(|| {
+                        format!("Reference {} is no path or walk of the graph", reference)
+                    })?;
+                walk_line_steps(walk)?
+            }
+        };
+        // Forward minus reverse visits
+        let mut balance = HashMap::<String, isize>::new();
+        for (node, forward) in steps {
+            *balance.entry(node.to_string()).or_default() += if forward { 1 } else { -1 };
+        }
+        Ok(Forwardize {
+            flipped: balance
+                .into_iter()
+                .filter(|(_, balance)| *balance < 0)
+                .map(|(node, _)| node)
+                .collect(),
+        })
+    }
+
+    /// Segment lines with the sequence of the flipped segments reverse complemented
+    pub fn segments(&self, segments: &[&str]) -> Vec<String> {
+        segments
+            .iter()
+            .map(|line| {
+                let mut fields = line.split('\t').collect::<Vec<_>>();
+                let flip = fields.get(1).is_some_and(|n| self.flipped.contains(*n));
+                match fields.get(2) {
+                    Some(&sequence) if flip && sequence != "*" => {
+                        let reversed = reverse_complement(sequence);
+                        fields[2] = &reversed;
+                        fields.join("\t")
+                    }
+                    _ => line.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// Links or jumps with the orientation of the flipped segments inverted
+    pub fn edges(&self, edges: &[&str]) -> Vec<String> {
+        let flip = |orientation: &str| if orientation == "-" { "+" } else { "-" };
+        edges
+            .iter()
+            .map(|line| {
+                let mut fields = line.split('\t').collect::<Vec<_>>();
+                if fields.len() < 5 {
+                    return line.to_string();
+                }
+                for (name, orientation) in [(1, 2), (3, 4)] {
+                    if self.flipped.contains(fields[name]) {
+                        fields[orientation] = flip(fields[orientation]);
+                    }
+                }
+                fields.join("\t")
+            })
+            .collect()
+    }
+
+    /// Inverts the steps of the paths and walks on the flipped segments, fails on a path or walk
+    /// with invalid steps
+    pub fn apply_steps(&self, trimmed: &mut Trimmed) -> Result<(), String> {
+        redirect_steps(trimmed, |name, forward| {
+            (name.to_string(), forward != self.flipped.contains(name))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_forwardize() {
+        let content = "S\t1\tACC\nS\t2\tGTT\tLN:i:3\nS\t3\t*\n\
+                       L\t1\t+\t2\t-\t1M\nL\t2\t-\t3\t-\t0M\n\
+                       P\tref\t1+,2-,3-\t*\nW\ts\t1\tc\t0\t6\t<3>2<1\n";
+        let mut trimmed =
+            trim_gfa(GfaLines::classify(content), None, &TrimOptions::default()).unwrap();
+        let forwardize = Forwardize::along(&trimmed, "ref").unwrap();
+        assert_eq!(
+            forwardize.flipped,
+            HashSet::from(["2".to_string(), "3".to_string()])
+        );
+        assert_eq!(
+            forwardize.segments(&trimmed.segments),
+            ["S\t1\tACC", "S\t2\tAAC\tLN:i:3", "S\t3\t*"]
+        );
+        assert_eq!(
+            forwardize.edges(&trimmed.links),
+            ["L\t1\t+\t2\t+\t1M", "L\t2\t+\t3\t+\t0M"]
+        );
+        forwardize.apply_steps(&mut trimmed).unwrap();
+        assert_eq!(trimmed.paths, ["P\tref\t1+,2+,3+\t*"]);
+        assert_eq!(trimmed.walks, ["W\ts\t1\tc\t0\t6\t>3<2<1"]);
+        assert!(Forwardize::along(&trimmed, "x").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Read support of segments and edges from alignments in GAF (`trim-graph annotate`).
+//!
+//! Every visit of a segment by the path of an alignment (column 6, e.g. `>12<13>15`) counts
+//! once, as does every pair of consecutive steps for the edge between them, in either
+//! orientation. Alignments to stable coordinates (`chr1`) cannot be placed on segments and are
+//! only counted as skipped.
+//!
+//! In `--assembly-mode`, trimming also keeps the segments and edges with at least
+//! `--min-reads` reads, so edges of assembly graphs without paths can be cleaned by support.
+
+use crate::edge::Edge;
+use crate::graph::tokenize_walk;
+use crate::trim::{Edges, Nodes};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadDepth {
+    /// Visits of every segment
+    pub nodes: HashMap<String, usize>,
+    /// Traversals of every edge, in either direction
+    pub edges: HashMap<Edge, usize>,
+    /// Number of alignments read
+    pub alignments: usize,
+    /// Number of alignments to stable coordinates
+    pub skipped: usize,
+}
+
+impl ReadDepth {
+    pub fn from_gaf(content: &str) -> Result<Self, String> {
+        let mut depth = ReadDepth::default();
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            let path = line
+                .split('\t')
+                .nth(5)
+                .ok_or_else(|| format!("GAF record without path: {}", line))?;
+            depth.alignments += 1;
+            if !path.starts_with(['>', '<']) {
+                depth.skipped += 1;
+                continue;
+            }
+            let steps = tokenize_walk(path)
+                .map_err(|e| format!("Invalid path of GAF record {}: {}", line, e))?;
+            for (node, _) in &steps {
+                *depth.nodes.entry(node.to_string()).or_default() += 1;
+            }
+            for pair in steps.windows(2) {
+                let (from, to) = (pair[0].0.to_string(), pair[1].0.to_string());
+                let edge = Edge::new(from, pair[0].1, to, pair[1].1);
+                *depth.edges.entry(edge.canonical()).or_default() += 1;
+            }
+        }
+        Ok(depth)
+    }
+
+    /// Segments and edges with at least `min_reads` reads
+    pub fn supported(&self, min_reads: usize) -> (Nodes, Edges) {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|(_, reads)| **reads >= min_reads)
+            .map(|(node, _)| node.clone())
+            .collect();
+        let edges = self
+            .edges
+            .iter()
+            .filter(|(_, reads)| **reads >= min_reads)
+            .map(|(edge, _)| edge.clone())
+            .collect();
+        (nodes, edges)
+    }
+
+    /// Reads supporting the edge of an L or J line
+    pub fn edge_reads(&self, line: &str) -> usize {
+        match Edge::from_line(line) {
+            Some(edge) => self.edges.get(&edge.into_owned()).copied().unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    /// Line with its read count as `RC:i:` tag (replacing an earlier one) on S, L and J lines,
+    /// other lines are returned as they are
+    pub fn annotate(&self, line: &str) -> String {
+        let reads = match line.as_bytes().first() {
+            Some(b'S') => {
+                let name = line.split('\t').nth(1).unwrap_or("");
+                self.nodes.get(name).copied().unwrap_or(0)
+            }
+            Some(b'L' | b'J') => self.edge_reads(line),
+            _ => return line.to_string(),
+        };
+        let mut fields = line
+            .split('\t')
+            .filter(|f| !f.starts_with("RC:i:"))
+            .collect::<Vec<_>>()
+            .join("\t");
+        fields.push_str(&format!("\tRC:i:{}", reads));
+        fields
+    }
+
+    /// Writes `S name reads` and `L from from_orientation to to_orientation reads` rows for all
+    /// segments and edges of the graph content
+    pub fn write_tsv<W: Write>(&self, content: &str, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "record\tfrom\tfrom_orientation\tto\tto_orientation\treads"
+        )?;
+        for line in content.lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            match fields[0] {
+                "S" if fields.len() > 1 => {
+                    let reads = self.nodes.get(fields[1]).copied().unwrap_or(0);
+                    writeln!(out, "S\t{}\t*\t*\t*\t{}", fields[1], reads)?;
+                }
+                "L" | "J" if fields.len() > 4 => writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    fields[0],
+                    fields[1],
+                    fields[2],
+                    fields[3],
+                    fields[4],
+                    self.edge_reads(line)
+                )?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_depth() {
+        let gaf = "r1\t10\t0\t10\t+\t>1>2>3\t12\t0\t10\t10\t10\t60\n\
+                   r2\t5\t0\t5\t+\t<3<2\t6\t0\t5\t5\t5\t60\n\
+                   r3\t5\t0\t5\t+\tchr1\t100\t0\t5\t5\t5\t60\n";
+        let depth = ReadDepth::from_gaf(gaf).unwrap();
+        assert_eq!((depth.alignments, depth.skipped), (3, 1));
+        assert_eq!(depth.nodes["2"], 2);
+        assert_eq!(depth.annotate("S\t2\tACG\tRC:i:7"), "S\t2\tACG\tRC:i:2");
+        assert_eq!(
+            depth.annotate("L\t3\t-\t2\t-\t0M"),
+            "L\t3\t-\t2\t-\t0M\tRC:i:2"
+        );
+        assert_eq!(
+            depth.annotate("L\t1\t+\t3\t+\t0M"),
+            "L\t1\t+\t3\t+\t0M\tRC:i:0"
+        );
+        assert_eq!(depth.annotate("P\tp\t1+\t*"), "P\tp\t1+\t*");
+        let mut out = Vec::new();
+        depth
+            .write_tsv("S\t1\tA\nL\t1\t+\t2\t+\t0M\n", &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "record\tfrom\tfrom_orientation\tto\tto_orientation\treads\n\
+             S\t1\t*\t*\t*\t1\nL\t1\t+\t2\t+\t1\n"
+        );
+        let (nodes, edges) = depth.supported(2);
+        assert_eq!(nodes.len(), 2);
+        assert!(edges.contains(&Edge::new("2".to_string(), true, "3".to_string(), true)));
+        assert!(edges.contains(&Edge::new("3".to_string(), false, "2".to_string(), false)));
+        assert_eq!(edges.len(), 1);
+        assert!(ReadDepth::from_gaf("r1\t10\n").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::graph::{format_path_steps, format_walk_steps, split_pansn, Graph};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Writes the graph as GFA in the form `vg gbwt --gbz-format -G` builds a GBZ from in a
+/// single pass: P lines with PanSN names become haplotype walks, other P lines are kept as
+/// reference paths, jumps are dropped since GBWTGraph has no representation for them.
+pub fn write_gbz_gfa<W: Write>(out: &mut W, graph: &Graph) -> io::Result<()> {
+    let lengths = graph
+        .segments
+        .iter()
+        .map(|s| (s.name.as_str(), s.length))
+        .collect::<HashMap<_, _>>();
+
+    writeln!(out, "H\tVN:Z:1.1")?;
+    for segment in &graph.segments {
+        let sequence = segment
+            .loaded_sequence()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if sequence == "*" {
+            log::warn!(
+                "Segment {} has no sequence, GBWTGraph requires sequences",
+                segment.name
+            );
+        }
+        writeln!(out, "S\t{}\t{}", segment.name, sequence)?;
+    }
+    for link in &graph.links {
+        writeln!(
+            out,
+            "L\t{}\t{}\t{}\t{}\t0M",
+            link.from,
+            if link.from_forward { '+' } else { '-' },
+            link.to,
+            if link.to_forward { '+' } else { '-' }
+        )?;
+    }
+    if !graph.jumps.is_empty() {
+        log::warn!(
+            "Dropping {} jumps, GBWTGraph does not support them",
+            graph.jumps.len()
+        );
+    }
+    for path in &graph.paths {
+        match split_pansn(&path.name) {
+            Some((sample, hap, contig)) => {
+                let length: usize = path
+                    .steps
+                    .iter()
+                    .map(|(node, _)| lengths.get(node.as_str()).copied().unwrap_or(0))
+                    .sum();
+                writeln!(
+                    out,
+                    "W\t{}\t{}\t{}\t0\t{}\t{}",
+                    sample,
+                    hap,
+                    contig,
+                    length,
+                    format_walk_steps(&path.steps)
+                )?;
+            }
+            None => writeln!(
+                out,
+                "P\t{}\t{}\t*",
+                path.name,
+                format_path_steps(&path.steps)
+            )?,
+        }
+    }
+    for walk in &graph.walks {
+        writeln!(
+            out,
+            "W\t{}\t{}\t{}\t{}\t{}\t{}",
+            walk.sample,
+            walk.hap_index,
+            walk.seq_id,
+            walk.seq_start,
+            walk.seq_end,
+            format_walk_steps(&walk.steps)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_gbz_gfa() {
+        let graph = Graph::from_lines(
+            &["S\t1\tACG\tLN:i:3", "S\t2\tT"],
+            &["L\t1\t+\t2\t-\t*"],
+            &["J\t2\t+\t1\t+\t*"],
+            &["P\tGRCh38#0#chr1\t1+,2-\t*", "P\tref\t1+\t*"],
+            &["W\tHG002\t1\tchr1\t5\t9\t>1<2"],
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        write_gbz_gfa(&mut out, &graph).unwrap();
+        let expected = "H\tVN:Z:1.1\n\
+            S\t1\tACG\n\
+            S\t2\tT\n\
+            L\t1\t+\t2\t-\t0M\n\
+            W\tGRCh38\t0\tchr1\t0\t4\t>1<2\n\
+            P\tref\t1+\t*\n\
+            W\tHG002\t1\tchr1\t5\t9\t>1<2\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
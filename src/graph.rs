@@ -0,0 +1,987 @@
+use crate::edge::Edge;
+use crate::scan;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+
+/// A segment (S line) of a GFA file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub name: String,
+    /// `*` if the sequence is not stored, `None` if it was not loaded
+    /// ([`Graph::from_gfa_topology`])
+    pub sequence: Option<String>,
+    /// Length of the sequence, from the LN tag for segments without sequence
+    pub length: usize,
+    /// Position on the stable sequence of an rGFA graph (SN, SO and SR tags)
+    pub stable: Option<Stable>,
+    /// Optional fields other than LN and the rGFA tags, which follow from the fields above, as
+    /// written (empty if there are none)
+    pub tags: String,
+}
+
+impl Segment {
+    /// Sequence as stored (`*` if there is none), fails if it was not loaded
+    pub fn loaded_sequence(&self) -> Result<&str, String> {
+        self.sequence
+            .as_deref()
+            .ok_or_else(|| format!("Sequence of segment {} is not loaded", self.name))
+    }
+}
+
+/// Origin of an rGFA segment: name of the stable sequence, offset on it and rank (0 for the
+/// reference)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stable {
+    pub name: String,
+    pub offset: usize,
+    pub rank: usize,
+}
+
+impl Stable {
+    /// Parses the SN, SO and SR tags of an S line, `None` unless all three are there
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let tags = scan::fields(line).skip(3);
+        let (mut name, mut offset, mut rank) = (None, None, None);
+        for tag in tags {
+            if let Some(value) = tag.strip_prefix("SN:Z:") {
+                name = Some(value.to_string());
+            } else if let Some(value) = tag.strip_prefix("SO:i:") {
+                offset = value.parse().ok();
+            } else if let Some(value) = tag.strip_prefix("SR:i:") {
+                rank = value.parse().ok();
+            }
+        }
+        Some(Stable {
+            name: name?,
+            offset: offset?,
+            rank: rank?,
+        })
+    }
+}
+
+/// A link or jump (L/J line) between two oriented segments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub from: String,
+    pub from_forward: bool,
+    pub to: String,
+    pub to_forward: bool,
+    /// Overlap (CIGAR) of an L line or distance of a J line, `*` if the column is missing
+    pub overlap: String,
+    /// Optional fields after the overlap as written (empty if there are none)
+    pub tags: String,
+}
+
+impl Link {
+    /// Link with a `0M` overlap and without tags, e.g. between the pieces of a split segment
+    pub fn new(from: String, from_forward: bool, to: String, to_forward: bool) -> Self {
+        Link {
+            from,
+            from_forward,
+            to,
+            to_forward,
+            overlap: "0M".to_string(),
+            tags: String::new(),
+        }
+    }
+}
+
+/// A path (P line), steps are (segment name, is forward)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub name: String,
+    pub steps: Vec<(String, bool)>,
+}
+
+/// A walk (W line), steps are (segment name, is forward)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Walk {
+    pub sample: String,
+    pub hap_index: String,
+    pub seq_id: String,
+    pub seq_start: String,
+    pub seq_end: String,
+    pub steps: Vec<(String, bool)>,
+}
+
+impl Walk {
+    /// Name of the walk following the PanSN convention (sample#hap#seq_id)
+    pub fn name(&self) -> String {
+        format!("{}#{}#{}", self.sample, self.hap_index, self.seq_id)
+    }
+}
+
+/// Parsed representation of the (trimmed) records of a GFA file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Graph {
+    pub segments: Vec<Segment>,
+    pub links: Vec<Link>,
+    pub jumps: Vec<Link>,
+    pub paths: Vec<Path>,
+    pub walks: Vec<Walk>,
+}
+
+/// Sequence length of an S line, given by the LN tag if the sequence is `*`, 0 if neither
+/// is there
+pub fn segment_length(line: &str) -> usize {
+    let mut fields = scan::fields(line).skip(2);
+    match fields.next() {
+        Some(sequence) if sequence != "*" => sequence.len(),
+        _ => fields
+            .find_map(|tag| tag.strip_prefix("LN:i:"))
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0),
+    }
+}
+
+/// Error in the steps column of a P line, at a byte position of the column (from 0)
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepError {
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+/// Step of a P line with the separator after it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathToken<'a> {
+    pub name: &'a str,
+    pub forward: bool,
+    /// `Some(true)` if a jump (`;`) follows, `Some(false)` if a link (`,`), `None` for the last
+    /// step
+    pub jump: Option<bool>,
+}
+
+/// Splits the steps column of a P line (`1+,2-;3+`) into steps. Whitespace around steps and a
+/// separator after the last step are ignored, empty steps, steps without orientation or name
+/// and steps not separated by `,` or `;` are errors.
+pub fn tokenize_path(steps: &str) -> Result<Vec<PathToken<'_>>, StepError> {
+    let bytes = steps.as_bytes();
+    let error = |position, message| Err(StepError { position, message });
+    let skip_whitespace = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    };
+    let mut tokens = Vec::new();
+    let mut i = skip_whitespace(0);
+    while i < bytes.len() {
+        let start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !matches!(bytes[i], b',' | b';')
+        {
+            i += 1;
+        }
+        let step = &steps[start..i];
+        let forward = match step.as_bytes().last() {
+            None => return error(start, "Empty step"),
+            Some(b'+') => true,
+            Some(b'-') => false,
+            Some(_) => return error(i - 1, "Step without orientation (+ or -)"),
+        };
+        if step.len() == 1 {
+            return error(start, "Step without segment name");
+        }
+        i = skip_whitespace(i);
+        let jump = match bytes.get(i) {
+            None => None,
+            Some(b',') => Some(false),
+            Some(b';') => Some(true),
+            Some(_) => return error(i, "Missing separator (, or ;) between steps"),
+        };
+        tokens.push(PathToken {
+            name: &step[..step.len() - 1],
+            forward,
+            jump,
+        });
+        i = skip_whitespace(i + 1);
+    }
+    if let Some(last) = tokens.last_mut() {
+        last.jump = None;
+    }
+    Ok(tokens)
+}
+
+/// Steps column of a P line with the tokens and their separators, the separator after the last
+/// token is left out
+pub fn format_path_tokens(tokens: &[PathToken]) -> String {
+    let mut steps = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        steps.push_str(token.name);
+        steps.push(if token.forward { '+' } else { '-' });
+        if i + 1 < tokens.len() {
+            steps.push(if token.jump == Some(true) { ';' } else { ',' });
+        }
+    }
+    steps
+}
+
+/// Steps of a P line as segment and orientation, errors name the path
+pub fn path_line_steps(line: &str) -> Result<Vec<(&str, bool)>, String> {
+    let tokens = tokenize_path(scan::field(line, 2).unwrap_or("")).map_err(|e| {
+        let name = scan::field(line, 1).unwrap_or("");
+        format!("Invalid steps of path {}: {}", name, e)
+    })?;
+    Ok(tokens.into_iter().map(|t| (t.name, t.forward)).collect())
+}
+
+/// Steps of a W line as segment and orientation, errors name the walk in PanSN form
+pub fn walk_line_steps(line: &str) -> Result<Vec<(&str, bool)>, String> {
+    tokenize_walk(scan::field(line, 6).unwrap_or("")).map_err(|e| {
+        let name = scan::fields(line).skip(1).take(3).collect::<Vec<_>>();
+        format!("Invalid steps of walk {}: {}", name.join("#"), e)
+    })
+}
+
+/// Splits the walk column of a W line (`>1<2>3`) into segments and their orientation, `*` or
+/// an empty column is a walk without steps. Steps without `>` or `<`, without segment name or
+/// with characters outside `!`-`~` in it are errors.
+pub fn tokenize_walk(walk: &str) -> Result<Vec<(&str, bool)>, StepError> {
+    let error = |position, message| Err(StepError { position, message });
+    if walk == "*" {
+        return Ok(Vec::new());
+    }
+    let bytes = walk.as_bytes();
+    let mut steps = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let forward = match bytes[i] {
+            b'>' => true,
+            b'<' => false,
+            _ => return error(i, "Step without orientation (> or <)"),
+        };
+        let start = i + 1;
+        i = start;
+        while i < bytes.len() && !matches!(bytes[i], b'>' | b'<') {
+            if !matches!(bytes[i], b'!'..=b'~') {
+                return error(i, "Invalid character in segment name");
+            }
+            i += 1;
+        }
+        if i == start {
+            return error(start - 1, "Step without segment name");
+        }
+        steps.push((&walk[start..i], forward));
+    }
+    Ok(steps)
+}
+
+/// Fields of a W line, borrowed from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkRecord<'a> {
+    pub sample: &'a str,
+    pub hap_index: usize,
+    pub seq_id: &'a str,
+    /// `None` if not given (`*`)
+    pub seq_start: Option<usize>,
+    /// `None` if not given (`*`)
+    pub seq_end: Option<usize>,
+    pub steps: Vec<(&'a str, bool)>,
+}
+
+impl WalkRecord<'_> {
+    /// Parses all fields of a W line, errors name the walk and the invalid field
+    pub fn parse(line: &str) -> Result<WalkRecord<'_>, String> {
+        let fields = scan::fields(line).collect::<Vec<_>>();
+        if fields.len() < 7 {
+            return Err(format!(
+                "Walk with {} instead of 7 columns: {}",
+                fields.len(),
+                line
+            ));
+        }
+        let name = fields[1..4].join("#");
+        let invalid =
+            |what: &str, value: &str| format!("Invalid {} of walk {}: {}", what, name, value);
+        if fields[1].is_empty() {
+            return Err(invalid("sample", fields[1]));
+        }
+        let hap_index = fields[2]
+            .parse::<usize>()
+            .map_err(|_| invalid("haplotype index", fields[2]))?;
+        let coordinate = |what: &str, value: &str| match value {
+            "*" => Ok(None),
+            value => value
+                .parse::<usize>()
+                .map(Some)
+                .map_err(|_| invalid(what, value)),
+        };
+        let seq_start = coordinate("start", fields[4])?;
+        let seq_end = coordinate("end", fields[5])?;
+        if let (Some(start), Some(end)) = (seq_start, seq_end) {
+            if end < start {
+                return Err(invalid("end", &format!("{} before start {}", end, start)));
+            }
+        }
+        let steps = tokenize_walk(fields[6]).map_err(|e| invalid("steps", &e.to_string()))?;
+        Ok(WalkRecord {
+            sample: fields[1],
+            hap_index,
+            seq_id: fields[3],
+            seq_start,
+            seq_end,
+            steps,
+        })
+    }
+}
+
+/// Fields of an L or J line, borrowed from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeRecord<'a> {
+    pub edge: Edge<&'a str>,
+    /// Overlap (CIGAR) of an L line or distance of a J line, `None` if the column is missing
+    pub overlap: Option<&'a str>,
+    /// Optional fields after the overlap, exactly as in the line (empty if there are none)
+    pub tags: &'a str,
+}
+
+impl EdgeRecord<'_> {
+    /// Parses the segments and orientations of an L or J line, which have to be there. The
+    /// overlap column is often left out by tools writing links without overlaps and may be
+    /// missing.
+    pub fn parse(line: &str) -> Result<EdgeRecord<'_>, String> {
+        let (kind, record) = match line.as_bytes().first() {
+            Some(b'J') => ("jump", "Jump"),
+            _ => ("link", "Link"),
+        };
+        let fields = scan::fields(line).collect::<Vec<_>>();
+        if fields.len() < 5 {
+            return Err(format!(
+                "{} with {} instead of at least 5 columns: {}",
+                record,
+                fields.len(),
+                line
+            ));
+        }
+        let invalid = |what: &str, value: &str| {
+            let edge = fields[1..5].join(" ");
+            format!("Invalid {} of {} {}: '{}'", what, kind, edge, value)
+        };
+        let orientation = |what: &str, value: &str| match value {
+            "+" => Ok(true),
+            "-" => Ok(false),
+            value => Err(invalid(what, value)),
+        };
+        for (what, name) in [("from segment", fields[1]), ("to segment", fields[3])] {
+            if name.is_empty() {
+                return Err(invalid(what, name));
+            }
+        }
+        let from_forward = orientation("from orientation", fields[2])?;
+        let to_forward = orientation("to orientation", fields[4])?;
+        // The tags start where the 7th field does, all fields are slices of the line
+        let tags = match fields.get(6) {
+            Some(first) => &line[first.as_ptr() as usize - line.as_ptr() as usize..],
+            None => "",
+        };
+        Ok(EdgeRecord {
+            edge: Edge::new(fields[1], from_forward, fields[3], to_forward),
+            overlap: fields.get(5).copied(),
+            tags,
+        })
+    }
+}
+
+/// Splits a path name following the PanSN convention (sample#hap#contig) into its parts
+pub fn split_pansn(name: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = name.splitn(3, '#');
+    let sample = parts.next()?;
+    let hap = parts.next()?;
+    let contig = parts.next()?;
+    if sample.is_empty() || hap.parse::<usize>().is_err() || contig.is_empty() {
+        return None;
+    }
+    Some((sample, hap, contig))
+}
+
+/// Reverse complement of a DNA sequence, IUPAC codes are complemented and case is kept
+pub fn reverse_complement(sequence: &str) -> String {
+    sequence
+        .chars()
+        .rev()
+        .map(|c| match c {
+            'A' => 'T',
+            'C' => 'G',
+            'G' => 'C',
+            'T' => 'A',
+            'U' => 'A',
+            'R' => 'Y',
+            'Y' => 'R',
+            'K' => 'M',
+            'M' => 'K',
+            'B' => 'V',
+            'V' => 'B',
+            'D' => 'H',
+            'H' => 'D',
+            'a' => 't',
+            'c' => 'g',
+            'g' => 'c',
+            't' => 'a',
+            'u' => 'a',
+            'r' => 'y',
+            'y' => 'r',
+            'k' => 'm',
+            'm' => 'k',
+            'b' => 'v',
+            'v' => 'b',
+            'd' => 'h',
+            'h' => 'd',
+            c => c,
+        })
+        .collect()
+}
+
+pub fn format_path_steps(steps: &[(String, bool)]) -> String {
+    steps
+        .iter()
+        .map(|(node, forward)| format!("{}{}", node, if *forward { '+' } else { '-' }))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn format_walk_steps(steps: &[(String, bool)]) -> String {
+    steps
+        .iter()
+        .map(|(node, forward)| format!("{}{}", if *forward { '>' } else { '<' }, node))
+        .collect()
+}
+
+fn write_link<W: Write>(out: &mut W, record: char, link: &Link) -> io::Result<()> {
+    write!(
+        out,
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        record,
+        link.from,
+        if link.from_forward { '+' } else { '-' },
+        link.to,
+        if link.to_forward { '+' } else { '-' },
+        link.overlap
+    )?;
+    if !link.tags.is_empty() {
+        write!(out, "\t{}", link.tags)?;
+    }
+    writeln!(out)
+}
+
+/// Segment of an S line, without sequence unless `sequence` is set
+fn parse_segment(line: &str, sequence: bool) -> Result<Segment, String> {
+    let mut fields = scan::fields(line).skip(1);
+    let name = fields
+        .next()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| format!("Segment without name: {}", line))?;
+    let stored = fields.next().unwrap_or("*");
+    Ok(Segment {
+        name: name.to_string(),
+        sequence: sequence.then(|| stored.to_string()),
+        length: segment_length(line),
+        stable: Stable::parse(line),
+        tags: fields
+            .filter(|f| {
+                !["LN:", "SN:", "SO:", "SR:"]
+                    .iter()
+                    .any(|t| f.starts_with(t))
+            })
+            .join("\t"),
+    })
+}
+
+fn parse_link(line: &str) -> Result<Link, String> {
+    let record = EdgeRecord::parse(line)?;
+    Ok(Link {
+        from: record.edge.from.to_string(),
+        from_forward: record.edge.from_forward,
+        to: record.edge.to.to_string(),
+        to_forward: record.edge.to_forward,
+        overlap: record.overlap.unwrap_or("*").to_string(),
+        tags: record.tags.to_string(),
+    })
+}
+
+fn parse_path(line: &str) -> Result<Path, String> {
+    let steps = path_line_steps(line)?;
+    Ok(Path {
+        name: scan::field(line, 1).unwrap_or("").to_string(),
+        steps: steps
+            .into_iter()
+            .map(|(node, forward)| (node.to_string(), forward))
+            .collect(),
+    })
+}
+
+fn parse_walk(line: &str) -> Result<Walk, String> {
+    let steps = WalkRecord::parse(line)?.steps;
+    // The columns are kept as written, they are checked by the parse
+    let fields = scan::fields(line).collect::<Vec<_>>();
+    Ok(Walk {
+        sample: fields[1].to_string(),
+        hap_index: fields[2].to_string(),
+        seq_id: fields[3].to_string(),
+        seq_start: fields[4].to_string(),
+        seq_end: fields[5].to_string(),
+        steps: steps
+            .into_iter()
+            .map(|(node, forward)| (node.to_string(), forward))
+            .collect(),
+    })
+}
+
+impl Graph {
+    /// Parses all segments, links, jumps, paths and walks of the content of a GFA file, fails
+    /// on the first invalid record
+    pub fn from_gfa(content: &str) -> Result<Self, String> {
+        Graph::parse(content, true)
+    }
+
+    /// Like [`Graph::from_gfa`], but the sequences are not copied, which keeps the memory low
+    /// for operations on the topology. Segments keep their length and rGFA tags without
+    /// sequence, [`Graph::load_sequences`] fills them in when needed.
+    pub fn from_gfa_topology(content: &str) -> Result<Self, String> {
+        Graph::parse(content, false)
+    }
+
+    fn parse(content: &str, sequences: bool) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut links = Vec::new();
+        let mut jumps = Vec::new();
+        let mut paths = Vec::new();
+        let mut walks = Vec::new();
+        for line in scan::lines(content) {
+            match line.as_bytes().first() {
+                Some(b'S') => segments.push(line),
+                Some(b'L') => links.push(line),
+                Some(b'J') => jumps.push(line),
+                Some(b'P') => paths.push(line),
+                Some(b'W') => walks.push(line),
+                _ => {}
+            }
+        }
+        Ok(Graph {
+            segments: segments
+                .iter()
+                .map(|l| parse_segment(l, sequences))
+                .collect::<Result<_, _>>()?,
+            ..Graph::from_lines(&[], &links, &jumps, &paths, &walks)?
+        })
+    }
+
+    /// Whether the sequences of all segments are loaded
+    pub fn has_sequences(&self) -> bool {
+        self.segments.iter().all(|s| s.sequence.is_some())
+    }
+
+    /// Fills in the sequences not loaded by [`Graph::from_gfa_topology`] from the content the
+    /// graph was parsed from, returns their number
+    pub fn load_sequences(&mut self, content: &str) -> usize {
+        let mut unloaded = self
+            .segments
+            .iter_mut()
+            .filter(|s| s.sequence.is_none())
+            .map(|s| (s.name.clone(), s))
+            .collect::<HashMap<_, _>>();
+        let mut loaded = 0;
+        for line in scan::lines(content).filter(|l| l.starts_with('S')) {
+            let mut fields = scan::fields(line).skip(1);
+            let Some(segment) = fields.next().and_then(|name| unloaded.remove(name)) else {
+                continue;
+            };
+            segment.sequence = Some(fields.next().unwrap_or("*").to_string());
+            loaded += 1;
+        }
+        loaded
+    }
+
+    /// Parses the lines of each record type, fails on the first invalid record
+    pub fn from_lines<S: AsRef<str>, T: AsRef<str>>(
+        segments: &[S],
+        links: &[S],
+        jumps: &[S],
+        paths: &[T],
+        walks: &[T],
+    ) -> Result<Self, String> {
+        Ok(Graph {
+            segments: segments
+                .iter()
+                .map(|l| parse_segment(l.as_ref(), true))
+                .collect::<Result<_, _>>()?,
+            links: links
+                .iter()
+                .map(|l| parse_link(l.as_ref()))
+                .collect::<Result<_, _>>()?,
+            jumps: jumps
+                .iter()
+                .map(|l| parse_link(l.as_ref()))
+                .collect::<Result<_, _>>()?,
+            paths: paths
+                .iter()
+                .map(|l| parse_path(l.as_ref()))
+                .collect::<Result<_, _>>()?,
+            walks: walks
+                .iter()
+                .map(|l| parse_walk(l.as_ref()))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Writes all records as GFA (without header). Segments, links and jumps keep their tags
+    /// and overlaps (distances), the LN tag is written for segments without sequence. Paths are
+    /// written without overlaps (`*`). Sequences that are not loaded are written as `*`.
+    pub fn write_gfa<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for segment in &self.segments {
+            let sequence = segment.sequence.as_deref().unwrap_or("*");
+            write!(out, "S\t{}\t{}", segment.name, sequence)?;
+            if sequence == "*" && segment.length > 0 {
+                write!(out, "\tLN:i:{}", segment.length)?;
+            }
+            if let Some(stable) = &segment.stable {
+                write!(
+                    out,
+                    "\tSN:Z:{}\tSO:i:{}\tSR:i:{}",
+                    stable.name, stable.offset, stable.rank
+                )?;
+            }
+            if !segment.tags.is_empty() {
+                write!(out, "\t{}", segment.tags)?;
+            }
+            writeln!(out)?;
+        }
+        for link in &self.links {
+            write_link(out, 'L', link)?;
+        }
+        for jump in &self.jumps {
+            write_link(out, 'J', jump)?;
+        }
+        for path in &self.paths {
+            writeln!(
+                out,
+                "P\t{}\t{}\t*",
+                path.name,
+                format_path_steps(&path.steps)
+            )?;
+        }
+        for walk in &self.walks {
+            writeln!(
+                out,
+                "W\t{}\t{}\t{}\t{}\t{}\t{}",
+                walk.sample,
+                walk.hap_index,
+                walk.seq_id,
+                walk.seq_start,
+                walk.seq_end,
+                format_walk_steps(&walk.steps)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Numeric ids for all segments. Segment names are used directly if all of them are
+    /// positive integers, otherwise segments are numbered consecutively starting at 1.
+    pub fn numeric_ids(&self) -> HashMap<&str, u64> {
+        let parsed = self
+            .segments
+            .iter()
+            .map(|s| s.name.parse::<u64>().ok().filter(|id| *id > 0))
+            .collect::<Option<Vec<_>>>();
+        match parsed {
+            Some(ids) => self
+                .segments
+                .iter()
+                .zip(ids)
+                .map(|(s, id)| (s.name.as_str(), id))
+                .collect(),
+            None => {
+                log::warn!("Segment names are not numeric, renumbering segments");
+                self.segments
+                    .iter()
+                    .enumerate()
+                    .map(|(i, s)| (s.name.as_str(), i as u64 + 1))
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_lines() {
+        let segments = vec!["S\t1\tACG", "S\t2\tT"];
+        let links = vec!["L\t1\t+\t2\t-\t0M"];
+        let paths = vec!["P\tp1\t1+,2-\t*".to_string()];
+        let walks = vec!["W\tHG002\t1\tchr1\t0\t4\t>1<2".to_string()];
+        let graph = Graph::from_lines(&segments, &links, &[], &paths, &walks).unwrap();
+        assert_eq!(graph.segments[1].loaded_sequence(), Ok("T"));
+        assert_eq!(
+            graph.links,
+            vec![Link::new("1".to_string(), true, "2".to_string(), false)]
+        );
+        assert_eq!(graph.paths[0].steps, graph.walks[0].steps);
+        assert_eq!(graph.walks[0].name(), "HG002#1#chr1");
+        let paths = vec!["P\tp2\t1+,,2-\t*"];
+        assert!(Graph::from_lines(&segments, &links, &[], &paths, &[])
+            .unwrap_err()
+            .starts_with("Invalid steps of path p2"));
+        let walks = vec!["W\tHG002\t2\tchr1\t0\t4\t>1 <2"];
+        assert!(Graph::from_lines(&segments, &links, &[], &[], &walks)
+            .unwrap_err()
+            .starts_with("Invalid steps of walk HG002#2#chr1"));
+        for invalid in ["S\t1\tA\nL\t1\t+\t1\n", "S\t1\tA\nW\ts\t1\tc\t0\n", "S\n"] {
+            assert!(Graph::from_gfa(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn test_split_pansn() {
+        assert_eq!(
+            split_pansn("HG002#1#chr1#0"),
+            Some(("HG002", "1", "chr1#0"))
+        );
+        assert_eq!(split_pansn("chr1"), None);
+        assert_eq!(split_pansn("HG002#a#chr1"), None);
+    }
+
+    #[test]
+    fn test_reverse_complement() {
+        assert_eq!(reverse_complement("AACGTn"), "nACGTT");
+    }
+
+    #[test]
+    fn test_segment_length() {
+        assert_eq!(segment_length("S\t1\tACGT\tLN:i:9"), 4);
+        assert_eq!(segment_length("S\t1\t*\tRC:i:3\tLN:i:9"), 9);
+        assert_eq!(segment_length("S\t1\t*"), 0);
+        let graph = Graph::from_gfa("S\t1\t*\tLN:i:9\nS\t2\t*\n").unwrap();
+        assert_eq!(graph.segments[0].length, 9);
+        let mut out = Vec::new();
+        graph.write_gfa(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "S\t1\t*\tLN:i:9\nS\t2\t*\n"
+        );
+    }
+
+    #[test]
+    fn test_write_tags() {
+        // LN is written again for segments without sequence only
+        let content = "S\t1\tACGT\tLN:i:4\tRC:i:7\nS\t2\t*\tLN:i:3\tdc:f:1.5\n\
+                       L\t1\t+\t2\t-\t2M\tID:Z:l1\nL\t2\t+\t1\t+\nJ\t1\t-\t2\t+\t10\n";
+        let graph = Graph::from_gfa(content).unwrap();
+        assert_eq!(graph.links[0].overlap, "2M");
+        let mut out = Vec::new();
+        graph.write_gfa(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "S\t1\tACGT\tRC:i:7\nS\t2\t*\tLN:i:3\tdc:f:1.5\n\
+             L\t1\t+\t2\t-\t2M\tID:Z:l1\nL\t2\t+\t1\t+\t*\nJ\t1\t-\t2\t+\t10\n"
+        );
+    }
+
+    #[test]
+    fn test_stable() {
+        let graph =
+            Graph::from_gfa("S\ts1\tACGT\tSN:Z:chr1\tSO:i:100\tSR:i:0\nS\ts2\tA\tSN:Z:chr1\n")
+                .unwrap();
+        assert_eq!(
+            graph.segments[0].stable,
+            Some(Stable {
+                name: "chr1".to_string(),
+                offset: 100,
+                rank: 0
+            })
+        );
+        assert_eq!(graph.segments[1].stable, None);
+        let mut out = Vec::new();
+        graph.write_gfa(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "S\ts1\tACGT\tSN:Z:chr1\tSO:i:100\tSR:i:0\nS\ts2\tA\n"
+        );
+    }
+
+    #[test]
+    fn test_topology() {
+        let content = "S\t1\tACGT\tSN:Z:chr1\tSO:i:0\tSR:i:0\nS\t2\t*\tLN:i:3\n\
+                       L\t1\t+\t2\t+\t0M\nP\tp\t1+,2+\t*\n";
+        let mut graph = Graph::from_gfa_topology(content).unwrap();
+        assert!(!graph.has_sequences());
+        assert_eq!(graph.segments[0].sequence, None);
+        assert!(graph.segments[0].loaded_sequence().is_err());
+        assert_eq!(graph.segments[0].length, 4);
+        assert!(graph.segments[0].stable.is_some());
+        let mut out = Vec::new();
+        graph.write_gfa(&mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("S\t1\t*\tLN:i:4\tSN:Z:chr1"));
+        assert_eq!(graph.load_sequences(content), 2);
+        assert!(graph.has_sequences());
+        assert_eq!(graph, Graph::from_gfa(content).unwrap());
+    }
+
+    #[test]
+    fn test_numeric_ids() {
+        let graph =
+            Graph::from_lines(&["S\t5\tA", "S\t2\tC"], &[], &[], &[] as &[&str], &[]).unwrap();
+        assert_eq!(graph.numeric_ids(), HashMap::from([("5", 5), ("2", 2)]));
+        let graph =
+            Graph::from_lines(&["S\ta\tA", "S\t2\tC"], &[], &[], &[] as &[&str], &[]).unwrap();
+        assert_eq!(graph.numeric_ids(), HashMap::from([("a", 1), ("2", 2)]));
+    }
+
+    #[test]
+    fn test_tokenize_path() {
+        let steps = |path| {
+            tokenize_path(path)
+                .unwrap()
+                .iter()
+                .map(|t| (t.name, t.forward, t.jump))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            steps(" 1+,2- ;\t3+ ,"),
+            vec![
+                ("1", true, Some(false)),
+                ("2", false, Some(true)),
+                ("3", true, None)
+            ]
+        );
+        assert_eq!(steps(""), vec![]);
+        let error = |path| tokenize_path(path).unwrap_err().to_string();
+        assert_eq!(error("1+, ,2+"), "Empty step at position 4");
+        assert_eq!(error(",1+"), "Empty step at position 0");
+        assert_eq!(
+            error("1+,2"),
+            "Step without orientation (+ or -) at position 3"
+        );
+        assert_eq!(error("1+,-"), "Step without segment name at position 3");
+        assert_eq!(
+            error("1+ 2+"),
+            "Missing separator (, or ;) between steps at position 3"
+        );
+    }
+
+    #[test]
+    fn test_walk_record() {
+        let walk = WalkRecord::parse("W\tHG002\t1\tchr1\t*\t12\t>s1<s2>3").unwrap();
+        assert_eq!((walk.seq_start, walk.seq_end), (None, Some(12)));
+        assert_eq!(walk.steps, vec![("s1", true), ("s2", false), ("3", true)]);
+        assert!(WalkRecord::parse("W\tHG002\t1\tchr1\t0\t0\t*")
+            .unwrap()
+            .steps
+            .is_empty());
+        let error = |line| WalkRecord::parse(line).unwrap_err();
+        assert_eq!(
+            error("W\tHG002\t1\tchr1\t0\t5\t>1<"),
+            "Invalid steps of walk HG002#1#chr1: Step without segment name at position 2"
+        );
+        assert_eq!(
+            error("W\tHG002\t1\tchr1\t0\t5\t1>2"),
+            "Invalid steps of walk HG002#1#chr1: Step without orientation (> or <) at position 0"
+        );
+        assert_eq!(
+            error("W\tHG002\ta\tchr1\t0\t5\t>1"),
+            "Invalid haplotype index of walk HG002#a#chr1: a"
+        );
+        assert_eq!(
+            error("W\tHG002\t1\tchr1\t5\t0\t>1"),
+            "Invalid end of walk HG002#1#chr1: 0 before start 5"
+        );
+        assert!(error("W\tHG002\t1\tchr1\t0\t5").starts_with("Walk with 6 instead of 7"));
+    }
+
+    #[test]
+    fn test_edge_record() {
+        let link = EdgeRecord::parse("L\t1\t+\t2\t-\t0M\tRC:i:4\t\tx:Z:a b").unwrap();
+        assert_eq!(link.edge, Edge::new("1", true, "2", false));
+        assert_eq!((link.overlap, link.tags), (Some("0M"), "RC:i:4\t\tx:Z:a b"));
+        let jump = EdgeRecord::parse("J\t1\t-\t2\t-").unwrap();
+        assert_eq!((jump.overlap, jump.tags), (None, ""));
+        let error = |line| EdgeRecord::parse(line).unwrap_err();
+        assert_eq!(
+            error("L\t1\t+\t2"),
+            "Link with 4 instead of at least 5 columns: L\t1\t+\t2"
+        );
+        assert_eq!(
+            error("J\t1\t+\t2\tx\t*"),
+            "Invalid to orientation of jump 1 + 2 x: 'x'"
+        );
+        assert_eq!(
+            error("L\t\t+\t2\t+\t0M"),
+            "Invalid from segment of link  + 2 +: ''"
+        );
+    }
+
+    proptest::proptest! {
+        /// Steps formatted according to the grammar of P lines, with any whitespace around the
+        /// separators, are tokenized back
+        #[test]
+        fn prop_tokenize_path(
+            steps in proptest::collection::vec(
+                ("[!-)+-<>-~&&[^,;]][!-~&&[^,;]]{0,8}", proptest::bool::ANY, proptest::bool::ANY),
+                1..20,
+            ),
+            spaces in proptest::collection::vec("[ \t]{0,2}", 40),
+            trailing in proptest::bool::ANY,
+        ) {
+            let mut path = String::new();
+            for (i, (name, forward, jump)) in steps.iter().enumerate() {
+                path.push_str(&spaces[2 * i]);
+                path.push_str(name);
+                path.push(if *forward { '+' } else { '-' });
+                path.push_str(&spaces[2 * i + 1]);
+                if i + 1 < steps.len() || trailing {
+                    path.push(if *jump { ';' } else { ',' });
+                }
+            }
+            let tokens = tokenize_path(&path).unwrap();
+            proptest::prop_assert_eq!(tokens.len(), steps.len());
+            for (i, (token, (name, forward, jump))) in tokens.iter().zip(&steps).enumerate() {
+                proptest::prop_assert_eq!(token.name, name.as_str());
+                proptest::prop_assert_eq!(token.forward, *forward);
+                let expected = (i + 1 < steps.len()).then_some(*jump);
+                proptest::prop_assert_eq!(token.jump, expected);
+            }
+        }
+
+        /// Any input is either tokenized or rejected with a position inside it
+        #[test]
+        fn prop_tokenize_path_errors(path in "[ -~\t]{0,30}") {
+            if let Err(e) = tokenize_path(&path) {
+                proptest::prop_assert!(e.position < path.len());
+            }
+        }
+
+        /// Walks following the grammar of W lines are tokenized back
+        #[test]
+        fn prop_tokenize_walk(
+            steps in proptest::collection::vec(("[!-;=?-~]{1,8}", proptest::bool::ANY), 0..20),
+        ) {
+            let walk = steps
+                .iter()
+                .map(|(name, forward)| format!("{}{}", if *forward { '>' } else { '<' }, name))
+                .collect::<String>();
+            let expected = steps
+                .iter()
+                .map(|(name, forward)| (name.as_str(), *forward))
+                .collect::<Vec<_>>();
+            proptest::prop_assert_eq!(tokenize_walk(&walk).unwrap(), expected);
+        }
+
+        /// Any input is either tokenized or rejected with a position inside it
+        #[test]
+        fn prop_tokenize_walk_errors(walk in "[ -~\t]{0,30}") {
+            if let Err(e) = tokenize_walk(&walk) {
+                proptest::prop_assert!(e.position < walk.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Selection of raw lines of a graph (`trim-graph grep`) by record type, regular expression
+//! over a field and tag presence, a structured alternative to awk.
+//!
+//! Fields are given by number, counted from 1 like awk does (the record type is field 1), or by
+//! name: `name`, `sequence` for S lines, `from`, `from_orientation`, `to`, `to_orientation`,
+//! `overlap` for L and J lines, `name`, `steps`, `overlaps` for P lines and `sample`, `hap`,
+//! `seq_id`, `start`, `end`, `steps` for W lines. A named field only matches on the record
+//! types having it. The `steps` of walks are matched in P line form (`1+,2-` for `>1<2`), so one
+//! expression finds a segment in a given orientation on paths and walks alike.
+
+use crate::graph::walk_line_steps;
+use crate::trim::RecordType;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// Type of a line, by its first character
+pub fn record_type(line: &str) -> RecordType {
+    match line.bytes().next() {
+        Some(b'H') => RecordType::Header,
+        Some(b'S') => RecordType::Segment,
+        Some(b'L') => RecordType::Link,
+        Some(b'J') => RecordType::Jump,
+        Some(b'P') => RecordType::Path,
+        Some(b'W') => RecordType::Walk,
+        _ => RecordType::Other,
+    }
+}
+
+/// Column of a named field on the record type, counted from 0
+fn named_field(record: RecordType, name: &str) -> Option<usize> {
+    let names: &[&str] = match record {
+        RecordType::Segment => &["name", "sequence"],
+        RecordType::Link | RecordType::Jump => &[
+            "from",
+            "from_orientation",
+            "to",
+            "to_orientation",
+            "overlap",
+        ],
+        RecordType::Path => &["name", "steps", "overlaps"],
+        RecordType::Walk => &["sample", "hap", "seq_id", "start", "end", "steps"],
+        RecordType::Header | RecordType::Other => &[],
+    };
+    names.iter().position(|n| *n == name).map(|i| i + 1)
+}
+
+/// First column holding tags on the record type
+fn first_tag(record: RecordType) -> Option<usize> {
+    match record {
+        RecordType::Header => Some(1),
+        RecordType::Segment => Some(3),
+        RecordType::Link | RecordType::Jump => Some(6),
+        RecordType::Path => Some(4),
+        RecordType::Walk => Some(7),
+        RecordType::Other => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LineFilter {
+    /// Record types to select, all if `None`
+    records: Option<HashSet<RecordType>>,
+    /// Field (whole line if `None`) and the expression it has to match
+    pattern: Option<(Option<String>, Regex)>,
+    /// Tags (`LN` or with type `LN:i`) that all have to be present
+    tags: Vec<String>,
+    invert: bool,
+}
+
+impl LineFilter {
+    pub fn new(
+        records: Option<HashSet<RecordType>>,
+        field: Option<String>,
+        regex: Option<&str>,
+        tags: Vec<String>,
+        invert: bool,
+    ) -> Result<Self, String> {
+        if let Some(field) = &field {
+            let known = field.parse::<usize>().is_ok_and(|n| n > 0)
+                || [
+                    RecordType::Segment,
+                    RecordType::Link,
+                    RecordType::Path,
+                    RecordType::Walk,
+                ]
+                .into_iter()
+                .any(|r| named_field(r, field).is_some());
+            if !known {
+                return Err(format!("Unknown field {}", field));
+            }
+        }
+        let pattern = match regex {
+            Some(regex) => Some((
+                field,
+                Regex::new(regex).map_err(|e| format!("Invalid expression {}: {}", regex, e))?,
+            )),
+            None if field.is_some() => return Err("--field needs an expression".to_string()),
+            None => None,
+        };
+        Ok(LineFilter {
+            records,
+            pattern,
+            tags,
+            invert,
+        })
+    }
+
+    /// Value of the field on the line, `None` if the line has no such field. Fails on the steps
+    /// of a walk that are invalid.
+    fn field<'a>(line: &'a str, field: &str) -> Result<Option<Cow<'a, str>>, String> {
+        let record = record_type(line);
+        let column = match field.parse::<usize>() {
+            Ok(number) => number.checked_sub(1),
+            Err(_) => named_field(record, field),
+        };
+        let Some(value) = column.and_then(|c| line.split('\t').nth(c)) else {
+            return Ok(None);
+        };
+        Ok(Some(match (record, column) {
+            (RecordType::Walk, Some(6)) => Cow::Owned(
+                walk_line_steps(line)?
+                    .iter()
+                    .map(|(node, forward)| format!("{}{}", node, if *forward { '+' } else { '-' }))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            _ => Cow::Borrowed(value),
+        }))
+    }
+
+    /// Whether the line is selected, fails if the field to match is the invalid steps of a walk
+    pub fn matches(&self, line: &str) -> Result<bool, String> {
+        let record = record_type(line);
+        if !self.records.as_ref().is_none_or(|r| r.contains(&record)) {
+            return Ok(self.invert);
+        }
+        let selected = match &self.pattern {
+            None => true,
+            Some((None, regex)) => regex.is_match(line),
+            Some((Some(field), regex)) => {
+                Self::field(line, field)?.is_some_and(|v| regex.is_match(&v))
+            }
+        } && self.tags.iter().all(|tag| {
+            first_tag(record).is_some_and(|first| {
+                line.split('\t').skip(first).any(|t| {
+                    t.strip_prefix(tag.as_str())
+                        .is_some_and(|rest| rest.starts_with(':'))
+                })
+            })
+        });
+        Ok(selected != self.invert)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_filter() {
+        let content = "H\tVN:Z:1.1\nS\t1\tACGT\tLN:i:4\nS\t12\tGG\nL\t1\t+\t12\t-\t0M\n\
+                       P\tref\t1+,12-\t*\nW\ts\t1\tchr\t0\t6\t>1<12\n# note 12-\n";
+        let grep = |filter: LineFilter| {
+            content
+                .lines()
+                .filter(|l| filter.matches(l).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let on_steps = LineFilter::new(
+            None,
+            Some("steps".to_string()),
+            Some("(^|,)12-"),
+            vec![],
+            false,
+        );
+        assert_eq!(
+            grep(on_steps.unwrap()),
+            vec!["P\tref\t1+,12-\t*", "W\ts\t1\tchr\t0\t6\t>1<12"]
+        );
+        let segments = HashSet::from([RecordType::Segment]);
+        let long = LineFilter::new(
+            Some(segments),
+            Some("3".to_string()),
+            Some("^.{3,}$"),
+            vec![],
+            false,
+        );
+        assert_eq!(grep(long.unwrap()), vec!["S\t1\tACGT\tLN:i:4"]);
+        let tagged = LineFilter::new(None, None, None, vec!["LN".to_string()], true).unwrap();
+        assert_eq!(grep(tagged).len(), 6);
+        assert!(
+            LineFilter::new(None, Some("length".to_string()), Some("."), vec![], false).is_err()
+        );
+        assert!(LineFilter::new(None, Some("name".to_string()), None, vec![], false).is_err());
+        let steps = LineFilter::new(None, Some("steps".to_string()), Some("."), vec![], false);
+        assert!(steps
+            .unwrap()
+            .matches("W\ts\t1\tchr\t0\t6\t>1<<12")
+            .is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Handle-based traversal API over a parsed graph, modelled after libhandlegraph.
+//!
+//! A [`Handle`] refers to a segment in one of its two orientations. Edges connect the end of
+//! one handle to the start of another, so an L/J line `a + b -` can be followed rightwards
+//! from `a+` to `b-` and rightwards from `b+` to `a-`. Links and jumps are both edges here.
+
+use crate::graph::{reverse_complement, Graph, Link};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Oriented segment, packed as `2 * rank + is_reverse` where rank is the segment's index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Handle(u64);
+
+impl Handle {
+    pub fn new(rank: usize, is_reverse: bool) -> Self {
+        Handle(((rank as u64) << 1) | is_reverse as u64)
+    }
+
+    /// Index of the segment in the graph
+    pub fn rank(&self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    pub fn is_reverse(&self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    /// Same segment in the opposite orientation
+    pub fn flip(&self) -> Self {
+        Handle(self.0 ^ 1)
+    }
+}
+
+/// Position on a path or walk: index of the path (paths first, then walks) and of the step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StepHandle {
+    pub path: usize,
+    pub rank: usize,
+}
+
+pub struct HandleGraph {
+    graph: Graph,
+    ranks: HashMap<String, usize>,
+    // Handles reachable by going right from a handle, indexed by the packed handle
+    right_edges: Vec<Vec<Handle>>,
+    edge_count: usize,
+    path_names: Vec<String>,
+    path_steps: Vec<Vec<Handle>>,
+    // Steps visiting a segment, indexed by segment rank
+    node_steps: Vec<Vec<StepHandle>>,
+}
+
+impl HandleGraph {
+    /// Handles of the graph, fails on a path or walk visiting a segment that is not in it
+    pub fn new(graph: Graph) -> Result<Self, String> {
+        let ranks = graph
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.name.clone(), i))
+            .collect::<HashMap<_, _>>();
+        let to_handle =
+            |node: &str, forward: bool| ranks.get(node).map(|rank| Handle::new(*rank, !forward));
+
+        let mut right_edges = vec![Vec::new(); graph.segments.len() * 2];
+        let mut seen = HashSet::new();
+        for Link {
+            from,
+            from_forward,
+            to,
+            to_forward,
+            ..
+        } in graph.links.iter().chain(graph.jumps.iter())
+        {
+            let (Some(left), Some(right)) =
+                (to_handle(from, *from_forward), to_handle(to, *to_forward))
+            else {
+                continue;
+            };
+            // An edge and its reverse are the same edge
+            if seen.contains(&(left, right)) || seen.contains(&(right.flip(), left.flip())) {
+                continue;
+            }
+            seen.insert((left, right));
+            right_edges[left.0 as usize].push(right);
+            if (right.flip(), left.flip()) != (left, right) {
+                right_edges[right.flip().0 as usize].push(left.flip());
+            }
+        }
+
+        let paths = graph
+            .paths
+            .iter()
+            .map(|p| (p.name.clone(), &p.steps))
+            .chain(graph.walks.iter().map(|w| (w.name(), &w.steps)));
+        let mut path_names = Vec::new();
+        let mut path_steps = Vec::new();
+        let mut node_steps = vec![Vec::new(); graph.segments.len()];
+        for (path, (name, steps)) in paths.enumerate() {
+            let handles = steps
+                .iter()
+                .map(|(node, forward)| {
+                    to_handle(node, *forward)
+                        .ok_or_else(|| format!("Path {} visits missing segment {}", name, node))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for (rank, handle) in handles.iter().enumerate() {
+                node_steps[handle.rank()].push(StepHandle { path, rank });
+            }
+            path_names.push(name);
+            path_steps.push(handles);
+        }
+
+        Ok(HandleGraph {
+            graph,
+            ranks,
+            right_edges,
+            edge_count: seen.len(),
+            path_names,
+            path_steps,
+            node_steps,
+        })
+    }
+
+    /// The underlying parsed graph
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.graph.segments.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    /// All segments in forward orientation
+    pub fn handles(&self) -> impl Iterator<Item = Handle> {
+        (0..self.node_count()).map(|rank| Handle::new(rank, false))
+    }
+
+    pub fn get_handle(&self, name: &str, is_reverse: bool) -> Option<Handle> {
+        self.ranks
+            .get(name)
+            .map(|rank| Handle::new(*rank, is_reverse))
+    }
+
+    /// Handle of an oriented segment written like a path step (`12+`, `12-`), a name without
+    /// orientation is taken as forward
+    pub fn parse_handle(&self, step: &str) -> Option<Handle> {
+        match step.strip_suffix(['+', '-']) {
+            Some(name) => self.get_handle(name, step.ends_with('-')),
+            None => self.get_handle(step, false),
+        }
+    }
+
+    pub fn get_name(&self, handle: Handle) -> &str {
+        &self.graph.segments[handle.rank()].name
+    }
+
+    pub fn get_length(&self, handle: Handle) -> usize {
+        self.graph.segments[handle.rank()].length
+    }
+
+    /// Sequence of the segment as read in the orientation of the handle, fails if it is not
+    /// loaded
+    pub fn get_sequence(&self, handle: Handle) -> Result<String, String> {
+        let sequence = self.graph.segments[handle.rank()].loaded_sequence()?;
+        Ok(if handle.is_reverse() {
+            reverse_complement(sequence)
+        } else {
+            sequence.to_string()
+        })
+    }
+
+    /// Handles adjacent to the given handle on its right side, or on its left side if
+    /// `go_left` is set
+    pub fn follow_edges(&self, handle: Handle, go_left: bool) -> impl Iterator<Item = Handle> + '_ {
+        let (from, flip) = if go_left {
+            (handle.flip(), true)
+        } else {
+            (handle, false)
+        };
+        self.right_edges[from.0 as usize]
+            .iter()
+            .map(move |h| if flip { h.flip() } else { *h })
+    }
+
+    pub fn degree(&self, handle: Handle, go_left: bool) -> usize {
+        self.follow_edges(handle, go_left).count()
+    }
+
+    /// Number of paths and walks
+    pub fn path_count(&self) -> usize {
+        self.path_names.len()
+    }
+
+    /// Index of the path or walk (PanSN name `sample#hap#contig`) with the given name
+    pub fn get_path(&self, name: &str) -> Option<usize> {
+        self.path_names.iter().position(|n| n == name)
+    }
+
+    pub fn get_path_name(&self, path: usize) -> &str {
+        &self.path_names[path]
+    }
+
+    /// Oriented segments visited by a path, in path order
+    pub fn path_handles(&self, path: usize) -> &[Handle] {
+        &self.path_steps[path]
+    }
+
+    /// All steps of any path visiting the segment, regardless of orientation
+    pub fn steps_on_node(&self, handle: Handle) -> &[StepHandle] {
+        &self.node_steps[handle.rank()]
+    }
+
+    pub fn get_step_handle(&self, step: StepHandle) -> Handle {
+        self.path_steps[step.path][step.rank]
+    }
+
+    pub fn next_step(&self, step: StepHandle) -> Option<StepHandle> {
+        (step.rank + 1 < self.path_steps[step.path].len()).then_some(StepHandle {
+            path: step.path,
+            rank: step.rank + 1,
+        })
+    }
+
+    pub fn prev_step(&self, step: StepHandle) -> Option<StepHandle> {
+        (step.rank > 0).then(|| StepHandle {
+            path: step.path,
+            rank: step.rank - 1,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> HandleGraph {
+        HandleGraph::new(
+            Graph::from_gfa(
+                "S\t1\tAAC\nS\t2\tG\nS\t3\tTT\n\
+             L\t1\t+\t2\t-\t0M\nL\t2\t-\t3\t+\t0M\nL\t3\t-\t2\t+\t0M\n\
+             P\tp\t1+,2-,3+\t*\n\
+             W\ts\t1\tc\t0\t3\t<3>1\n",
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_handles() {
+        let graph = example();
+        let h = graph.get_handle("1", true).unwrap();
+        assert_eq!(graph.get_name(h), "1");
+        assert_eq!(graph.parse_handle("1-"), Some(h));
+        assert_eq!(graph.parse_handle("1"), Some(h.flip()));
+        assert_eq!(graph.parse_handle("4+"), None);
+        assert!(h.is_reverse());
+        assert_eq!(h.flip().flip(), h);
+        assert_eq!(graph.get_sequence(h).unwrap(), "GTT");
+        assert_eq!(graph.get_length(h), 3);
+        assert_eq!(graph.node_count(), 3);
+        // the third link is the reverse of the second
+        assert_eq!(graph.edge_count(), 2);
+        let topology = HandleGraph::new(Graph::from_gfa_topology("S\t1\tA\n").unwrap()).unwrap();
+        let one = topology.get_handle("1", false).unwrap();
+        assert_eq!(
+            topology.get_sequence(one),
+            Err("Sequence of segment 1 is not loaded".to_string())
+        );
+        let missing = Graph::from_gfa("S\t1\tA\nP\tp\t1+,4+\t*\n").unwrap();
+        assert_eq!(
+            HandleGraph::new(missing).err(),
+            Some("Path p visits missing segment 4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_follow_edges() {
+        let graph = example();
+        let one = graph.get_handle("1", false).unwrap();
+        let two = graph.get_handle("2", false).unwrap();
+        let three = graph.get_handle("3", false).unwrap();
+        assert_eq!(
+            graph.follow_edges(one, false).collect::<Vec<_>>(),
+            vec![two.flip()]
+        );
+        assert_eq!(
+            graph.follow_edges(two.flip(), true).collect::<Vec<_>>(),
+            vec![one]
+        );
+        assert_eq!(
+            graph.follow_edges(two, false).collect::<Vec<_>>(),
+            vec![one.flip()]
+        );
+        assert_eq!(
+            graph.follow_edges(three, true).collect::<Vec<_>>(),
+            vec![two.flip()]
+        );
+        assert_eq!(graph.degree(three, false), 0);
+    }
+
+    #[test]
+    fn test_steps() {
+        let graph = example();
+        assert_eq!(graph.path_count(), 2);
+        let walk = graph.get_path("s#1#c").unwrap();
+        let one = graph.get_handle("1", false).unwrap();
+        let steps = graph.steps_on_node(one);
+        assert_eq!(
+            steps,
+            &[
+                StepHandle { path: 0, rank: 0 },
+                StepHandle {
+                    path: walk,
+                    rank: 1
+                }
+            ]
+        );
+        let next = graph.next_step(steps[0]).unwrap();
+        assert_eq!(graph.get_name(graph.get_step_handle(next)), "2");
+        assert!(graph.get_step_handle(next).is_reverse());
+        assert_eq!(graph.next_step(steps[1]), None);
+        assert_eq!(
+            graph.prev_step(steps[1]),
+            Some(StepHandle { path: 1, rank: 0 })
+        );
+        assert_eq!(
+            graph.path_handles(walk)[0],
+            graph.get_handle("3", true).unwrap()
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Graphs split into segments without sequence and a FASTA file of the sequences
+//! (`--sequences-fasta`, `trim-graph rehydrate`).
+//!
+//! Sequences make up most of a large graph but compress differently from its topology, so
+//! storing them in a FASTA file next to a graph with `*` in place of every sequence compresses
+//! and transfers better. The records are named like the segments and hold their sequence on a
+//! single line. Rehydrating fills them back in, giving the graph as it was before.
+//!
+//! Graphs that repeat the same sequence in many segments can store every sequence only once
+//! (`--dedup-sequences`): the records are then named by the SHA-1 of their sequence, which the
+//! segments reference with the same `SH:Z` tag `--checksum` adds. Rehydrating looks up segments
+//! without record of their own name by that tag and keeps it.
+
+use crate::digest::{hex_sha1, SEGMENT_TAG};
+use crate::external::parse_fasta;
+use crate::scan;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Segments written by [`dehydrate`] and the number of FASTA records for their sequences
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dehydrated {
+    pub segments: Vec<String>,
+    pub records: usize,
+}
+
+/// Writes the sequences of the segments to `fasta` and returns the segments with `*` instead.
+/// With `dedup`, every distinct sequence is written once under its SHA-1, which the segments
+/// get as `SH:Z` tag. Segments without sequence are left as they are.
+pub fn dehydrate<W: Write>(
+    segments: &[&str],
+    fasta: &mut W,
+    dedup: bool,
+) -> io::Result<Dehydrated> {
+    let mut dehydrated = Dehydrated {
+        segments: Vec::with_capacity(segments.len()),
+        records: 0,
+    };
+    let mut written = HashSet::new();
+    for line in segments {
+        let mut fields = line.split('\t').collect::<Vec<_>>();
+        let sequence = match fields.get(2) {
+            Some(&sequence) if sequence != "*" => sequence,
+            _ => {
+                dehydrated.segments.push(line.to_string());
+                continue;
+            }
+        };
+        fields[2] = "*";
+        if !dedup {
+            writeln!(fasta, ">{}\n{}", fields[1], sequence)?;
+            dehydrated.records += 1;
+            dehydrated.segments.push(fields.join("\t"));
+            continue;
+        }
+        let hash = hex_sha1(sequence.as_bytes());
+        if !written.contains(&hash) {
+            writeln!(fasta, ">{}\n{}", hash, sequence)?;
+            dehydrated.records += 1;
+        }
+        fields.retain(|field| !field.starts_with(SEGMENT_TAG));
+        dehydrated
+            .segments
+            .push(format!("{}\t{}{}", fields.join("\t"), SEGMENT_TAG, hash));
+        written.insert(hash);
+    }
+    Ok(dehydrated)
+}
+
+/// Segments filled in by [`rehydrate`] and records of the FASTA file without segment
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rehydrated {
+    pub segments: usize,
+    pub unused: usize,
+}
+
+/// Writes the graph with the sequence of every segment without one taken from the FASTA
+/// record of the same name, or else of the name of its `SH:Z` tag. Segments without record
+/// keep `*`, a record whose length contradicts the LN tag of its segment or that is found by
+/// a tag the SHA-1 of its sequence does not match is invalid.
+pub fn rehydrate<W: Write>(gfa: &str, fasta: &str, out: &mut W) -> io::Result<Rehydrated> {
+    let records = parse_fasta(fasta).into_iter().collect::<HashMap<_, _>>();
+    let mut rehydrated = Rehydrated::default();
+    let mut used = HashSet::new();
+    for line in scan::lines(gfa) {
+        let mut fields = line.split('\t').collect::<Vec<_>>();
+        let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        let hash = fields.iter().find_map(|tag| tag.strip_prefix(SEGMENT_TAG));
+        let record = match fields[..] {
+            ["S", name, "*", ..] => records
+                .get_key_value(name)
+                .map(|record| (record, false))
+                .or_else(|| Some((records.get_key_value(hash?)?, true))),
+            _ => None,
+        };
+        let Some(((name, sequence), by_hash)) = record else {
+            writeln!(out, "{}", line)?;
+            continue;
+        };
+        if by_hash && hex_sha1(sequence.as_bytes()) != *name {
+            return invalid(format!(
+                "Sequence of record {} for segment {} does not match its SHA-1",
+                name, fields[1]
+            ));
+        }
+        let length = fields
+            .iter()
+            .find_map(|tag| tag.strip_prefix("LN:i:"))
+            .and_then(|length| length.parse::<usize>().ok());
+        if length.is_some_and(|length| length != sequence.len()) {
+            return invalid(format!(
+                "Sequence of segment {} has {} bp, its LN tag says {}",
+                fields[1],
+                sequence.len(),
+                length.unwrap_or_default()
+            ));
+        }
+        fields[2] = sequence;
+        writeln!(out, "{}", fields.join("\t"))?;
+        rehydrated.segments += 1;
+        used.insert(name);
+    }
+    rehydrated.unused = records.len() - used.len();
+    Ok(rehydrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let gfa = "H\tVN:Z:1.0\nS\t1\tACGT\tLN:i:4\nS\t2\t*\tLN:i:3\nS\t3\tGG\n\
+                   L\t1\t+\t3\t+\t0M\nP\tp\t1+,3+\t*\n";
+        let lines = gfa.lines().collect::<Vec<_>>();
+        let mut fasta = Vec::new();
+        let segments = dehydrate(&lines[1..4], &mut fasta, false).unwrap().segments;
+        let fasta = String::from_utf8(fasta).unwrap();
+        assert_eq!(fasta, ">1\nACGT\n>3\nGG\n");
+        assert_eq!(segments, ["S\t1\t*\tLN:i:4", "S\t2\t*\tLN:i:3", "S\t3\t*"]);
+
+        let dehydrated = format!(
+            "H\tVN:Z:1.0\n{}\nL\t1\t+\t3\t+\t0M\nP\tp\t1+,3+\t*\n",
+            segments.join("\n")
+        );
+        let mut out = Vec::new();
+        let rehydrated = rehydrate(&dehydrated, &fasta, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), gfa);
+        assert_eq!(
+            rehydrated,
+            Rehydrated {
+                segments: 2,
+                unused: 0
+            }
+        );
+        assert!(rehydrate(&dehydrated, ">1\nACG\n", &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_dedup() {
+        let gfa = "S\t1\tACGT\nS\t2\tACGT\tSH:Z:0\nS\t3\tGG\n";
+        let lines = gfa.lines().collect::<Vec<_>>();
+        let mut fasta = Vec::new();
+        let dehydrated = dehydrate(&lines, &mut fasta, true).unwrap();
+        let fasta = String::from_utf8(fasta).unwrap();
+        let (acgt, gg) = (hex_sha1(b"ACGT"), hex_sha1(b"GG"));
+        assert_eq!(fasta, format!(">{}\nACGT\n>{}\nGG\n", acgt, gg));
+        assert_eq!(dehydrated.records, 2);
+        assert_eq!(dehydrated.segments[1], format!("S\t2\t*\tSH:Z:{}", acgt));
+
+        let mut out = Vec::new();
+        let rehydrated = rehydrate(&dehydrated.segments.join("\n"), &fasta, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "S\t1\tACGT\tSH:Z:{0}\nS\t2\tACGT\tSH:Z:{0}\nS\t3\tGG\tSH:Z:{1}\n",
+                acgt, gg
+            )
+        );
+        assert_eq!(rehydrated.segments, 3);
+        let tampered = fasta.replace("GG\n", "GA\n");
+        assert!(rehydrate(&dehydrated.segments.join("\n"), &tampered, &mut Vec::new()).is_err());
+    }
+}
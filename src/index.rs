@@ -0,0 +1,680 @@
+//! Sidecar index of a GFA file, written by `trim-graph index`.
+//!
+//! The index stores the byte span of every line, the length of every segment and the steps
+//! of all paths and walks as [`Handle`]s. Trimming against an index derives the segments and
+//! edges to keep from the stored steps instead of parsing the path lines again, and the spans
+//! give random access to single records. Coordinates on a path follow from its start and the
+//! lengths of the segments it visits (see [`GraphIndex::step_offsets`]).
+
+use crate::compress::Compression;
+use crate::extract::Region;
+use crate::graph::EdgeRecord;
+use crate::graph::{segment_length, split_pansn, tokenize_path, Graph, Path, Walk, WalkRecord};
+use crate::handle::Handle;
+use crate::scan;
+use crate::trim::{Candidate, GfaLines};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::time::UNIX_EPOCH;
+
+const MAGIC: &[u8; 4] = b"TGIX";
+/// Format version, bumped whenever the layout of [`GraphIndex`] or the meaning of its fields
+/// changes (4: edges covered in their reverse direction, 5: walk ends stored)
+pub const INDEX_VERSION: u32 = 5;
+/// Appended to the graph file name to get the default index file name
+pub const INDEX_EXTENSION: &str = ".idx";
+
+/// Byte range of a line in the (decompressed) indexed file, without the line break
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl Span {
+    /// Span of `line`, which has to be a slice of `content`
+    fn of(content: &str, line: &str) -> Self {
+        Span {
+            offset: (line.as_ptr() as usize - content.as_ptr() as usize) as u64,
+            length: line.len() as u64,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.offset as usize..(self.offset + self.length) as usize
+    }
+
+    /// The line in `content`, `None` if the span lies outside of it
+    pub fn slice<'a>(&self, content: &'a str) -> Option<&'a str> {
+        content.get(self.range())
+    }
+}
+
+/// Size and modification time of the indexed file, used to detect stale indexes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    /// Seconds since the Unix epoch
+    pub modified: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &str) -> io::Result<Self> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Ok(FileStamp {
+            size: metadata.len(),
+            modified,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedSegment {
+    pub name: String,
+    pub length: u64,
+    pub span: Span,
+}
+
+/// L or J line, `to` is reversed if the line reads `a + b -`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedEdge {
+    pub from: Handle,
+    pub to: Handle,
+    pub span: Span,
+}
+
+/// P or W line, walks are named in PanSN form `sample#hap#contig`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedPath {
+    pub name: String,
+    pub span: Span,
+    pub steps: Vec<Handle>,
+    /// Steps followed by a jump (`;`) instead of a link, only used by paths
+    pub jumps: Vec<usize>,
+    /// Coordinate of the first base (the walk start, 0 for paths)
+    pub start: u64,
+    /// Coordinate after the last base as given in the W line, `None` for paths and walks
+    /// without start or end (`*`)
+    pub end: Option<u64>,
+    /// Indices of the L lines covered by the path, in either direction
+    pub link_ids: Vec<usize>,
+    /// Indices of the J lines covered by the path, in either direction
+    pub jump_ids: Vec<usize>,
+}
+
+/// Records to keep, as flags parallel to the records of the index
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeepSet {
+    pub segments: Vec<bool>,
+    pub links: Vec<bool>,
+    pub jumps: Vec<bool>,
+    pub paths: Vec<bool>,
+    pub walks: Vec<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphIndex {
+    pub stamp: FileStamp,
+    /// Compression of the indexed file, spans always refer to the decompressed content
+    pub compression: Compression,
+    /// Size of the decompressed content
+    pub content_size: u64,
+    pub headers: Vec<Span>,
+    pub segments: Vec<IndexedSegment>,
+    pub links: Vec<IndexedEdge>,
+    pub jumps: Vec<IndexedEdge>,
+    pub paths: Vec<IndexedPath>,
+    pub walks: Vec<IndexedPath>,
+    pub others: Vec<Span>,
+}
+
+/// Indices of the edges by their handles, in both directions of the L/J line (`b+ -> a-` for
+/// `a+ -> b-`, see [`crate::edge::Edge`])
+fn edge_ids(edges: &[IndexedEdge]) -> HashMap<(Handle, Handle), Vec<usize>> {
+    let mut ids: HashMap<_, Vec<_>> = HashMap::new();
+    for (i, e) in edges.iter().enumerate() {
+        ids.entry((e.from, e.to)).or_default().push(i);
+        let reverse = (e.to.flip(), e.from.flip());
+        if reverse != (e.from, e.to) {
+            ids.entry(reverse).or_default().push(i);
+        }
+    }
+    ids
+}
+
+impl GraphIndex {
+    /// Indexes the (decompressed) content of a GFA file, the stamp and compression of the file
+    /// have to be set by the caller
+    pub fn build(content: &str) -> Result<Self, String> {
+        let lines = GfaLines::classify(content);
+        let span = |line: &&str| Span::of(content, line);
+
+        let segments = lines
+            .segments
+            .iter()
+            .map(|line| {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                let name = fields.get(1).ok_or("Segment without name")?;
+                Ok(IndexedSegment {
+                    name: name.to_string(),
+                    length: segment_length(line) as u64,
+                    span: span(line),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let ranks = segments
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.name.as_str(), i))
+            .collect::<HashMap<_, _>>();
+        let to_handle = |node: &str, forward: bool| {
+            ranks
+                .get(node)
+                .map(|rank| Handle::new(*rank, !forward))
+                .ok_or_else(|| format!("Unknown segment {}", node))
+        };
+
+        let edges = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|line| {
+                    let edge = EdgeRecord::parse(line)?.edge;
+                    Ok(IndexedEdge {
+                        from: to_handle(edge.from, edge.from_forward)?,
+                        to: to_handle(edge.to, edge.to_forward)?,
+                        span: span(line),
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()
+        };
+        let links = edges(&lines.links)?;
+        let jumps = edges(&lines.jumps)?;
+
+        let paths = lines
+            .paths
+            .iter()
+            .map(|line| {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                if fields.len() < 3 {
+                    return Err(format!("Invalid path line {}", line));
+                }
+                let tokens = tokenize_path(fields[2])
+                    .map_err(|e| format!("Invalid steps of path {}: {}", fields[1], e))?;
+                Ok(IndexedPath {
+                    name: fields[1].to_string(),
+                    span: span(line),
+                    steps: tokens
+                        .iter()
+                        .map(|t| to_handle(t.name, t.forward))
+                        .collect::<Result<_, _>>()?,
+                    // Positions of the steps followed by a jump
+                    jumps: tokens
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, t)| t.jump == Some(true))
+                        .map(|(i, _)| i)
+                        .collect(),
+                    start: 0,
+                    end: None,
+                    link_ids: Vec::new(),
+                    jump_ids: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let walks = lines
+            .walks
+            .iter()
+            .map(|line| {
+                let walk = WalkRecord::parse(line)?;
+                let name = line.split('\t').skip(1).take(3).collect::<Vec<_>>();
+                Ok(IndexedPath {
+                    name: name.join("#"),
+                    span: span(line),
+                    steps: walk
+                        .steps
+                        .iter()
+                        .map(|(node, forward)| to_handle(node, *forward))
+                        .collect::<Result<_, _>>()?,
+                    jumps: Vec::new(),
+                    start: walk.seq_start.unwrap_or(0) as u64,
+                    end: walk.seq_start.and(walk.seq_end).map(|end| end as u64),
+                    link_ids: Vec::new(),
+                    jump_ids: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut index = GraphIndex {
+            stamp: FileStamp::default(),
+            compression: Compression::None,
+            content_size: content.len() as u64,
+            headers: lines.headers.iter().map(span).collect(),
+            segments,
+            links,
+            jumps,
+            paths,
+            walks,
+            others: lines.others.iter().map(span).collect(),
+        };
+        index.cover_edges();
+        Ok(index)
+    }
+
+    /// Records for every path and walk which L/J lines it covers, so that the keep set of a
+    /// selection is a union of precomputed ids
+    fn cover_edges(&mut self) {
+        let link_ids = edge_ids(&self.links);
+        let jump_ids = edge_ids(&self.jumps);
+        for path in self.paths.iter_mut().chain(self.walks.iter_mut()) {
+            let jump_steps = path.jumps.iter().collect::<HashSet<_>>();
+            let mut links = BTreeSet::new();
+            let mut jumps = BTreeSet::new();
+            for (i, pair) in path.steps.windows(2).enumerate() {
+                let (ids, covered) = match jump_steps.contains(&i) {
+                    true => (&jump_ids, &mut jumps),
+                    false => (&link_ids, &mut links),
+                };
+                if let Some(ids) = ids.get(&(pair[0], pair[1])) {
+                    covered.extend(ids);
+                }
+            }
+            path.link_ids = links.into_iter().collect();
+            path.jump_ids = jumps.into_iter().collect();
+        }
+    }
+
+    /// Whether the index still describes the file with the given stamp
+    pub fn is_current(&self, stamp: &FileStamp) -> bool {
+        self.stamp == *stamp
+    }
+
+    /// Offsets of all steps of a path or walk, in the coordinates of the path
+    pub fn step_offsets(&self, path: &IndexedPath) -> Vec<u64> {
+        let mut offset = path.start;
+        path.steps
+            .iter()
+            .map(|h| {
+                let current = offset;
+                offset += self.segments[h.rank()].length;
+                current
+            })
+            .collect()
+    }
+
+    /// Names of the paths and walks that traverse any of the given nodes
+    pub fn paths_through(&self, nodes: &HashSet<String>) -> (Vec<String>, HashSet<String>) {
+        let ranks = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| nodes.contains(&s.name))
+            .map(|(i, _)| i)
+            .collect::<HashSet<_>>();
+        let through = |paths: &[IndexedPath]| {
+            paths
+                .iter()
+                .filter(|p| p.steps.iter().any(|h| ranks.contains(&h.rank())))
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>()
+        };
+        (
+            through(&self.paths),
+            through(&self.walks).into_iter().collect(),
+        )
+    }
+
+    /// Names of the paths and walks of the given samples, like
+    /// [`crate::trim::GfaLines::paths_of_samples`]
+    pub fn paths_of_samples(&self, samples: &HashSet<String>) -> (Vec<String>, HashSet<String>) {
+        let of_samples = |paths: &[IndexedPath]| {
+            paths
+                .iter()
+                .filter(|p| samples.contains(split_pansn(&p.name).map_or(p.name.as_str(), |s| s.0)))
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>()
+        };
+        (
+            of_samples(&self.paths),
+            of_samples(&self.walks).into_iter().collect(),
+        )
+    }
+
+    /// Ranks of the segments visited by a reference, like
+    /// [`crate::trim::GfaLines::reference_segments`]
+    pub fn reference_segments(&self, reference: &str) -> Result<HashSet<usize>, String> {
+        self.paths
+            .iter()
+            .chain(&self.walks)
+            .find(|p| p.name == reference)
+            .map(|p| p.steps.iter().map(|h| h.rank()).collect())
+            .ok_or_else(|| format!("Reference {} is no path or walk of the graph", reference))
+    }
+
+    /// Paths and walks among the given ones (all if `None`) with their lengths, like
+    /// [`crate::trim::GfaLines::candidates`]
+    pub fn candidates(
+        &self,
+        paths_to_keep: Option<&[String]>,
+        walks_to_keep: Option<&HashSet<String>>,
+        reference: Option<&HashSet<usize>>,
+    ) -> Vec<Candidate> {
+        let paths_to_keep =
+            paths_to_keep.map(|keep| keep.iter().map(|p| p.as_str()).collect::<HashSet<_>>());
+        let length = |path: &IndexedPath| {
+            path.steps
+                .iter()
+                .map(|h| self.segments[h.rank()].length as usize)
+                .sum()
+        };
+        let nonref = |path: &IndexedPath| {
+            reference.map_or(0, |reference| {
+                path.steps
+                    .iter()
+                    .filter(|h| !reference.contains(&h.rank()))
+                    .map(|h| self.segments[h.rank()].length as usize)
+                    .sum()
+            })
+        };
+        let paths = self
+            .paths
+            .iter()
+            .filter(|p| {
+                paths_to_keep
+                    .as_ref()
+                    .is_none_or(|keep| keep.contains(p.name.as_str()))
+            })
+            .map(|p| Candidate {
+                sample: split_pansn(&p.name)
+                    .map_or(p.name.as_str(), |s| s.0)
+                    .to_string(),
+                name: p.name.clone(),
+                is_walk: false,
+                length: length(p),
+                nonref: nonref(p),
+            });
+        let walks = self
+            .walks
+            .iter()
+            .filter(|w| walks_to_keep.is_none_or(|keep| keep.contains(&w.name)))
+            .map(|w| Candidate {
+                sample: w.name.split('#').next().unwrap_or("").to_string(),
+                name: w.name.clone(),
+                is_walk: true,
+                length: length(w),
+                nonref: nonref(w),
+            });
+        paths.chain(walks).collect()
+    }
+
+    /// Names of the walks overlapping any of the ranges on their contig, by the start and end
+    /// of the W lines like [`crate::trim::GfaLines::walks_in`]
+    pub fn walks_in(&self, ranges: &[Region]) -> HashSet<String> {
+        self.walks
+            .iter()
+            .filter(|w| {
+                let (start, Some(end)) = (w.start as usize, w.end) else {
+                    return false;
+                };
+                let end = end as usize;
+                let contig = w.name.splitn(3, '#').nth(2).unwrap_or("");
+                ranges
+                    .iter()
+                    .any(|r| r.path == contig && start < r.end && r.start < end)
+            })
+            .map(|w| w.name.clone())
+            .collect()
+    }
+
+    /// Records covered by the given paths and walks (all of them if `None`). Edges match in
+    /// either direction of the L/J line, like [`crate::trim::filter_edges`].
+    pub fn keep_set(
+        &self,
+        paths_to_keep: Option<&[String]>,
+        walks_to_keep: Option<&HashSet<String>>,
+    ) -> KeepSet {
+        let paths_to_keep = paths_to_keep.map(|keep| keep.iter().collect::<HashSet<_>>());
+        let paths = self
+            .paths
+            .iter()
+            .map(|p| {
+                paths_to_keep
+                    .as_ref()
+                    .is_none_or(|keep| keep.contains(&p.name))
+            })
+            .collect::<Vec<_>>();
+        let walks = self
+            .walks
+            .iter()
+            .map(|w| walks_to_keep.is_none_or(|keep| keep.contains(&w.name)))
+            .collect::<Vec<_>>();
+
+        let mut keep = KeepSet {
+            segments: vec![false; self.segments.len()],
+            links: vec![false; self.links.len()],
+            jumps: vec![false; self.jumps.len()],
+            paths,
+            walks,
+        };
+        let kept = self
+            .paths
+            .iter()
+            .zip(&keep.paths)
+            .chain(self.walks.iter().zip(&keep.walks))
+            .filter(|(_, keep)| **keep)
+            .map(|(p, _)| p);
+        for path in kept {
+            for h in &path.steps {
+                keep.segments[h.rank()] = true;
+            }
+            for id in &path.link_ids {
+                keep.links[*id] = true;
+            }
+            for id in &path.jump_ids {
+                keep.jumps[*id] = true;
+            }
+        }
+        keep
+    }
+
+    /// Spans of all lines to keep, including headers and other lines
+    pub fn kept_spans(&self, keep: &KeepSet) -> Vec<Span> {
+        let flagged = |spans: &mut dyn Iterator<Item = Span>, flags: &[bool]| {
+            spans
+                .zip(flags)
+                .filter(|(_, keep)| **keep)
+                .map(|(span, _)| span)
+                .collect::<Vec<_>>()
+        };
+        let mut spans = self.headers.clone();
+        spans.extend(flagged(
+            &mut self.segments.iter().map(|s| s.span),
+            &keep.segments,
+        ));
+        spans.extend(flagged(&mut self.paths.iter().map(|p| p.span), &keep.paths));
+        spans.extend(flagged(&mut self.walks.iter().map(|w| w.span), &keep.walks));
+        spans.extend(flagged(&mut self.links.iter().map(|l| l.span), &keep.links));
+        spans.extend(flagged(&mut self.jumps.iter().map(|j| j.span), &keep.jumps));
+        spans.extend(&self.others);
+        spans
+    }
+
+    /// The graph of the indexed content, with the steps of the paths and walks taken from the
+    /// index instead of parsing their lines again. Fails if the content does not match the
+    /// index.
+    pub fn to_graph(&self, content: &str) -> Result<Graph, String> {
+        if content.len() as u64 != self.content_size {
+            return Err(
+                "Index does not match the graph, rebuild it with trim-graph index".to_string(),
+            );
+        }
+        let lines = |spans: &mut dyn Iterator<Item = &Span>| {
+            spans
+                .map(|span| {
+                    span.slice(content)
+                        .ok_or_else(|| "Index does not match the graph".to_string())
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let mut graph = Graph::from_lines::<_, &str>(
+            &lines(&mut self.segments.iter().map(|s| &s.span))?,
+            &lines(&mut self.links.iter().map(|l| &l.span))?,
+            &lines(&mut self.jumps.iter().map(|j| &j.span))?,
+            &[],
+            &[],
+        )?;
+        let steps = |path: &IndexedPath| {
+            path.steps
+                .iter()
+                .map(|h| (self.segments[h.rank()].name.clone(), !h.is_reverse()))
+                .collect::<Vec<_>>()
+        };
+        graph.paths = self
+            .paths
+            .iter()
+            .map(|p| Path {
+                name: p.name.clone(),
+                steps: steps(p),
+            })
+            .collect();
+        // The other columns of the walks are cut from their lines as written
+        graph.walks = self
+            .walks
+            .iter()
+            .zip(lines(&mut self.walks.iter().map(|w| &w.span))?)
+            .map(|(w, line)| {
+                let fields = scan::fields(line).take(6).collect::<Vec<_>>();
+                if fields.len() < 6 {
+                    return Err("Index does not match the graph".to_string());
+                }
+                Ok(Walk {
+                    sample: fields[1].to_string(),
+                    hap_index: fields[2].to_string(),
+                    seq_id: fields[3].to_string(),
+                    seq_start: fields[4].to_string(),
+                    seq_end: fields[5].to_string(),
+                    steps: steps(w),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(graph)
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&INDEX_VERSION.to_le_bytes())?;
+        bincode::serialize_into(out, self).map_err(io::Error::other)
+    }
+
+    pub fn read<R: Read>(input: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a trim-graph index",
+            ));
+        }
+        let mut version = [0; 4];
+        input.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != INDEX_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Index has version {}, expected {}, rebuild it with trim-graph index",
+                    version, INDEX_VERSION
+                ),
+            ));
+        }
+        bincode::deserialize_from(input).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GFA: &str = "H\tVN:Z:1.1\nS\t1\tAAAA\nS\t2\tC\nS\t3\tGG\nS\t4\tT\n\
+                       L\t1\t+\t2\t-\t0M\nL\t3\t+\t2\t+\t0M\nL\t2\t+\t4\t+\t0M\n\
+                       J\t2\t-\t3\t+\t*\t*\n\
+                       P\tp1\t1+,2-;3+\t*\nP\tp2\t2+,4+\t*\n\
+                       W\ts\t1\tc\t5\t11\t>1<2\n";
+
+    #[test]
+    fn test_build() {
+        let index = GraphIndex::build(GFA).unwrap();
+        assert_eq!(index.segments.len(), 4);
+        assert_eq!(&GFA[index.segments[2].span.range()], "S\t3\tGG");
+        assert_eq!(index.paths[0].jumps, vec![1]);
+        assert_eq!(index.paths[0].link_ids, vec![0]);
+        assert_eq!(index.paths[0].jump_ids, vec![0]);
+        assert_eq!(index.paths[1].link_ids, vec![2]);
+        assert_eq!(index.walks[0].name, "s#1#c");
+        assert_eq!(index.step_offsets(&index.walks[0]), vec![5, 9]);
+        assert!(GraphIndex::build("S\t1\tA\nP\tp\t1+,2+\t*\n").is_err());
+        // 2- 1- traverses 1+ 2+ from the other strand, 2+ 1+ is another edge
+        let index = GraphIndex::build(
+            "S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t1\t+\t0M\nP\tp\t2-,1-\t*\n",
+        )
+        .unwrap();
+        assert_eq!(index.paths[0].link_ids, vec![0]);
+    }
+
+    #[test]
+    fn test_keep_set() {
+        let index = GraphIndex::build(GFA).unwrap();
+        let keep = index.keep_set(Some(&["p1".to_string()]), None);
+        assert_eq!(keep.segments, vec![true, true, true, false]);
+        assert_eq!(keep.links, vec![true, false, false]);
+        assert_eq!(keep.jumps, vec![true]);
+        assert_eq!(keep.paths, vec![true, false]);
+        assert_eq!(keep.walks, vec![true]);
+    }
+
+    #[test]
+    fn test_paths_through() {
+        let index = GraphIndex::build(GFA).unwrap();
+        let (paths, walks) = index.paths_through(&HashSet::from(["4".to_string()]));
+        assert_eq!(paths, vec!["p2"]);
+        assert!(walks.is_empty());
+        let (paths, walks) = index.paths_through(&HashSet::from(["1".to_string()]));
+        assert_eq!(paths, vec!["p1"]);
+        assert_eq!(walks, HashSet::from(["s#1#c".to_string()]));
+    }
+
+    #[test]
+    fn test_walks_in() {
+        // The walk ends at 11 by its W line, at 10 by the lengths of its segments
+        let index = GraphIndex::build(GFA).unwrap();
+        let lines = crate::trim::GfaLines::classify(GFA);
+        for (range, walks) in [("c:10-12", 1), ("c:11-12", 0), ("c:0-6", 1), ("d:0-20", 0)] {
+            let ranges = [range.parse().unwrap()];
+            assert_eq!(index.walks_in(&ranges), lines.walks_in(&ranges));
+            assert_eq!(index.walks_in(&ranges).len(), walks);
+        }
+        let index = GraphIndex::build("S\t1\tA\nW\ts\t1\tc\t*\t1\t>1\n").unwrap();
+        assert!(index.walks_in(&["c:0-5".parse().unwrap()]).is_empty());
+    }
+
+    #[test]
+    fn test_to_graph() {
+        let index = GraphIndex::build(GFA).unwrap();
+        assert_eq!(index.to_graph(GFA).unwrap(), Graph::from_gfa(GFA).unwrap());
+        assert!(index.to_graph(&GFA[1..]).is_err());
+    }
+
+    #[test]
+    fn test_write_read() {
+        let index = GraphIndex::build(GFA).unwrap();
+        let mut buffer = Vec::new();
+        index.write(&mut buffer).unwrap();
+        assert_eq!(GraphIndex::read(&mut buffer.as_slice()).unwrap(), index);
+        assert!(GraphIndex::read(&mut &b"GFA"[..]).is_err());
+    }
+}
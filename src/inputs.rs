@@ -0,0 +1,125 @@
+//! Expansion of glob patterns and directories given as the graph (`--output-dir`).
+//!
+//! Graph repositories are often split by chromosome into many files. A directory stands for
+//! all graph files below it, recursively, and a pattern like `graphs/**/*.gfa.gz` for the files
+//! it matches. Every file keeps its path relative to the directory, or to the part of the
+//! pattern before the first wildcard, so the trimmed graphs can be written to the same
+//! structure under another directory.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Extensions of the graph files collected from directories
+pub const GRAPH_EXTENSIONS: [&str; 4] = [".gfa", ".gfa.gz", ".gfa.bgz", ".gfa.zst"];
+
+/// Graph file found for a directory or pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphInput {
+    pub path: PathBuf,
+    /// Path below the directory or the fixed part of the pattern
+    pub relative: PathBuf,
+}
+
+/// Whether the graph argument is a directory or a glob pattern instead of a single file or URL
+pub fn is_multiple(input: &str) -> bool {
+    let path = Path::new(input);
+    !input.contains("://") && (path.is_dir() || (!path.exists() && input.contains(['*', '?', '['])))
+}
+
+fn is_graph_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    GRAPH_EXTENSIONS.iter().any(|e| name.ends_with(e))
+}
+
+/// Graph files below the directory, recursively
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if is_graph_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Graph files of a directory or glob pattern, sorted by path. Patterns match any file,
+/// directories only contribute files with one of the [`GRAPH_EXTENSIONS`].
+pub fn graph_inputs(input: &str) -> io::Result<Vec<GraphInput>> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+    let (base, mut files) = match Path::new(input).is_dir() {
+        true => {
+            let mut files = Vec::new();
+            walk(Path::new(input), &mut files)?;
+            (PathBuf::from(input), files)
+        }
+        false => {
+            let base = Path::new(input)
+                .components()
+                .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+                .collect::<PathBuf>();
+            let files = glob::glob(input)
+                .map_err(|e| invalid(format!("Invalid pattern {}: {}", input, e)))?
+                .filter_map(|path| path.ok())
+                .filter(|path| path.is_file())
+                .collect();
+            (base, files)
+        }
+    };
+    files.sort();
+    if files.is_empty() {
+        return Err(invalid(format!("{} contains no graph files", input)));
+    }
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let relative = path
+                .strip_prefix(&base)
+                .unwrap_or(&path)
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect();
+            GraphInput { path, relative }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_inputs() {
+        let dir = std::env::temp_dir().join(format!("trim-graph-inputs-{}", std::process::id()));
+        for file in ["chr1.gfa", "sub/chr2.gfa.gz", "sub/notes.txt"] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let root = dir.to_str().unwrap();
+        assert!(is_multiple(root));
+        let relative = |inputs: Vec<GraphInput>| {
+            inputs
+                .into_iter()
+                .map(|i| i.relative.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            relative(graph_inputs(root).unwrap()),
+            ["chr1.gfa", "sub/chr2.gfa.gz"]
+        );
+        let pattern = format!("{}/**/*.gfa*", root);
+        assert!(is_multiple(&pattern) && !is_multiple(&format!("{}/chr1.gfa", root)));
+        assert!(!is_multiple(
+            "https://example.org/graph.gfa?X-Amz-Signature=1"
+        ));
+        assert_eq!(
+            relative(graph_inputs(&pattern).unwrap()),
+            ["chr1.gfa", "sub/chr2.gfa.gz"]
+        );
+        assert!(graph_inputs(&format!("{}/*.vg", root)).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use crate::graph::Graph;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+// Protobuf's JSON mapping encodes 64 bit integers as strings, vg follows this for ids and ranks
+
+#[derive(Serialize)]
+struct VgGraph {
+    node: Vec<VgNode>,
+    edge: Vec<VgEdge>,
+    path: Vec<VgPath>,
+}
+
+#[derive(Serialize)]
+struct VgNode {
+    sequence: String,
+    id: String,
+}
+
+#[derive(Serialize)]
+struct VgEdge {
+    from: String,
+    to: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    from_start: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    to_end: bool,
+}
+
+#[derive(Serialize)]
+struct VgPath {
+    name: String,
+    mapping: Vec<VgMapping>,
+}
+
+#[derive(Serialize)]
+struct VgMapping {
+    position: VgPosition,
+    edit: Vec<VgEdit>,
+    rank: String,
+}
+
+#[derive(Serialize)]
+struct VgPosition {
+    node_id: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_reverse: bool,
+}
+
+#[derive(Serialize)]
+struct VgEdit {
+    from_length: usize,
+    to_length: usize,
+}
+
+fn to_vg_path(
+    name: String,
+    steps: &[(String, bool)],
+    ids: &HashMap<&str, u64>,
+    lengths: &HashMap<&str, usize>,
+) -> io::Result<VgPath> {
+    let mapping = steps
+        .iter()
+        .enumerate()
+        .map(|(rank, (node, forward))| {
+            let id = ids.get(node.as_str()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Path {name} visits segment {node} which is not in the graph"),
+                )
+            })?;
+            let length = lengths.get(node.as_str()).copied().unwrap_or(0);
+            Ok(VgMapping {
+                position: VgPosition {
+                    node_id: id.to_string(),
+                    is_reverse: !forward,
+                },
+                edit: vec![VgEdit {
+                    from_length: length,
+                    to_length: length,
+                }],
+                rank: (rank + 1).to_string(),
+            })
+        })
+        .collect::<io::Result<_>>()?;
+    Ok(VgPath { name, mapping })
+}
+
+fn to_vg_graph(graph: &Graph) -> io::Result<VgGraph> {
+    let ids = graph.numeric_ids();
+    let lengths = graph
+        .segments
+        .iter()
+        .map(|s| (s.name.as_str(), s.length))
+        .collect::<HashMap<_, _>>();
+    let node = graph
+        .segments
+        .iter()
+        .map(|s| {
+            Ok(VgNode {
+                sequence: s
+                    .loaded_sequence()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+                    .to_string(),
+                id: ids[s.name.as_str()].to_string(),
+            })
+        })
+        .collect::<io::Result<_>>()?;
+    if !graph.jumps.is_empty() {
+        log::info!(
+            "vg has no jumps, writing {} jumps as edges",
+            graph.jumps.len()
+        );
+    }
+    let edge = graph
+        .links
+        .iter()
+        .chain(graph.jumps.iter())
+        .filter(|l| ids.contains_key(l.from.as_str()) && ids.contains_key(l.to.as_str()))
+        .map(|l| VgEdge {
+            from: ids[l.from.as_str()].to_string(),
+            to: ids[l.to.as_str()].to_string(),
+            from_start: !l.from_forward,
+            to_end: !l.to_forward,
+        })
+        .collect();
+    let path = graph
+        .paths
+        .iter()
+        .map(|p| to_vg_path(p.name.clone(), &p.steps, &ids, &lengths))
+        .chain(
+            graph
+                .walks
+                .iter()
+                .map(|w| to_vg_path(w.name(), &w.steps, &ids, &lengths)),
+        )
+        .collect::<io::Result<_>>()?;
+    Ok(VgGraph { node, edge, path })
+}
+
+/// Writes the graph in the JSON graph format used by vg (`vg view -j`)
+pub fn write_json<W: Write>(out: &mut W, graph: &Graph) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, &to_vg_graph(graph)?)?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_json() {
+        let graph = Graph::from_lines(
+            &["S\t1\tACG", "S\t2\tT"],
+            &["L\t1\t+\t2\t-\t0M"],
+            &[],
+            &["P\tp1\t1+,2-\t*"],
+            &[],
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        write_json(&mut out, &graph).unwrap();
+        let expected = concat!(
+            r#"{"node":[{"sequence":"ACG","id":"1"},{"sequence":"T","id":"2"}],"#,
+            r#""edge":[{"from":"1","to":"2","to_end":true}],"#,
+            r#""path":[{"name":"p1","mapping":["#,
+            r#"{"position":{"node_id":"1"},"edit":[{"from_length":3,"to_length":3}],"rank":"1"},"#,
+            r#"{"position":{"node_id":"2","is_reverse":true},"edit":[{"from_length":1,"to_length":1}],"rank":"2"}"#,
+            r#"]}]}"#,
+            "\n"
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_json_missing_segment() {
+        let graph = Graph::from_lines(&["S\t1\tACG"], &[], &[], &["P\tp1\t1+,2-\t*"], &[]).unwrap();
+        let error = write_json(&mut Vec::new(), &graph).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("Path p1 visits segment 2"));
+    }
+}
@@ -0,0 +1,205 @@
+//! Keep sets saved to a file (`--save-keepset`, `--load-keepset`).
+//!
+//! The segments, links and jumps covered by the kept paths and walks only depend on their
+//! steps, so the keep set computed once can be applied to related graphs with the same segment
+//! names, e.g. the same graph with and without sequences, without parsing the paths again. The
+//! file also names the paths and walks it was computed for, which are the ones kept when it
+//! is loaded.
+//!
+//! `trim-graph apply` uses a saved keep set as a pure line filter: every line is kept or
+//! dropped on its own, in input order, so a graph of any size streams through with the memory
+//! of the keep set only.
+
+use crate::edge::Edge;
+use crate::grep::record_type;
+use crate::scan;
+use crate::trim::{get_nodes_edges, walk_name, Edges, Nodes, RecordType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Read, Write};
+
+const MAGIC: &[u8; 4] = b"TGKS";
+/// Format version, bumped whenever the layout of [`SavedKeepSet`] changes
+pub const KEEPSET_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedKeepSet {
+    /// Names of the kept paths
+    pub paths: Vec<String>,
+    /// PanSN names of the kept walks
+    pub walks: Vec<String>,
+    pub nodes: Nodes,
+    pub links: Edges,
+    pub jumps: Edges,
+}
+
+impl SavedKeepSet {
+    /// Keep set of the given P and W lines
+    pub fn of(paths: &Vec<String>, walks: &Vec<String>) -> Result<Self, String> {
+        let (nodes, links, jumps) = get_nodes_edges(paths, walks)?;
+        Ok(SavedKeepSet {
+            paths: paths
+                .iter()
+                .map(|p| scan::field(p, 1).unwrap_or("").to_string())
+                .collect(),
+            walks: walks.iter().map(|w| walk_name(w)).collect(),
+            nodes,
+            links,
+            jumps,
+        })
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&KEEPSET_VERSION.to_le_bytes())?;
+        bincode::serialize_into(out, self).map_err(io::Error::other)
+    }
+
+    pub fn read<R: Read>(input: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a trim-graph keep set",
+            ));
+        }
+        let mut version = [0; 4];
+        input.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != KEEPSET_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Keep set has version {}, expected {}, save it again with --save-keepset",
+                    version, KEEPSET_VERSION
+                ),
+            ));
+        }
+        bincode::deserialize_from(input).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Lines written and dropped by [`apply_keepset`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Applied {
+    pub kept: usize,
+    pub removed: usize,
+}
+
+/// Copies the lines of `input` the keep set keeps to `out`: segments, links and jumps of the
+/// keep set, its paths and walks and all other lines. Line endings are written as read.
+pub fn apply_keepset<R: BufRead, W: Write>(
+    keep: &SavedKeepSet,
+    mut input: R,
+    out: &mut W,
+) -> io::Result<Applied> {
+    let paths = keep
+        .paths
+        .iter()
+        .map(|p| p.as_str())
+        .collect::<HashSet<_>>();
+    let walks = keep
+        .walks
+        .iter()
+        .map(|w| w.as_str())
+        .collect::<HashSet<_>>();
+    let edge = |line: &str, edges: &Edges| match Edge::from_line(line) {
+        Some(edge) => Ok(edges.contains(&edge.into_owned())),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Edge without two oriented segments: {}", line),
+        )),
+    };
+    let mut applied = Applied::default();
+    let mut line = String::new();
+    while input.read_line(&mut line)? > 0 {
+        let record = line.trim_end_matches(['\n', '\r']);
+        let kept = match record_type(record) {
+            RecordType::Segment => scan::field(record, 1).is_some_and(|n| keep.nodes.contains(n)),
+            RecordType::Link => edge(record, &keep.links)?,
+            RecordType::Jump => edge(record, &keep.jumps)?,
+            RecordType::Path => scan::field(record, 1).is_some_and(|n| paths.contains(n)),
+            RecordType::Walk => walks.contains(walk_name(record).as_str()),
+            RecordType::Header | RecordType::Other => true,
+        };
+        match kept {
+            true => {
+                out.write_all(line.as_bytes())?;
+                applied.kept += 1;
+            }
+            false => applied.removed += 1,
+        }
+        line.clear();
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, trim_gfa_with, GfaLines, TrimOptions};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_keep_set_round_trip() {
+        let gfa = "S\t1\tA\nS\t2\tC\nS\t3\tG\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\n\
+                   P\tp1\t1+,2+\t*\nP\tp2\t2+,3+\t*\nW\ts\t1\tc\t0\t2\t>2>3\n";
+        let paths = vec!["P\tp1\t1+,2+\t*".to_string()];
+        let keep = SavedKeepSet::of(&paths, &Vec::new()).unwrap();
+        assert_eq!(keep.paths, vec!["p1"]);
+        let mut file = Vec::new();
+        keep.write(&mut file).unwrap();
+        let loaded = SavedKeepSet::read(&mut file.as_slice()).unwrap();
+        assert_eq!(loaded, keep);
+
+        // Applied to the graph without sequences, it keeps the same lines
+        let without_sequences = gfa.replace("\tA\n", "\t*\n").replace("\tC\n", "\t*\n");
+        let options = TrimOptions {
+            walks_to_keep: Some(loaded.walks.iter().cloned().collect::<HashSet<_>>()),
+            ..TrimOptions::default()
+        };
+        let trimmed = trim_gfa_with(
+            GfaLines::classify(&without_sequences),
+            Some(loaded.paths.clone()),
+            &options,
+            |_, _| {
+                Ok::<_, String>((
+                    loaded.nodes.clone(),
+                    loaded.links.clone(),
+                    loaded.jumps.clone(),
+                ))
+            },
+        )
+        .unwrap();
+        let expected = trim_gfa(GfaLines::classify(gfa), Some(keep.paths), &options).unwrap();
+        assert_eq!(trimmed.segments, vec!["S\t1\t*", "S\t2\t*"]);
+        assert_eq!(trimmed.links, expected.links);
+        assert_eq!(trimmed.paths, expected.paths);
+        assert!(trimmed.walks.is_empty());
+        assert!(SavedKeepSet::read(&mut &b"TGIX\x01\0\0\0"[..]).is_err());
+    }
+
+    #[test]
+    fn test_apply_keepset() {
+        let gfa = "H\tVN:Z:1.0\nS\t1\tA\nL\t2\t-\t1\t-\t0M\nS\t2\tC\nS\t3\tG\n\
+                   L\t2\t+\t3\t+\t0M\nP\tp1\t1+,2+\t*\nP\tp2\t2+,3+\t*\n\
+                   W\ts\t1\tc\t0\t2\t>2>3\n";
+        let paths = vec!["P\tp1\t1+,2+\t*".to_string()];
+        let keep = SavedKeepSet::of(&paths, &Vec::new()).unwrap();
+        let mut out = Vec::new();
+        let applied = apply_keepset(&keep, gfa.as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.0\nS\t1\tA\nL\t2\t-\t1\t-\t0M\nS\t2\tC\nP\tp1\t1+,2+\t*\n"
+        );
+        assert_eq!(
+            applied,
+            Applied {
+                kept: 5,
+                removed: 4
+            }
+        );
+        assert!(apply_keepset(&keep, "L\t1\t+\n".as_bytes(), &mut Vec::new()).is_err());
+    }
+}
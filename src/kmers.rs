@@ -0,0 +1,191 @@
+//! Canonical k-mers of a graph, e.g. for k-mer based genotypers.
+//!
+//! The k-mers are spelled by all walks through the segments and links of the graph in both
+//! orientations. Jumps are not followed, since their ends are not adjacent in sequence.
+//! k-mers containing other bases than ACGT are skipped.
+
+use crate::graph::{reverse_complement, Graph};
+use crate::handle::{Handle, HandleGraph};
+use crate::par::{IntoParallelIterator, ParallelIterator};
+use crate::trim::flatten_into_hashset;
+use std::io::{self, Write};
+
+/// Lexicographically smaller of the k-mer and its reverse complement
+pub fn canonical(kmer: &str) -> String {
+    let reverse = reverse_complement(kmer);
+    match reverse.as_str() < kmer {
+        true => reverse,
+        false => kmer.to_string(),
+    }
+}
+
+/// Extends `prefix` by `missing` bases along all walks leaving `handle` on the right. Runs of
+/// more than `empty` steps through handles without sequence are not followed, they visit one of
+/// them twice and spell nothing new.
+fn extend(
+    graph: &HandleGraph,
+    sequences: &[String],
+    handle: Handle,
+    prefix: &str,
+    missing: usize,
+    empty: usize,
+    kmers: &mut Vec<String>,
+) {
+    // Handle, bases so far, bases missing and steps since the last base
+    let mut stack = vec![(handle, prefix.to_string(), missing, 0)];
+    while let Some((handle, prefix, missing, run)) = stack.pop() {
+        for next in graph.follow_edges(handle, false) {
+            let sequence = &sequences[2 * next.rank() + next.is_reverse() as usize];
+            let taken = missing.min(sequence.len());
+            let extended = format!("{}{}", prefix, &sequence[..taken]);
+            let run = if taken == 0 { run + 1 } else { 0 };
+            if taken == missing {
+                kmers.push(extended);
+            } else if run <= empty {
+                stack.push((next, extended, missing - taken, run));
+            }
+        }
+    }
+}
+
+/// Sorted canonical k-mers of the graph, fails on a path or walk visiting a missing segment
+pub fn graph_kmers(graph: Graph, k: usize) -> Result<Vec<String>, String> {
+    let graph = HandleGraph::new(Graph {
+        jumps: Vec::new(),
+        ..graph
+    })?;
+    let handles = graph
+        .handles()
+        .flat_map(|h| [h, h.flip()])
+        .collect::<Vec<_>>();
+    let sequences = handles
+        .iter()
+        .map(|h| Ok(graph.get_sequence(*h)?.to_ascii_uppercase()))
+        .collect::<Result<Vec<_>, String>>()?;
+    let empty = sequences.iter().filter(|s| s.is_empty()).count();
+    let kmers = handles
+        .into_par_iter()
+        .map(|handle| {
+            let sequence = &sequences[2 * handle.rank() + handle.is_reverse() as usize];
+            let mut kmers = Vec::new();
+            for start in 0..sequence.len() {
+                let prefix = &sequence[start..sequence.len().min(start + k)];
+                if prefix.len() == k {
+                    kmers.push(prefix.to_string());
+                } else {
+                    let missing = k - prefix.len();
+                    extend(
+                        &graph, &sequences, handle, prefix, missing, empty, &mut kmers,
+                    );
+                }
+            }
+            kmers
+                .into_iter()
+                .filter(|kmer| kmer.bytes().all(|b| b"ACGT".contains(&b)))
+                .map(|kmer| canonical(&kmer))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut kmers = flatten_into_hashset(kmers).into_iter().collect::<Vec<_>>();
+    kmers.sort_unstable();
+    Ok(kmers)
+}
+
+/// Writes one k-mer per line
+pub fn write_kmers_text<W: Write>(out: &mut W, kmers: &[String]) -> io::Result<()> {
+    for kmer in kmers {
+        writeln!(out, "{}", kmer)?;
+    }
+    Ok(())
+}
+
+/// Bases in the 2-bit encoding declared in the KFF header (A=0, C=1, G=2, T=3)
+const KFF_ENCODING: u8 = 0b00_01_10_11;
+
+fn write_kff_variables<W: Write>(out: &mut W, variables: &[(&str, u64)]) -> io::Result<()> {
+    out.write_all(b"v")?;
+    out.write_all(&(variables.len() as u64).to_be_bytes())?;
+    for (name, value) in variables {
+        out.write_all(name.as_bytes())?;
+        out.write_all(&[0])?;
+        out.write_all(&value.to_be_bytes())?;
+    }
+    Ok(())
+}
+
+/// Writes the k-mers as a KFF 1.0 file with a single raw section, one k-mer per block and no
+/// data attached to the k-mers
+pub fn write_kmers_kff<W: Write>(out: &mut W, k: usize, kmers: &[String]) -> io::Result<()> {
+    out.write_all(b"KFF")?;
+    // version 1.0, encoding, unique, canonical, no free block
+    out.write_all(&[1, 0, KFF_ENCODING, 1, 1])?;
+    out.write_all(&0u32.to_be_bytes())?;
+    write_kff_variables(out, &[("k", k as u64), ("max", 1), ("data_size", 0)])?;
+
+    out.write_all(b"r")?;
+    out.write_all(&(kmers.len() as u64).to_be_bytes())?;
+    // Packed 2 bits per base, the first byte is padded on the left
+    let bytes = (2 * k).div_ceil(8);
+    for kmer in kmers {
+        let mut packed = vec![0u8; bytes];
+        for (i, base) in kmer.bytes().rev().enumerate() {
+            let code = match base {
+                b'A' => 0,
+                b'C' => 1,
+                b'G' => 2,
+                _ => 3,
+            };
+            packed[bytes - 1 - i / 4] |= code << (2 * (i % 4));
+        }
+        out.write_all(&packed)?;
+    }
+
+    // The footer ends with its own size, so readers can find it from the end of the file
+    let footer_size = 1 + 8 + "footer_size".len() as u64 + 1 + 8;
+    write_kff_variables(out, &[("footer_size", footer_size)])?;
+    out.write_all(b"KFF")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_kmers() {
+        // 1+ -> 2- spells ACGT + reverse complement of TTA
+        let graph =
+            Graph::from_gfa("S\t1\tACGT\nS\t2\tTTA\nL\t1\t+\t2\t-\t0M\nS\t3\tNAC\n").unwrap();
+        let kmers = graph_kmers(graph, 4).unwrap();
+        let mut expected = ["ACGT", "CGTT", "GTTA", "TTAA"]
+            .iter()
+            .map(|kmer| canonical(kmer))
+            .collect::<Vec<_>>();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(kmers, expected);
+        assert_eq!(canonical("TAAC"), "GTTA");
+
+        // A cycle of segments without sequence between 1 and 4
+        let graph = Graph::from_gfa(
+            "S\t1\tACG\nS\t2\t\nS\t3\t\nS\t4\tT\n\
+             L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t3\t+\t2\t+\t0M\nL\t3\t+\t4\t+\t0M\n",
+        )
+        .unwrap();
+        assert_eq!(graph_kmers(graph, 4).unwrap(), vec!["ACGT"]);
+    }
+
+    #[test]
+    fn test_write_kmers_kff() {
+        let mut out = Vec::new();
+        write_kmers_kff(&mut out, 5, &["ACGTT".to_string()]).unwrap();
+        assert!(out.starts_with(b"KFF\x01\x00"));
+        assert!(out.ends_with(b"KFF"));
+        // A=00 C=01 G=10 T=11, padded on the left: 000000|00 01101111
+        let raw = 12 + 1 + 8 + ("k\0".len() + 8) + ("max\0".len() + 8) + ("data_size\0".len() + 8);
+        assert_eq!(out[raw], b'r');
+        assert_eq!(&out[raw + 9..raw + 9 + 2], &[0b00, 0b0110_1111]);
+        let footer_size =
+            u64::from_be_bytes(out[out.len() - 11..out.len() - 3].try_into().unwrap());
+        assert_eq!(out[out.len() - 3 - footer_size as usize], b'v');
+    }
+}
@@ -0,0 +1,85 @@
+//! Library behind the `trim-graph` tool: parsing of GFA graphs, removal of segments/links/jumps
+//! not covered by paths/walks and writers for the supported output formats.
+
+pub mod anonymize;
+pub mod between;
+pub mod bgzf;
+pub mod bloom;
+pub mod budget;
+pub mod checkpoint;
+pub mod collapse;
+pub mod complement;
+pub mod components;
+pub mod compress;
+pub mod config;
+pub mod connected;
+pub mod coverage;
+pub mod dangling;
+pub mod digest;
+pub mod edge;
+pub mod equal;
+pub mod error;
+pub mod exact;
+pub mod explode;
+pub mod external;
+pub mod extract;
+pub mod fasta;
+pub mod ffi;
+pub mod filter;
+pub mod forwardize;
+pub mod gaf;
+pub mod gbz;
+pub mod graph;
+pub mod grep;
+pub mod handle;
+pub mod hydrate;
+pub mod index;
+pub mod inputs;
+pub mod json;
+pub mod keepset;
+pub mod kmers;
+pub mod limits;
+pub mod metrics;
+pub mod multiedge;
+pub mod ncontent;
+pub mod odgi;
+pub mod overlaps;
+pub mod pansn;
+mod par;
+pub mod pathcov;
+#[cfg(feature = "petgraph")]
+pub mod petgraph;
+pub mod positions;
+pub mod pread;
+pub mod provenance;
+pub mod prune;
+pub mod reheader;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod removed;
+pub mod rename;
+pub mod rgfa;
+pub mod sample;
+pub mod scan;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shortest;
+pub mod sidecar;
+pub mod sniff;
+pub mod softmask;
+pub mod sort;
+pub mod stats;
+pub mod steps;
+pub mod stitch;
+pub mod summary;
+pub mod sv;
+pub mod traverse;
+pub mod trim;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod unitigs;
+pub mod universe;
+pub mod vg;
+pub mod visit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
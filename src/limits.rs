@@ -0,0 +1,207 @@
+//! CPU and memory limits of the environment, for defaults that share a node politely.
+//!
+//! Containers (Kubernetes, Docker) and batch systems (SLURM) usually restrict a job with
+//! cgroups rather than by the CPUs it can see. The quota and memory limit are read from the
+//! cgroup v2 files `cpu.max` and `memory.max` under `/sys/fs/cgroup`, or from the v1
+//! controllers `cpu/cpu.cfs_quota_us` and `memory/memory.limit_in_bytes`, and
+//! `SLURM_CPUS_PER_TASK` caps the CPUs as well. The default number of threads and the size of
+//! the output buffer follow these limits, and `--max-threads-per-stage` caps the threads of
+//! single stages.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Threads used without `--threads` and without a lower CPU limit
+pub const DEFAULT_THREADS: usize = 4;
+
+/// Output buffer without a memory limit, and the smallest one with a limit
+const BUFFER_SIZE: usize = 1 << 20;
+const MIN_BUFFER_SIZE: usize = 64 << 10;
+
+/// Memory limits at least this large are "unlimited" in cgroup v1
+const UNLIMITED_MEMORY: u64 = 1 << 60;
+
+/// Part of a run with its own threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Parsing and trimming, in the global thread pool
+    Compute,
+    /// Workers of the zstd encoder
+    Compress,
+    /// Positioned reads of `--parallel-read`
+    Read,
+}
+
+/// Most threads a stage may use, `STAGE=N` on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageThreads {
+    pub stage: Stage,
+    pub threads: usize,
+}
+
+impl FromStr for StageThreads {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid stage limit {}, expected compute=N or compress=N",
+                s
+            )
+        };
+        let (stage, threads) = s.split_once('=').ok_or_else(invalid)?;
+        let stage = match stage {
+            "compute" => Stage::Compute,
+            "compress" => Stage::Compress,
+            "read" => Stage::Read,
+            _ => return Err(invalid()),
+        };
+        match threads.parse() {
+            Ok(threads) if threads > 0 => Ok(StageThreads { stage, threads }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// CPUs of the quota, rounded up
+    pub cpus: Option<usize>,
+    /// Memory limit in bytes
+    pub memory: Option<u64>,
+}
+
+impl fmt::Display for ResourceLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cpus {
+            Some(cpus) => write!(f, "{} CPUs", cpus)?,
+            None => write!(f, "no CPU limit")?,
+        }
+        match self.memory {
+            Some(memory) => write!(f, ", {} MiB of memory", memory >> 20),
+            None => write!(f, ", no memory limit"),
+        }
+    }
+}
+
+/// CPUs of a `cpu.max` file (`quota period` or `max period`)
+fn parse_cpu_max(content: &str) -> Option<usize> {
+    let mut fields = content.split_whitespace();
+    let quota = fields.next()?.parse::<u64>().ok()?;
+    let period = fields
+        .next()
+        .and_then(|p| p.parse::<u64>().ok())
+        .unwrap_or(100_000);
+    cpus_of_quota(quota, period)
+}
+
+fn cpus_of_quota(quota: u64, period: u64) -> Option<usize> {
+    (period > 0).then(|| quota.div_ceil(period).max(1) as usize)
+}
+
+/// Bytes of a `memory.max` or `memory.limit_in_bytes` file, `None` for `max`
+fn parse_memory(content: &str) -> Option<u64> {
+    content
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|memory| *memory < UNLIMITED_MEMORY)
+}
+
+impl ResourceLimits {
+    /// Limits of the cgroup trim-graph runs in and of the SLURM job
+    pub fn detect() -> Self {
+        let slurm_cpus = std::env::var("SLURM_CPUS_PER_TASK").ok();
+        Self::of(Path::new("/sys/fs/cgroup"), slurm_cpus.as_deref())
+    }
+
+    /// Limits of the cgroup file system mounted at `root`
+    pub fn of(root: &Path, slurm_cpus: Option<&str>) -> Self {
+        let read = |file: &str| fs::read_to_string(root.join(file)).ok();
+        let v1_cpus = || {
+            let quota = read("cpu/cpu.cfs_quota_us")?.trim().parse::<u64>().ok()?;
+            let period = read("cpu/cpu.cfs_period_us")?.trim().parse::<u64>().ok()?;
+            cpus_of_quota(quota, period)
+        };
+        let cgroup_cpus = match read("cpu.max") {
+            Some(content) => parse_cpu_max(&content),
+            None => v1_cpus(),
+        };
+        let slurm_cpus = slurm_cpus.and_then(|cpus| cpus.parse::<usize>().ok());
+        let memory = read("memory.max")
+            .or_else(|| read("memory/memory.limit_in_bytes"))
+            .and_then(|content| parse_memory(&content));
+        ResourceLimits {
+            cpus: [cgroup_cpus, slurm_cpus].into_iter().flatten().min(),
+            memory,
+        }
+    }
+
+    /// Threads of a stage: the requested ones, or [`DEFAULT_THREADS`] but at most the CPU
+    /// limit, and at most the cap of the stage
+    pub fn threads(&self, requested: Option<usize>, stage: Stage, caps: &[StageThreads]) -> usize {
+        let threads =
+            requested.unwrap_or_else(|| DEFAULT_THREADS.min(self.cpus.unwrap_or(DEFAULT_THREADS)));
+        caps.iter()
+            .filter(|cap| cap.stage == stage)
+            .fold(threads, |threads, cap| threads.min(cap.threads))
+    }
+
+    /// Size of the output buffer, a thousandth of the memory limit within 64 KiB and 1 MiB
+    pub fn buffer_size(&self) -> usize {
+        match self.memory {
+            Some(memory) => ((memory / 1024) as usize).clamp(MIN_BUFFER_SIZE, BUFFER_SIZE),
+            None => BUFFER_SIZE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let root = std::env::temp_dir().join(format!("trim-graph-limits-{}", std::process::id()));
+        fs::create_dir_all(root.join("cpu")).unwrap();
+        fs::write(root.join("cpu/cpu.cfs_quota_us"), "250000\n").unwrap();
+        fs::write(root.join("cpu/cpu.cfs_period_us"), "100000\n").unwrap();
+        let limits = ResourceLimits::of(&root, None);
+        assert_eq!(
+            limits,
+            ResourceLimits {
+                cpus: Some(3),
+                memory: None
+            }
+        );
+        assert_eq!(limits.threads(None, Stage::Compute, &[]), 3);
+        assert_eq!(ResourceLimits::of(&root, Some("2")).cpus, Some(2));
+
+        fs::write(root.join("cpu.max"), "max 100000\n").unwrap();
+        fs::write(root.join("memory.max"), "268435456\n").unwrap();
+        let limits = ResourceLimits::of(&root, None);
+        assert_eq!(
+            limits,
+            ResourceLimits {
+                cpus: None,
+                memory: Some(256 << 20)
+            }
+        );
+        assert_eq!(limits.buffer_size(), 256 << 10);
+        let caps = ["compress=1".parse::<StageThreads>().unwrap()];
+        assert_eq!(limits.threads(None, Stage::Compute, &caps), DEFAULT_THREADS);
+        assert_eq!(limits.threads(Some(16), Stage::Compress, &caps), 1);
+        assert!("compress=0".parse::<StageThreads>().is_err());
+        assert!("io=2".parse::<StageThreads>().is_err());
+        assert_eq!(
+            "read=2".parse(),
+            Ok(StageThreads {
+                stage: Stage::Read,
+                threads: 2
+            })
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+}
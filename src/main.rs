@@ -1,33 +1,236 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use itertools::Itertools;
-use lazy_static::lazy_static;
-use rayon::iter::IntoParallelIterator;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use regex::Regex;
 use std::collections::HashSet;
-use std::error::Error;
 use std::fs;
-use std::hash::Hash;
-use std::io::Write;
+use std::io::{self, BufWriter, Write};
+use std::iter;
+use std::path::Path;
+use std::process::{self, ExitCode};
+use std::time::Instant;
+use trim_graph::anonymize::Pseudonyms;
+use trim_graph::between::nodes_between;
+use trim_graph::budget::{fit_budget, Budget};
+use trim_graph::checkpoint::{Checkpoint, Inputs, Phase};
+use trim_graph::collapse::Collapse;
+use trim_graph::complement::complement;
+use trim_graph::components::trim_by_component;
+use trim_graph::compress::{decompress, Compression, Encoder};
+use trim_graph::config::parse_jobs;
+use trim_graph::connected::{node_components, write_connectivity};
+use trim_graph::coverage::CoverageTrack;
+use trim_graph::dangling::Consistency;
+use trim_graph::digest::{graph_digest, tag_segments, DIGEST_HEADER};
+use trim_graph::equal::Canonical;
+use trim_graph::error::{Error, EXIT_DIFFERENT};
+use trim_graph::exact::LineChecksums;
+use trim_graph::explode::{path_graph, path_names};
+use trim_graph::external::resolve_sequences;
+use trim_graph::extract::{Extractor, Region, MIN_MAX_DETOUR};
+use trim_graph::forwardize::Forwardize;
+use trim_graph::gaf::ReadDepth;
+use trim_graph::graph::{split_pansn, Graph};
+use trim_graph::grep::LineFilter;
+use trim_graph::handle::{Handle, HandleGraph};
+use trim_graph::hydrate::{dehydrate, rehydrate};
+use trim_graph::index::{FileStamp, GraphIndex, INDEX_EXTENSION};
+use trim_graph::inputs::{graph_inputs, is_multiple};
+use trim_graph::keepset::{apply_keepset, SavedKeepSet};
+use trim_graph::kmers::{graph_kmers, write_kmers_kff, write_kmers_text};
+use trim_graph::limits::{ResourceLimits, Stage, StageThreads};
+use trim_graph::metrics::{RecordCounts, RunMetrics};
+use trim_graph::multiedge::{collapse_multi_edges, multi_edges, write_multi_edges};
+use trim_graph::ncontent::drop_n_segments;
+use trim_graph::overlaps::{check_overlaps, fix_overlaps, OverlapFix};
+use trim_graph::pansn::PansnNames;
+use trim_graph::pathcov::{filter_path_coverage, CoverageFilter};
+use trim_graph::positions::NodePositions;
+use trim_graph::pread::{read_parallel, BLOCK_SIZE};
+use trim_graph::provenance::{is_provenance, provenance_headers};
+use trim_graph::prune::{prune_complex, PruneOptions};
+use trim_graph::reheader::{reheader, HeaderEdit};
+#[cfg(feature = "remote")]
+use trim_graph::remote;
+use trim_graph::removed::RemovedReport;
+use trim_graph::rename::{rename_paths, RenameRule};
+use trim_graph::rgfa::reference_paths;
+use trim_graph::sample::sample_subgraph;
+use trim_graph::shortest::{shortest_path, Weight};
+use trim_graph::sidecar::{filter_sidecar, SegmentFilter, SidecarFormat};
+use trim_graph::softmask::{drop_soft_masked, masked_bp, uppercase_segments};
+use trim_graph::sort::{compact_ids, sort_segments};
+use trim_graph::stats::GraphStats;
+use trim_graph::steps::write_path_steps;
+use trim_graph::stitch::stitch;
+use trim_graph::summary::RemovedSummary;
+use trim_graph::sv::parse_events;
+use trim_graph::traverse::{traverse, write_traversal, Order};
+use trim_graph::trim::{
+    combine_keep_lists, get_nodes_edges, longest_per_sample, trim_gfa, trim_gfa_approximate,
+    trim_gfa_with, trim_gfa_with_index, write_parse_trace, Candidate, Combine, GfaLines,
+    RecordType, TrimOptions, Trimmed,
+};
+use trim_graph::unitigs::{link_overlaps, Unitigs};
+use trim_graph::universe::{missing_nodes, parse_universe, write_missing};
+use trim_graph::{fasta, gbz, json, odgi, sniff, vg};
 
-lazy_static! {
-    static ref RE: Regex = Regex::new(r"([><])([!-;=?-~]+)").unwrap();
-}
-
-#[derive(Parser)]
-#[command(version, about)]
+#[derive(Parser, Clone)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Params {
-    /// Graph that should be trimmed
-    graph_file: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Graph that should be trimmed, may be a http(s):// or s3:// URL with the remote feature
+    #[arg(required_unless_present_any = ["generate_man", "config"])]
+    graph_file: Option<String>,
+
+    /// Run the jobs described in a TOML file, its keys are the long options of trim-graph
+    #[arg(long, value_name = "FILE", exclusive = true)]
+    config: Option<String>,
 
-    /// File containing a list of paths to keep, if this is not set all paths are kept
+    /// Write the man page of trim-graph (roff) to stdout and exit
+    #[arg(long, exclusive = true)]
+    generate_man: bool,
+
+    /// File containing a list of paths to keep, if this is not set all paths are kept. May be
+    /// repeated, the lists are combined as set with --combine
     #[arg(short, long, value_name = "FILE")]
-    paths_to_keep: Option<String>,
+    paths_to_keep: Vec<String>,
+
+    /// How to combine several --paths-to-keep lists: keep the paths in any list, in all lists,
+    /// or in the first list but none of the others
+    #[arg(long, value_enum, default_value_t = CombineMode::Union)]
+    combine: CombineMode,
+
+    /// File containing a list of nodes, keeps only the paths and walks traversing any of them
+    #[arg(long, value_name = "FILE", conflicts_with = "paths_to_keep")]
+    keep_paths_through: Option<String>,
+
+    /// Also keep the paths and walks of the reference samples listed in the RS:Z: header tag
+    #[arg(long, conflicts_with = "index")]
+    keep_references_from_header: bool,
+
+    /// Add a P line for every reference sequence of an rGFA graph (e.g. built by minigraph),
+    /// from the stable sequence tags of its segments, so that it can be kept like a path
+    #[arg(long, conflicts_with = "index")]
+    synthesize_reference_paths: bool,
+
+    /// Also keep the segments and edges with at least --min-reads alignments in --gaf, edges
+    /// supported neither by a kept path nor by reads are removed
+    #[arg(long, requires = "gaf", conflicts_with = "index")]
+    assembly_mode: bool,
+
+    /// Alignments of reads to the graph for --assembly-mode
+    #[arg(long, value_name = "FILE", requires = "assembly_mode")]
+    gaf: Option<String>,
+
+    /// Alignments needed to keep a segment or edge in --assembly-mode
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        requires = "assembly_mode"
+    )]
+    min_reads: usize,
+
+    /// Keep all paths and walks of a trio of PanSN samples and of the sample of --reference
+    #[arg(
+        long,
+        value_name = "CHILD,MOTHER,FATHER",
+        value_delimiter = ',',
+        requires = "reference",
+        conflicts_with_all = ["paths_to_keep", "keep_paths_through"]
+    )]
+    trio: Option<Vec<String>>,
+
+    /// Keep at most N paths and walks per sample, the longest ones, e.g. for assemblies with
+    /// many scaffolds
+    #[arg(long, value_name = "N")]
+    max_paths_per_sample: Option<usize>,
+
+    /// Keep only the paths and walks with at least BP on segments that --reference does not
+    /// visit, and the reference
+    #[arg(long, value_name = "BP", requires = "reference")]
+    min_nonref_bp: Option<usize>,
 
-    /// Sets the number of threads for trim-graph to use
+    /// Keep only the paths and walks with at most BP on segments that --reference does not
+    /// visit, and the reference
+    #[arg(long, value_name = "BP", requires = "reference")]
+    max_nonref_bp: Option<usize>,
+
+    /// Keep only the walks whose start/end columns overlap the range, may be repeated
+    #[arg(long, value_name = "CONTIG:START-END")]
+    walk_range: Vec<Region>,
+
+    /// Sets the number of threads for trim-graph to use [default: 4, fewer with a lower cgroup
+    /// or SLURM CPU limit]
     #[arg(short, long)]
     threads: Option<usize>,
 
+    /// Use at most N threads for the stage, compute (parsing and trimming), compress (zstd
+    /// workers) or read (--parallel-read), may be repeated
+    #[arg(long, value_name = "STAGE=N", value_delimiter = ',')]
+    max_threads_per_stage: Vec<StageThreads>,
+
+    /// Read a local graph in large blocks from several threads at once, faster on striped
+    /// file systems like Lustre (Unix only)
+    #[arg(long)]
+    parallel_read: bool,
+
+    /// Index of the graph written by `trim-graph index`, skips parsing the paths
+    #[arg(long, value_name = "FILE")]
+    index: Option<String>,
+
+    /// Directory to record completed phases in, so that an interrupted run can be resumed
+    #[arg(long, value_name = "DIR", conflicts_with = "index")]
+    checkpoint: Option<String>,
+
+    /// Continue from the last phase completed in the checkpoint directory
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Save the kept paths and walks and the segments and edges they cover to FILE, to trim
+    /// related graphs with --load-keepset
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["index", "checkpoint", "by_component", "bloom_false_keep_rate"]
+    )]
+    save_keepset: Option<String>,
+
+    /// Keep the paths, walks, segments and edges saved with --save-keepset instead of
+    /// selecting paths and parsing their steps
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "index", "checkpoint", "by_component", "bloom_false_keep_rate", "paths_to_keep",
+            "keep_paths_through", "trio", "max_paths_per_sample", "min_nonref_bp",
+            "max_nonref_bp", "walk_range", "keep_references_from_header"
+        ]
+    )]
+    load_keepset: Option<String>,
+
+    /// Trim the connected components of the graph (e.g. one per contig) in parallel, the graph
+    /// is still read and written as a whole
+    #[arg(long, conflicts_with_all = ["index", "checkpoint"])]
+    by_component: bool,
+
+    /// Remember the kept nodes and edges in Bloom filters with this false positive rate and
+    /// check only the segments and edges passing them exactly, for huge graphs
+    #[arg(
+        long,
+        value_name = "RATE",
+        value_parser = parse_rate,
+        conflicts_with_all = ["index", "checkpoint", "by_component"]
+    )]
+    bloom_false_keep_rate: Option<f64>,
+
     /// Do not remove any segment lines
     #[arg(short = 'S', long)]
     ignore_segments: bool,
@@ -39,10 +242,885 @@ struct Params {
     /// Do not remove any jump lines
     #[arg(short = 'J', long)]
     ignore_jumps: bool,
+
+    /// Only write records of these types (e.g. S,L), all other lines are dropped
+    #[arg(long, value_enum, value_delimiter = ',', ignore_case = true)]
+    only: Option<Vec<Record>>,
+
+    /// Do not write records of these types (e.g. P,W to ship pure topology)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        ignore_case = true,
+        conflicts_with = "only"
+    )]
+    drop: Option<Vec<Record>>,
+
+    /// Exit with code 3 if trimming removes nothing (the output is written anyway)
+    #[arg(long)]
+    fail_if_unchanged: bool,
+
+    /// Write the runtime, the input size and the lines kept and removed per record type as
+    /// Prometheus metrics to FILE
+    #[arg(long, value_name = "FILE")]
+    metrics_file: Option<String>,
+
+    /// Push the metrics to a Prometheus Pushgateway, e.g.
+    /// http://localhost:9091/metrics/job/trim-graph (needs the remote feature)
+    #[arg(long, value_name = "URL")]
+    metrics_push: Option<String>,
+
+    /// Write every kept line byte for byte as in the input, verified by checksums, and refuse
+    /// the options that rewrite lines
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "json", "to_pansn", "pansn_map", "rename_paths", "anonymize_paths", "checksum",
+            "fix_overlaps", "drop_n_segments", "max_n_fraction", "uppercase",
+            "collapse_identical_segments", "collapse_reverse_complements", "compact_ids",
+            "resolve_sequences", "sequences_fasta", "forwardize", "provenance",
+            "collapse_multi_edges", "synthesize_reference_paths"
+        ]
+    )]
+    byte_exact: bool,
+
+    /// Write the segments without sequence (*) and their sequences as FASTA to FILE,
+    /// compressed like the graph; `trim-graph rehydrate` merges them again
+    #[arg(long, value_name = "FILE")]
+    sequences_fasta: Option<String>,
+
+    /// Write every distinct sequence only once to the --sequences-fasta file, named by its
+    /// SHA-1, which the segments reference with an SH:Z tag
+    #[arg(long, requires = "sequences_fasta")]
+    dedup_sequences: bool,
+
+    /// Format of the trimmed graph
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Gfa)]
+    format: OutputFormat,
+
+    /// Write the trimmed graph as vg-style JSON (same as --format json)
+    #[arg(long, conflicts_with = "format")]
+    json: bool,
+
+    /// File to write the trimmed graph to instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<String>,
+
+    /// Directory to write the trimmed graphs to if the graph is a directory or glob pattern,
+    /// at their paths relative to it
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "index"])]
+    output_dir: Option<String>,
+
+    /// Compress the output, zstd uses as many threads as set with --threads
+    #[arg(short = 'z', long, value_enum, default_value_t = OutputCompression::None)]
+    compress: OutputCompression,
+
+    /// Compress the output with bgzip and write a .gzi block index next to the --output file
+    /// (same as --compress bgzip)
+    #[arg(long, conflicts_with = "compress")]
+    bgzip: bool,
+
+    /// Compression level (gzip: 0-9, zstd: 1-22), defaults to the format's default. Ignored
+    /// with a warning if the output is not compressed
+    #[arg(long, value_name = "LEVEL")]
+    compression_level: Option<u32>,
+
+    /// Convert legacy path names like sample.hap.contig or sample#contig into PanSN names
+    /// (sample#hap#contig)
+    #[arg(long)]
+    to_pansn: bool,
+
+    /// Take the PanSN names of paths and walks from a TSV file (legacy name, PanSN name)
+    #[arg(long, value_name = "FILE")]
+    pansn_map: Option<String>,
+
+    /// Rename the kept paths and the samples of walks with a sed-like rule s/pattern/replacement/
+    /// (regex, `g` flag to replace all matches), may be repeated
+    #[arg(long, value_name = "RULE")]
+    rename_paths: Vec<RenameRule>,
+
+    /// Replace the sample names of paths and walks with pseudonyms, the mapping is written to
+    /// FILE (pseudonyms already in FILE are reused)
+    #[arg(long, value_name = "FILE")]
+    anonymize_paths: Option<String>,
+
+    /// Tag segments with the SHA-1 of their sequence (SH:Z) and write a digest of the whole
+    /// graph to the header, the digest does not depend on line order or tags
+    #[arg(long)]
+    checksum: bool,
+
+    /// Write the command line and the names of the kept paths and walks to the header, so
+    /// that the trimmed graph describes how it was produced
+    #[arg(long)]
+    provenance: bool,
+
+    /// Command line written by --provenance if not the one of the process, e.g. for the jobs
+    /// of --config
+    #[arg(skip)]
+    command_line: Option<Vec<String>>,
+
+    /// Repair P lines whose overlaps do not match their steps, by replacing the overlaps with
+    /// `*` or with a 0M list
+    #[arg(long, value_enum, value_name = "MODE")]
+    fix_overlaps: Option<Overlaps>,
+
+    /// Fail on P lines whose overlaps do not match their steps instead of warning
+    #[arg(long, conflicts_with = "fix_overlaps")]
+    strict_overlaps: bool,
+
+    /// Remove links in complex regions that no kept path or walk traverses, for GCSA/Giraffe
+    /// index construction (like vg prune)
+    #[arg(long)]
+    prune_complex: bool,
+
+    /// Number of edge choices allowed within a window when pruning
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = PruneOptions::default().max_edges,
+        requires = "prune_complex"
+    )]
+    max_edges_per_window: usize,
+
+    /// Length of the windows in bp when pruning
+    #[arg(
+        long,
+        value_name = "BP",
+        default_value_t = PruneOptions::default().window,
+        requires = "prune_complex"
+    )]
+    prune_window: usize,
+
+    /// Remove the segments visited by fewer than N of the kept paths and walks, with their
+    /// edges and the paths and walks through them
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    min_path_coverage: Option<u64>,
+
+    /// File with the names of paths and walks (as sample#hap#contig) that --min-path-coverage
+    /// keeps contiguous, their segments below the threshold are rescued
+    #[arg(long, value_name = "FILE", requires = "min_path_coverage")]
+    always_keep: Option<String>,
+
+    /// Write the segments rescued for --always-keep to FILE, one per line
+    #[arg(long, value_name = "FILE", requires = "always_keep")]
+    rescued_nodes: Option<String>,
+
+    /// What to do about consecutive steps of kept paths or walks without a link or jump in
+    /// the graph: skip the check, log them, add the missing edges or fail
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = MissingEdges::Ignore)]
+    missing_edges: MissingEdges,
+
+    /// Drop the segments consisting of N, split the paths and walks through them and remove
+    /// their links
+    #[arg(long)]
+    drop_n_segments: bool,
+
+    /// Like --drop-n-segments, for the segments with a larger fraction of N than X
+    #[arg(long, value_name = "X", value_parser = parse_fraction)]
+    max_n_fraction: Option<f64>,
+
+    /// Keep or drop the segments that are soft-masked (lowercase) completely, and report the
+    /// soft-masked bp retained
+    #[arg(long, value_enum, value_name = "MODE")]
+    masked_segments: Option<MaskedSegments>,
+
+    /// Write all sequences in uppercase
+    #[arg(long)]
+    uppercase: bool,
+
+    /// Merge segments with identical sequences and redirect their edges, paths and walks
+    #[arg(long)]
+    collapse_identical_segments: bool,
+
+    /// Like --collapse-identical-segments, also merging segments that are reverse complements
+    /// of each other, with the orientation of their edges and steps flipped
+    #[arg(long)]
+    collapse_reverse_complements: bool,
+
+    /// Write the links and jumps joining the same segment ends once, with the number of lines
+    /// as RC:i: tag
+    #[arg(long)]
+    collapse_multi_edges: bool,
+
+    /// Flip the segments --reference traverses mostly in reverse, reverse complementing their
+    /// sequence and inverting their edges and steps, so that the reference reads forward
+    #[arg(long, requires = "reference")]
+    forwardize: bool,
+
+    /// Order the segments topologically, along --reference (or the first path) first
+    #[arg(long, value_enum, value_name = "ORDER")]
+    sort: Option<SortOrder>,
+
+    /// Rename the segments to 1..n in their order
+    #[arg(long)]
+    compact_ids: bool,
+
+    /// Drop the segments visited by the fewest walks, and the walks through them, until the
+    /// sequence length is at most BP; segments of kept paths are never dropped
+    #[arg(long, value_name = "BP", conflicts_with = "target_nodes")]
+    target_bp: Option<usize>,
+
+    /// Like --target-bp, but for the number of segments
+    #[arg(long, value_name = "N")]
+    target_nodes: Option<usize>,
+
+    /// Reference for --removed-bed, --node-positions, --coverage-bedgraph and
+    /// --min/max-nonref-bp, a path name or the PanSN name (sample#hap#contig) of a walk, with
+    /// --trio its sample is kept
+    #[arg(long, value_name = "NAME")]
+    reference: Option<String>,
+
+    /// Write the bp removed by trimming per window of the reference as BED to FILE
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "reference",
+        conflicts_with = "index"
+    )]
+    removed_bed: Option<String>,
+
+    /// Length of the windows of --removed-bed in bp
+    #[arg(long, value_name = "BP", default_value_t = 10000, value_parser = clap::value_parser!(u64).range(1..))]
+    removed_window: u64,
+
+    /// Write the removed part of the graph as GFA to FILE: the removed segments, the edges
+    /// between them and the fragments of the removed paths and walks through them
+    #[arg(long, value_name = "FILE", conflicts_with = "index")]
+    complement_output: Option<String>,
+
+    /// Write the lines and bp removed per record type, sample and component as TSV to FILE
+    #[arg(long, value_name = "FILE", conflicts_with = "index")]
+    removed_summary: Option<String>,
+
+    /// Write the steps, links and jumps parsed from every path and walk as JSON lines to FILE,
+    /// for debugging which edges a path covers
+    #[arg(long, value_name = "FILE", conflicts_with = "index")]
+    trace_parsing: Option<String>,
+
+    /// Fill in the sequence of segments without one (*) from the FASTA file their UR:Z: tag
+    /// points to, a local path or a URL; unreadable files are skipped with a warning
+    #[arg(long)]
+    resolve_sequences: bool,
+
+    /// Write one line per step of the written paths and walks to FILE as TSV: path, step
+    /// index, segment, orientation and bp offset on the path
+    #[arg(long, value_name = "FILE")]
+    path_steps: Option<String>,
+
+    /// Write the intervals of the written segments on --reference as BED to FILE, segments off
+    /// the reference are placed between the reference segments around them on the paths
+    #[arg(long, value_name = "FILE", requires = "reference")]
+    node_positions: Option<String>,
+
+    /// Write the number of paths and walks through the segments of --reference along it as
+    /// bedGraph to FILE
+    #[arg(long, value_name = "FILE", requires = "reference")]
+    coverage_bedgraph: Option<String>,
 }
 
-fn set_number_of_threads(params: &Params) {
-    let threads = params.threads.unwrap_or(4);
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputCompression {
+    None,
+    Gzip,
+    /// Blocked gzip, allows random access with the .gzi index written next to --output
+    Bgzip,
+    Zstd,
+}
+
+impl From<OutputCompression> for Compression {
+    fn from(compression: OutputCompression) -> Self {
+        match compression {
+            OutputCompression::None => Compression::None,
+            OutputCompression::Gzip => Compression::Gzip,
+            OutputCompression::Bgzip => Compression::Bgzf,
+            OutputCompression::Zstd => Compression::Zstd,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Record {
+    #[value(name = "H")]
+    Header,
+    #[value(name = "S")]
+    Segment,
+    #[value(name = "L")]
+    Link,
+    #[value(name = "J")]
+    Jump,
+    #[value(name = "P")]
+    Path,
+    #[value(name = "W")]
+    Walk,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum CombineMode {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl From<CombineMode> for Combine {
+    fn from(mode: CombineMode) -> Self {
+        match mode {
+            CombineMode::Union => Combine::Union,
+            CombineMode::Intersection => Combine::Intersection,
+            CombineMode::Difference => Combine::Difference,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum SortOrder {
+    /// Along the links, with the reference segments in reference order (like odgi sort -O)
+    Topological,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum MissingEdges {
+    Ignore,
+    Warn,
+    Add,
+    Fail,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum MaskedSegments {
+    Keep,
+    Drop,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Sidecar {
+    Bed,
+    Tsv,
+    #[value(name = "bandage-csv")]
+    BandageCsv,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Overlaps {
+    Star,
+    #[value(name = "0M")]
+    Zero,
+}
+
+impl From<Overlaps> for OverlapFix {
+    fn from(overlaps: Overlaps) -> Self {
+        match overlaps {
+            Overlaps::Star => OverlapFix::Star,
+            Overlaps::Zero => OverlapFix::Zero,
+        }
+    }
+}
+
+impl From<Record> for RecordType {
+    fn from(record: Record) -> Self {
+        match record {
+            Record::Header => RecordType::Header,
+            Record::Segment => RecordType::Segment,
+            Record::Link => RecordType::Link,
+            Record::Jump => RecordType::Jump,
+            Record::Path => RecordType::Path,
+            Record::Walk => RecordType::Walk,
+        }
+    }
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Write an index of the graph so that later runs can trim it without parsing all paths
+    Index {
+        /// Graph to index
+        graph_file: String,
+
+        /// Index file, defaults to the graph file with .idx appended
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Merge path and walk fragments (name:start-end) of one or more graphs where their
+    /// coordinates are adjacent
+    Stitch {
+        /// Graphs containing the fragments
+        #[arg(required = true)]
+        graph_files: Vec<String>,
+
+        /// File to write the stitched graph to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Extract the segments on any route between the reference segments holding the first and
+    /// the last base of a region, exits with code 4 if there is none
+    Between {
+        /// Graph to extract from
+        graph_file: String,
+
+        /// Reference path or walk and the positions of the anchors (0-based, end exclusive)
+        #[arg(short, long, value_name = "NAME:START-END")]
+        region: Region,
+
+        /// Keep routes at most this many bp longer than the reference between the anchors
+        #[arg(long, value_name = "BP", default_value_t = MIN_MAX_DETOUR)]
+        max_detour_bp: usize,
+
+        /// Only keep the paths/walks of these samples besides the reference
+        #[arg(short, long, value_delimiter = ',', value_name = "SAMPLES")]
+        samples: Option<Vec<String>>,
+
+        /// File to write the subgraph to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Extract a random connected subgraph of about N segments with the path and walk
+    /// fragments through it, the same for the same graph and seed (e.g. for test fixtures)
+    SampleSubgraph {
+        /// Graph to sample from
+        graph_file: String,
+
+        /// Number of segments to sample
+        #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        nodes: u64,
+
+        /// Seed of the random numbers
+        #[arg(long, value_name = "S", default_value_t = 0)]
+        seed: u64,
+
+        /// File to write the subgraph to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Extract the subgraph spanning each structural variant of a breakpoint TSV or VCF with
+    /// its flanks, one GFA per event
+    Sv {
+        /// Graph to extract from
+        graph_file: String,
+
+        /// TSV with contig, start, end and an optional name per event, or a VCF
+        #[arg(short, long, value_name = "FILE")]
+        events: String,
+
+        /// Directory to write NAME.gfa for every event to
+        #[arg(short, long, value_name = "DIR")]
+        output_dir: String,
+
+        /// bp of reference to add on both sides of each event
+        #[arg(long, value_name = "BP", default_value_t = 1000)]
+        flank: usize,
+
+        /// Prefix turning the contigs of the events into reference names (e.g. GRCh38#0#)
+        #[arg(long, default_value = "")]
+        prefix: String,
+
+        /// Keep routes at most this many bp longer than the reference between the anchors
+        #[arg(long, value_name = "BP", default_value_t = MIN_MAX_DETOUR)]
+        max_detour_bp: usize,
+
+        /// Only keep the paths/walks of these samples besides the reference
+        #[arg(short, long, value_delimiter = ',', value_name = "SAMPLES")]
+        samples: Option<Vec<String>>,
+    },
+
+    /// Write one GFA per path and walk with only the segments and edges it traverses, e.g. as
+    /// input for tools that cannot handle graphs with several paths
+    ExplodePaths {
+        /// Graph to split
+        graph_file: String,
+
+        /// File with the names of the paths and walks (as sample#hap#contig) to write, all if
+        /// not given
+        #[arg(short, long, value_name = "FILE")]
+        paths_to_keep: Option<String>,
+
+        /// Directory to write NAME.gfa for every path and walk to
+        #[arg(short, long, value_name = "DIR")]
+        out_dir: String,
+    },
+
+    /// Add the number of reads of a GAF file supporting each segment and edge as RC:i: tags,
+    /// or write them as TSV
+    Annotate {
+        /// Graph to annotate
+        graph_file: String,
+
+        /// Alignments of the reads to the graph
+        #[arg(long, value_name = "FILE")]
+        gaf: String,
+
+        /// Write a TSV with the read count of every segment and edge instead of the graph
+        #[arg(long)]
+        tsv: bool,
+
+        /// File to write the graph or TSV to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Write the unitigs of a graph as FASTA and the links between them as GFA for assembly
+    /// tools and Bandage
+    Unitigs {
+        /// Graph to take the unitigs from
+        graph_file: String,
+
+        /// GFA file to write the links between the unitigs to
+        #[arg(long, value_name = "FILE")]
+        links: Option<String>,
+
+        /// File to write the FASTA to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Check whether two graphs are equivalent up to segment names and line order, exits with
+    /// code 1 if they differ
+    Equal {
+        /// First graph
+        first: String,
+
+        /// Second graph
+        second: String,
+    },
+
+    /// Print the number of nodes, edges, bp, paths and components and the N50 of a graph
+    Stats {
+        /// Graph to describe
+        #[arg(required_unless_present = "compare", conflicts_with = "compare")]
+        graph_file: Option<String>,
+
+        /// Print the statistics of two versions of a graph side by side with their difference
+        #[arg(long, num_args = 2, value_names = ["BEFORE", "AFTER"])]
+        compare: Option<Vec<String>>,
+    },
+
+    /// Write the segments reachable from seed segments in breadth- or depth-first order, with
+    /// their depth and the segment they were reached from, as TSV
+    Traverse {
+        /// Graph to traverse
+        graph_file: String,
+
+        /// Segments to start from
+        #[arg(
+            short,
+            long,
+            required = true,
+            value_delimiter = ',',
+            value_name = "NODES"
+        )]
+        seeds: Vec<String>,
+
+        /// Traversal order
+        #[arg(long, value_enum, default_value_t = TraversalOrder::Bfs)]
+        order: TraversalOrder,
+
+        /// Do not go further than this many edges from the seeds
+        #[arg(long, value_name = "N")]
+        max_depth: Option<usize>,
+
+        /// File to write the traversal to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Write the lines of a graph selected by record type, a regular expression over a field
+    /// and tag presence
+    Grep {
+        /// Graph to select from
+        graph_file: String,
+
+        /// Only select records of these types (e.g. S,L)
+        #[arg(short, long, value_enum, value_delimiter = ',', ignore_case = true)]
+        records: Option<Vec<Record>>,
+
+        /// Field the expression has to match, a number counted from 1 (the record type) or a
+        /// name like name, sequence, from, to, steps or sample; the whole line by default
+        #[arg(short, long, requires = "regex")]
+        field: Option<String>,
+
+        /// Regular expression to match, walk steps are matched in P line form (1+,2-)
+        #[arg(short = 'e', long, value_name = "REGEX")]
+        regex: Option<String>,
+
+        /// Only select lines with this tag (e.g. LN or LN:i), can be repeated
+        #[arg(short, long)]
+        tag: Vec<String>,
+
+        /// Write the lines that are not selected instead
+        #[arg(short = 'v', long)]
+        invert_match: bool,
+
+        /// File to write the lines to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Edit the H lines of a graph, all other lines are written unchanged
+    Reheader {
+        /// Graph to edit
+        graph_file: String,
+
+        /// Set the GFA version (VN:Z: tag)
+        #[arg(long, value_name = "VERSION")]
+        version: Option<String>,
+
+        /// Set the reference samples (RS:Z: tag)
+        #[arg(long, value_delimiter = ',', value_name = "SAMPLES")]
+        reference_samples: Option<Vec<String>>,
+
+        /// Set a tag (e.g. pg:Z:trim-graph), replacing its current value, can be repeated
+        #[arg(long, value_name = "TAG", value_parser = parse_tag)]
+        set: Vec<String>,
+
+        /// Remove all tags with this name (e.g. pg), can be repeated
+        #[arg(long, value_name = "NAME")]
+        remove: Vec<String>,
+
+        /// File to write the graph to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Report which of the listed segments are in the same connected component, as TSV
+    Connected {
+        /// Graph to check
+        graph_file: String,
+
+        /// File with one segment name per line
+        #[arg(short, long, value_name = "FILE")]
+        nodes: String,
+
+        /// File to write the report to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Filter a graph line by line with a keep set saved by --save-keepset, streaming it with
+    /// the memory of the keep set only; lines stay in input order
+    Apply {
+        /// Uncompressed graph to filter, stdin if not given
+        graph_file: Option<String>,
+
+        /// Keep set saved by --save-keepset
+        #[arg(long, value_name = "FILE")]
+        keepset: String,
+
+        /// File to write the graph to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// List the segments of a node universe, e.g. the original graph, missing from a trimmed
+    /// graph with their lengths, as TSV
+    Missing {
+        /// Trimmed graph to check
+        graph_file: String,
+
+        /// Original graph, or file with one segment name per line and an optional length as
+        /// second column
+        #[arg(short, long, value_name = "FILE")]
+        universe: String,
+
+        /// File to write the list to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// List the edges given by more than one link or jump, in either direction, with their
+    /// number of links and jumps as TSV
+    MultiEdges {
+        /// Graph to check for multi-edges
+        graph_file: String,
+
+        /// File to write the list to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Drop the rows of removed segments from a per-segment file (BED, TSV or Bandage CSV) and
+    /// rename the kept ones, so that it matches a trimmed graph
+    Sidecar {
+        /// File to filter, the format is taken from the extension unless given
+        sidecar_file: String,
+
+        /// Keep set saved by --save-keepset, rows of segments not in it are dropped
+        #[arg(long, value_name = "FILE", required_unless_present = "translation")]
+        keepset: Option<String>,
+
+        /// Table of old and new segment names (old<TAB>new), rows of segments not in it are
+        /// dropped
+        #[arg(long, value_name = "FILE")]
+        translation: Option<String>,
+
+        /// Format of the file
+        #[arg(long, value_enum)]
+        format: Option<Sidecar>,
+
+        /// Column of the segment name in TSV files (1-based)
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        column: u64,
+
+        /// The first line of a TSV file is a header and kept
+        #[arg(long)]
+        header: bool,
+
+        /// File to write the filtered rows to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Print the shortest path between two oriented segments with its number of steps and bp,
+    /// exits with code 4 if there is none
+    ShortestPath {
+        /// Graph to search
+        graph_file: String,
+
+        /// Segment to start from, with orientation (e.g. 12+)
+        #[arg(long, value_name = "NODE")]
+        from: String,
+
+        /// Segment to end at, with orientation (e.g. 987-)
+        #[arg(long, value_name = "NODE")]
+        to: String,
+
+        /// What makes a path short
+        #[arg(long, value_enum, default_value_t = PathWeight::Bp)]
+        weight: PathWeight,
+    },
+
+    /// Write a shell completion script to stdout
+    Completions {
+        /// Shell to complete in
+        shell: Shell,
+    },
+
+    /// Fill the sequences written by --sequences-fasta back into the segments without sequence
+    Rehydrate {
+        /// Graph with segments without sequence (*)
+        graph_file: String,
+
+        /// FASTA file of the sequences, with records named like the segments
+        fasta_file: String,
+
+        /// File to write the graph to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Write the canonical k-mers spelled by the segments and links of a (trimmed) graph
+    Kmers {
+        /// Graph to take the k-mers from
+        graph_file: String,
+
+        /// Length of the k-mers
+        #[arg(short, long, default_value_t = 31, value_parser = clap::value_parser!(u32).range(1..))]
+        k: u32,
+
+        /// Format of the k-mer list
+        #[arg(short, long, value_enum, default_value_t = KmerFormat::Text)]
+        format: KmerFormat,
+
+        /// File to write the k-mers to instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Load a graph once and serve subgraphs of reference regions over HTTP
+    #[cfg(feature = "server")]
+    Serve {
+        /// Graph to serve
+        graph_file: String,
+
+        /// Index of the graph written by `trim-graph index`, skips parsing the paths
+        #[arg(long, value_name = "FILE")]
+        index: Option<String>,
+
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+
+    /// Browse the statistics, components and paths of a graph in the terminal and preview what
+    /// trimming to a selection of paths would remove
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Graph to browse
+        graph_file: String,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum TraversalOrder {
+    /// Breadth-first, from all seeds at once
+    Bfs,
+    /// Depth-first, one seed after the other
+    Dfs,
+}
+
+impl From<TraversalOrder> for Order {
+    fn from(order: TraversalOrder) -> Self {
+        match order {
+            TraversalOrder::Bfs => Order::BreadthFirst,
+            TraversalOrder::Dfs => Order::DepthFirst,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum PathWeight {
+    /// Sequence length of the segments
+    Bp,
+    /// Number of segments
+    Steps,
+}
+
+impl From<PathWeight> for Weight {
+    fn from(weight: PathWeight) -> Self {
+        match weight {
+            PathWeight::Bp => Weight::Bp,
+            PathWeight::Steps => Weight::Steps,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum KmerFormat {
+    /// One k-mer per line
+    Text,
+    /// Binary K-mer File Format
+    Kff,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// GFA, lines are written as they appear in the input
+    Gfa,
+    /// JSON graph in the schema of `vg view -j`
+    Json,
+    /// vg Protobuf graph (.vg)
+    Vg,
+    /// GFA prepared for building a GBZ with `vg gbwt --gbz-format -G`
+    GbzGfa,
+    /// Sorted GFA with compacted segment ids, ready for `odgi build`
+    OdgiGfa,
+    /// FASTA with the sequence of every path and walk, named `name:start-end`
+    Fasta,
+}
+
+#[cfg(feature = "parallel")]
+fn set_number_of_threads(threads: Option<usize>, caps: &[StageThreads]) {
+    let limits = ResourceLimits::detect();
+    if limits != ResourceLimits::default() {
+        log::info!("Resource limits: {}", limits);
+    }
+    let threads = limits.threads(threads, Stage::Compute, caps);
     //if num_threads is 0 then the Rayon will select
     //the number of threads to the core number automatically
     rayon::ThreadPoolBuilder::new()
@@ -55,392 +1133,1759 @@ fn set_number_of_threads(params: &Params) {
     );
 }
 
-fn get_paths(paths: Vec<&str>, paths_to_keep: Vec<String>) -> Vec<String> {
-    log::info!("Filtering paths");
-    let paths = paths
-        .into_par_iter()
-        .filter(|l| {
-            paths_to_keep.contains(
-                &l.split('\t')
-                    .nth(1)
-                    .expect("All paths should have names")
-                    .to_string(),
-            )
-        })
-        .map(|s| s.to_string())
-        .collect::<Vec<_>>();
-    paths
+#[cfg(not(feature = "parallel"))]
+fn set_number_of_threads(threads: Option<usize>, _caps: &[StageThreads]) {
+    if threads.is_some() {
+        log::warn!("Built without the parallel feature, running on a single thread");
+    }
 }
 
-type SortedNodes = Vec<String>;
-type SortedEdges = Vec<((String, bool), (String, bool))>;
-type Nodes = HashSet<String>;
-type Edges = HashSet<((String, bool), (String, bool))>;
+const OUTDATED_INDEX: &str = "Index is out of date, rebuild it with trim-graph index";
 
-fn flatten_into_hashset<T: Eq + Hash + Send + Sync + Clone>(v: Vec<Vec<T>>) -> HashSet<T> {
-    v.into_par_iter()
-        .map(|row| HashSet::from_iter(row.iter().cloned()))
-        .reduce(HashSet::new, |acc: HashSet<T>, set| {
-            acc.union(&set).cloned().collect()
-        })
+#[cfg(feature = "remote")]
+fn is_remote(path: &str) -> bool {
+    remote::is_remote(path)
 }
 
-fn get_nodes_edges_from_path(path: &str) -> (SortedNodes, SortedEdges, SortedEdges) {
-    let node_texts = path.split_inclusive(&[',', ';']);
-    let mut nodes: Vec<(String, bool)> = Vec::new();
-    let mut links: SortedEdges = Vec::new();
-    let mut jumps: SortedEdges = Vec::new();
-    for node_text in node_texts.rev() {
-        let node_text = node_text.trim();
-        let node = node_text.replace(['+', '-', ',', ';'], "");
-        let is_jump = if node_text.ends_with(';') {
-            Some(true)
-        } else if node_text.ends_with(',') {
-            Some(false)
-        } else {
-            None
-        };
-        let orientation = if is_jump.is_some() {
-            node_text[..node_text.len() - 1].ends_with('+')
-        } else {
-            node_text[..node_text.len()].ends_with('+')
-        };
-        println!("{} - {} - {:?}", node, orientation, is_jump);
+#[cfg(not(feature = "remote"))]
+fn is_remote(_path: &str) -> bool {
+    false
+}
 
-        if let Some(prev_node) = nodes.last() {
-            if is_jump.expect("All nodes before last should have separator") {
-                jumps.push(((node.clone(), orientation), prev_node.clone()));
-            } else {
-                links.push(((node.clone(), orientation), prev_node.clone()));
+/// Reads a local file, or a http(s):// or s3:// URL with the remote feature
+fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    #[cfg(feature = "remote")]
+    if remote::is_remote(path) {
+        return remote::read_to_end(path);
+    }
+    fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))
+}
+
+/// The decompressed content of `path` as text, failing with a description of binary content
+fn to_string(content: Vec<u8>, path: &str) -> io::Result<String> {
+    sniff::text(content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+/// The decompressed content of the graph `path` as ASCII text
+fn graph_to_string(content: Vec<u8>, path: &str) -> io::Result<String> {
+    sniff::ascii_text(content)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+/// Reads and decompresses a (possibly gzip or zstd compressed) text file
+fn read_input_to_string(path: &str) -> io::Result<String> {
+    to_string(decompress(read_input(path)?)?, path)
+}
+
+/// Reads a graph as ASCII text, a local one with positioned reads on `threads` threads if given
+fn read_graph(path: &str, threads: Option<usize>) -> io::Result<String> {
+    match threads {
+        Some(threads) if !is_remote(path) => {
+            log::info!("Reading {} on {} threads", path, threads);
+            let content = read_parallel(Path::new(path), threads, BLOCK_SIZE)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+            graph_to_string(decompress(content)?, path)
+        }
+        _ => graph_to_string(decompress(read_input(path)?)?, path),
+    }
+}
+
+/// Parses a fraction between 0 and 1
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(x) if (0.0..=1.0).contains(&x) => Ok(x),
+        _ => Err(format!("{} is no fraction between 0 and 1", s)),
+    }
+}
+
+/// Parses a rate between 0 and 1, both excluded
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match parse_fraction(s) {
+        Ok(x) if x > 0.0 && x < 1.0 => Ok(x),
+        _ => Err(format!("{} is no rate between 0 and 1 (both excluded)", s)),
+    }
+}
+
+/// Checks that a tag has the form NAME:TYPE:VALUE
+fn parse_tag(s: &str) -> Result<String, String> {
+    match s.splitn(3, ':').collect::<Vec<_>>()[..] {
+        [name, kind, _] if name.len() == 2 && kind.len() == 1 => Ok(s.to_string()),
+        _ => Err(format!("{} is no tag of the form NAME:TYPE:VALUE", s)),
+    }
+}
+
+/// Writer for the given file, or for stdout if it is not set
+fn create_output(file: Option<&str>) -> io::Result<Box<dyn Write>> {
+    Ok(match file {
+        Some(file) => Box::new(fs::File::create(file)?),
+        None => Box::new(io::stdout()),
+    })
+}
+
+/// Subgraph of all routes between the anchors of the region (see `trim-graph between`)
+fn extract_between(
+    handles: &HandleGraph,
+    extractor: &Extractor,
+    region: &Region,
+    max_detour_bp: usize,
+    samples: Option<&HashSet<String>>,
+) -> Result<Graph, Error> {
+    let (first, last, between) = extractor.anchors(region)?;
+    let handle = |(name, forward): (&str, bool)| {
+        handles
+            .get_handle(name, !forward)
+            .expect("Reference segments should be in the graph")
+    };
+    let max_bp = between + max_detour_bp;
+    let ranks = nodes_between(handles, handle(first), handle(last), max_bp).ok_or_else(|| {
+        Error::NothingSelected(format!(
+            "There is no route from {} to {} within {} bp",
+            first.0, last.0, max_bp
+        ))
+    })?;
+    let nodes = ranks
+        .into_iter()
+        .map(|rank| handles.get_name(Handle::new(rank, false)))
+        .collect();
+    Ok(extractor.extract_nodes(region, &nodes, samples)?)
+}
+
+/// Pruning, destination and format of the trimmed graph
+struct Output {
+    /// Fill in the sequences of segments pointing to a FASTA file with a UR tag
+    resolve_sequences: bool,
+    /// How to handle steps without an edge
+    missing_edges: MissingEdges,
+    /// Drop the segments consisting of N
+    drop_n_segments: bool,
+    /// Drop the segments with a larger fraction of N
+    max_n_fraction: Option<f64>,
+    /// Keep or drop the completely soft-masked segments and report the soft-masked bp
+    masked_segments: Option<MaskedSegments>,
+    /// Write sequences in uppercase
+    uppercase: bool,
+    /// Merge segments with identical sequences
+    collapse_identical: bool,
+    /// Also merge segments that are reverse complements of each other
+    collapse_reverse: bool,
+    /// Write every edge given by several links and jumps once
+    collapse_multi_edges: bool,
+    prune: Option<PruneOptions>,
+    /// Minimum number of paths and walks per segment, and the paths to keep contiguous
+    path_coverage: Option<CoverageFilter>,
+    /// Where to write the segments rescued for the paths to keep contiguous
+    rescued_nodes: Option<String>,
+    /// Size to shrink the graph to
+    budget: Option<Budget>,
+    /// Record types to write, all if `None`
+    records: Option<HashSet<RecordType>>,
+    /// Reference to orient the segments along
+    forwardize: Option<String>,
+    /// Order of the segments and the reference guiding it
+    sort: Option<(SortOrder, Option<String>)>,
+    /// Rename the segments to 1..n
+    compact_ids: bool,
+    /// Conversion of path names into PanSN names
+    pansn: Option<PansnNames>,
+    /// Rules to rename paths and walk samples with, applied in order
+    rename: Vec<RenameRule>,
+    /// Mapping file of the sample pseudonyms
+    anonymize: Option<String>,
+    /// Tag segments with checksums and add a digest of the graph to the header
+    checksum: bool,
+    /// Command line to write to the header with the kept paths and walks
+    provenance: Option<Vec<String>>,
+    /// Repair invalid overlaps of P lines instead of warning
+    fix_overlaps: Option<OverlapFix>,
+    /// Fail on invalid overlaps of P lines instead of warning (`--strict-overlaps`)
+    strict_overlaps: bool,
+    /// Where to write the table of path steps
+    path_steps: Option<String>,
+    /// Where to write the positions of the segments on the reference, and the reference
+    node_positions: Option<(String, String)>,
+    /// Where to write the path coverage along the reference, and the reference
+    coverage_bedgraph: Option<(String, String)>,
+    format: OutputFormat,
+    /// Checksums of the input lines every written line has to match (`--byte-exact`)
+    byte_exact: Option<LineChecksums>,
+    /// Where to write the sequences of the segments instead of into the graph
+    sequences: Option<Encoder<BufWriter<Box<dyn Write>>>>,
+    /// Write every distinct sequence only once (`--dedup-sequences`)
+    dedup_sequences: bool,
+    encoder: Encoder<BufWriter<Box<dyn Write>>>,
+    /// Where to write the block index of BGZF output
+    gzi_file: Option<String>,
+}
+
+impl Output {
+    fn write(self, trimmed: Trimmed) -> Result<(), Error> {
+        // Lines added here live shorter than the lines of the graph
+        let (digest_header, tagged_segments, uppercased, collapsed_links, collapsed_jumps, renamed);
+        let (resolved, dehydrated, forward_segments, forward_links, forward_jumps);
+        let multi_edges;
+        let provenance;
+        let mut trimmed = trimmed;
+        if self.resolve_sequences {
+            resolved = resolve_sequences(&trimmed.segments, read_input_to_string)?;
+            if resolved.resolved > 0 || resolved.unavailable > 0 {
+                log::info!(
+                    "Filled in {} external sequences, {} are unavailable",
+                    resolved.resolved,
+                    resolved.unavailable
+                );
+            }
+            trimmed.segments = resolved.segments.iter().map(|s| s.as_str()).collect();
+        }
+        let added_edges = match self.missing_edges {
+            MissingEdges::Ignore => Vec::new(),
+            _ => trimmed.missing_edges()?,
+        };
+        if !added_edges.is_empty() {
+            let message = format!(
+                "{} consecutive steps of kept paths or walks have no link or jump, e.g. {}",
+                added_edges.len(),
+                added_edges[0].replace('\t', " ")
+            );
+            match self.missing_edges {
+                MissingEdges::Ignore | MissingEdges::Warn => log::warn!("{}", message),
+                MissingEdges::Fail => return Err(Error::InvalidInput(message)),
+                MissingEdges::Add => {
+                    log::info!("Adding {} missing edges", added_edges.len());
+                    for edge in &added_edges {
+                        match edge.starts_with('J') {
+                            true => trimmed.jumps.push(edge),
+                            false => trimmed.links.push(edge),
+                        }
+                    }
+                }
             }
         }
-        nodes.push((node, orientation));
+        if self.drop_n_segments || self.max_n_fraction.is_some() {
+            let dropped = drop_n_segments(&mut trimmed, self.max_n_fraction)?;
+            log::info!("Dropped {} segments of N", dropped);
+        }
+        if let Some(masked) = self.masked_segments {
+            if masked == MaskedSegments::Drop {
+                let dropped = drop_soft_masked(&mut trimmed)?;
+                log::info!("Dropped {} soft-masked segments", dropped);
+            }
+            let (bp, segments) = masked_bp(&trimmed);
+            log::info!(
+                "Retained {} soft-masked bp, {} segments are soft-masked completely",
+                bp,
+                segments
+            );
+        }
+        if self.collapse_identical || self.collapse_reverse {
+            let collapse = Collapse::identical(&trimmed.segments, self.collapse_reverse);
+            log::info!(
+                "Merged {} segments with identical sequences, {} bp",
+                collapse.merged.len(),
+                collapse.bp
+            );
+            collapse.apply(&mut trimmed)?;
+            collapsed_links = collapse.edges(&trimmed.links);
+            collapsed_jumps = collapse.edges(&trimmed.jumps);
+            trimmed.links = collapsed_links.iter().map(|l| l.as_str()).collect();
+            trimmed.jumps = collapsed_jumps.iter().map(|j| j.as_str()).collect();
+        }
+        if self.collapse_multi_edges {
+            multi_edges = collapse_multi_edges(&trimmed.links, &trimmed.jumps);
+            log::info!(
+                "Collapsed {} edges given by several lines, removing {} lines",
+                multi_edges.edges,
+                multi_edges.removed
+            );
+            trimmed.links = multi_edges.links.iter().map(|l| l.as_str()).collect();
+            trimmed.jumps = multi_edges.jumps.iter().map(|j| j.as_str()).collect();
+        }
+        if self.uppercase {
+            uppercased = uppercase_segments(&trimmed.segments);
+            trimmed.segments = uppercased.iter().map(|s| s.as_str()).collect();
+        }
+        if let Some(options) = &self.prune {
+            let pruned = prune_complex(&mut trimmed, options)?;
+            log::info!("Removed {} complex links", pruned);
+        }
+        if let Some(filter) = &self.path_coverage {
+            let filtered = filter_path_coverage(&mut trimmed, filter)?;
+            log::info!(
+                "Removed {} segments visited by fewer than {} paths and walks, and {} paths and walks through them",
+                filtered.segments,
+                filter.min_paths,
+                filtered.paths
+            );
+            if filtered.missing > 0 {
+                log::warn!(
+                    "{} of the paths to keep contiguous are not kept",
+                    filtered.missing
+                );
+            }
+            if !filtered.rescued.is_empty() {
+                log::info!(
+                    "Rescued {} segments to keep the paths contiguous: {}",
+                    filtered.rescued.len(),
+                    filtered.rescued.iter().take(10).join(", ")
+                );
+            }
+            if let Some(file) = &self.rescued_nodes {
+                let mut out = BufWriter::new(fs::File::create(file)?);
+                for node in &filtered.rescued {
+                    writeln!(out, "{}", node)?;
+                }
+                out.flush()?;
+            }
+        }
+        if let Some(budget) = self.budget {
+            if !fit_budget(&mut trimmed, budget)? {
+                log::warn!("The kept paths alone exceed the budget of {:?}", budget);
+            }
+        }
+        if let Some(records) = &self.records {
+            trimmed.retain_records(|r| records.contains(&r));
+        }
+        if let Some(reference) = &self.forwardize {
+            let forwardize = Forwardize::along(&trimmed, reference)?;
+            log::info!("Flipping {} segments", forwardize.flipped.len());
+            forward_segments = forwardize.segments(&trimmed.segments);
+            forward_links = forwardize.edges(&trimmed.links);
+            forward_jumps = forwardize.edges(&trimmed.jumps);
+            forwardize.apply_steps(&mut trimmed)?;
+            trimmed.segments = forward_segments.iter().map(|s| s.as_str()).collect();
+            trimmed.links = forward_links.iter().map(|l| l.as_str()).collect();
+            trimmed.jumps = forward_jumps.iter().map(|j| j.as_str()).collect();
+        }
+        if let Some((SortOrder::Topological, reference)) = &self.sort {
+            log::info!("Sorting segments");
+            sort_segments(&mut trimmed, reference.as_deref())?;
+        }
+        if self.compact_ids {
+            renamed = compact_ids(&mut trimmed)?;
+            trimmed.segments = renamed.segments.iter().map(|s| s.as_str()).collect();
+            trimmed.links = renamed.links.iter().map(|l| l.as_str()).collect();
+            trimmed.jumps = renamed.jumps.iter().map(|j| j.as_str()).collect();
+        }
+        match self.fix_overlaps {
+            Some(fix) => {
+                let fixed = fix_overlaps(&mut trimmed.paths, fix)?;
+                log::info!("Repaired the overlaps of {} paths", fixed);
+            }
+            None => match check_overlaps(&trimmed.paths) {
+                Err(e) if self.strict_overlaps => return Err(Error::InvalidInput(e)),
+                Err(e) => log::warn!("{}", e),
+                Ok(()) => {}
+            },
+        }
+        // Before the paths are renamed, the reference is given by its name in the input
+        if let Some((bed_file, reference)) = &self.node_positions {
+            let positions = NodePositions::new(&trimmed, reference)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            if positions.unplaced > 0 {
+                log::info!(
+                    "{} segments are on no path touching {}",
+                    positions.unplaced,
+                    reference
+                );
+            }
+            let mut bed_out = BufWriter::new(fs::File::create(bed_file)?);
+            positions.write_bed(&mut bed_out)?;
+            bed_out.flush()?;
+        }
+        if let Some((bedgraph_file, reference)) = &self.coverage_bedgraph {
+            let track = CoverageTrack::new(&trimmed, reference)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let mut bedgraph_out = BufWriter::new(fs::File::create(bedgraph_file)?);
+            track.write_bedgraph(&mut bedgraph_out)?;
+            bedgraph_out.flush()?;
+        }
+        if let Some(pansn) = &self.pansn {
+            let unconverted = pansn.normalize(&mut trimmed);
+            if unconverted > 0 {
+                log::warn!(
+                    "{} paths could not be converted to PanSN names",
+                    unconverted
+                );
+            }
+        }
+        if !self.rename.is_empty() {
+            rename_paths(&mut trimmed, &self.rename);
+        }
+        if let Some(map_file) = &self.anonymize {
+            let mut pseudonyms = match fs::File::open(map_file) {
+                Ok(file) => Pseudonyms::read(io::BufReader::new(file))?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Pseudonyms::default(),
+                Err(e) => return Err(e.into()),
+            };
+            pseudonyms.anonymize(&mut trimmed);
+            let mut map_out = BufWriter::new(fs::File::create(map_file)?);
+            pseudonyms.write(&mut map_out)?;
+            map_out.flush()?;
+        }
+        if self.checksum {
+            let digest = graph_digest(&trimmed);
+            log::info!("Graph digest (SHA-1): {}", digest);
+            digest_header = format!("{}{}", DIGEST_HEADER, digest);
+            tagged_segments = tag_segments(&trimmed.segments);
+            trimmed.headers.retain(|h| !h.starts_with(DIGEST_HEADER));
+            trimmed.headers.push(&digest_header);
+            trimmed.segments = tagged_segments.iter().map(|s| s.as_str()).collect();
+        }
+        if let Some(steps_file) = &self.path_steps {
+            let mut steps_out = BufWriter::new(fs::File::create(steps_file)?);
+            write_path_steps(&trimmed, &mut steps_out)?;
+            steps_out.flush()?;
+        }
+        if let Some(args) = &self.provenance {
+            provenance = provenance_headers(args, &trimmed);
+            trimmed.headers.retain(|h| !is_provenance(h));
+            trimmed
+                .headers
+                .extend(provenance.iter().map(|h| h.as_str()));
+        }
+        if let Some(checksums) = &self.byte_exact {
+            let verified = checksums.verify(&trimmed).map_err(Error::Validation)?;
+            log::info!("All {} lines to write match lines of the input", verified);
+        }
+        if let Some(mut fasta) = self.sequences {
+            dehydrated = dehydrate(&trimmed.segments, &mut fasta, self.dedup_sequences)?;
+            fasta.finish()?.flush()?;
+            log::info!(
+                "Wrote {} sequences of {} segments to FASTA",
+                dehydrated.records,
+                dehydrated.segments.len()
+            );
+            trimmed.segments = dehydrated.segments.iter().map(|s| s.as_str()).collect();
+        }
+        let mut out = self.encoder;
+        match self.format {
+            OutputFormat::Gfa => trimmed.write_gfa(&mut out),
+            OutputFormat::Json => json::write_json(&mut out, &trimmed.to_graph()?),
+            OutputFormat::Vg => vg::write_vg(&mut out, &trimmed.to_graph()?),
+            OutputFormat::GbzGfa => gbz::write_gbz_gfa(&mut out, &trimmed.to_graph()?),
+            OutputFormat::OdgiGfa => odgi::write_odgi_gfa(&mut out, &trimmed.to_graph()?),
+            OutputFormat::Fasta => fasta::write_fasta(&mut out, &trimmed.to_graph()?),
+        }?;
+        let (mut out, gzi) = out.finish_with_index()?;
+        out.flush()?;
+        if let (Some(gzi), Some(gzi_file)) = (gzi, self.gzi_file) {
+            let mut gzi_out = BufWriter::new(fs::File::create(gzi_file)?);
+            gzi.write(&mut gzi_out)?;
+            gzi_out.flush()?;
+        }
+        Ok(())
     }
-    let nodes = nodes.into_iter().map(|(s, _)| s).collect();
-    (nodes, links, jumps)
 }
 
-fn get_nodes_edges_from_walk(walk: &str) -> (SortedNodes, SortedEdges) {
-    let full_nodes = RE
-        .captures_iter(walk)
-        .map(|caps| (caps[2].to_string(), &caps[1] == ">"))
-        .collect::<Vec<_>>();
-    let nodes = full_nodes.iter().cloned().map(|(s, _)| s).collect();
-    let links = full_nodes.into_iter().tuple_windows().collect();
-    (nodes, links)
+/// Checks of the trimmed graph before and after it is written, and where to report its metrics
+struct Checks {
+    /// Whether paths were selected by a keep list
+    selects_paths: bool,
+    /// Whether walks were selected (by --keep-paths-through)
+    selects_walks: bool,
+    fail_if_unchanged: bool,
+    metrics_file: Option<String>,
+    metrics_push: Option<String>,
+    /// Start of the run, for the runtime metric
+    start: Instant,
 }
 
-fn get_nodes_edges(paths: &Vec<String>, walks: &Vec<String>) -> (Nodes, Edges, Edges) {
-    let (nodes, (links, jumps)): (Vec<SortedNodes>, (Vec<SortedEdges>, Vec<SortedEdges>)) = paths
-        .par_iter()
-        .map(|p| {
-            let path = p.split('\t').nth(2).unwrap();
-            let (nodes, links, jumps) = get_nodes_edges_from_path(path);
-            (nodes, (links, jumps))
-        })
-        .unzip();
-    let mut nodes = flatten_into_hashset(nodes);
-    let mut links = flatten_into_hashset(links);
-    let jumps = flatten_into_hashset(jumps);
-    let (walk_nodes, walk_links): (Vec<SortedNodes>, Vec<SortedEdges>) = walks
-        .par_iter()
-        .map(|w| {
-            let w_line = w.split('\t').nth(6).unwrap();
-            get_nodes_edges_from_walk(w_line)
-        })
-        .unzip();
-    let walk_nodes = flatten_into_hashset(walk_nodes);
-    let walk_links = flatten_into_hashset(walk_links);
-    nodes.extend(walk_nodes);
-    links.extend(walk_links);
-    (nodes, links, jumps)
-}
-
-fn filter_segments(segments: Vec<&str>, nodes_to_keep: HashSet<String>) -> Vec<&str> {
-    segments
-        .into_par_iter()
-        .filter(|n| {
-            nodes_to_keep.contains(n.split('\t').nth(1).expect("All nodes should have ids"))
+/// Writes the trimmed graph unless the selection matched nothing, and warns if trimming
+/// removed none of the `input` lines, which usually means that the keep list covers the whole
+/// graph
+fn finish(
+    out: Output,
+    trimmed: Trimmed,
+    input: RecordCounts,
+    input_bytes: u64,
+    checks: &Checks,
+) -> Result<(), Error> {
+    if checks.selects_paths
+        && trimmed.paths.is_empty()
+        && (!checks.selects_walks || trimmed.walks.is_empty())
+    {
+        return Err(Error::NothingSelected(
+            "The selection matches no path or walk of the graph".to_string(),
+        ));
+    }
+    let kept = RecordCounts::of_trimmed(&trimmed);
+    out.write(trimmed)?;
+    export_metrics(
+        &RunMetrics {
+            seconds: checks.start.elapsed().as_secs_f64(),
+            input_bytes,
+            input,
+            kept,
+        },
+        checks,
+    )?;
+    kept.check_changed(&input, checks.fail_if_unchanged)?;
+    Ok(())
+}
+
+/// Writes the metrics of the run to --metrics-file and pushes them to --metrics-push
+fn export_metrics(metrics: &RunMetrics, checks: &Checks) -> Result<(), Error> {
+    if checks.metrics_file.is_none() && checks.metrics_push.is_none() {
+        return Ok(());
+    }
+    let mut text = Vec::new();
+    metrics.write_prometheus(&mut text)?;
+    if let Some(file) = &checks.metrics_file {
+        fs::write(file, &text)?;
+    }
+    #[cfg(feature = "remote")]
+    if let Some(url) = &checks.metrics_push {
+        let text = String::from_utf8_lossy(&text);
+        remote::put_text(url, "text/plain; version=0.0.4", &text)?;
+    }
+    Ok(())
+}
+
+/// Warns about records referring to removed segments when -S, -L or -J kept all lines of a type
+fn warn_dangling(trimmed: &Trimmed, options: &TrimOptions) -> Result<(), Error> {
+    if options.ignore_segments || options.ignore_links || options.ignore_jumps {
+        for warning in Consistency::of(trimmed)?.warnings(options) {
+            log::warn!("{}", warning);
+        }
+    }
+    Ok(())
+}
+
+/// Logs the number of paths and walks selected, `by` tells how (e.g. `through the listed
+/// nodes`)
+fn log_selection(paths: &[String], walks: &HashSet<String>, by: &str) {
+    log::info!(
+        "Keeping {} paths and {} walks {}",
+        paths.len(),
+        walks.len(),
+        by
+    );
+}
+
+/// Keeps only those of the walks selected so far that are in `walks`
+fn restrict_walks(options: &mut TrimOptions, walks: HashSet<String>) {
+    let walks = match options.walks_to_keep.take() {
+        Some(selected) => selected.intersection(&walks).cloned().collect(),
+        None => walks,
+    };
+    log::info!("Keeping {} walks in the walk ranges", walks.len());
+    options.walks_to_keep = Some(walks);
+}
+
+/// Restricts the selection to the reference and the paths and walks with --min/max-nonref-bp
+/// off the reference
+fn filter_nonref(
+    paths_to_keep: &mut Option<Vec<String>>,
+    options: &mut TrimOptions,
+    candidates: Vec<Candidate>,
+    reference: &str,
+    min: Option<usize>,
+    max: Option<usize>,
+) {
+    let count = candidates.len();
+    let (walks, paths): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .filter(|c| {
+            c.name == reference
+                || (min.is_none_or(|min| c.nonref >= min) && max.is_none_or(|max| c.nonref <= max))
         })
-        .collect::<Vec<_>>()
+        .partition(|c| c.is_walk);
+    log::info!(
+        "Keeping {} of {} paths and walks by their bp off the reference",
+        paths.len() + walks.len(),
+        count
+    );
+    *paths_to_keep = Some(paths.into_iter().map(|c| c.name).collect());
+    options.walks_to_keep = Some(walks.into_iter().map(|c| c.name).collect());
+}
+
+/// Restricts the selection to the `max` longest paths and walks of every sample
+fn cap_per_sample(
+    paths_to_keep: &mut Option<Vec<String>>,
+    options: &mut TrimOptions,
+    candidates: Vec<Candidate>,
+    max: usize,
+) {
+    let count = candidates.len();
+    let (paths, walks) = longest_per_sample(candidates, max);
+    log::info!(
+        "Keeping {} of {} paths and walks, at most {} per sample",
+        paths.len() + walks.len(),
+        count,
+        max
+    );
+    *paths_to_keep = Some(paths);
+    options.walks_to_keep = Some(walks);
 }
 
-fn filter_edges(links: Vec<&str>, edges_to_keep: Edges) -> Vec<&str> {
-    links
-        .into_par_iter()
-        .filter(|l| {
-            let fields = l.split('\t').collect::<Vec<_>>();
-            let edge = (
-                (fields[1].to_string(), fields[2].contains('+')),
-                (fields[3].to_string(), fields[4].contains('+')),
+fn run_command(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Index { graph_file, output } => {
+            let raw_content = read_input(&graph_file)?;
+            let compression = Compression::detect(&raw_content);
+            let stamp = match is_remote(&graph_file) {
+                true => FileStamp {
+                    size: raw_content.len() as u64,
+                    modified: 0,
+                },
+                false => FileStamp::of(&graph_file)?,
+            };
+            let graph_content = graph_to_string(decompress(raw_content)?, &graph_file)?;
+            log::info!("Indexing graph");
+            let mut index = GraphIndex::build(&graph_content)?;
+            index.stamp = stamp;
+            index.compression = compression;
+            let output = output.unwrap_or_else(|| graph_file.clone() + INDEX_EXTENSION);
+            let mut out = BufWriter::new(fs::File::create(&output)?);
+            index.write(&mut out)?;
+            log::info!("Wrote index to {}", output);
+            Ok(())
+        }
+        Command::Stitch {
+            graph_files,
+            output,
+        } => {
+            let graphs = graph_files
+                .iter()
+                .map(|file| Ok(Graph::from_gfa(&read_graph(file, None)?)?))
+                .collect::<Result<Vec<_>, Error>>()?;
+            log::info!("Stitching {} graphs", graphs.len());
+            let stitched = stitch(graphs);
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            stitched.write_gfa(&mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Between {
+            graph_file,
+            region,
+            max_detour_bp,
+            samples,
+            output,
+        } => {
+            let graph = Graph::from_gfa(&read_graph(&graph_file, None)?)?;
+            let handles = HandleGraph::new(graph.clone())?;
+            let extractor = Extractor::new(graph);
+            let samples = samples.map(|s| s.into_iter().collect());
+            let subgraph = extract_between(
+                &handles,
+                &extractor,
+                &region,
+                max_detour_bp,
+                samples.as_ref(),
+            )?;
+            log::info!("Kept {} segments", subgraph.segments.len());
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            subgraph.write_gfa(&mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::SampleSubgraph {
+            graph_file,
+            nodes,
+            seed,
+            output,
+        } => {
+            let graph = Graph::from_gfa(&read_graph(&graph_file, None)?)?;
+            let subgraph = sample_subgraph(graph, nodes as usize, seed)?;
+            log::info!(
+                "Sampled {} segments, {} path and {} walk fragments",
+                subgraph.segments.len(),
+                subgraph.paths.len(),
+                subgraph.walks.len()
+            );
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            subgraph.write_gfa(&mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Sv {
+            graph_file,
+            events,
+            output_dir,
+            flank,
+            prefix,
+            max_detour_bp,
+            samples,
+        } => {
+            let events = parse_events(&read_input_to_string(&events)?)?;
+            let graph = Graph::from_gfa(&read_graph(&graph_file, None)?)?;
+            let handles = HandleGraph::new(graph.clone())?;
+            let extractor = Extractor::new(graph);
+            let samples = samples.map(|s| s.into_iter().collect());
+            fs::create_dir_all(&output_dir)?;
+            let mut written = 0;
+            for event in &events {
+                let region = event.region(&prefix, flank);
+                let subgraph = match extract_between(
+                    &handles,
+                    &extractor,
+                    &region,
+                    max_detour_bp,
+                    samples.as_ref(),
+                ) {
+                    Ok(subgraph) => subgraph,
+                    Err(Error::Io(e)) => return Err(Error::Io(e)),
+                    Err(e) => {
+                        log::warn!("Skipping {}: {}", event.name, e);
+                        continue;
+                    }
+                };
+                let file = Path::new(&output_dir).join(event.file_name());
+                let mut out = BufWriter::new(fs::File::create(&file)?);
+                subgraph.write_gfa(&mut out)?;
+                out.flush()?;
+                written += 1;
+            }
+            log::info!(
+                "Wrote {} of {} events to {}",
+                written,
+                events.len(),
+                output_dir
             );
-            let rev_edge = (
-                (fields[3].to_string(), fields[4].contains('+')),
-                (fields[1].to_string(), fields[2].contains('+')),
+            if written == 0 {
+                return Err(Error::NothingSelected(
+                    "No event could be extracted".to_string(),
+                ));
+            }
+            Ok(())
+        }
+        Command::ExplodePaths {
+            graph_file,
+            paths_to_keep,
+            out_dir,
+        } => {
+            let content = read_graph(&graph_file, None)?;
+            let lines = GfaLines::classify(&content);
+            let mut paths = path_names(&lines);
+            if let Some(file) = paths_to_keep {
+                let keep = read_input_to_string(&file)?
+                    .lines()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect::<HashSet<_>>();
+                paths.retain(|p| keep.contains(&p.name));
+                let found = paths.iter().map(|p| &p.name).collect::<HashSet<_>>();
+                let missing = keep.iter().filter(|name| !found.contains(name)).count();
+                if missing > 0 {
+                    log::warn!("{} of the listed paths are not in the graph", missing);
+                }
+            }
+            if paths.is_empty() {
+                return Err(Error::NothingSelected("No path to write".to_string()));
+            }
+            fs::create_dir_all(&out_dir)?;
+            let mut files = HashSet::new();
+            for path in &paths {
+                let file_name = path.file_name();
+                if !files.insert(file_name.clone()) {
+                    log::warn!("Skipping {}: {} is already written", path.name, file_name);
+                    continue;
+                }
+                let graph = path_graph(&lines, path)?;
+                let mut out =
+                    BufWriter::new(fs::File::create(Path::new(&out_dir).join(file_name))?);
+                graph.write_gfa(&mut out)?;
+                out.flush()?;
+            }
+            log::info!("Wrote {} graphs to {}", files.len(), out_dir);
+            Ok(())
+        }
+        Command::Annotate {
+            graph_file,
+            gaf,
+            tsv,
+            output,
+        } => {
+            let depth = ReadDepth::from_gaf(&read_input_to_string(&gaf)?)?;
+            if depth.skipped > 0 {
+                log::warn!(
+                    "Skipped {} of {} alignments to stable coordinates",
+                    depth.skipped,
+                    depth.alignments
+                );
+            }
+            let content = read_graph(&graph_file, None)?;
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            if tsv {
+                depth.write_tsv(&content, &mut out)?;
+            } else {
+                for line in content.lines() {
+                    writeln!(out, "{}", depth.annotate(line))?;
+                }
+            }
+            out.flush()?;
+            Ok(())
+        }
+        Command::Unitigs {
+            graph_file,
+            links,
+            output,
+        } => {
+            let content = read_graph(&graph_file, None)?;
+            let lines = GfaLines::classify(&content);
+            let overlaps = link_overlaps(&lines.links)?;
+            let graph = HandleGraph::new(Graph::from_gfa(&content)?)?;
+            let unitigs = Unitigs::new(&graph, &overlaps)?;
+            log::info!(
+                "{} segments form {} unitigs with {} links",
+                graph.node_count(),
+                unitigs.unitigs.len(),
+                unitigs.links.len()
             );
-            edges_to_keep.contains(&edge) || edges_to_keep.contains(&rev_edge)
-        })
-        .collect::<Vec<_>>()
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            unitigs.write_fasta(&graph, &mut out)?;
+            out.flush()?;
+            if let Some(links) = links {
+                let mut out = BufWriter::new(fs::File::create(links)?);
+                unitigs.write_links(&mut out)?;
+                out.flush()?;
+            }
+            Ok(())
+        }
+        Command::Equal { first, second } => {
+            let first = Canonical::of(&Graph::from_gfa(&read_graph(&first, None)?)?);
+            let second = Canonical::of(&Graph::from_gfa(&read_graph(&second, None)?)?);
+            let differences = first.differences(&second);
+            if differences.is_empty() {
+                println!("Graphs are equivalent");
+                return Ok(());
+            }
+            for difference in differences {
+                println!("{}", difference);
+            }
+            process::exit(EXIT_DIFFERENT.into());
+        }
+        Command::Stats {
+            graph_file,
+            compare,
+        } => {
+            let stats = |file: &str| -> Result<GraphStats, Error> {
+                let content = read_graph(file, None)?;
+                Ok(GraphStats::of(&GfaLines::classify(&content))?)
+            };
+            let mut out = BufWriter::new(io::stdout());
+            match (graph_file, compare) {
+                (_, Some(files)) => {
+                    stats(&files[0])?.write_comparison(&stats(&files[1])?, &mut out)
+                }
+                (Some(file), None) => stats(&file)?.write(&mut out),
+                (None, None) => unreachable!("clap requires a graph or --compare"),
+            }?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Traverse {
+            graph_file,
+            seeds,
+            order,
+            max_depth,
+            output,
+        } => {
+            let graph =
+                HandleGraph::new(Graph::from_gfa_topology(&read_graph(&graph_file, None)?)?)?;
+            let visits = traverse(&graph, &seeds, order.into(), max_depth)?;
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            write_traversal(&graph, &visits, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Grep {
+            graph_file,
+            records,
+            field,
+            regex,
+            tag,
+            invert_match,
+            output,
+        } => {
+            let records = records.map(|r| r.into_iter().map(RecordType::from).collect());
+            let filter = LineFilter::new(records, field, regex.as_deref(), tag, invert_match)?;
+            let content = read_graph(&graph_file, None)?;
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            for line in content.lines() {
+                if filter.matches(line)? {
+                    writeln!(out, "{}", line)?;
+                }
+            }
+            out.flush()?;
+            Ok(())
+        }
+        Command::Reheader {
+            graph_file,
+            version,
+            reference_samples,
+            set,
+            remove,
+            output,
+        } => {
+            let set = version
+                .map(|version| format!("VN:Z:{}", version))
+                .into_iter()
+                .chain(reference_samples.map(|samples| format!("RS:Z:{}", samples.join(" "))))
+                .chain(set)
+                .collect();
+            let content = read_graph(&graph_file, None)?;
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            reheader(&content, &HeaderEdit { set, remove }, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Connected {
+            graph_file,
+            nodes,
+            output,
+        } => {
+            let nodes = read_input_to_string(&nodes)?
+                .lines()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>();
+            let content = read_graph(&graph_file, None)?;
+            let components = node_components(&GfaLines::classify(&content), &nodes);
+            let missing = components.iter().filter(|c| c.is_none()).count();
+            if missing > 0 {
+                log::warn!("{} of the nodes are not in the graph", missing);
+            }
+            let distinct = components.iter().flatten().collect::<HashSet<_>>().len();
+            if distinct > 1 {
+                log::warn!("The nodes are split over {} components", distinct);
+            }
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            write_connectivity(&nodes, &components, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Apply {
+            graph_file,
+            keepset,
+            output,
+        } => {
+            let keep = SavedKeepSet::read(&mut read_input(&keepset)?.as_slice())?;
+            let input: Box<dyn io::BufRead> = match &graph_file {
+                Some(file) => {
+                    Box::new(io::BufReader::new(fs::File::open(file).map_err(|e| {
+                        io::Error::new(e.kind(), format!("{}: {}", file, e))
+                    })?))
+                }
+                None => Box::new(io::stdin().lock()),
+            };
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            let applied = apply_keepset(&keep, input, &mut out)?;
+            out.flush()?;
+            log::info!("Kept {} lines, removed {}", applied.kept, applied.removed);
+            Ok(())
+        }
+        Command::Missing {
+            graph_file,
+            universe,
+            output,
+        } => {
+            let universe = parse_universe(&read_input_to_string(&universe)?);
+            let content = read_graph(&graph_file, None)?;
+            let missing = missing_nodes(&universe, &GfaLines::classify(&content));
+            let bp = missing.iter().filter_map(|node| node.length).sum::<usize>();
+            log::info!(
+                "{} of {} segments are missing, {} bp",
+                missing.len(),
+                universe.len(),
+                bp
+            );
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            write_missing(&missing, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::MultiEdges { graph_file, output } => {
+            let content = read_graph(&graph_file, None)?;
+            let lines = GfaLines::classify(&content);
+            let multi = multi_edges(&lines.links, &lines.jumps);
+            log::info!(
+                "{} edges are given by {} lines",
+                multi.len(),
+                multi.iter().map(|m| m.links + m.jumps).sum::<usize>()
+            );
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            write_multi_edges(&multi, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Sidecar {
+            sidecar_file,
+            keepset,
+            translation,
+            format,
+            column,
+            header,
+            output,
+        } => {
+            let column = column as usize - 1;
+            let format = match format {
+                Some(Sidecar::Bed) => SidecarFormat::Bed,
+                Some(Sidecar::Tsv) => SidecarFormat::Tsv { column, header },
+                Some(Sidecar::BandageCsv) => SidecarFormat::BandageCsv,
+                None => match SidecarFormat::of_file(&sidecar_file) {
+                    SidecarFormat::Tsv { .. } => SidecarFormat::Tsv { column, header },
+                    format => format,
+                },
+            };
+            let filter = SegmentFilter {
+                keep: match keepset {
+                    Some(file) => {
+                        Some(SavedKeepSet::read(&mut read_input(&file)?.as_slice())?.nodes)
+                    }
+                    None => None,
+                },
+                rename: match translation {
+                    Some(file) => Some(SegmentFilter::read_translation(&read_input_to_string(
+                        &file,
+                    )?)?),
+                    None => None,
+                },
+            };
+            let content = read_input_to_string(&sidecar_file)?;
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            let counts = filter_sidecar(&content, format, &filter, &mut out)?;
+            out.flush()?;
+            log::info!(
+                "Kept {} and dropped {} rows of {}",
+                counts.kept,
+                counts.dropped,
+                sidecar_file
+            );
+            Ok(())
+        }
+        Command::ShortestPath {
+            graph_file,
+            from,
+            to,
+            weight,
+        } => {
+            let graph =
+                HandleGraph::new(Graph::from_gfa_topology(&read_graph(&graph_file, None)?)?)?;
+            let handle = |step: &str| {
+                graph
+                    .parse_handle(step)
+                    .ok_or_else(|| format!("{} is not a segment of the graph", step))
+            };
+            let path = shortest_path(&graph, handle(&from)?, handle(&to)?, weight.into())
+                .ok_or_else(|| {
+                    Error::NothingSelected(format!("There is no path from {} to {}", from, to))
+                })?;
+            let mut out = BufWriter::new(io::stdout());
+            path.write(&graph, &mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(
+                shell,
+                &mut Params::command(),
+                "trim-graph",
+                &mut io::stdout(),
+            );
+            Ok(())
+        }
+        Command::Rehydrate {
+            graph_file,
+            fasta_file,
+            output,
+        } => {
+            let graph = read_graph(&graph_file, None)?;
+            let fasta = read_input_to_string(&fasta_file)?;
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            let rehydrated = rehydrate(&graph, &fasta, &mut out)?;
+            out.flush()?;
+            log::info!(
+                "Filled in the sequences of {} segments",
+                rehydrated.segments
+            );
+            if rehydrated.unused > 0 {
+                log::warn!(
+                    "{} records of {} name no segment without sequence",
+                    rehydrated.unused,
+                    fasta_file
+                );
+            }
+            Ok(())
+        }
+        Command::Kmers {
+            graph_file,
+            k,
+            format,
+            output,
+        } => {
+            let graph = Graph::from_gfa(&read_graph(&graph_file, None)?)?;
+            log::info!("Enumerating {}-mers", k);
+            let kmers = graph_kmers(graph, k as usize)?;
+            let mut out = BufWriter::new(create_output(output.as_deref())?);
+            match format {
+                KmerFormat::Text => write_kmers_text(&mut out, &kmers),
+                KmerFormat::Kff => write_kmers_kff(&mut out, k as usize, &kmers),
+            }?;
+            out.flush()?;
+            Ok(())
+        }
+        #[cfg(feature = "server")]
+        Command::Serve {
+            graph_file,
+            index,
+            port,
+            host,
+        } => {
+            let graph_content = read_graph(&graph_file, None)?;
+            log::info!("Loading graph");
+            let graph = match index {
+                Some(index_file) => GraphIndex::read(&mut read_input(&index_file)?.as_slice())?
+                    .to_graph(&graph_content)?,
+                None => Graph::from_gfa(&graph_content)?,
+            };
+            drop(graph_content);
+            let extractor = Extractor::new(graph);
+            trim_graph::server::serve(&extractor, &format!("{}:{}", host, port))?;
+            Ok(())
+        }
+        #[cfg(feature = "tui")]
+        Command::Tui { graph_file } => {
+            let content = read_graph(&graph_file, None)?;
+            trim_graph::tui::run(&content)?;
+            Ok(())
+        }
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> ExitCode {
     env_logger::init();
 
     let params = Params::parse();
+    let result = match &params.config {
+        Some(config) => run_config(config),
+        None => {
+            set_number_of_threads(params.threads, &params.max_threads_per_stage);
+            run(params)
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+/// Runs all jobs of a config file, the thread pool is set up with the threads of the first job
+fn run_config(config: &str) -> Result<(), Error> {
+    let jobs = parse_jobs(&read_input_to_string(config)?)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, args)| {
+            let args = iter::once("trim-graph".to_string())
+                .chain(args)
+                .collect::<Vec<_>>();
+            let mut job = Params::try_parse_from(&args)
+                .map_err(|e| Error::InvalidInput(format!("Job {} of {}: {}", i + 1, config, e)))?;
+            job.command_line = Some(args);
+            Ok(job)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    match jobs.first() {
+        Some(job) => set_number_of_threads(job.threads, &job.max_threads_per_stage),
+        None => set_number_of_threads(None, &[]),
+    }
+    let count = jobs.len();
+    for (i, job) in jobs.into_iter().enumerate() {
+        log::info!("Running job {} of {}", i + 1, count);
+        run(job)?;
+    }
+    Ok(())
+}
 
-    set_number_of_threads(&params);
-
-    let graph_content =
-        fs::read_to_string(params.graph_file).expect("Should have been able to read the file");
-    let graph = graph_content.lines().collect::<Vec<_>>();
-
-    let mut segments = Vec::new();
-    let mut paths = Vec::new();
-    let mut walks = Vec::new();
-    let mut link_lines = Vec::new();
-    let mut jump_lines = Vec::new();
-    let mut headers = Vec::new();
-    let mut others = Vec::new();
-    for line in graph {
-        if line.starts_with('S') {
-            segments.push(line);
-        } else if line.starts_with('L') {
-            link_lines.push(line);
-        } else if line.starts_with('P') {
-            paths.push(line);
-        } else if line.starts_with('W') {
-            walks.push(line);
-        } else if line.starts_with('J') {
-            jump_lines.push(line);
-        } else if line.starts_with('H') {
-            headers.push(line);
-        } else {
-            others.push(line);
-        }
-    }
-
-    let paths_to_keep = match params.paths_to_keep {
-        Some(path_file) => {
-            let contents =
-                fs::read_to_string(path_file).expect("Should have been able to read the file");
-            contents.lines().map(|s| s.to_string()).collect::<Vec<_>>()
-        }
-        None => paths
-            .par_iter()
-            .map(|l| {
-                l.split('\t')
-                    .nth(1)
-                    .expect("All paths should have names")
-                    .to_string()
-            })
-            .collect(),
+/// Trims every graph of a directory or glob pattern into --output-dir, skipping the graphs in
+/// which the selection matches nothing
+fn run_each_graph(params: Params) -> Result<(), Error> {
+    let input = params.graph_file.clone().unwrap_or_default();
+    let Some(output_dir) = params.output_dir.clone() else {
+        return Err(Error::InvalidInput(format!(
+            "{} is a directory or glob pattern, --output-dir is needed for the trimmed graphs",
+            input
+        )));
     };
+    let graphs = graph_inputs(&input)?;
+    log::info!("Trimming {} graphs of {}", graphs.len(), input);
+    for graph in graphs {
+        let output = Path::new(&output_dir).join(&graph.relative);
+        if fs::canonicalize(&output).ok() == Some(fs::canonicalize(&graph.path)?) {
+            return Err(Error::InvalidInput(format!(
+                "Trimming {} would overwrite it, choose another --output-dir",
+                graph.path.display()
+            )));
+        }
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        log::info!("Trimming {} to {}", graph.path.display(), output.display());
+        let mut job = params.clone();
+        job.graph_file = Some(graph.path.to_string_lossy().into_owned());
+        job.output = Some(output.to_string_lossy().into_owned());
+        job.output_dir = None;
+        match run(job) {
+            Err(Error::NothingSelected(e)) => {
+                log::warn!("Skipping {}: {}", graph.path.display(), e);
+                // The output may be created before the selection is checked
+                if output.exists() {
+                    fs::remove_file(&output)?;
+                }
+            }
+            result => result?,
+        }
+    }
+    Ok(())
+}
 
-    let paths = get_paths(paths, paths_to_keep);
-    let walks = walks.into_par_iter().map(|s| s.to_string()).collect();
+/// Appends the P lines of the reference sequences of an rGFA graph to its content
+fn add_reference_paths(content: &mut String) {
+    let lines = GfaLines::classify(content);
+    let reference = reference_paths(&lines.segments, &lines.paths);
+    log::info!(
+        "Synthesized {} reference paths from rGFA tags",
+        reference.paths.len()
+    );
+    if reference.gaps > 0 {
+        log::warn!(
+            "{} segments of reference paths do not start where the segment before them ends",
+            reference.gaps
+        );
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for path in &reference.paths {
+        content.push_str(path);
+        content.push('\n');
+    }
+}
 
-    log::info!("Getting nodes/edges to keep");
-    let (nodes, links, jumps) = get_nodes_edges(&paths, &walks);
+fn run(params: Params) -> Result<(), Error> {
+    let start = Instant::now();
+    if let Some(command) = params.command {
+        return run_command(command);
+    }
+    if params.generate_man {
+        clap_mangen::Man::new(Params::command()).render(&mut io::stdout())?;
+        return Ok(());
+    }
+    if params.graph_file.as_deref().is_some_and(is_multiple) {
+        return run_each_graph(params);
+    }
+    if params.output_dir.is_some() {
+        return Err(Error::InvalidInput(
+            "--output-dir needs a directory or glob pattern as graph".to_string(),
+        ));
+    }
 
-    let segments = match params.ignore_segments {
+    let checks = Checks {
+        selects_paths: !params.paths_to_keep.is_empty()
+            || params.keep_paths_through.is_some()
+            || params.trio.is_some(),
+        selects_walks: params.keep_paths_through.is_some() || params.trio.is_some(),
+        fail_if_unchanged: params.fail_if_unchanged,
+        metrics_file: params.metrics_file.clone(),
+        metrics_push: params.metrics_push.clone(),
+        start,
+    };
+    if checks.metrics_push.is_some() && !cfg!(feature = "remote") {
+        return Err(Error::InvalidInput(
+            "--metrics-push needs trim-graph built with the remote feature".to_string(),
+        ));
+    }
+    let graph_file = params
+        .graph_file
+        .expect("Graph file is a required argument");
+    let format = if params.json {
+        OutputFormat::Json
+    } else {
+        params.format
+    };
+    if params.byte_exact
+        && (format != OutputFormat::Gfa || params.missing_edges == MissingEdges::Add)
+    {
+        return Err(Error::InvalidInput(
+            "--byte-exact only writes GFA and cannot add missing edges".to_string(),
+        ));
+    }
+    let drops_segments = match (&params.only, &params.drop) {
+        (Some(only), _) => !only.contains(&Record::Segment),
+        (None, Some(drop)) => drop.contains(&Record::Segment),
+        (None, None) => false,
+    };
+    if drops_segments && format != OutputFormat::Gfa {
+        return Err(Error::InvalidInput(
+            "--only/--drop without S only writes GFA, the other formats need the segments"
+                .to_string(),
+        ));
+    }
+    if params.sequences_fasta.is_some() && format != OutputFormat::Gfa {
+        return Err(Error::InvalidInput(
+            "--sequences-fasta only writes GFA, the other formats need the sequences".to_string(),
+        ));
+    }
+    let mut paths_to_keep = match params.paths_to_keep.is_empty() {
+        true => None,
         false => {
-            log::info!("Removing nodes");
-            filter_segments(segments, nodes)
+            let lists = params
+                .paths_to_keep
+                .iter()
+                .map(|path_file| {
+                    Ok(read_input_to_string(path_file)?
+                        .lines()
+                        .map(|s| s.to_string())
+                        .collect::<Vec<_>>())
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            Some(combine_keep_lists(lists, params.combine.into()))
         }
-        true => segments,
+    };
+    let nodes_through = match params.keep_paths_through {
+        Some(node_file) => Some(
+            read_input_to_string(&node_file)?
+                .lines()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect::<HashSet<_>>(),
+        ),
+        None => None,
+    };
+    if params.trio.as_ref().is_some_and(|trio| trio.len() != 3) {
+        return Err(Error::InvalidInput(
+            "--trio takes three samples: child,mother,father".to_string(),
+        ));
+    }
+    let samples = params.trio.as_ref().map(|trio| {
+        let reference = params.reference.as_deref().unwrap_or_default();
+        let reference_sample = split_pansn(reference).map_or(reference, |p| p.0);
+        trio.iter()
+            .cloned()
+            .chain(iter::once(reference_sample.to_string()))
+            .collect::<HashSet<_>>()
+    });
+    let nonref = params.min_nonref_bp.is_some() || params.max_nonref_bp.is_some();
+    let mut options = TrimOptions {
+        ignore_segments: params.ignore_segments,
+        ignore_links: params.ignore_links,
+        ignore_jumps: params.ignore_jumps,
+        walks_to_keep: None,
+        read_support: None,
     };
 
-    let link_lines = match params.ignore_links {
-        false => {
-            log::info!("Removing links");
-            filter_edges(link_lines, links)
+    let compression = match params.bgzip {
+        true => Compression::Bgzf,
+        false => params.compress.into(),
+    };
+    if compression == Compression::None && params.compression_level.is_some() {
+        log::warn!("--compression-level has no effect without --compress or --bgzip");
+    }
+    let writer = create_output(params.output.as_deref())?;
+    let gzi_file = match (compression, &params.output) {
+        (Compression::Bgzf, Some(file)) => Some(file.clone() + ".gzi"),
+        (Compression::Bgzf, None) => {
+            log::warn!("Writing to stdout, no .gzi index is written without --output");
+            None
         }
-        true => link_lines,
+        _ => None,
     };
-
-    let jump_lines = match params.ignore_jumps {
-        false => {
-            log::info!("Removing jumps");
-            filter_edges(jump_lines, jumps)
+    let pansn = match (params.to_pansn, &params.pansn_map) {
+        (false, None) => None,
+        (heuristics, map_file) => {
+            let mut names = PansnNames::new(heuristics);
+            if let Some(map_file) = map_file {
+                names.read_map(read_input(map_file)?.as_slice())?;
+            }
+            Some(names)
         }
-        true => jump_lines,
+    };
+    let limits = ResourceLimits::detect();
+    let compress_threads = limits.threads(
+        params.threads,
+        Stage::Compress,
+        &params.max_threads_per_stage,
+    ) as u32;
+    let read_threads = params
+        .parallel_read
+        .then(|| limits.threads(params.threads, Stage::Read, &params.max_threads_per_stage));
+    let mut out = Output {
+        missing_edges: params.missing_edges,
+        drop_n_segments: params.drop_n_segments,
+        max_n_fraction: params.max_n_fraction,
+        masked_segments: params.masked_segments,
+        uppercase: params.uppercase,
+        collapse_identical: params.collapse_identical_segments,
+        collapse_reverse: params.collapse_reverse_complements,
+        collapse_multi_edges: params.collapse_multi_edges,
+        prune: params.prune_complex.then_some(PruneOptions {
+            window: params.prune_window,
+            max_edges: params.max_edges_per_window,
+        }),
+        path_coverage: match params.min_path_coverage {
+            Some(min_paths) => Some(CoverageFilter {
+                min_paths: min_paths as usize,
+                always_keep: match &params.always_keep {
+                    Some(file) => read_input_to_string(file)?
+                        .lines()
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect(),
+                    None => HashSet::new(),
+                },
+            }),
+            None => None,
+        },
+        rescued_nodes: params.rescued_nodes.clone(),
+        budget: match (params.target_bp, params.target_nodes) {
+            (Some(bp), _) => Some(Budget::Bp(bp)),
+            (None, Some(nodes)) => Some(Budget::Nodes(nodes)),
+            (None, None) => None,
+        },
+        records: match (&params.only, &params.drop) {
+            (Some(only), _) => Some(only.iter().map(|r| (*r).into()).collect()),
+            (None, Some(drop)) => Some(
+                [
+                    RecordType::Header,
+                    RecordType::Segment,
+                    RecordType::Link,
+                    RecordType::Jump,
+                    RecordType::Path,
+                    RecordType::Walk,
+                    RecordType::Other,
+                ]
+                .into_iter()
+                .filter(|r| !drop.iter().any(|d| RecordType::from(*d) == *r))
+                .collect(),
+            ),
+            (None, None) => None,
+        },
+        forwardize: params.reference.clone().filter(|_| params.forwardize),
+        sort: params.sort.map(|order| (order, params.reference.clone())),
+        compact_ids: params.compact_ids,
+        pansn,
+        rename: params.rename_paths,
+        anonymize: params.anonymize_paths,
+        checksum: params.checksum,
+        provenance: params.provenance.then(|| {
+            params
+                .command_line
+                .clone()
+                .unwrap_or_else(|| std::env::args().collect())
+        }),
+        fix_overlaps: params.fix_overlaps.map(OverlapFix::from),
+        strict_overlaps: params.strict_overlaps,
+        path_steps: params.path_steps,
+        resolve_sequences: params.resolve_sequences,
+        node_positions: params.node_positions.zip(params.reference.clone()),
+        coverage_bedgraph: params.coverage_bedgraph.zip(params.reference.clone()),
+        format,
+        byte_exact: None,
+        sequences: match &params.sequences_fasta {
+            Some(file) => Some(Encoder::new(
+                BufWriter::with_capacity(limits.buffer_size(), create_output(Some(file))?),
+                compression,
+                params.compression_level,
+                compress_threads,
+            )?),
+            None => None,
+        },
+        dedup_sequences: params.dedup_sequences,
+        encoder: Encoder::new(
+            BufWriter::with_capacity(limits.buffer_size(), writer),
+            compression,
+            params.compression_level,
+            compress_threads,
+        )?,
+        gzi_file,
     };
 
-    let mut out = std::io::BufWriter::new(std::io::stdout());
-    for h in headers {
-        writeln!(out, "{}", h)?;
+    if let Some(index_file) = params.index {
+        let index = GraphIndex::read(&mut read_input(&index_file)?.as_slice())?;
+        if let Some(nodes) = &nodes_through {
+            let (paths, walks) = index.paths_through(nodes);
+            log_selection(&paths, &walks, "through the listed nodes");
+            paths_to_keep = Some(paths);
+            options.walks_to_keep = Some(walks);
+        }
+        if let Some(samples) = &samples {
+            let (paths, walks) = index.paths_of_samples(samples);
+            log_selection(&paths, &walks, "of the trio and the reference");
+            paths_to_keep = Some(paths);
+            options.walks_to_keep = Some(walks);
+        }
+        if !params.walk_range.is_empty() {
+            restrict_walks(&mut options, index.walks_in(&params.walk_range));
+        }
+        if let (Some(reference), true) = (&params.reference, nonref) {
+            let segments = index.reference_segments(reference)?;
+            let candidates = index.candidates(
+                paths_to_keep.as_deref(),
+                options.walks_to_keep.as_ref(),
+                Some(&segments),
+            );
+            filter_nonref(
+                &mut paths_to_keep,
+                &mut options,
+                candidates,
+                reference,
+                params.min_nonref_bp,
+                params.max_nonref_bp,
+            );
+        }
+        if let Some(max) = params.max_paths_per_sample {
+            let candidates = index.candidates(
+                paths_to_keep.as_deref(),
+                options.walks_to_keep.as_ref(),
+                None,
+            );
+            cap_per_sample(&mut paths_to_keep, &mut options, candidates, max);
+        }
+        // Only the kept lines of a remote graph are downloaded
+        #[cfg(feature = "remote")]
+        if remote::is_remote(&graph_file) && index.compression == Compression::None {
+            if remote::content_length(&graph_file)? != index.stamp.size {
+                return Err(Error::Validation(OUTDATED_INDEX.to_string()));
+            }
+            let keep = trim_graph::trim::index_keep_set(&index, paths_to_keep, &options);
+            let lines = remote::RemoteLines::fetch(&graph_file, &index.kept_spans(&keep))?;
+            let trimmed = trim_graph::trim::cut_kept_lines(&index, &keep, |s| lines.line(s))
+                .map_err(Error::Validation)?;
+            warn_dangling(&trimmed, &options)?;
+            if params.byte_exact {
+                let spans = index.kept_spans(&keep);
+                out.byte_exact = Some(LineChecksums::new(
+                    spans.iter().filter_map(|s| lines.line(s)),
+                ));
+            }
+            return finish(
+                out,
+                trimmed,
+                RecordCounts::of_index(&index),
+                index.content_size,
+                &checks,
+            );
+        }
+        if !is_remote(&graph_file) && !index.is_current(&FileStamp::of(&graph_file)?) {
+            return Err(Error::Validation(OUTDATED_INDEX.to_string()));
+        }
+        // Compressed graphs have to be decompressed completely
+        let graph_content = read_graph(&graph_file, read_threads)?;
+        if params.byte_exact {
+            out.byte_exact = Some(LineChecksums::of(&graph_content)?);
+        }
+        let trimmed = trim_gfa_with_index(&graph_content, &index, paths_to_keep, &options)
+            .map_err(Error::Validation)?;
+        warn_dangling(&trimmed, &options)?;
+        return finish(
+            out,
+            trimmed,
+            RecordCounts::of_index(&index),
+            index.content_size,
+            &checks,
+        );
     }
-    for s in segments {
-        writeln!(out, "{}", s)?;
+
+    let mut graph_content = read_graph(&graph_file, read_threads)?;
+    if params.synthesize_reference_paths {
+        add_reference_paths(&mut graph_content);
     }
-    for p in paths {
-        writeln!(out, "{}", p)?;
+    if params.byte_exact {
+        out.byte_exact = Some(LineChecksums::of(&graph_content)?);
     }
-    for w in walks {
-        writeln!(out, "{}", w)?;
+    let lines = GfaLines::classify(&graph_content);
+    let input = RecordCounts::of_lines(&lines);
+    let input_bytes = graph_content.len() as u64;
+    if let Some(trace_file) = &params.trace_parsing {
+        let mut trace_out = BufWriter::new(fs::File::create(trace_file)?);
+        write_parse_trace(&lines, &mut trace_out)?;
+        trace_out.flush()?;
     }
-    for l in link_lines {
-        writeln!(out, "{}", l)?;
+    if let Some(nodes) = &nodes_through {
+        let (paths, walks) = lines.paths_through(nodes)?;
+        log_selection(&paths, &walks, "through the listed nodes");
+        paths_to_keep = Some(paths);
+        options.walks_to_keep = Some(walks);
     }
-    for j in jump_lines {
-        writeln!(out, "{}", j)?;
+    if let Some(samples) = &samples {
+        let (paths, walks) = lines.paths_of_samples(samples);
+        log_selection(&paths, &walks, "of the trio and the reference");
+        paths_to_keep = Some(paths);
+        options.walks_to_keep = Some(walks);
     }
-    for o in others {
-        writeln!(out, "{}", o)?;
+    if !params.walk_range.is_empty() {
+        restrict_walks(&mut options, lines.walks_in(&params.walk_range));
     }
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-
-    #[test]
-    fn test_get_paths() {
-        let paths = vec!["P\tp1\t1+, 2-, 3+", "P\tp2\t2+, 4-", "P\tp3\t5-, 3-, 1+"];
-        let paths_to_keep = vec!["p2".to_string(), "p3".to_string()];
-        let calculated = get_paths(paths, paths_to_keep);
-        let expected = vec!["P\tp2\t2+, 4-".to_string(), "P\tp3\t5-, 3-, 1+".to_string()];
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_get_nodes_edges_from_path_nodes() {
-        let path = "1+, 2-, 3+";
-        let mut expected = vec!["1".to_string(), "2".to_string(), "3".to_string()];
-        let (mut calculated, _, _) = get_nodes_edges_from_path(path);
-        calculated.sort();
-        expected.sort();
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_get_nodes_edges_from_path_links() {
-        let path = "1+, 2-; 3+, 2+";
-        let mut expected = vec![
-            (("1".to_string(), true), ("2".to_string(), false)),
-            (("3".to_string(), true), ("2".to_string(), true)),
-        ];
-        let (_, mut calculated, _) = get_nodes_edges_from_path(path);
-        calculated.sort();
-        expected.sort();
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_get_nodes_edges_from_path_jumps() {
-        let path = "1+; 2-, 3+; 2+";
-        let mut expected = vec![
-            (("1".to_string(), true), ("2".to_string(), false)),
-            (("3".to_string(), true), ("2".to_string(), true)),
-        ];
-        let (_, _, mut calculated) = get_nodes_edges_from_path(path);
-        calculated.sort();
-        expected.sort();
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_get_node_edges_for_paths() {
-        let paths = vec!["P\tp1\t1+, 2-; 3+".to_string(), "P\tp2\t2+, 4-".to_string()];
-        let expected = (
-            HashSet::from([
-                "1".to_string(),
-                "2".to_string(),
-                "3".to_string(),
-                "4".to_string(),
-            ]),
-            HashSet::from([
-                (("1".to_string(), true), ("2".to_string(), false)),
-                (("2".to_string(), true), ("4".to_string(), false)),
-            ]),
-            HashSet::from([(("2".to_string(), false), ("3".to_string(), true))]),
+    if let (Some(reference), true) = (&params.reference, nonref) {
+        let segments = lines.reference_segments(reference)?;
+        let candidates = lines.candidates(
+            paths_to_keep.as_deref(),
+            options.walks_to_keep.as_ref(),
+            Some(&segments),
+        )?;
+        filter_nonref(
+            &mut paths_to_keep,
+            &mut options,
+            candidates,
+            reference,
+            params.min_nonref_bp,
+            params.max_nonref_bp,
+        );
+    }
+    if let Some(max) = params.max_paths_per_sample {
+        let candidates = lines.candidates(
+            paths_to_keep.as_deref(),
+            options.walks_to_keep.as_ref(),
+            None,
+        )?;
+        cap_per_sample(&mut paths_to_keep, &mut options, candidates, max);
+    }
+    if params.keep_references_from_header {
+        let samples = lines.reference_samples();
+        if samples.is_empty() {
+            log::warn!("The header declares no reference samples (RS:Z: tag)");
+        }
+        let (paths, walks) = lines.paths_of_samples(&samples.iter().cloned().collect());
+        log::info!(
+            "Keeping {} paths and {} walks of the reference samples {}",
+            paths.len(),
+            walks.len(),
+            samples.join(", ")
+        );
+        if let Some(paths_to_keep) = &mut paths_to_keep {
+            for path in paths {
+                if !paths_to_keep.contains(&path) {
+                    paths_to_keep.push(path);
+                }
+            }
+        }
+        if let Some(walks_to_keep) = &mut options.walks_to_keep {
+            walks_to_keep.extend(walks);
+        }
+    }
+    if let (true, Some(gaf)) = (params.assembly_mode, &params.gaf) {
+        let depth = ReadDepth::from_gaf(&read_input_to_string(gaf)?)?;
+        let (nodes, edges) = depth.supported(params.min_reads);
+        log::info!(
+            "{} segments are supported by at least {} of {} alignments",
+            nodes.len(),
+            params.min_reads,
+            depth.alignments
+        );
+        options.read_support = Some((nodes, edges));
+    }
+    let loaded = match &params.load_keepset {
+        Some(file) => {
+            let keep = SavedKeepSet::read(&mut read_input(file)?.as_slice())?;
+            log::info!(
+                "Loaded the keep set of {} paths and {} walks with {} segments",
+                keep.paths.len(),
+                keep.walks.len(),
+                keep.nodes.len()
+            );
+            paths_to_keep = Some(keep.paths.clone());
+            options.walks_to_keep = Some(keep.walks.iter().cloned().collect());
+            Some(keep)
+        }
+        None => None,
+    };
+    // The lines of the whole graph are needed to find the removed segments afterwards
+    let untrimmed = (params.removed_bed.is_some()
+        || params.complement_output.is_some()
+        || params.removed_summary.is_some())
+    .then(|| lines.clone());
+    let trimmed = match params.checkpoint {
+        Some(dir) => {
+            let stamp = match is_remote(&graph_file) {
+                true => FileStamp {
+                    size: graph_content.len() as u64,
+                    modified: 0,
+                },
+                false => FileStamp::of(&graph_file)?,
+            };
+            let inputs = Inputs {
+                graph: graph_file,
+                stamp,
+                paths_to_keep: paths_to_keep.clone(),
+                walks_to_keep: options
+                    .walks_to_keep
+                    .as_ref()
+                    .map(|walks| walks.iter().cloned().sorted().collect()),
+            };
+            let mut checkpoint = Checkpoint::open(Path::new(&dir), inputs, params.resume)?;
+            trim_gfa_with(lines, paths_to_keep, &options, |paths, walks| {
+                checkpoint.try_run(Phase::KeepSet, || {
+                    log::info!("Getting nodes/edges to keep");
+                    get_nodes_edges(paths, walks).map_err(Error::from)
+                })
+            })?
+        }
+        None if loaded.is_some() || params.save_keepset.is_some() => {
+            trim_gfa_with(lines, paths_to_keep, &options, |paths, walks| {
+                let keep = match loaded {
+                    Some(keep) => keep,
+                    None => {
+                        log::info!("Getting nodes/edges to keep");
+                        SavedKeepSet::of(paths, walks)?
+                    }
+                };
+                if let Some(file) = &params.save_keepset {
+                    let mut keep_out = BufWriter::new(fs::File::create(file)?);
+                    keep.write(&mut keep_out)?;
+                    keep_out.flush()?;
+                }
+                Ok::<_, Error>((keep.nodes, keep.links, keep.jumps))
+            })?
+        }
+        None if params.by_component => trim_by_component(lines, paths_to_keep, &options)?,
+        None if params.bloom_false_keep_rate.is_some() => {
+            let rate = params.bloom_false_keep_rate.unwrap_or_default();
+            trim_gfa_approximate(lines, paths_to_keep, &options, rate)?
+        }
+        None => trim_gfa(lines, paths_to_keep, &options)?,
+    };
+    warn_dangling(&trimmed, &options)?;
+    if let (Some(untrimmed), Some(bed_file), Some(reference)) =
+        (&untrimmed, &params.removed_bed, &params.reference)
+    {
+        let report = RemovedReport::new(
+            untrimmed,
+            &trimmed,
+            reference,
+            params.removed_window as usize,
+        )?;
+        log::info!(
+            "Removed {} bp along {}, {} bp not connected to it",
+            report.removed.iter().sum::<usize>(),
+            reference,
+            report.unplaced
         );
-        let calculated = get_nodes_edges(&paths, &Vec::new());
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_get_nodes_edges_from_walk_nodes() {
-        let walk = ">1<2>3";
-        let mut expected = vec!["1".to_string(), "2".to_string(), "3".to_string()];
-        let (mut calculated, _) = get_nodes_edges_from_walk(walk);
-        expected.sort();
-        calculated.sort();
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_get_nodes_edges_from_walk_links() {
-        let walk = ">1<2>3";
-        let mut expected = vec![
-            (("1".to_string(), true), ("2".to_string(), false)),
-            (("2".to_string(), false), ("3".to_string(), true)),
-        ];
-        let (_, mut calculated) = get_nodes_edges_from_walk(walk);
-        expected.sort();
-        calculated.sort();
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_get_nodes_edges_for_walks() {
-        let walks = vec![
-            "W\tNA12878\t1\tchr1\t0\t11\t>1<2>3".to_string(),
-            "W\tNA12878\t1\tchr1\t0\t11\t>2<4".to_string(),
-        ];
-        let expected = (
-            HashSet::from([
-                "1".to_string(),
-                "2".to_string(),
-                "3".to_string(),
-                "4".to_string(),
-            ]),
-            HashSet::from([
-                (("1".to_string(), true), ("2".to_string(), false)),
-                (("2".to_string(), false), ("3".to_string(), true)),
-                (("2".to_string(), true), ("4".to_string(), false)),
-            ]),
-            HashSet::from([]),
+        let mut bed_out = BufWriter::new(fs::File::create(bed_file)?);
+        report.write_bed(&mut bed_out)?;
+        bed_out.flush()?;
+    }
+    if let (Some(untrimmed), Some(summary_file)) = (&untrimmed, &params.removed_summary) {
+        let summary = RemovedSummary::new(untrimmed, &trimmed)?;
+        log::info!("Removed {} bp of segments", summary.removed_bp());
+        let mut summary_out = BufWriter::new(fs::File::create(summary_file)?);
+        summary.write_tsv(&mut summary_out)?;
+        summary_out.flush()?;
+    }
+    if let (Some(untrimmed), Some(complement_file)) = (&untrimmed, &params.complement_output) {
+        let removed = complement(untrimmed, &trimmed)?;
+        log::info!(
+            "Writing {} removed segments and {} path fragments to {}",
+            removed.segments.len(),
+            removed.paths.len() + removed.walks.len(),
+            complement_file
         );
-        let calculated = get_nodes_edges(&Vec::new(), &walks);
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_flatten_into_hashset() {
-        let v = vec![vec![1, 2, 3], vec![2, 4]];
-        let expected = HashSet::from([1, 2, 3, 4]);
-        let calculated = flatten_into_hashset(v);
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_filter_segments() {
-        let segments = vec!["S\t1\tTCCGAT", "S\t2\tTA", "S\t3\tACG"];
-        let nodes = HashSet::from(["1".to_string(), "2".to_string()]);
-        let expected = vec!["S\t1\tTCCGAT", "S\t2\tTA"];
-        let calculated = filter_segments(segments, nodes);
-        assert_eq!(calculated, expected);
-    }
-
-    #[test]
-    fn test_filter_links() {
-        let links = vec![
-            "L\t2\t-\t1\t+",
-            "L\t2\t-\t3\t+",
-            "L\t2\t-\t4\t+",
-            "L\t5\t-\t4\t+",
-        ];
-        let links_to_keep = HashSet::from([
-            (("1".to_string(), true), ("2".to_string(), false)),
-            (("2".to_string(), false), ("3".to_string(), true)),
-            (("2".to_string(), true), ("4".to_string(), false)),
-            (("5".to_string(), false), ("3".to_string(), false)),
-        ]);
-        let expected = vec!["L\t2\t-\t1\t+", "L\t2\t-\t3\t+"];
-        let calculated = filter_edges(links, links_to_keep);
-        assert_eq!(calculated, expected);
+        let mut complement_out = BufWriter::new(fs::File::create(complement_file)?);
+        removed.write_gfa(&mut complement_out)?;
+        complement_out.flush()?;
     }
+    finish(out, trimmed, input, input_bytes, &checks)
 }
@@ -0,0 +1,187 @@
+//! Metrics of a trimming run in the Prometheus text format (`--metrics-file`, `--metrics-push`).
+//!
+//! The format is the one read by the node exporter's textfile collector and accepted by a
+//! Prometheus Pushgateway, ending with the `# EOF` marker of OpenMetrics, which Prometheus
+//! reads as a comment. All metrics are gauges describing the last run.
+
+use crate::error::Error;
+use crate::index::GraphIndex;
+use crate::trim::{GfaLines, Trimmed};
+use std::io::{self, Write};
+
+/// Letters of the record types counted, other lines count as `other`
+const RECORDS: [&str; 7] = ["H", "S", "L", "J", "P", "W", "other"];
+
+/// Number of lines per record type, in the order of `H, S, L, J, P, W` and other lines
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RecordCounts(pub [usize; 7]);
+
+impl RecordCounts {
+    pub fn of_lines(lines: &GfaLines) -> Self {
+        RecordCounts([
+            lines.headers.len(),
+            lines.segments.len(),
+            lines.links.len(),
+            lines.jumps.len(),
+            lines.paths.len(),
+            lines.walks.len(),
+            lines.others.len(),
+        ])
+    }
+
+    pub fn of_trimmed(trimmed: &Trimmed) -> Self {
+        RecordCounts([
+            trimmed.headers.len(),
+            trimmed.segments.len(),
+            trimmed.links.len(),
+            trimmed.jumps.len(),
+            trimmed.paths.len(),
+            trimmed.walks.len(),
+            trimmed.others.len(),
+        ])
+    }
+
+    pub fn of_index(index: &GraphIndex) -> Self {
+        RecordCounts([
+            index.headers.len(),
+            index.segments.len(),
+            index.links.len(),
+            index.jumps.len(),
+            index.paths.len(),
+            index.walks.len(),
+            index.others.len(),
+        ])
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
+    /// Warns if these kept lines are all `input` lines, which usually means that the keep list
+    /// covers the whole graph, or fails with [`Error::Unchanged`] if `fail_if_unchanged` is set
+    pub fn check_changed(
+        &self,
+        input: &RecordCounts,
+        fail_if_unchanged: bool,
+    ) -> Result<(), Error> {
+        if self.total() != input.total() {
+            return Ok(());
+        }
+        let message = "Trimming removed nothing, the kept paths cover the whole graph";
+        if fail_if_unchanged {
+            return Err(Error::Unchanged(message.to_string()));
+        }
+        log::warn!("{}", message);
+        Ok(())
+    }
+}
+
+/// Metrics of a trimming run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunMetrics {
+    pub seconds: f64,
+    /// Size of the (decompressed) input graph
+    pub input_bytes: u64,
+    pub input: RecordCounts,
+    pub kept: RecordCounts,
+}
+
+/// Writes a gauge with its help text and one sample per value, labelled by record type if
+/// there are several
+fn write_gauge<W: Write>(out: &mut W, name: &str, help: &str, values: &[String]) -> io::Result<()> {
+    writeln!(out, "# HELP trim_graph_{} {}", name, help)?;
+    writeln!(out, "# TYPE trim_graph_{} gauge", name)?;
+    match values {
+        [value] => writeln!(out, "trim_graph_{} {}", name, value),
+        _ => RECORDS.iter().zip(values).try_for_each(|(record, value)| {
+            writeln!(
+                out,
+                "trim_graph_{}{{record=\"{}\"}} {}",
+                name, record, value
+            )
+        }),
+    }
+}
+
+impl RunMetrics {
+    pub fn write_prometheus<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let per_record = |counts: [usize; 7]| counts.map(|c| c.to_string());
+        let removed = std::array::from_fn(|i| self.input.0[i].saturating_sub(self.kept.0[i]));
+        write_gauge(
+            out,
+            "runtime_seconds",
+            "Wall time of the run in seconds",
+            &[format!("{:.3}", self.seconds)],
+        )?;
+        write_gauge(
+            out,
+            "input_bytes",
+            "Size of the decompressed input graph in bytes",
+            &[self.input_bytes.to_string()],
+        )?;
+        write_gauge(
+            out,
+            "records",
+            "Lines of the input graph per record type",
+            &per_record(self.input.0),
+        )?;
+        write_gauge(
+            out,
+            "records_kept",
+            "Lines kept by trimming per record type",
+            &per_record(self.kept.0),
+        )?;
+        write_gauge(
+            out,
+            "records_removed",
+            "Lines removed by trimming per record type",
+            &per_record(removed),
+        )?;
+        writeln!(out, "# EOF")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_prometheus() {
+        let metrics = RunMetrics {
+            seconds: 1.5,
+            input_bytes: 120,
+            input: RecordCounts([1, 4, 4, 0, 2, 0, 0]),
+            kept: RecordCounts([1, 3, 2, 0, 1, 0, 0]),
+        };
+        let mut out = Vec::new();
+        metrics.write_prometheus(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with(
+            "# HELP trim_graph_runtime_seconds Wall time of the run in seconds\n\
+             # TYPE trim_graph_runtime_seconds gauge\ntrim_graph_runtime_seconds 1.500\n"
+        ));
+        assert!(text.contains("trim_graph_records{record=\"S\"} 4\n"));
+        assert!(text.contains("trim_graph_records_kept{record=\"L\"} 2\n"));
+        assert!(text.contains("trim_graph_records_removed{record=\"P\"} 1\n"));
+        assert!(text.ends_with("trim_graph_records_removed{record=\"other\"} 0\n# EOF\n"));
+        assert_eq!(metrics.input.total(), 11);
+    }
+
+    #[test]
+    fn test_check_changed() {
+        let content = "H\tVN:Z:1.0\nS\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nP\tp\t1+,2+\t*\n";
+        let lines = crate::trim::GfaLines::classify(content);
+        let input = RecordCounts::of_lines(&lines);
+        let options = crate::trim::TrimOptions::default();
+        let trimmed = crate::trim::trim_gfa(lines, None, &options).unwrap();
+        let kept = RecordCounts::of_trimmed(&trimmed);
+        assert_eq!(kept, input);
+        assert!(kept.check_changed(&input, false).is_ok());
+        assert!(matches!(
+            kept.check_changed(&input, true),
+            Err(Error::Unchanged(_))
+        ));
+        let trimmed = RecordCounts([1, 1, 0, 0, 1, 0, 0]);
+        assert!(trimmed.check_changed(&input, true).is_ok());
+    }
+}
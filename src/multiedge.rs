@@ -0,0 +1,170 @@
+//! Edges given by several L and J lines (`trim-graph multi-edges`, `--collapse-multi-edges`).
+//!
+//! Lines joining the same ends of two segments are the same edge, however they read it and
+//! whether they are links or jumps, e.g. `L 1 + 2 -` and `J 2 + 1 - *`. Such a multi-edge is
+//! collapsed into its first line, links before jumps, which gets the number of lines as `RC:i:`
+//! tag (replacing an earlier one); the overlaps and other tags of the other lines are dropped.
+
+use crate::edge::Edge;
+use crate::scan;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Tag of the number of lines collapsed into one
+pub const MULTIPLICITY_TAG: &str = "RC:i:";
+
+/// Edge given by more than one line
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiEdge<'a> {
+    /// The edge as its first line reads it
+    pub edge: Edge<&'a str>,
+    pub links: usize,
+    pub jumps: usize,
+}
+
+/// Lines of every edge as indices into the links followed by the jumps, in order of their
+/// first line
+fn edge_lines<'a>(links: &[&'a str], jumps: &[&'a str]) -> Vec<(Edge<&'a str>, Vec<usize>)> {
+    let mut edges: Vec<(Edge<&str>, Vec<usize>)> = Vec::new();
+    let mut index = HashMap::new();
+    for (i, line) in links.iter().chain(jumps).enumerate() {
+        let Some(edge) = Edge::from_line(line) else {
+            continue;
+        };
+        let e = *index.entry(edge).or_insert_with(|| {
+            edges.push((edge, Vec::new()));
+            edges.len() - 1
+        });
+        edges[e].1.push(i);
+    }
+    edges
+}
+
+/// The edges given by more than one L or J line, in order of their first line
+pub fn multi_edges<'a>(links: &[&'a str], jumps: &[&'a str]) -> Vec<MultiEdge<'a>> {
+    edge_lines(links, jumps)
+        .into_iter()
+        .filter(|(_, lines)| lines.len() > 1)
+        .map(|(edge, lines)| {
+            let links = lines.iter().filter(|i| **i < links.len()).count();
+            MultiEdge {
+                edge,
+                links,
+                jumps: lines.len() - links,
+            }
+        })
+        .collect()
+}
+
+/// Writes one line `from from_orientation to to_orientation links jumps` per multi-edge,
+/// with a header
+pub fn write_multi_edges<W: Write>(edges: &[MultiEdge], out: &mut W) -> io::Result<()> {
+    let orientation = |forward: bool| if forward { "+" } else { "-" };
+    writeln!(
+        out,
+        "from\tfrom_orientation\tto\tto_orientation\tlinks\tjumps"
+    )?;
+    for multi in edges {
+        let edge = &multi.edge;
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            edge.from,
+            orientation(edge.from_forward),
+            edge.to,
+            orientation(edge.to_forward),
+            multi.links,
+            multi.jumps
+        )?;
+    }
+    Ok(())
+}
+
+/// Links and jumps with every multi-edge collapsed into its first line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Collapsed {
+    pub links: Vec<String>,
+    pub jumps: Vec<String>,
+    /// Number of multi-edges
+    pub edges: usize,
+    /// Number of lines removed
+    pub removed: usize,
+}
+
+/// Collapses every edge given by more than one line into its first line, tagged with the
+/// number of lines. Lines with less than 5 columns are kept as they are.
+pub fn collapse_multi_edges(links: &[&str], jumps: &[&str]) -> Collapsed {
+    // Number of lines of the edge at its first line, None at the other lines
+    let mut counts = vec![Some(1); links.len() + jumps.len()];
+    let mut collapsed = Collapsed::default();
+    for (_, lines) in edge_lines(links, jumps) {
+        if lines.len() > 1 {
+            collapsed.edges += 1;
+            collapsed.removed += lines.len() - 1;
+            counts[lines[0]] = Some(lines.len());
+            lines[1..].iter().for_each(|i| counts[*i] = None);
+        }
+    }
+    let mut counts = counts.into_iter();
+    let mut keep = |line: &&str| -> Option<String> {
+        match counts.next().flatten()? {
+            1 => Some(line.to_string()),
+            count => {
+                let mut fields = scan::fields(line)
+                    .filter(|f| !f.starts_with(MULTIPLICITY_TAG))
+                    .collect::<Vec<_>>()
+                    .join("\t");
+                fields.push_str(&format!("\t{}{}", MULTIPLICITY_TAG, count));
+                Some(fields)
+            }
+        }
+    };
+    collapsed.links = links.iter().filter_map(&mut keep).collect();
+    collapsed.jumps = jumps.iter().filter_map(&mut keep).collect();
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_edges() {
+        let links = [
+            "L\t1\t+\t2\t+\t0M\tRC:i:9",
+            "L\t2\t+\t3\t+\t0M",
+            "L\t2\t-\t1\t-\t1M",
+            "L\t1\t+\t2\t-\t0M",
+            "L\t1\t+",
+        ];
+        let jumps = ["J\t1\t+\t2\t+\t*", "J\t3\t+\t1\t+\t*"];
+        let multi = multi_edges(&links, &jumps);
+        assert_eq!(
+            multi,
+            [MultiEdge {
+                edge: Edge::new("1", true, "2", true),
+                links: 2,
+                jumps: 1
+            }]
+        );
+        let mut out = Vec::new();
+        write_multi_edges(&multi, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "from\tfrom_orientation\tto\tto_orientation\tlinks\tjumps\n1\t+\t2\t+\t2\t1\n"
+        );
+
+        let collapsed = collapse_multi_edges(&links, &jumps);
+        assert_eq!(
+            collapsed.links,
+            [
+                "L\t1\t+\t2\t+\t0M\tRC:i:3",
+                "L\t2\t+\t3\t+\t0M",
+                "L\t1\t+\t2\t-\t0M",
+                "L\t1\t+"
+            ]
+        );
+        assert_eq!(collapsed.jumps, ["J\t3\t+\t1\t+\t*"]);
+        assert_eq!((collapsed.edges, collapsed.removed), (1, 2));
+    }
+}
@@ -0,0 +1,127 @@
+//! Removal of segments made of N, like the junk nodes of scaffolding gaps (`--drop-n-segments`,
+//! `--max-n-fraction`).
+//!
+//! Both `N` and `n` count as N. The links and jumps of the dropped segments are removed, and
+//! paths and walks through them are split into the fragments between them, named like the
+//! fragments of `--complement-output`.
+
+use crate::complement::{clip_path, clip_walk};
+use crate::graph::{path_line_steps, segment_length, walk_line_steps, EdgeRecord};
+use crate::trim::Trimmed;
+use std::collections::{HashMap, HashSet};
+
+/// Fraction of N in a sequence, 0 for empty sequences
+pub fn n_fraction(sequence: &str) -> f64 {
+    if sequence.is_empty() {
+        return 0.0;
+    }
+    let n = sequence
+        .bytes()
+        .filter(|b| b.eq_ignore_ascii_case(&b'N'))
+        .count();
+    n as f64 / sequence.len() as f64
+}
+
+/// Drops the segments with more than `max_fraction` N, or only the segments consisting of N if
+/// it is `None`, returns the number of dropped segments
+pub fn drop_n_segments(trimmed: &mut Trimmed, max_fraction: Option<f64>) -> Result<usize, String> {
+    drop_segments(trimmed, |sequence| {
+        let fraction = n_fraction(sequence);
+        max_fraction.map_or(fraction == 1.0, |max| fraction > max)
+    })
+}
+
+/// Drops the segments whose sequence matches `drop` with their links and jumps, and splits the
+/// paths and walks through them, returns the number of dropped segments. Fails on a path or
+/// walk with invalid steps or an invalid link or jump.
+pub(crate) fn drop_segments<F: Fn(&str) -> bool>(
+    trimmed: &mut Trimmed,
+    drop: F,
+) -> Result<usize, String> {
+    let mut lengths = HashMap::new();
+    let mut dropped = HashSet::new();
+    for line in &trimmed.segments {
+        let mut fields = line.split('\t').skip(1);
+        let name = fields.next().expect("All nodes should have ids");
+        let sequence = fields.next().filter(|s| *s != "*").unwrap_or("");
+        lengths.insert(name, segment_length(line));
+        if drop(sequence) {
+            dropped.insert(name);
+        }
+    }
+    if dropped.is_empty() {
+        return Ok(0);
+    }
+    let kept = lengths
+        .keys()
+        .filter(|n| !dropped.contains(*n))
+        .copied()
+        .collect::<HashSet<_>>();
+    let visits_dropped =
+        |steps: Vec<(&str, bool)>| steps.iter().any(|(node, _)| dropped.contains(node));
+
+    let mut paths = Vec::new();
+    for path in &trimmed.paths {
+        match visits_dropped(path_line_steps(path)?) {
+            true => paths.extend(clip_path(path, &lengths, &kept)?),
+            false => paths.push(path.clone()),
+        }
+    }
+    let mut walks = Vec::new();
+    for walk in &trimmed.walks {
+        match visits_dropped(walk_line_steps(walk)?) {
+            true => walks.extend(clip_walk(walk, &lengths, &kept)?),
+            false => walks.push(walk.clone()),
+        }
+    }
+    trimmed.paths = paths;
+    trimmed.walks = walks;
+    trimmed
+        .segments
+        .retain(|s| !dropped.contains(s.split('\t').nth(1).expect("All nodes should have ids")));
+    for edges in [&mut trimmed.links, &mut trimmed.jumps] {
+        let mut kept = Vec::with_capacity(edges.len());
+        for line in edges.drain(..) {
+            let edge = EdgeRecord::parse(line)?.edge;
+            if !dropped.contains(edge.from) && !dropped.contains(edge.to) {
+                kept.push(line);
+            }
+        }
+        *edges = kept;
+    }
+    Ok(dropped.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_drop_n_segments() {
+        let content = "S\t1\tACGT\nS\t2\tNNNN\nS\t3\tANnG\nS\t4\tGG\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t3\t+\t4\t+\t0M\n\
+                       P\tref\t1+,2+,3+,4+\t0M,0M,0M\nP\tshort\t4+\t*\n\
+                       W\ts\t1\tc\t10\t24\t>1>2>3>4\n";
+        let lines = GfaLines::classify(content);
+        let mut trimmed = trim_gfa(lines.clone(), None, &TrimOptions::default()).unwrap();
+        assert_eq!(drop_n_segments(&mut trimmed, None), Ok(1));
+        assert_eq!(
+            trimmed.paths,
+            vec![
+                "P\tref:0-4\t1+\t*",
+                "P\tref:8-14\t3+,4+\t*",
+                "P\tshort\t4+\t*"
+            ]
+        );
+        assert_eq!(
+            trimmed.walks,
+            vec!["W\ts\t1\tc\t10\t14\t>1", "W\ts\t1\tc\t18\t24\t>3>4"]
+        );
+        assert_eq!(trimmed.links, vec!["L\t3\t+\t4\t+\t0M"]);
+
+        let mut trimmed = trim_gfa(lines, None, &TrimOptions::default()).unwrap();
+        assert_eq!(drop_n_segments(&mut trimmed, Some(0.25)), Ok(2));
+        assert_eq!(trimmed.segments, vec!["S\t1\tACGT", "S\t4\tGG"]);
+    }
+}
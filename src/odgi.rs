@@ -0,0 +1,131 @@
+use crate::graph::{Graph, Link};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Order of the segments by first traversal along the paths (in file order) and walks,
+/// segments not on any path/walk are appended in input order
+fn path_guided_order(graph: &Graph) -> Vec<&str> {
+    let mut order = Vec::with_capacity(graph.segments.len());
+    let mut seen = HashSet::new();
+    let steps = graph
+        .paths
+        .iter()
+        .flat_map(|p| p.steps.iter())
+        .chain(graph.walks.iter().flat_map(|w| w.steps.iter()))
+        .map(|(node, _)| node.as_str())
+        .chain(graph.segments.iter().map(|s| s.name.as_str()));
+    for node in steps {
+        if seen.insert(node) {
+            order.push(node);
+        }
+    }
+    order
+}
+
+/// Sorts the segments along the paths and renames them to consecutive ids starting at 1
+pub fn compact_sorted(graph: &Graph) -> Graph {
+    let order = path_guided_order(graph);
+    let new_ids = order
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, (i + 1).to_string()))
+        .collect::<HashMap<_, _>>();
+    let rename = |node: &String| new_ids[node.as_str()].clone();
+    let rename_steps = |steps: &[(String, bool)]| {
+        steps
+            .iter()
+            .map(|(node, forward)| (rename(node), *forward))
+            .collect::<Vec<_>>()
+    };
+    let rename_link = |link: &Link| Link {
+        from: rename(&link.from),
+        to: rename(&link.to),
+        ..link.clone()
+    };
+
+    let mut segments = graph.segments.clone();
+    for segment in segments.iter_mut() {
+        segment.name = rename(&segment.name);
+    }
+    segments.sort_by_key(|s| s.name.parse::<usize>().unwrap());
+    let mut paths = graph.paths.clone();
+    for path in paths.iter_mut() {
+        path.steps = rename_steps(&path.steps);
+    }
+    let mut walks = graph.walks.clone();
+    for walk in walks.iter_mut() {
+        walk.steps = rename_steps(&walk.steps);
+    }
+    // Links and jumps kept with --keep-links may name trimmed segments, which have no new id
+    let rename_links = |links: &[Link]| {
+        links
+            .iter()
+            .filter(|l| {
+                new_ids.contains_key(l.from.as_str()) && new_ids.contains_key(l.to.as_str())
+            })
+            .map(rename_link)
+            .collect()
+    };
+    Graph {
+        segments,
+        links: rename_links(&graph.links),
+        jumps: rename_links(&graph.jumps),
+        paths,
+        walks,
+    }
+}
+
+/// Writes the graph as sorted GFA with compacted segment ids, which odgi builds without
+/// further sorting or id optimization. The header is flagged with `od:Z:compacted-sorted`.
+pub fn write_odgi_gfa<W: Write>(out: &mut W, graph: &Graph) -> io::Result<()> {
+    let graph = compact_sorted(graph);
+    writeln!(out, "H\tVN:Z:1.2\tod:Z:compacted-sorted")?;
+    graph.write_gfa(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_odgi_gfa() {
+        let graph = Graph::from_lines(
+            &["S\t10\tA", "S\t3\tC", "S\t7\tG", "S\tx\tT"],
+            &["L\t7\t+\t3\t-\t0M", "L\t3\t-\t10\t+\t0M"],
+            &[],
+            &["P\tp\t7+,3-,10+\t*"],
+            &["W\ts\t1\tc\t0\t2\t>7>x"],
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        write_odgi_gfa(&mut out, &graph).unwrap();
+        let expected = "H\tVN:Z:1.2\tod:Z:compacted-sorted\n\
+            S\t1\tG\n\
+            S\t2\tC\n\
+            S\t3\tA\n\
+            S\t4\tT\n\
+            L\t1\t+\t2\t-\t0M\n\
+            L\t2\t-\t3\t+\t0M\n\
+            P\tp\t1+,2-,3+\t*\n\
+            W\ts\t1\tc\t0\t2\t>1>4\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_compact_sorted_dangling_links() {
+        let graph = Graph::from_lines(
+            &["S\t5\tA", "S\t6\tC"],
+            &["L\t5\t+\t6\t+\t0M", "L\t6\t+\t9\t+\t0M"],
+            &["J\t9\t+\t5\t+\t*"],
+            &["P\tp\t5+,6+\t*"],
+            &[],
+        )
+        .unwrap();
+        let compacted = compact_sorted(&graph);
+        assert_eq!(
+            compacted.links,
+            vec![Link::new("1".to_string(), true, "2".to_string(), true)]
+        );
+        assert!(compacted.jumps.is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+//! Validation of the overlap column of P lines.
+//!
+//! The overlaps of a path are either `*` or a comma-separated list of CIGARs, one between each
+//! pair of consecutive steps. Lists of the wrong length or with malformed CIGARs are rejected
+//! by strict parsers, so they are checked before writing, reported (or rejected with
+//! `--strict-overlaps`) and can be repaired with `--fix-overlaps`.
+
+use crate::graph::path_line_steps;
+
+/// How to repair an invalid overlap column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapFix {
+    /// Replace the overlaps with `*`
+    Star,
+    /// Replace the overlaps with a `0M` for every pair of steps
+    Zero,
+}
+
+fn is_cigar(cigar: &str) -> bool {
+    let mut digits = 0;
+    for c in cigar.chars() {
+        match c {
+            '0'..='9' => digits += 1,
+            'M' | 'I' | 'D' | 'N' | 'S' | 'H' | 'P' | 'X' | '=' if digits > 0 => digits = 0,
+            _ => return false,
+        }
+    }
+    !cigar.is_empty() && digits == 0
+}
+
+/// Why the overlap column of the P line is invalid, `None` if it is valid. Invalid steps make
+/// the overlaps invalid too.
+pub fn overlap_error(path: &str) -> Option<String> {
+    let fields = path.split('\t').collect::<Vec<_>>();
+    let name = fields.get(1).unwrap_or(&"");
+    let steps = match path_line_steps(path) {
+        Ok(steps) => steps.len(),
+        Err(e) => return Some(e),
+    };
+    let overlaps = fields.get(3).unwrap_or(&"*");
+    if *overlaps == "*" {
+        return None;
+    }
+    let cigars = overlaps.split(',').collect::<Vec<_>>();
+    if cigars.len() != steps.saturating_sub(1) {
+        Some(format!(
+            "Path {} has {} overlaps for {} steps",
+            name,
+            cigars.len(),
+            steps
+        ))
+    } else {
+        cigars
+            .iter()
+            .find(|c| !is_cigar(c))
+            .map(|c| format!("Path {} has an invalid overlap {}", name, c))
+    }
+}
+
+/// Fails with the number of paths whose overlaps are invalid and the first of them
+pub fn check_overlaps(paths: &[String]) -> Result<(), String> {
+    let mut errors = paths.iter().filter_map(|p| overlap_error(p));
+    match errors.next() {
+        Some(error) => Err(format!(
+            "{} paths have invalid overlaps, e.g. {}, repair them with --fix-overlaps",
+            errors.count() + 1,
+            error
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Replaces the invalid overlap columns, returns the number of repaired paths. Fails on a path
+/// with invalid steps, which no overlaps can repair.
+pub fn fix_overlaps(paths: &mut [String], fix: OverlapFix) -> Result<usize, String> {
+    let mut fixed = 0;
+    for path in paths.iter_mut() {
+        let steps = path_line_steps(path)?.len();
+        if overlap_error(path).is_none() {
+            continue;
+        }
+        let mut fields = path.split('\t').map(|f| f.to_string()).collect::<Vec<_>>();
+        let overlaps = match fix {
+            OverlapFix::Star => "*".to_string(),
+            OverlapFix::Zero => vec!["0M"; steps.saturating_sub(1)].join(","),
+        };
+        match fields.get_mut(3) {
+            Some(field) => *field = overlaps,
+            None => fields.push(overlaps),
+        }
+        *path = fields.join("\t");
+        fixed += 1;
+    }
+    Ok(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fix_overlaps() {
+        let mut paths = vec![
+            "P\ta\t1+,2+,3-\t*".to_string(),
+            "P\tb\t1+,2+,3-\t0M,4M".to_string(),
+            "P\tc\t1+,2+,3-\t0M".to_string(),
+            "P\td\t1+,2+\tM".to_string(),
+        ];
+        assert_eq!(overlap_error(&paths[0]), None);
+        assert_eq!(overlap_error(&paths[1]), None);
+        assert_eq!(
+            check_overlaps(&paths),
+            Err(
+                "2 paths have invalid overlaps, e.g. Path c has 1 overlaps for 3 steps, \
+                 repair them with --fix-overlaps"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            overlap_error(&paths[3]),
+            Some("Path d has an invalid overlap M".to_string())
+        );
+
+        assert_eq!(fix_overlaps(&mut paths, OverlapFix::Zero), Ok(2));
+        assert_eq!(paths[1], "P\tb\t1+,2+,3-\t0M,4M");
+        assert_eq!(paths[2], "P\tc\t1+,2+,3-\t0M,0M");
+        assert_eq!(paths[3], "P\td\t1+,2+\t0M");
+        let mut paths = vec!["P\tc\t1+,2+,3-\t0M".to_string()];
+        fix_overlaps(&mut paths, OverlapFix::Star).unwrap();
+        assert_eq!(paths, vec!["P\tc\t1+,2+,3-\t*"]);
+        assert_eq!(check_overlaps(&paths), Ok(()));
+
+        let mut paths = vec!["P\te\t1+,,2+\t0M".to_string()];
+        assert!(overlap_error(&paths[0])
+            .unwrap()
+            .starts_with("Invalid steps of path e"));
+        assert!(fix_overlaps(&mut paths, OverlapFix::Star).is_err());
+    }
+}
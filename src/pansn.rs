@@ -0,0 +1,181 @@
+//! Conversion of legacy path names into PanSN names `sample#hap#contig` (`--to-pansn`).
+//!
+//! Names are taken from a mapping file (`legacy<TAB>sample#hap#contig`, W lines are looked up
+//! by `sample#hap#contig` of their columns) or guessed:
+//! - `sample#contig` gets haplotype 0,
+//! - `sample.hap.contig` and `sample_hap_contig` are split, where the haplotype is a number
+//!   (optionally prefixed with `hap` or `h`) or `pat`/`mat` for 1/2,
+//! - W lines with a full PanSN name as contig (`HG002#1#chr1`) keep only the contig part.
+//!
+//! Names already in PanSN form and names matching no rule are left unchanged.
+
+use crate::graph::split_pansn;
+use crate::trim::{walk_name, Trimmed};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+lazy_static! {
+    static ref LEGACY: Regex =
+        Regex::new(r"^([^#._]+)[._](?:hap|h)?(\d+|pat|mat|paternal|maternal)[._](.+)$").unwrap();
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PansnNames {
+    /// Legacy names with their PanSN names
+    map: HashMap<String, String>,
+    /// Guess the names not in `map`
+    heuristics: bool,
+}
+
+impl PansnNames {
+    pub fn new(heuristics: bool) -> Self {
+        PansnNames {
+            map: HashMap::new(),
+            heuristics,
+        }
+    }
+
+    /// Reads a mapping `legacy<TAB>sample#hap#contig`, one name per line
+    pub fn read_map<R: BufRead>(&mut self, input: R) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid PanSN mapping {}", line),
+                )
+            };
+            let (legacy, name) = line.split_once('\t').ok_or_else(invalid)?;
+            if split_pansn(name).is_none() {
+                return Err(invalid());
+            }
+            self.map.insert(legacy.to_string(), name.to_string());
+        }
+        Ok(())
+    }
+
+    /// PanSN name of a path, `None` if it stays unchanged
+    pub fn path_name(&self, name: &str) -> Option<String> {
+        if let Some(mapped) = self.map.get(name) {
+            return Some(mapped.clone());
+        }
+        if !self.heuristics || split_pansn(name).is_some() {
+            return None;
+        }
+        if let Some((sample, contig)) = name.split_once('#') {
+            return Some(format!("{}#0#{}", sample, contig));
+        }
+        let caps = LEGACY.captures(name)?;
+        let hap = match &caps[2] {
+            "pat" | "paternal" => "1",
+            "mat" | "maternal" => "2",
+            hap => hap,
+        };
+        Some(format!("{}#{}#{}", &caps[1], hap, &caps[3]))
+    }
+
+    /// Sample, haplotype and contig of a walk, `None` if they stay unchanged
+    fn walk_columns(&self, walk: &str) -> Option<(String, String, String)> {
+        if let Some((sample, hap, contig)) =
+            self.map.get(&walk_name(walk)).and_then(|n| split_pansn(n))
+        {
+            return Some((sample.to_string(), hap.to_string(), contig.to_string()));
+        }
+        let fields = walk.split('\t').collect::<Vec<_>>();
+        match split_pansn(fields[3]) {
+            Some((_, _, contig)) if self.heuristics => Some((
+                fields[1].to_string(),
+                fields[2].to_string(),
+                contig.to_string(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Renames the paths and walks of the graph, returns the number of paths and walks that
+    /// were not in PanSN form and could not be converted
+    pub fn normalize(&self, trimmed: &mut Trimmed) -> usize {
+        let mut unconverted = 0;
+        for path in trimmed.paths.iter_mut() {
+            let mut fields = path.split('\t').collect::<Vec<_>>();
+            match self.path_name(fields[1]) {
+                Some(name) => {
+                    fields[1] = &name;
+                    *path = fields.join("\t");
+                }
+                None if split_pansn(fields[1]).is_none() => unconverted += 1,
+                None => {}
+            }
+        }
+        for walk in trimmed.walks.iter_mut() {
+            if let Some((sample, hap, contig)) = self.walk_columns(walk) {
+                let mut fields = walk.split('\t').collect::<Vec<_>>();
+                fields[1] = &sample;
+                fields[2] = &hap;
+                fields[3] = &contig;
+                *walk = fields.join("\t");
+            }
+        }
+        unconverted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let mut names = PansnNames::new(true);
+        names
+            .read_map("chm13_chr1\tCHM13#0#chr1\nHG004#1#chr1\tHG004#2#chr1\n".as_bytes())
+            .unwrap();
+        let mut trimmed = Trimmed {
+            paths: [
+                "chm13_chr1",
+                "GRCh38#chr1",
+                "HG002.1.chr1",
+                "HG002_hap2_chr1_random",
+                "HG003.pat.chrX",
+                "HG002#1#chr1",
+                "chrM",
+            ]
+            .iter()
+            .map(|name| format!("P\t{}\t1+\t*", name))
+            .collect(),
+            walks: vec![
+                "W\tHG002\t1\tHG002#1#chr1\t0\t1\t>1".to_string(),
+                "W\tHG004\t1\tchr1\t0\t1\t>1".to_string(),
+            ],
+            ..Trimmed::default()
+        };
+        assert_eq!(names.normalize(&mut trimmed), 1);
+        let paths = trimmed
+            .paths
+            .iter()
+            .map(|p| p.split('\t').nth(1).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "CHM13#0#chr1",
+                "GRCh38#0#chr1",
+                "HG002#1#chr1",
+                "HG002#2#chr1_random",
+                "HG003#1#chrX",
+                "HG002#1#chr1",
+                "chrM"
+            ]
+        );
+        assert_eq!(
+            trimmed.walks,
+            vec!["W\tHG002\t1\tchr1\t0\t1\t>1", "W\tHG004\t2\tchr1\t0\t1\t>1"]
+        );
+        assert!(names.read_map("a\tb\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+//! Parallel iterators from rayon with feature `parallel` (the default), otherwise sequential
+//! stand-ins with the same method names, e.g. for wasm32 where threads are not available.
+
+#[cfg(feature = "parallel")]
+pub use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+#[cfg(not(feature = "parallel"))]
+pub use std::iter::Iterator as ParallelIterator;
+
+#[cfg(not(feature = "parallel"))]
+pub trait IntoParallelIterator: IntoIterator + Sized {
+    fn into_par_iter(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<T: IntoIterator> IntoParallelIterator for T {}
+
+#[cfg(not(feature = "parallel"))]
+pub trait IntoParallelRefIterator<'a> {
+    type Iter: Iterator;
+
+    fn par_iter(&'a self) -> Self::Iter;
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<'a, T: 'a + ?Sized> IntoParallelRefIterator<'a> for T
+where
+    &'a T: IntoIterator,
+{
+    type Iter = <&'a T as IntoIterator>::IntoIter;
+
+    fn par_iter(&'a self) -> Self::Iter {
+        self.into_iter()
+    }
+}
@@ -0,0 +1,176 @@
+//! Removal of segments traversed by few of the kept paths and walks (`--min-path-coverage`).
+//!
+//! The coverage of a segment is the number of kept paths and walks visiting it. Segments below
+//! the threshold are removed with their links and jumps, and the paths and walks through them
+//! are dropped since they would no longer be contiguous. Paths and walks that have to stay
+//! (`--always-keep`) form a backbone: their segments below the threshold are rescued, which is
+//! the smallest set keeping every step of them in the graph.
+
+use crate::graph::{path_line_steps, walk_line_steps};
+use crate::scan;
+use crate::trim::{walk_name, Trimmed};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default)]
+pub struct CoverageFilter {
+    /// Number of paths and walks a segment needs to be visited by
+    pub min_paths: usize,
+    /// Names of the paths and walks (PanSN) to keep contiguous
+    pub always_keep: HashSet<String>,
+}
+
+/// What [`filter_path_coverage`] removed and rescued
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageFiltered {
+    pub segments: usize,
+    pub paths: usize,
+    /// Segments below the threshold kept for the backbone, in graph order
+    pub rescued: Vec<String>,
+    /// Names to keep contiguous that are no kept path or walk
+    pub missing: usize,
+}
+
+/// Distinct segments of every kept path and walk, by name, fails on invalid steps
+fn visited_segments(trimmed: &Trimmed) -> Result<Vec<(String, HashSet<String>)>, String> {
+    let mut visited = Vec::new();
+    for p in &trimmed.paths {
+        let steps = path_line_steps(p)?;
+        let name = scan::field(p, 1).unwrap_or("").to_string();
+        visited.push((
+            name,
+            steps
+                .into_iter()
+                .map(|(node, _)| node.to_string())
+                .collect(),
+        ));
+    }
+    for w in &trimmed.walks {
+        let steps = walk_line_steps(w)?;
+        visited.push((
+            walk_name(w),
+            steps
+                .into_iter()
+                .map(|(node, _)| node.to_string())
+                .collect(),
+        ));
+    }
+    Ok(visited)
+}
+
+/// Removes the segments visited by fewer than `filter.min_paths` paths and walks, except those
+/// of the paths and walks to keep contiguous, together with their edges and the paths and
+/// walks through them. Fails on a path or walk with invalid steps.
+pub fn filter_path_coverage(
+    trimmed: &mut Trimmed,
+    filter: &CoverageFilter,
+) -> Result<CoverageFiltered, String> {
+    let visited = visited_segments(trimmed)?;
+    let mut coverage = HashMap::<&str, usize>::new();
+    for node in visited.iter().flat_map(|(_, nodes)| nodes) {
+        *coverage.entry(node.as_str()).or_default() += 1;
+    }
+    let backbone = visited
+        .iter()
+        .filter(|(name, _)| filter.always_keep.contains(name))
+        .flat_map(|(_, nodes)| nodes.iter().map(|n| n.as_str()))
+        .collect::<HashSet<_>>();
+    let found = visited.iter().map(|(name, _)| name).collect::<HashSet<_>>();
+
+    let mut filtered = CoverageFiltered {
+        missing: filter
+            .always_keep
+            .iter()
+            .filter(|n| !found.contains(n))
+            .count(),
+        ..Default::default()
+    };
+    let mut removed = HashSet::new();
+    for segment in &trimmed.segments {
+        let name = scan::field(segment, 1).unwrap_or("");
+        if coverage.get(name).copied().unwrap_or(0) >= filter.min_paths {
+            continue;
+        }
+        match backbone.contains(name) {
+            true => filtered.rescued.push(name.to_string()),
+            false => {
+                removed.insert(name.to_string());
+            }
+        }
+    }
+    if removed.is_empty() {
+        return Ok(filtered);
+    }
+
+    let is_kept = |line: &str| !removed.contains(scan::field(line, 1).unwrap_or(""));
+    trimmed.segments.retain(|s| is_kept(s));
+    let touches_removed = |line: &&str| {
+        [1, 3]
+            .into_iter()
+            .any(|i| scan::field(line, i).is_some_and(|n| removed.contains(n)))
+    };
+    trimmed.links.retain(|l| !touches_removed(l));
+    trimmed.jumps.retain(|j| !touches_removed(j));
+    let through_removed = visited
+        .iter()
+        .map(|(_, nodes)| nodes.iter().any(|n| removed.contains(n)))
+        .collect::<Vec<_>>();
+    let (paths, walks) = through_removed.split_at(trimmed.paths.len());
+    let mut i = 0;
+    trimmed.paths.retain(|_| {
+        i += 1;
+        !paths[i - 1]
+    });
+    let mut i = 0;
+    trimmed.walks.retain(|_| {
+        i += 1;
+        !walks[i - 1]
+    });
+    filtered.segments = removed.len();
+    filtered.paths = through_removed.iter().filter(|r| **r).count();
+    Ok(filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_filter_path_coverage() {
+        // 2 and 3 are private to one haplotype each, the reference takes 3
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\n\
+                       L\t1\t+\t2\t+\t0M\nL\t1\t+\t3\t+\t0M\nL\t2\t+\t4\t+\t0M\n\
+                       L\t3\t+\t4\t+\t0M\n\
+                       P\tref\t1+,3+,4+\t*\nP\ta\t1+,4+\t*\n\
+                       W\ts\t1\tc\t0\t3\t>1>2>4\n";
+        let lines = GfaLines::classify(content);
+        let mut trimmed = trim_gfa(lines.clone(), None, &TrimOptions::default()).unwrap();
+        let filter = CoverageFilter {
+            min_paths: 2,
+            always_keep: HashSet::from(["ref".to_string(), "x".to_string()]),
+        };
+        let filtered = filter_path_coverage(&mut trimmed, &filter).unwrap();
+        assert_eq!(
+            filtered,
+            CoverageFiltered {
+                segments: 1,
+                paths: 1,
+                rescued: vec!["3".to_string()],
+                missing: 1
+            }
+        );
+        assert_eq!(trimmed.segments, ["S\t1\tA", "S\t3\tG", "S\t4\tT"]);
+        assert_eq!(trimmed.links, ["L\t1\t+\t3\t+\t0M", "L\t3\t+\t4\t+\t0M"]);
+        assert_eq!(trimmed.paths.len(), 2);
+        assert!(trimmed.walks.is_empty());
+
+        let mut trimmed = trim_gfa(lines, None, &TrimOptions::default()).unwrap();
+        let filter = CoverageFilter {
+            min_paths: 2,
+            ..Default::default()
+        };
+        let filtered = filter_path_coverage(&mut trimmed, &filter).unwrap();
+        assert_eq!((filtered.segments, filtered.paths), (2, 2));
+        assert_eq!(trimmed.paths, ["P\ta\t1+,4+\t*"]);
+    }
+}
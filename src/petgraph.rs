@@ -0,0 +1,92 @@
+//! Conversion into petgraph (feature `petgraph`).
+//!
+//! The bidirected graph is encoded as a directed graph with one node per handle, i.e. two
+//! nodes per segment: the node with index `2 * rank` is the forward and `2 * rank + 1` the
+//! reverse orientation of the segment with that rank. Every edge is added in both of its
+//! directions, so `a+ -> b-` also yields `b+ -> a-`, and directed paths in the petgraph
+//! correspond to walks through the bidirected graph.
+
+use crate::handle::{Handle, HandleGraph};
+use petgraph::graph::NodeIndex;
+
+/// Weight of a petgraph node, one oriented segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeWeight {
+    pub name: String,
+    pub length: usize,
+    pub is_reverse: bool,
+}
+
+impl HandleGraph {
+    /// Directed petgraph encoding of the graph, see the module documentation
+    pub fn to_petgraph(&self) -> petgraph::Graph<NodeWeight, ()> {
+        let mut graph =
+            petgraph::Graph::with_capacity(2 * self.node_count(), 2 * self.edge_count());
+        for handle in self.handles() {
+            for h in [handle, handle.flip()] {
+                graph.add_node(NodeWeight {
+                    name: self.get_name(h).to_string(),
+                    length: self.get_length(h),
+                    is_reverse: h.is_reverse(),
+                });
+            }
+        }
+        for handle in self.handles() {
+            for h in [handle, handle.flip()] {
+                for next in self.follow_edges(h, false) {
+                    graph.add_edge(node_index(h), node_index(next), ());
+                }
+            }
+        }
+        graph
+    }
+}
+
+/// Index of the petgraph node of a handle
+pub fn node_index(handle: Handle) -> NodeIndex {
+    NodeIndex::new(2 * handle.rank() + handle.is_reverse() as usize)
+}
+
+/// Handle of a petgraph node
+pub fn handle(index: NodeIndex) -> Handle {
+    Handle::new(index.index() / 2, index.index() % 2 == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+    use petgraph::algo::has_path_connecting;
+
+    #[test]
+    fn test_to_petgraph() {
+        let graph = HandleGraph::new(
+            Graph::from_gfa("S\t1\tAAC\nS\t2\tG\nS\t3\tTT\nL\t1\t+\t2\t-\t0M\nL\t2\t-\t3\t+\t0M\n")
+                .unwrap(),
+        )
+        .unwrap();
+        let pg = graph.to_petgraph();
+        assert_eq!(pg.node_count(), 6);
+        assert_eq!(pg.edge_count(), 4);
+        let two_rev = graph.get_handle("2", true).unwrap();
+        assert_eq!(handle(node_index(two_rev)), two_rev);
+        assert_eq!(
+            pg[node_index(two_rev)],
+            NodeWeight {
+                name: "2".to_string(),
+                length: 1,
+                is_reverse: true
+            }
+        );
+        let one = node_index(graph.get_handle("1", false).unwrap());
+        let three = node_index(graph.get_handle("3", false).unwrap());
+        assert!(has_path_connecting(&pg, one, three, None));
+        assert!(!has_path_connecting(&pg, three, one, None));
+        assert!(has_path_connecting(
+            &pg,
+            node_index(graph.get_handle("3", true).unwrap()),
+            node_index(graph.get_handle("1", true).unwrap()),
+            None
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+//! Projection of the kept segments onto a reference path (`--node-positions`), as BED.
+//!
+//! Segments of the reference get the interval of each of their visits on it. Other segments
+//! are placed by the paths and walks through them: between the reference segments a path
+//! visits before and after them, e.g. the interval of the deleted reference sequence for the
+//! branch of a deletion bubble and an empty interval for an insertion. If a path leaves or ends
+//! off the reference, the segments after its last reference segment get an empty interval at
+//! its end (or before its first one at its start). Segments placed differently by several paths
+//! get all their intervals, segments not on a path touching the reference are left out.
+
+use crate::graph::{path_line_steps, segment_length, walk_line_steps};
+use crate::removed::find_reference;
+use crate::trim::Trimmed;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodePositions {
+    /// Contig of the reference, the sample and haplotype of PanSN names are left out
+    pub contig: String,
+    /// Start, end and segment, sorted
+    pub intervals: BTreeSet<(usize, usize, String)>,
+    /// Number of segments that could not be placed
+    pub unplaced: usize,
+}
+
+impl NodePositions {
+    /// Places the segments of `trimmed` on the reference, a path name or the PanSN name of a walk
+    pub fn new(trimmed: &Trimmed, reference: &str) -> Result<Self, String> {
+        let (steps, contig, start) = find_reference(&trimmed.paths, &trimmed.walks, reference)?;
+        let lengths = trimmed
+            .segments
+            .iter()
+            .map(|s| {
+                let name = s.split('\t').nth(1).expect("All nodes should have ids");
+                (name, segment_length(s))
+            })
+            .collect::<HashMap<_, _>>();
+        let length = |node: &str| lengths.get(node).copied().unwrap_or(0);
+
+        let mut intervals = BTreeSet::new();
+        // First visit of each reference segment
+        let mut on_reference = HashMap::new();
+        let mut offset = start;
+        for node in &steps {
+            let end = offset + length(node);
+            on_reference.entry(node.clone()).or_insert((offset, end));
+            intervals.insert((offset, end, node.clone()));
+            offset = end;
+        }
+
+        let mut paths = trimmed
+            .paths
+            .iter()
+            .map(|p| path_line_steps(p))
+            .collect::<Result<Vec<_>, String>>()?;
+        for walk in &trimmed.walks {
+            paths.push(walk_line_steps(walk)?);
+        }
+        for path in paths {
+            let mut before: Option<(usize, usize)> = None;
+            let mut pending = Vec::new();
+            for (node, _) in path {
+                let Some(&after) = on_reference.get(node) else {
+                    pending.push(node.to_string());
+                    continue;
+                };
+                // The interval between the two reference segments, in either direction
+                let (start, end) = match before {
+                    Some(before) if before.0 <= after.0 => (before.1, after.0.max(before.1)),
+                    Some(before) => (after.1, before.0.max(after.1)),
+                    None => (after.0, after.0),
+                };
+                for node in pending.drain(..) {
+                    intervals.insert((start, end, node));
+                }
+                before = Some(after);
+            }
+            if let Some((_, end)) = before {
+                for node in pending {
+                    intervals.insert((end, end, node));
+                }
+            }
+        }
+
+        let placed = intervals
+            .iter()
+            .map(|(_, _, node)| node.as_str())
+            .collect::<HashSet<_>>();
+        let unplaced = lengths.keys().filter(|n| !placed.contains(*n)).count();
+        Ok(NodePositions {
+            contig,
+            intervals,
+            unplaced,
+        })
+    }
+
+    /// Writes one BED line `contig start end segment` per interval
+    pub fn write_bed<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (start, end, node) in &self.intervals {
+            writeln!(out, "{}\t{}\t{}\t{}", self.contig, start, end, node)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_positions() {
+        // 2 is an insertion after 1, 5 replaces 3 and 4 in reverse, 6 is not on any path
+        let trimmed = Trimmed {
+            segments: vec![
+                "S\t1\tACGT",
+                "S\t2\tAA",
+                "S\t3\tCCC",
+                "S\t4\tGG",
+                "S\t5\tT",
+                "S\t6\tT",
+            ],
+            paths: vec!["P\tGRCh38#0#chr1:100-109\t1+,3+,4+\t*".to_string()],
+            walks: vec!["W\tHG002\t1\tchr1\t0\t7\t<4<5<1>2".to_string()],
+            ..Trimmed::default()
+        };
+        let positions = NodePositions::new(&trimmed, "GRCh38#0#chr1:100-109").unwrap();
+        assert_eq!(positions.unplaced, 1);
+        let mut out = Vec::new();
+        positions.write_bed(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "chr1\t100\t104\t1\nchr1\t104\t104\t2\nchr1\t104\t107\t3\nchr1\t104\t107\t5\n\
+             chr1\t107\t109\t4\n"
+        );
+        assert!(NodePositions::new(&trimmed, "CHM13").is_err());
+    }
+}
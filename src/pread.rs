@@ -0,0 +1,79 @@
+//! Reading a file with positioned reads from several threads (`--parallel-read`).
+//!
+//! On striped parallel file systems such as Lustre, a single sequential reader only talks to
+//! one storage target at a time and leaves most of the bandwidth unused. The file is therefore
+//! split into large blocks that several threads read at their offsets (`pread`), the blocks
+//! of each thread spread over the whole file. Only on Unix, elsewhere the file is read
+//! sequentially.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Size of the blocks read at once, a multiple of common stripe sizes
+pub const BLOCK_SIZE: usize = 16 << 20;
+
+/// Reads the whole file in blocks of `block_size` bytes on `threads` threads
+#[cfg(unix)]
+pub fn read_parallel(path: &Path, threads: usize, block_size: usize) -> io::Result<Vec<u8>> {
+    use std::os::unix::fs::FileExt;
+
+    let file = fs::File::open(path)?;
+    let mut content = vec![0; file.metadata()?.len() as usize];
+    let (threads, block_size) = (threads.max(1), block_size.max(1));
+    let mut blocks = (0..threads).map(|_| Vec::new()).collect::<Vec<_>>();
+    for (i, block) in content.chunks_mut(block_size).enumerate() {
+        blocks[i % threads].push(((i * block_size) as u64, block));
+    }
+    std::thread::scope(|scope| {
+        let readers = blocks
+            .into_iter()
+            .map(|blocks| {
+                let file = &file;
+                scope.spawn(move || {
+                    for (offset, block) in blocks {
+                        let mut done = 0;
+                        while done < block.len() {
+                            match file.read_at(&mut block[done..], offset + done as u64)? {
+                                0 => {
+                                    return Err(io::Error::new(
+                                        io::ErrorKind::UnexpectedEof,
+                                        "File shrank while reading it",
+                                    ))
+                                }
+                                n => done += n,
+                            }
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+        readers
+            .into_iter()
+            .try_for_each(|reader| reader.join().expect("Reader thread panicked"))
+    })?;
+    Ok(content)
+}
+
+#[cfg(not(unix))]
+pub fn read_parallel(path: &Path, _threads: usize, _block_size: usize) -> io::Result<Vec<u8>> {
+    fs::read(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_parallel() {
+        let file = std::env::temp_dir().join(format!("trim-graph-pread-{}", std::process::id()));
+        let content = (0..1000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        fs::write(&file, &content).unwrap();
+        for (threads, block_size) in [(1, BLOCK_SIZE), (3, 7), (8, 1000), (4, 1)] {
+            assert_eq!(read_parallel(&file, threads, block_size).unwrap(), content);
+        }
+        fs::remove_file(&file).unwrap();
+        assert!(read_parallel(&file, 2, 7).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! Header lines describing how a graph was trimmed (`--provenance`).
+//!
+//! The first line names the program, its version and the command line, which holds the
+//! selection and filters applied; one line per kept path and walk follows, so the selection
+//! can be read back without the keep lists. The tags are lowercase, i.e. user-defined in GFA,
+//! and lines written by an earlier run are replaced when a graph is trimmed again (tabs shown
+//! as spaces):
+//!
+//! ```text
+//! H  tg:Z:trim-graph 0.1.0  cl:Z:trim-graph graph.gfa -p keep.txt --provenance
+//! H  kp:Z:GRCh38#0#chr1
+//! H  kw:Z:HG002#1#chr1
+//! ```
+
+use crate::scan;
+use crate::trim::{walk_name, Trimmed};
+
+/// Header line of the program and command line, without the values
+pub const PROGRAM_HEADER: &str = "H\ttg:Z:";
+/// Header line of a kept path, without its name
+pub const PATH_HEADER: &str = "H\tkp:Z:";
+/// Header line of a kept walk, without its PanSN name
+pub const WALK_HEADER: &str = "H\tkw:Z:";
+
+/// Whether the header line was written by [`provenance_headers`]
+pub fn is_provenance(header: &str) -> bool {
+    [PROGRAM_HEADER, PATH_HEADER, WALK_HEADER]
+        .iter()
+        .any(|prefix| header.starts_with(prefix))
+}
+
+/// Header lines for the command line `args` and the paths and walks of `trimmed`. Tabs and
+/// line breaks in the arguments become spaces.
+pub fn provenance_headers(args: &[String], trimmed: &Trimmed) -> Vec<String> {
+    let command_line = args.join(" ").replace(['\t', '\n', '\r'], " ");
+    let program = format!(
+        "{}trim-graph {}\tcl:Z:{}",
+        PROGRAM_HEADER,
+        env!("CARGO_PKG_VERSION"),
+        command_line
+    );
+    let paths = trimmed
+        .paths
+        .iter()
+        .map(|p| format!("{}{}", PATH_HEADER, scan::field(p, 1).unwrap_or("")));
+    let walks = trimmed
+        .walks
+        .iter()
+        .map(|w| format!("{}{}", WALK_HEADER, walk_name(w)));
+    std::iter::once(program).chain(paths).chain(walks).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_provenance_headers() {
+        let content = "H\tVN:Z:1.0\nH\tkp:Z:old\nS\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\n\
+                       P\tp1\t1+\t*\nP\tp2\t2+\t*\nW\ts\t1\tc\t0\t1\t>2\n";
+        let trimmed = trim_gfa(
+            GfaLines::classify(content),
+            Some(vec!["p1".to_string()]),
+            &TrimOptions::default(),
+        )
+        .unwrap();
+        let args = ["trim-graph", "g.gfa", "-p", "keep\tlist.txt"].map(String::from);
+        let headers = provenance_headers(&args, &trimmed);
+        assert_eq!(
+            headers,
+            [
+                format!(
+                    "H\ttg:Z:trim-graph {}\tcl:Z:trim-graph g.gfa -p keep list.txt",
+                    env!("CARGO_PKG_VERSION")
+                ),
+                "H\tkp:Z:p1".to_string(),
+                "H\tkw:Z:s#1#c".to_string()
+            ]
+        );
+        assert!(headers.iter().all(|h| is_provenance(h)));
+        assert!(is_provenance(trimmed.headers[1]));
+        assert!(!is_provenance(trimmed.headers[0]));
+    }
+}
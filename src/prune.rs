@@ -0,0 +1,193 @@
+//! Pruning of complex regions before GCSA/Giraffe index construction, like `vg prune`.
+//!
+//! A window of `window` bp is followed from the end of every segment in both orientations. If
+//! it has to choose between several edges at more than `max_edges` points, the edges after the
+//! last allowed choice are complex. Complex links that no kept path or walk traverses are
+//! removed, so the paths and walks stay intact. Jumps are left as they are.
+
+use crate::graph::{path_line_steps, segment_length, walk_line_steps, EdgeRecord};
+use crate::handle::Handle;
+use crate::par::{IntoParallelIterator, ParallelIterator};
+use crate::trim::{flatten_into_hashset, Trimmed};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct PruneOptions {
+    /// Length of the window in bp, the k-mer length of the index
+    pub window: usize,
+    /// Number of edge choices allowed within a window
+    pub max_edges: usize,
+}
+
+impl Default for PruneOptions {
+    /// Defaults of `vg prune`
+    fn default() -> Self {
+        PruneOptions {
+            window: 24,
+            max_edges: 3,
+        }
+    }
+}
+
+fn slot(h: Handle) -> usize {
+    2 * h.rank() + h.is_reverse() as usize
+}
+
+/// Ids of the complex edges, `right` holds the (handle, edge id) pairs reachable by going
+/// right from every handle (indexed by [`slot`])
+fn complex_edges(
+    lengths: &[usize],
+    right: &[Vec<(Handle, usize)>],
+    options: &PruneOptions,
+) -> HashSet<usize> {
+    let complex = (0..right.len())
+        .into_par_iter()
+        .map(|start| {
+            let mut complex = Vec::new();
+            let start = Handle::new(start / 2, start % 2 == 1);
+            let mut stack = vec![(start, options.window.saturating_sub(1), 0)];
+            // Segments without sequence do not shorten the window, a cycle of them would be
+            // followed forever without the states already seen from this start
+            let mut seen = HashSet::new();
+            while let Some((handle, remaining, choices)) = stack.pop() {
+                if !seen.insert((handle, remaining, choices)) {
+                    continue;
+                }
+                let next = &right[slot(handle)];
+                let choices = choices + (next.len() > 1) as usize;
+                for (to, id) in next {
+                    if choices > options.max_edges {
+                        complex.push(*id);
+                        continue;
+                    }
+                    let remaining = remaining.saturating_sub(lengths[to.rank()]);
+                    if remaining > 0 {
+                        stack.push((*to, remaining, choices));
+                    }
+                }
+            }
+            complex
+        })
+        .collect::<Vec<_>>();
+    flatten_into_hashset(complex)
+}
+
+/// Removes the complex links not traversed by a kept path or walk, returns their number. Fails
+/// on an invalid link or a path or walk with invalid steps.
+pub fn prune_complex(trimmed: &mut Trimmed, options: &PruneOptions) -> Result<usize, String> {
+    log::info!("Pruning complex regions");
+    let mut ranks = HashMap::new();
+    let mut lengths = Vec::new();
+    for line in &trimmed.segments {
+        let name = line.split('\t').nth(1).expect("All nodes should have ids");
+        ranks.insert(name, lengths.len());
+        lengths.push(segment_length(line));
+    }
+    let handle = |node: &str, forward: bool| ranks.get(node).map(|r| Handle::new(*r, !forward));
+
+    // Edges can be followed in both directions, a+ -> b- also leads from b+ to a-
+    let mut right = vec![Vec::new(); 2 * lengths.len()];
+    let mut edge_ids = HashMap::new();
+    for (id, line) in trimmed.links.iter().enumerate() {
+        let edge = EdgeRecord::parse(line)?.edge;
+        let (Some(from), Some(to)) = (
+            handle(edge.from, edge.from_forward),
+            handle(edge.to, edge.to_forward),
+        ) else {
+            continue;
+        };
+        right[slot(from)].push((to, id));
+        edge_ids.insert((from, to), id);
+        if to.flip() != from {
+            right[slot(to.flip())].push((from.flip(), id));
+            edge_ids.insert((to.flip(), from.flip()), id);
+        }
+    }
+    let used_edges = |steps: &[(&str, bool)]| {
+        steps
+            .iter()
+            .map(|(node, forward)| handle(node, *forward))
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default()
+            .windows(2)
+            .filter_map(|pair| edge_ids.get(&(pair[0], pair[1])).copied())
+            .collect::<Vec<_>>()
+    };
+
+    let complex = complex_edges(&lengths, &right, options);
+    let mut supported = HashSet::new();
+    for path in &trimmed.paths {
+        supported.extend(used_edges(&path_line_steps(path)?));
+    }
+    for walk in &trimmed.walks {
+        supported.extend(used_edges(&walk_line_steps(walk)?));
+    }
+    let removed = complex.difference(&supported).collect::<HashSet<_>>();
+
+    let mut id = 0;
+    trimmed.links.retain(|_| {
+        id += 1;
+        !removed.contains(&(id - 1))
+    });
+    Ok(removed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_prune_complex() {
+        // Two bubbles in a row, the reference takes the upper branch of both
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nS\t5\tA\nS\t6\tC\nS\t7\tG\n\
+                       L\t1\t+\t2\t+\t0M\nL\t1\t+\t3\t+\t0M\nL\t2\t+\t4\t+\t0M\n\
+                       L\t3\t+\t4\t+\t0M\nL\t4\t+\t5\t+\t0M\nL\t4\t+\t6\t+\t0M\n\
+                       L\t5\t+\t7\t+\t0M\nL\t6\t+\t7\t+\t0M\n\
+                       P\tref\t1+,2+,4+,5+,7+\t*\n\
+                       W\ts\t1\tc\t0\t5\t>1>3>4>6>7\nW\tt\t1\tc\t0\t5\t>1>3>4>5>7\n\
+                       W\tu\t1\tc\t0\t5\t<7<5<4<2<1\n";
+        // Walk s is not kept, the links of its branches stay as links are not trimmed
+        let trim_options = TrimOptions {
+            ignore_segments: true,
+            ignore_links: true,
+            walks_to_keep: Some(HashSet::from(["t#1#c".to_string(), "u#1#c".to_string()])),
+            ..TrimOptions::default()
+        };
+        let mut trimmed = trim_gfa(GfaLines::classify(content), None, &trim_options).unwrap();
+        let options = PruneOptions {
+            window: 24,
+            max_edges: 1,
+        };
+        // 3+ 4+ is complex as well, but walk t traverses it
+        assert_eq!(prune_complex(&mut trimmed, &options), Ok(1));
+        assert_eq!(trimmed.links.len(), 7);
+        assert!(!trimmed.links.contains(&"L\t4\t+\t6\t+\t0M"));
+        assert_eq!(trimmed.walks.len(), 2);
+
+        let mut untouched = trim_gfa(GfaLines::classify(content), None, &trim_options).unwrap();
+        assert_eq!(
+            prune_complex(&mut untouched, &PruneOptions::default()),
+            Ok(0)
+        );
+        assert_eq!(untouched.links.len(), 8);
+    }
+
+    #[test]
+    fn test_prune_cycle_without_sequence() {
+        // The window never ends on a cycle of segments without sequence
+        let content = "S\t1\t*\nS\t2\t*\nS\t3\tA\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t1\t+\t0M\n\
+                       L\t3\t+\t1\t+\t0M\nL\t3\t+\t2\t+\t0M\nP\tp\t3+,1+,2+,1+\t*\n";
+        let trim_options = TrimOptions {
+            ignore_links: true,
+            ..TrimOptions::default()
+        };
+        let mut trimmed = trim_gfa(GfaLines::classify(content), None, &trim_options).unwrap();
+        let options = PruneOptions {
+            window: 24,
+            max_edges: 0,
+        };
+        assert_eq!(prune_complex(&mut trimmed, &options), Ok(1));
+        assert!(!trimmed.links.contains(&"L\t3\t+\t2\t+\t0M"));
+    }
+}
@@ -0,0 +1,81 @@
+//! Editing of the header (H lines) of a graph (`trim-graph reheader`).
+//!
+//! Tags are removed from all H lines, set tags replace all earlier values and are appended to
+//! the first H line, which is added at the top if the graph has none. H lines left without tags
+//! are dropped. All other lines are written byte for byte as they are.
+
+use std::io::{self, Write};
+
+/// Tags to set (`VN:Z:1.1`) and names of tags to remove (`RS`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderEdit {
+    pub set: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+/// Name of a tag, the part before the first colon
+fn tag_name(tag: &str) -> &str {
+    tag.split(':').next().unwrap_or(tag)
+}
+
+/// Writes the content with the edited header
+pub fn reheader<W: Write>(content: &str, edit: &HeaderEdit, out: &mut W) -> io::Result<()> {
+    let dropped = |tag: &str| {
+        let name = tag_name(tag);
+        edit.remove.iter().any(|r| r == name) || edit.set.iter().any(|s| tag_name(s) == name)
+    };
+    let mut set_written = edit.set.is_empty();
+    if !content.lines().any(|l| l.starts_with('H')) && !set_written {
+        writeln!(out, "H\t{}", edit.set.join("\t"))?;
+        set_written = true;
+    }
+    for line in content.split_inclusive('\n') {
+        if !line.starts_with('H') {
+            out.write_all(line.as_bytes())?;
+            continue;
+        }
+        let text = line.trim_end_matches(['\n', '\r']);
+        let mut tags = text
+            .split('\t')
+            .skip(1)
+            .filter(|t| !dropped(t))
+            .collect::<Vec<_>>();
+        if !set_written {
+            tags.extend(edit.set.iter().map(|s| s.as_str()));
+            set_written = true;
+        }
+        if tags.is_empty() {
+            continue;
+        }
+        let ending = &line[text.len()..];
+        write!(out, "H\t{}{}", tags.join("\t"), ending)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reheader() {
+        let edit = HeaderEdit {
+            set: vec!["VN:Z:1.1".to_string(), "RS:Z:GRCh38 CHM13".to_string()],
+            remove: vec!["pg".to_string()],
+        };
+        let content = "H\tVN:Z:1.0\tpg:Z:minigraph\r\nS\t1\tA\r\nH\tpg:Z:vg\r\nS\t2\tC";
+        let mut out = Vec::new();
+        reheader(content, &edit, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.1\tRS:Z:GRCh38 CHM13\r\nS\t1\tA\r\nS\t2\tC"
+        );
+
+        let mut out = Vec::new();
+        reheader("S\t1\tA\n", &edit, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "H\tVN:Z:1.1\tRS:Z:GRCh38 CHM13\nS\t1\tA\n"
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Report of the sequence removed by trimming along a reference path (`--removed-bed`).
+//!
+//! Every segment is placed at the reference segment closest to it (in number of links or
+//! jumps), at the offset of that segment on the reference. The lengths of the removed segments
+//! are summed per window of the reference and written as BED, so regions where trimming
+//! discarded much non-reference sequence stand out.
+
+use crate::extract::split_fragment;
+use crate::graph::{path_line_steps, segment_length, split_pansn, WalkRecord};
+use crate::trim::{walk_name, GfaLines, Trimmed};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, Write};
+
+/// Removed bp per window of a reference path
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovedReport {
+    /// Contig of the reference, the sample and haplotype of PanSN names are left out
+    pub contig: String,
+    pub window: usize,
+    /// Start and end of the reference on the contig
+    pub start: usize,
+    pub end: usize,
+    /// Removed bp per window, starting with the window of `start`
+    pub removed: Vec<usize>,
+    /// Removed bp not connected to the reference
+    pub unplaced: usize,
+}
+
+/// Steps, contig and start of the reference among the P and W lines, given by the name of a P
+/// line or the PanSN name of a W line. Fails if there is no such line or its steps are invalid.
+pub(crate) fn find_reference<S: AsRef<str>>(
+    paths: &[S],
+    walks: &[S],
+    reference: &str,
+) -> Result<(Vec<String>, String, usize), String> {
+    for line in paths {
+        let line = line.as_ref();
+        if line.split('\t').nth(1) == Some(reference) {
+            let (name, start) = split_fragment(reference);
+            let contig = split_pansn(name).map_or(name, |(_, _, contig)| contig);
+            let steps = path_line_steps(line)?;
+            let steps = steps
+                .into_iter()
+                .map(|(node, _)| node.to_string())
+                .collect();
+            return Ok((steps, contig.to_string(), start));
+        }
+    }
+    for line in walks {
+        let line = line.as_ref();
+        if walk_name(line) == reference {
+            let walk = WalkRecord::parse(line)?;
+            let steps = walk
+                .steps
+                .into_iter()
+                .map(|(node, _)| node.to_string())
+                .collect();
+            return Ok((steps, walk.seq_id.to_string(), walk.seq_start.unwrap_or(0)));
+        }
+    }
+    Err(format!(
+        "Reference {} is no path or walk of the graph",
+        reference
+    ))
+}
+
+impl RemovedReport {
+    /// Sums up the segments of `lines` missing in `trimmed` along the reference
+    pub fn new(
+        lines: &GfaLines,
+        trimmed: &Trimmed,
+        reference: &str,
+        window: usize,
+    ) -> Result<Self, String> {
+        let (steps, contig, start) = find_reference(&lines.paths, &lines.walks, reference)?;
+        let lengths = lines
+            .segments
+            .iter()
+            .map(|s| {
+                let name = s.split('\t').nth(1).expect("All nodes should have ids");
+                (name, segment_length(s))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut neighbours = HashMap::<&str, Vec<&str>>::new();
+        for line in lines.links.iter().chain(&lines.jumps) {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            if let (Some(from), Some(to)) = (fields.get(1), fields.get(3)) {
+                neighbours.entry(from).or_default().push(to);
+                neighbours.entry(to).or_default().push(from);
+            }
+        }
+
+        // Breadth-first search from all reference segments at once
+        let mut positions = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut offset = start;
+        for node in &steps {
+            if !positions.contains_key(node.as_str()) {
+                positions.insert(node.as_str(), offset);
+                queue.push_back(node.as_str());
+            }
+            offset += lengths.get(node.as_str()).copied().unwrap_or(0);
+        }
+        let end = offset;
+        while let Some(node) = queue.pop_front() {
+            let position = positions[node];
+            for next in neighbours.get(node).into_iter().flatten() {
+                if !positions.contains_key(next) {
+                    positions.insert(next, position);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let kept = trimmed
+            .segments
+            .iter()
+            .map(|s| s.split('\t').nth(1).expect("All nodes should have ids"))
+            .collect::<HashSet<_>>();
+        let mut removed = vec![0; (end - start).div_ceil(window).max(1)];
+        let mut unplaced = 0;
+        for (name, length) in &lengths {
+            if kept.contains(name) {
+                continue;
+            }
+            match positions.get(name) {
+                Some(position) => {
+                    let bin = ((position - start) / window).min(removed.len() - 1);
+                    removed[bin] += length;
+                }
+                None => unplaced += length,
+            }
+        }
+        Ok(RemovedReport {
+            contig,
+            window,
+            start,
+            end,
+            removed,
+            unplaced,
+        })
+    }
+
+    /// Writes one BED line `contig start end removed_bp` per window
+    pub fn write_bed<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (i, removed) in self.removed.iter().enumerate() {
+            let start = self.start + i * self.window;
+            let end = (start + self.window).min(self.end);
+            writeln!(out, "{}\t{}\t{}\t{}", self.contig, start, end, removed)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, TrimOptions};
+
+    #[test]
+    fn test_removed_report() {
+        // The sample takes a detour of 6 bp at the start and skips segment 4 near the end,
+        // segment 6 hangs off the end and segment 7 is not connected to anything
+        let content = "S\t1\tACGT\nS\t2\tAAAAAA\nS\t3\tCCCC\nS\t4\tGG\n\
+                       S\t5\tTT\nS\t6\tA\nS\t7\tCCC\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t1\t+\t3\t+\t0M\n\
+                       L\t3\t+\t4\t+\t0M\nL\t4\t+\t5\t+\t0M\nL\t3\t+\t5\t+\t0M\n\
+                       L\t5\t+\t6\t+\t0M\n\
+                       P\tGRCh38#0#chr1\t1+,3+,4+,5+\t*\nW\tHG002\t1\tchr1\t0\t16\t>1>2>3>5\n";
+        let lines = GfaLines::classify(content);
+        let options = TrimOptions {
+            walks_to_keep: Some(HashSet::new()),
+            ..TrimOptions::default()
+        };
+        let trimmed = trim_gfa(lines.clone(), None, &options).unwrap();
+        let report = RemovedReport::new(&lines, &trimmed, "GRCh38#0#chr1", 5).unwrap();
+        assert_eq!(report.removed, vec![6, 0, 1]);
+        assert_eq!(report.unplaced, 3);
+
+        let trimmed = trim_gfa(lines.clone(), Some(Vec::new()), &TrimOptions::default()).unwrap();
+        let report = RemovedReport::new(&lines, &trimmed, "HG002#1#chr1", 5).unwrap();
+        assert_eq!(report.removed, vec![0, 0, 3, 0]);
+        let mut out = Vec::new();
+        report.write_bed(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "chr1\t0\t5\t0\nchr1\t5\t10\t0\nchr1\t10\t15\t3\nchr1\t15\t16\t0\n"
+        );
+    }
+
+    #[test]
+    fn test_find_reference_truncated() {
+        // A reference W line cut off after the contig or the start is an error, not a panic
+        for line in ["W\tHG002\t1\tchr1", "W\tHG002\t1\tchr1\t0"] {
+            assert!(find_reference(&[], &[line], "HG002#1#chr1")
+                .unwrap_err()
+                .starts_with("Walk with"));
+        }
+    }
+}
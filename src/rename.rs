@@ -0,0 +1,128 @@
+//! Renaming of paths and walk samples with sed-like rules (`--rename-paths 's/old/new/'`).
+//!
+//! A rule is `s<d>pattern<d>replacement<d>` with any delimiter `<d>`, optionally followed by `g`
+//! to replace all matches instead of the first. Patterns are regular expressions, groups are
+//! referenced as `$1` or `\1` in the replacement. Rules apply in order to the names of P lines
+//! and the sample column of W lines.
+
+use crate::trim::Trimmed;
+use regex::Regex;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct RenameRule {
+    pattern: Regex,
+    replacement: String,
+    global: bool,
+}
+
+/// Splits at the delimiters not escaped with a backslash, escaped delimiters are unescaped
+fn split_unescaped(s: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&delimiter) => {
+                parts.last_mut().unwrap().push(delimiter);
+                chars.next();
+            }
+            c if c == delimiter => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+impl FromStr for RenameRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid rename rule {}, expected s/pattern/replacement/", s);
+        let mut chars = s.chars();
+        let delimiter = match (chars.next(), chars.next()) {
+            (Some('s'), Some(d)) if !d.is_alphanumeric() && d != '\\' => d,
+            _ => return Err(invalid()),
+        };
+        let parts = split_unescaped(chars.as_str(), delimiter);
+        let [pattern, replacement, flags] = &parts[..] else {
+            return Err(invalid());
+        };
+        if !flags.is_empty() && flags != "g" {
+            return Err(invalid());
+        }
+        let pattern = Regex::new(pattern).map_err(|e| format!("{}: {}", invalid(), e))?;
+        let replacement = Regex::new(r"\\(\d)")
+            .expect("Valid regex")
+            .replace_all(replacement, "$${$1}")
+            .into_owned();
+        Ok(RenameRule {
+            pattern,
+            replacement,
+            global: flags == "g",
+        })
+    }
+}
+
+impl RenameRule {
+    pub fn apply(&self, name: &str) -> String {
+        match self.global {
+            true => self.pattern.replace_all(name, self.replacement.as_str()),
+            false => self.pattern.replace(name, self.replacement.as_str()),
+        }
+        .into_owned()
+    }
+}
+
+/// Applies all rules in order
+pub fn rename(rules: &[RenameRule], name: &str) -> String {
+    rules
+        .iter()
+        .fold(name.to_string(), |name, rule| rule.apply(&name))
+}
+
+/// Renames the paths and the samples of the walks of the graph
+pub fn rename_paths(trimmed: &mut Trimmed, rules: &[RenameRule]) {
+    for line in trimmed.paths.iter_mut().chain(trimmed.walks.iter_mut()) {
+        let mut fields = line.split('\t').collect::<Vec<_>>();
+        let renamed = rename(rules, fields[1]);
+        if renamed != fields[1] {
+            fields[1] = &renamed;
+            *line = fields.join("\t");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_paths() {
+        let rules = ["s/^chm13\\.//", "s|(\\w+)_hap(\\d)|\\1#$2|", "s/\\./_/g"]
+            .iter()
+            .map(|r| r.parse::<RenameRule>().unwrap())
+            .collect::<Vec<_>>();
+        let mut trimmed = Trimmed {
+            paths: vec![
+                "P\tchm13.chr1\t1+\t*".to_string(),
+                "P\tHG002_hap1#chr1.1\t1+\t*".to_string(),
+            ],
+            walks: vec!["W\tHG002.v1\t1\tchr1\t0\t1\t>1".to_string()],
+            ..Trimmed::default()
+        };
+        rename_paths(&mut trimmed, &rules);
+        assert_eq!(
+            trimmed.paths,
+            vec!["P\tchr1\t1+\t*", "P\tHG002#1#chr1_1\t1+\t*"]
+        );
+        assert_eq!(trimmed.walks, vec!["W\tHG002_v1\t1\tchr1\t0\t1\t>1"]);
+
+        assert!("s/a/b".parse::<RenameRule>().is_err());
+        assert!("s/(/b/".parse::<RenameRule>().is_err());
+        assert!("x/a/b/".parse::<RenameRule>().is_err());
+        assert_eq!(
+            "s,a\\,b,c,".parse::<RenameRule>().unwrap().apply("a,b"),
+            "c"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Reference paths of minigraph rGFA graphs (`--synthesize-reference-paths`).
+//!
+//! rGFA graphs as minigraph builds them have no P lines: the reference is implicit in the
+//! stable sequence tags of its segments, which have rank 0 (`SR:i:0`), the name of the
+//! reference sequence (`SN:Z:`) and their offset on it (`SO:i:`). The segments of every stable
+//! sequence of rank 0 are put in order of their offset and written as a P line named after the
+//! stable sequence, so that the reference can be selected like any path. Segments of other
+//! ranks only cover the variation of other assemblies and give no paths.
+
+use crate::graph::{segment_length, Stable};
+use crate::scan;
+use std::collections::{HashMap, HashSet};
+
+/// Offset and length of a segment on its stable sequence, and its name
+type Placed<'a> = (usize, usize, &'a str);
+
+/// P lines of the reference sequences
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReferencePaths {
+    pub paths: Vec<String>,
+    /// Places where a segment does not start at the end of the one before it
+    pub gaps: usize,
+}
+
+/// P lines of the stable sequences of rank 0 of the segments, in order of their first segment.
+/// Stable sequences with a path of the same name already are skipped.
+pub fn reference_paths(segments: &[&str], paths: &[&str]) -> ReferencePaths {
+    let existing = paths
+        .iter()
+        .filter_map(|p| scan::field(p, 1))
+        .collect::<HashSet<_>>();
+    let mut sequences: Vec<(String, Vec<Placed>)> = Vec::new();
+    let mut index = HashMap::new();
+    for line in segments {
+        let Some(stable) = Stable::parse(line).filter(|s| s.rank == 0) else {
+            continue;
+        };
+        if existing.contains(stable.name.as_str()) {
+            continue;
+        }
+        let name = scan::field(line, 1).unwrap_or("");
+        let i = *index.entry(stable.name.clone()).or_insert_with(|| {
+            sequences.push((stable.name, Vec::new()));
+            sequences.len() - 1
+        });
+        sequences[i]
+            .1
+            .push((stable.offset, segment_length(line), name));
+    }
+
+    let mut reference = ReferencePaths::default();
+    for (sequence, mut steps) in sequences {
+        steps.sort_unstable();
+        reference.gaps += steps
+            .windows(2)
+            .filter(|pair| pair[0].0 + pair[0].1 != pair[1].0)
+            .count();
+        let steps = steps
+            .iter()
+            .map(|(_, _, name)| format!("{}+", name))
+            .collect::<Vec<_>>()
+            .join(",");
+        reference
+            .paths
+            .push(format!("P\t{}\t{}\t*", sequence, steps));
+    }
+    reference
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_paths() {
+        let segments = [
+            "S\ts2\tGG\tSN:Z:chr1\tSO:i:4\tSR:i:0",
+            "S\ts1\tACGT\tSN:Z:chr1\tSO:i:0\tSR:i:0",
+            "S\ts3\tT\tSN:Z:sample1#chr1\tSO:i:7\tSR:i:1",
+            "S\ts4\t*\tLN:i:5\tSN:Z:chr2\tSO:i:0\tSR:i:0",
+            "S\ts5\tA\tSN:Z:chr2\tSO:i:6\tSR:i:0",
+            "S\ts6\tA\tSN:Z:chr3\tSO:i:0\tSR:i:0",
+            "S\ts7\tA",
+        ];
+        let reference = reference_paths(&segments, &["P\tchr3\ts6+\t*"]);
+        assert_eq!(
+            reference,
+            ReferencePaths {
+                paths: vec![
+                    "P\tchr1\ts1+,s2+\t*".to_string(),
+                    "P\tchr2\ts4+,s5+\t*".to_string()
+                ],
+                gaps: 1
+            }
+        );
+    }
+}
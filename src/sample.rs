@@ -0,0 +1,105 @@
+//! Random connected subgraphs as small test fixtures (`trim-graph sample-subgraph`).
+//!
+//! Starting from a random segment, the subgraph grows by a random segment adjacent to it (on
+//! either side, regardless of orientation) until it has the requested number of segments or
+//! its connected component is exhausted. The fragments of all paths and walks through it are
+//! kept, named like those of `trim-graph between`. The random numbers come from SplitMix64,
+//! so the same graph and seed give the same subgraph on every platform and version.
+
+use crate::extract::Extractor;
+use crate::graph::Graph;
+use crate::handle::{Handle, HandleGraph};
+use std::collections::HashSet;
+
+/// SplitMix64 generator
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Number below `n` (slightly biased towards small numbers for huge `n`)
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Ranks of about `nodes` connected segments grown from a random segment, in the order they
+/// were added
+pub fn sample_nodes(graph: &HandleGraph, nodes: usize, seed: u64) -> Vec<usize> {
+    if graph.node_count() == 0 {
+        return Vec::new();
+    }
+    let mut random = SplitMix64(seed);
+    let mut reached = vec![false; graph.node_count()];
+    let mut frontier = vec![random.below(graph.node_count())];
+    reached[frontier[0]] = true;
+    let mut sampled = Vec::new();
+    while sampled.len() < nodes && !frontier.is_empty() {
+        let node = frontier.swap_remove(random.below(frontier.len()));
+        sampled.push(node);
+        let handle = Handle::new(node, false);
+        for next in graph
+            .follow_edges(handle, false)
+            .chain(graph.follow_edges(handle, true))
+        {
+            if !reached[next.rank()] {
+                reached[next.rank()] = true;
+                frontier.push(next.rank());
+            }
+        }
+    }
+    sampled
+}
+
+/// Random connected subgraph of about `nodes` segments with the path and walk fragments
+/// through it, fails on a path or walk visiting a missing segment
+pub fn sample_subgraph(graph: Graph, nodes: usize, seed: u64) -> Result<Graph, String> {
+    let handles = HandleGraph::new(graph.clone())?;
+    let extractor = Extractor::new(graph);
+    let sampled = sample_nodes(&handles, nodes, seed);
+    if sampled.len() < nodes {
+        log::warn!(
+            "The connected component of the first segment has only {} segments",
+            sampled.len()
+        );
+    }
+    let names = sampled
+        .iter()
+        .map(|rank| handles.get_name(Handle::new(*rank, false)))
+        .collect::<HashSet<_>>();
+    Ok(extractor.extract_fragments(&names))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_subgraph() {
+        // Chain 1-2-3-4-5 with a separate segment 6
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nS\t5\tA\nS\t6\tC\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t4\t-\t3\t-\t0M\n\
+                       L\t4\t+\t5\t+\t0M\nP\tp\t1+,2+,3+,4+,5+\t*\n";
+        let graph = Graph::from_gfa(content).unwrap();
+        let first = sample_subgraph(graph.clone(), 3, 7).unwrap();
+        assert_eq!(first, sample_subgraph(graph.clone(), 3, 7).unwrap());
+        assert_eq!(first.segments.len(), 3);
+        assert_eq!(first.paths.len(), 1);
+        assert_eq!(first.paths[0].steps.len(), 3);
+        assert!(first.paths[0].name.starts_with("p:"));
+
+        let handles = HandleGraph::new(graph).unwrap();
+        for seed in 0..20 {
+            let sampled = sample_nodes(&handles, 2, seed);
+            assert!(sampled == [5] || sampled.len() == 2 && sampled.iter().all(|r| *r < 5));
+            let sampled = sample_nodes(&handles, 10, seed);
+            assert!(sampled == [5] || sampled.len() == 5);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Line and field scanning with `memchr`.
+//!
+//! Classifying a graph touches every byte of it, so lines and tab-separated fields are found by
+//! searching the whole buffer for `\n` and `\t` with `memchr`, which compares 16 or 32 bytes at
+//! a time with SIMD instructions where the target has them, instead of `str::lines` and
+//! `split('\t')`. Fields are short except for the sequences and steps, so the first bytes of a
+//! field are still checked one by one. `benches/scan.rs` fails if this becomes slower than the
+//! `std` equivalents.
+
+use memchr::memchr;
+
+/// Bytes checked one by one before handing over to `memchr`, whose setup costs more than it
+/// saves on the short names, orientations and overlaps that make up most fields
+const SHORT: usize = 16;
+
+/// Position of the first tab, most fields are short
+#[inline]
+fn find_tab(bytes: &[u8]) -> Option<usize> {
+    let head = &bytes[..bytes.len().min(SHORT)];
+    match head.iter().position(|b| *b == b'\t') {
+        Some(position) => Some(position),
+        None if bytes.len() > SHORT => memchr(b'\t', &bytes[SHORT..]).map(|p| p + SHORT),
+        None => None,
+    }
+}
+
+/// Lines of the content without their `\n` or `\r\n`, like `str::lines`
+#[inline]
+pub fn lines(content: &str) -> Lines<'_> {
+    Lines {
+        content,
+        position: 0,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Lines<'a> {
+    content: &'a str,
+    position: usize,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = &self.content[self.position..];
+        if rest.is_empty() {
+            return None;
+        }
+        match memchr(b'\n', rest.as_bytes()) {
+            Some(end) => {
+                self.position += end + 1;
+                let line = &rest[..end];
+                Some(line.strip_suffix('\r').unwrap_or(line))
+            }
+            None => {
+                self.position = self.content.len();
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// Tab-separated fields of a line, like `line.split('\t')`
+#[inline]
+pub fn fields(line: &str) -> Fields<'_> {
+    Fields {
+        line,
+        position: Some(0),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+    line: &'a str,
+    /// Start of the next field, `None` after the last one
+    position: Option<usize>,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a str> {
+        let start = self.position?;
+        let rest = &self.line[start..];
+        match find_tab(rest.as_bytes()) {
+            Some(end) => {
+                self.position = Some(start + end + 1);
+                Some(&rest[..end])
+            }
+            None => {
+                self.position = None;
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// Field `n` (0-based) of a line, like `line.split('\t').nth(n)`
+#[inline]
+pub fn field(line: &str, n: usize) -> Option<&str> {
+    fields(line).nth(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        for content in [
+            "",
+            "S\t1\tA",
+            "H\r\nS\t1\tA\n\nP\tp\t1+\t*\n",
+            "a\r",
+            "\n\n",
+        ] {
+            assert_eq!(
+                lines(content).collect::<Vec<_>>(),
+                content.lines().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        for line in ["", "S\t1\tA", "L\t1\t+\t2\t-\t0M\t", "\t\t"] {
+            let expected = line.split('\t').collect::<Vec<_>>();
+            assert_eq!(fields(line).collect::<Vec<_>>(), expected);
+            for n in 0..=expected.len() {
+                assert_eq!(field(line, n), expected.get(n).copied());
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! HTTP server answering subgraph queries (feature `server`).
+//!
+//! Endpoints:
+//! - `GET /paths`: names of all paths and walks, one per line
+//! - `GET /extract?region=NAME:START-END[&samples=A,B][&format=gfa|json][&exact=true]`:
+//!   subgraph around the region (see [`crate::extract`]), names containing `#` have to be
+//!   URL-encoded (`%23`), `exact=true` splits the segments at the ends of the region
+//!
+//! Segments, links and jumps are written with their tags and overlaps, as far as the
+//! extraction does not change them.
+
+use crate::extract::{Extractor, Region};
+use crate::json::write_json;
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+/// Status code, content type and body of a response
+type Response = (u16, &'static str, Vec<u8>);
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).expect("Hex digits are ASCII");
+                decoded.push(u8::from_str_radix(hex, 16).expect("Two hex digits are a byte"));
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Splits a request URL into its path and decoded query parameters
+fn parse_url(url: &str) -> (&str, HashMap<String, String>) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let params = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (key, value) = p.split_once('=').unwrap_or((p, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+    (path, params)
+}
+
+fn text(status: u16, body: String) -> Response {
+    (status, "text/plain; charset=utf-8", body.into_bytes())
+}
+
+fn extract(extractor: &Extractor, params: &HashMap<String, String>) -> Response {
+    let Some(region) = params.get("region") else {
+        return text(400, "Missing parameter region\n".to_string());
+    };
+    let region = match region.parse::<Region>() {
+        Ok(region) => region,
+        Err(e) => return text(400, e + "\n"),
+    };
+    let samples = params
+        .get("samples")
+        .map(|s| s.split(',').map(|s| s.to_string()).collect::<HashSet<_>>());
+    let graph = match params.get("exact").map(|e| e.as_str()) {
+        None | Some("false") => extractor.extract(&region, samples.as_ref()),
+        Some("true") => extractor.extract_exact(&region, samples.as_ref()),
+        Some(exact) => return text(400, format!("Invalid value {} for exact\n", exact)),
+    };
+    let graph = match graph {
+        Ok(graph) => graph,
+        Err(e) => return text(404, e + "\n"),
+    };
+    let mut body = Vec::new();
+    match params.get("format").map(|f| f.as_str()) {
+        None | Some("gfa") => {
+            graph
+                .write_gfa(&mut body)
+                .expect("Writing to memory should not fail");
+            (200, "text/x-gfa", body)
+        }
+        Some("json") => match write_json(&mut body, &graph) {
+            Ok(()) => (200, "application/json", body),
+            Err(e) => text(500, format!("{}\n", e)),
+        },
+        Some(format) => text(400, format!("Unknown format {}\n", format)),
+    }
+}
+
+fn respond(extractor: &Extractor, url: &str) -> Response {
+    let (path, params) = parse_url(url);
+    match path {
+        "/paths" => text(200, extractor.path_names().join("\n") + "\n"),
+        "/extract" => extract(extractor, &params),
+        _ => text(404, format!("Unknown endpoint {}\n", path)),
+    }
+}
+
+/// Answers requests on the given address (e.g. `127.0.0.1:8080`) until the process is killed
+pub fn serve(extractor: &Extractor, address: &str) -> io::Result<()> {
+    let server = tiny_http::Server::http(address).map_err(io::Error::other)?;
+    log::info!("Listening on http://{}", address);
+    for request in server.incoming_requests() {
+        let (status, content_type, body) = if request.method() == &tiny_http::Method::Get {
+            respond(extractor, request.url())
+        } else {
+            text(405, "Only GET requests are supported\n".to_string())
+        };
+        log::info!("{} {} {}", request.method(), request.url(), status);
+        let header = tiny_http::Header::from_bytes("Content-Type", content_type)
+            .expect("Content type should be a valid header");
+        let response = tiny_http::Response::from_data(body)
+            .with_status_code(status)
+            .with_header(header);
+        if let Err(e) = request.respond(response) {
+            log::warn!("Could not send response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    #[test]
+    fn test_parse_url() {
+        let (path, params) = parse_url("/extract?region=GRCh38%230%23chr1:1-5&samples=a,b");
+        assert_eq!(path, "/extract");
+        assert_eq!(params["region"], "GRCh38#0#chr1:1-5");
+        assert_eq!(params["samples"], "a,b");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%41%2"), "A%2");
+        // A sign is no hex digit
+        assert_eq!(percent_decode("%-1%g1"), "%-1%g1");
+    }
+
+    #[test]
+    fn test_respond() {
+        let extractor = Extractor::new(
+            Graph::from_gfa("S\t1\tAAAA\nS\t2\tCC\nL\t1\t+\t2\t+\t0M\nP\tref\t1+,2+\t*\n").unwrap(),
+        );
+        let (status, _, body) = respond(&extractor, "/extract?region=ref:4-6");
+        assert_eq!(status, 200);
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "S\t2\tCC\nP\tref:4-6\t2+\t*\n"
+        );
+        assert_eq!(respond(&extractor, "/extract?region=x:4-6").0, 404);
+        assert_eq!(
+            respond(&extractor, "/extract?region=ref:4-6&exact=1").0,
+            400
+        );
+        assert_eq!(respond(&extractor, "/extract").0, 400);
+        assert_eq!(respond(&extractor, "/paths").2, b"ref\n");
+        assert_eq!(respond(&extractor, "/").0, 404);
+    }
+}
@@ -0,0 +1,122 @@
+//! Shortest path between two oriented segments (`trim-graph shortest-path`).
+//!
+//! The path leaves the first segment on its right end in the given orientation and enters the
+//! last one in its orientation, following links and jumps like a path would, so `12+` to `987-`
+//! reads 987 in reverse. Paths are weighted by the bp of their segments (both ends included)
+//! or by their number of steps. Among equally short paths, the one through segments earlier in
+//! the graph wins.
+
+use crate::handle::{Handle, HandleGraph};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weight {
+    Bp,
+    Steps,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortestPath {
+    pub handles: Vec<Handle>,
+    pub bp: usize,
+}
+
+/// Shortest path from `from` to `to` by the given weight, `None` if `to` cannot be reached
+pub fn shortest_path(
+    graph: &HandleGraph,
+    from: Handle,
+    to: Handle,
+    weight: Weight,
+) -> Option<ShortestPath> {
+    let cost = |handle: Handle| match weight {
+        Weight::Bp => graph.get_length(handle),
+        Weight::Steps => 1,
+    };
+    // Indexed by the packed handle, 2 * rank + is_reverse
+    let index = |handle: Handle| 2 * handle.rank() + handle.is_reverse() as usize;
+    let mut distances = vec![usize::MAX; 2 * graph.node_count()];
+    let mut previous = vec![None; 2 * graph.node_count()];
+    distances[index(from)] = cost(from);
+    let mut queue = BinaryHeap::from([Reverse((cost(from), from))]);
+    while let Some(Reverse((distance, handle))) = queue.pop() {
+        if handle == to {
+            break;
+        }
+        if distance > distances[index(handle)] {
+            continue;
+        }
+        for next in graph.follow_edges(handle, false) {
+            let through = distance + cost(next);
+            if through < distances[index(next)] {
+                distances[index(next)] = through;
+                previous[index(next)] = Some(handle);
+                queue.push(Reverse((through, next)));
+            }
+        }
+    }
+    if distances[index(to)] == usize::MAX {
+        return None;
+    }
+
+    let mut handles = vec![to];
+    while let Some(handle) = previous[index(handles[handles.len() - 1])] {
+        handles.push(handle);
+    }
+    handles.reverse();
+    let bp = handles.iter().map(|h| graph.get_length(*h)).sum();
+    Some(ShortestPath { handles, bp })
+}
+
+impl ShortestPath {
+    /// Writes the steps as a P line step list, then the number of steps and the bp
+    pub fn write<W: Write>(&self, graph: &HandleGraph, out: &mut W) -> io::Result<()> {
+        let steps = self
+            .handles
+            .iter()
+            .map(|h| {
+                let orientation = if h.is_reverse() { '-' } else { '+' };
+                format!("{}{}", graph.get_name(*h), orientation)
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "path\t{}", steps.join(","))?;
+        writeln!(out, "steps\t{}", steps.len())?;
+        writeln!(out, "bp\t{}", self.bp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    #[test]
+    fn test_shortest_path() {
+        // From 1 to 4 either through the long segment 2 or through 3 and 5, 4 is entered in
+        // reverse
+        let graph = HandleGraph::new(
+            Graph::from_gfa(
+                "S\t1\tA\nS\t2\tCCCCC\nS\t3\tG\nS\t4\tTT\nS\t5\tGG\n\
+             L\t1\t+\t2\t+\t0M\nL\t2\t+\t4\t-\t0M\nL\t1\t+\t3\t-\t0M\n\
+             L\t5\t+\t3\t+\t0M\nL\t4\t+\t5\t+\t0M\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let from = graph.parse_handle("1+").unwrap();
+        let to = graph.parse_handle("4-").unwrap();
+        let mut out = Vec::new();
+        let by_bp = shortest_path(&graph, from, to, Weight::Bp).unwrap();
+        by_bp.write(&graph, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "path\t1+,3-,5-,4-\nsteps\t4\nbp\t6\n"
+        );
+        let by_steps = shortest_path(&graph, from, to, Weight::Steps).unwrap();
+        assert_eq!(by_steps.handles.len(), 3);
+        assert_eq!(by_steps.bp, 8);
+        assert_eq!(shortest_path(&graph, from, to.flip(), Weight::Bp), None);
+        assert_eq!(shortest_path(&graph, from, from, Weight::Bp).unwrap().bp, 1);
+    }
+}
@@ -0,0 +1,200 @@
+//! Filtering of per-segment sidecar files by a trimming (`trim-graph sidecar`).
+//!
+//! Annotations of segments kept next to a graph, such as BED intervals on segments, TSV tables
+//! and Bandage label CSVs, refer to segments by name. After trimming, the rows of removed
+//! segments are dropped with a keep set saved by `--save-keepset`, and segments are renamed
+//! with a translation table (`old<TAB>new`) of a renumbering; with a table, rows of segments
+//! missing from it are dropped as well. All other bytes of the kept rows are written
+//! unchanged.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Layout of a sidecar file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidecarFormat {
+    /// Intervals on segments, the segment is the first column. `#`, `track` and `browser`
+    /// lines are kept.
+    Bed,
+    /// Tab-separated table with the segment in the given column (0-based), `#` lines and the
+    /// first line if it is a header are kept
+    Tsv { column: usize, header: bool },
+    /// Bandage labels, a header line and the segment in the first of the comma-separated
+    /// columns
+    BandageCsv,
+}
+
+impl SidecarFormat {
+    /// Format by the extension of the file name, TSV with the segment in the first column for
+    /// unknown extensions
+    pub fn of_file(name: &str) -> Self {
+        let name = name.strip_suffix(".gz").unwrap_or(name);
+        match name.rsplit('.').next() {
+            Some("bed") => SidecarFormat::Bed,
+            Some("csv") => SidecarFormat::BandageCsv,
+            _ => SidecarFormat::Tsv {
+                column: 0,
+                header: false,
+            },
+        }
+    }
+
+    fn separator(&self) -> char {
+        match self {
+            SidecarFormat::BandageCsv => ',',
+            _ => '\t',
+        }
+    }
+
+    fn column(&self) -> usize {
+        match self {
+            SidecarFormat::Tsv { column, .. } => *column,
+            _ => 0,
+        }
+    }
+
+    /// Whether the line is kept as it is, without looking at a segment
+    fn passes(&self, line: &str, number: usize) -> bool {
+        line.is_empty()
+            || line.starts_with('#')
+            || match self {
+                SidecarFormat::Bed => line.starts_with("track") || line.starts_with("browser"),
+                SidecarFormat::Tsv { header, .. } => *header && number == 0,
+                SidecarFormat::BandageCsv => number == 0,
+            }
+    }
+}
+
+/// Segments to keep and their new names, rows of segments in neither are dropped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmentFilter {
+    /// Segments to keep, all if `None`
+    pub keep: Option<HashSet<String>>,
+    /// New names of the segments, names are kept if `None`
+    pub rename: Option<HashMap<String, String>>,
+}
+
+impl SegmentFilter {
+    /// Reads a translation table of two tab-separated columns, the name in the input graph and
+    /// the name in the trimmed graph
+    pub fn read_translation(content: &str) -> Result<HashMap<String, String>, String> {
+        content
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|line| match line.split('\t').collect::<Vec<_>>()[..] {
+                [old, new, ..] => Ok((old.to_string(), new.to_string())),
+                _ => Err(format!("Invalid translation {}", line)),
+            })
+            .collect()
+    }
+
+    /// Name of the segment after trimming, `None` if it was removed
+    fn apply<'a>(&'a self, segment: &'a str) -> Option<&'a str> {
+        if self
+            .keep
+            .as_ref()
+            .is_some_and(|keep| !keep.contains(segment))
+        {
+            return None;
+        }
+        match &self.rename {
+            Some(rename) => rename.get(segment).map(|s| s.as_str()),
+            None => Some(segment),
+        }
+    }
+}
+
+/// Rows kept and dropped by [`filter_sidecar`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SidecarCounts {
+    pub kept: usize,
+    pub dropped: usize,
+}
+
+/// Writes the lines of the sidecar file whose segment is kept, with the segment renamed
+pub fn filter_sidecar<W: Write>(
+    content: &str,
+    format: SidecarFormat,
+    filter: &SegmentFilter,
+    out: &mut W,
+) -> io::Result<SidecarCounts> {
+    let separator = format.separator();
+    let column = format.column();
+    let mut counts = SidecarCounts::default();
+    for (number, line) in content.lines().enumerate() {
+        if format.passes(line, number) {
+            writeln!(out, "{}", line)?;
+            continue;
+        }
+        let mut fields = line.split(separator).collect::<Vec<_>>();
+        let Some(segment) = fields.get(column).copied() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Line {} has no column {}: {}", number + 1, column + 1, line),
+            ));
+        };
+        match filter.apply(segment) {
+            Some(name) if name == segment => writeln!(out, "{}", line)?,
+            Some(name) => {
+                fields[column] = name;
+                writeln!(out, "{}", fields.join(&separator.to_string()))?;
+            }
+            None => {
+                counts.dropped += 1;
+                continue;
+            }
+        }
+        counts.kept += 1;
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(content: &str, format: SidecarFormat, filter: &SegmentFilter) -> String {
+        let mut out = Vec::new();
+        filter_sidecar(content, format, filter, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_filter_sidecar() {
+        let keep = SegmentFilter {
+            keep: Some(HashSet::from(["1".to_string(), "3".to_string()])),
+            rename: None,
+        };
+        assert_eq!(
+            filter(
+                "track name=x\n1\t0\t5\tgene a\n2\t0\t3\n3\t1\t2\n",
+                SidecarFormat::of_file("genes.bed"),
+                &keep
+            ),
+            "track name=x\n1\t0\t5\tgene a\n3\t1\t2\n"
+        );
+        assert_eq!(
+            filter(
+                "Node name,Label\n2,b\n3,c\n",
+                SidecarFormat::of_file("labels.csv"),
+                &keep
+            ),
+            "Node name,Label\n3,c\n"
+        );
+        let tsv = SidecarFormat::Tsv {
+            column: 1,
+            header: true,
+        };
+        let translated = SegmentFilter {
+            keep: None,
+            rename: Some(SegmentFilter::read_translation("1\t10\n3\t30\n").unwrap()),
+        };
+        assert_eq!(
+            filter("id\tsegment\na\t1\nb\t2\nc\t3\n", tsv, &translated),
+            "id\tsegment\na\t10\nc\t30\n"
+        );
+        let mut out = Vec::new();
+        assert!(filter_sidecar("id\tsegment\na\n", tsv, &translated, &mut out).is_err());
+        assert!(SegmentFilter::read_translation("1\n").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Validation of text inputs, recognizing binary files passed by mistake.
+//!
+//! Inputs are decompressed first, so gzip, BGZF and zstd are handled before this. Anything else
+//! has to be text: the start of the data is compared against the magic bytes of common
+//! genomics containers and archives, so that e.g. a BAM or GBZ file is reported as such with a
+//! hint instead of as a parse error, and other data with NUL or control bytes is reported as
+//! binary, like the vg, odgi and xg graph formats. Graphs have to be ASCII as the GFA
+//! specification requires; the first other byte is reported with its line and column, which
+//! also keeps byte-offset slicing in the parsers on character boundaries.
+
+/// Number of leading bytes checked for control bytes
+const SNIFF_BYTES: usize = 8192;
+
+/// Magic bytes at the start of (decompressed) data, the format they identify and a hint
+const FORMATS: &[(&[u8], &str, &str)] = &[
+    (
+        b"BAM\x01",
+        "BAM",
+        "trim-graph reads graph alignments as GAF",
+    ),
+    (b"CRAM", "CRAM", "trim-graph reads graph alignments as GAF"),
+    (b"BCF\x02", "BCF", "convert it with `bcftools view` first"),
+    (b"GBZ ", "GBZ", "extract the GFA with `vg convert -f` first"),
+    (b"TGIX", "trim-graph index", "pass it with --index"),
+    (
+        b"TGKS",
+        "trim-graph keep set",
+        "pass it with --load-keepset",
+    ),
+    (
+        b"\x43\x27\x41\x1a",
+        "2bit",
+        "convert it with `twoBitToFa` first",
+    ),
+    (b"BZh", "bzip2", "decompress it with `bunzip2` first"),
+    (b"\xfd7zXZ\x00", "xz", "decompress it with `unxz` first"),
+    (b"PK\x03\x04", "zip", "unpack it first"),
+    (b"\x89HDF\r\n\x1a\n", "HDF5", "trim-graph reads text files"),
+];
+
+/// Type tags of vg's Protobuf streams, the format they identify and a hint
+const VG_STREAMS: &[(&[u8], &str, &str)] = &[
+    (b"VG", "vg Protobuf", "convert it with `vg view` first"),
+    (
+        b"GAM",
+        "GAM",
+        "convert it with `vg convert --gam-to-gaf` first",
+    ),
+];
+
+/// Type tag of a vg Protobuf stream: a group starts with the number of its messages and the
+/// length of the first one, the tag, as varints
+fn vg_stream_tag(data: &[u8]) -> Option<&[u8]> {
+    let count = data.iter().position(|b| b & 0x80 == 0)?;
+    let length = *data.get(count + 1)? as usize;
+    data.get(count + 2..count + 2 + length)
+}
+
+/// Name of the binary format the data appears to be in, with a hint what to do instead
+pub fn sniff(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    let tag = vg_stream_tag(data);
+    FORMATS
+        .iter()
+        .find(|(magic, _, _)| data.starts_with(magic))
+        .or_else(|| VG_STREAMS.iter().find(|(magic, _, _)| tag == Some(magic)))
+        .map(|(_, name, hint)| (*name, *hint))
+}
+
+/// Line and column (both from 1) of a byte position
+fn line_column(data: &[u8], position: usize) -> (usize, usize) {
+    let before = &data[..position];
+    let line_start = before
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |p| p + 1);
+    let lines = before.iter().filter(|b| **b == b'\n').count();
+    (lines + 1, position - line_start + 1)
+}
+
+/// Fails with a description of the format for data that is not text
+fn check_not_binary(data: &[u8]) -> Result<(), String> {
+    if let Some((name, hint)) = sniff(data) {
+        return Err(format!("Input appears to be {}, {}", name, hint));
+    }
+    let start = &data[..data.len().min(SNIFF_BYTES)];
+    if start
+        .iter()
+        .any(|b| b.is_ascii_control() && !b"\t\n\r".contains(b))
+    {
+        return Err(
+            "Input appears to be binary, not text; vg, odgi and xg graphs have to be \
+                    converted to GFA first (`vg view`, `odgi view -g`)"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// The data as text, failing with a description of binary data
+pub fn text(data: Vec<u8>) -> Result<String, String> {
+    check_not_binary(&data)?;
+    String::from_utf8(data).map_err(|e| {
+        let (line, column) = line_column(e.as_bytes(), e.utf8_error().valid_up_to());
+        format!("Line {} column {} is not valid UTF-8", line, column)
+    })
+}
+
+/// The data as ASCII text, failing with a description of binary data or the position of the
+/// first byte outside ASCII
+pub fn ascii_text(data: Vec<u8>) -> Result<String, String> {
+    check_not_binary(&data)?;
+    // Checking the whole data at once is faster than looking for the byte
+    if !data.is_ascii() {
+        let position = data.iter().position(|b| !b.is_ascii()).unwrap_or_default();
+        let (line, column) = line_column(&data, position);
+        return Err(format!(
+            "Line {} column {} has the non-ASCII byte 0x{:02x}, GFA is ASCII text",
+            line, column, data[position]
+        ));
+    }
+    Ok(String::from_utf8(data).expect("ASCII is valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_inputs() {
+        assert_eq!(ascii_text(b"S\t1\tA\r\n".to_vec()).unwrap(), "S\t1\tA\r\n");
+        let bam = ascii_text(b"BAM\x01\x00\x00".to_vec()).unwrap_err();
+        assert!(bam.contains("BAM") && bam.contains("GAF"), "{}", bam);
+        let gam = text(b"\x81\x07\x03GAM\x00".to_vec()).unwrap_err();
+        assert!(gam.contains("vg convert --gam-to-gaf"), "{}", gam);
+        let binary = text(b"\x2a\x5e\x00\x01".to_vec()).unwrap_err();
+        assert!(binary.contains("binary"), "{}", binary);
+        assert_eq!(
+            ascii_text("S\t1\tA\nP\tp\t1+,é+\t*\n".as_bytes().to_vec()).unwrap_err(),
+            "Line 2 column 8 has the non-ASCII byte 0xc3, GFA is ASCII text"
+        );
+        assert_eq!(text("# é\n".as_bytes().to_vec()).unwrap(), "# é\n");
+        assert_eq!(
+            text(b"a\nb\xff\n".to_vec()).unwrap_err(),
+            "Line 2 column 2 is not valid UTF-8"
+        );
+    }
+}
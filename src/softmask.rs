@@ -0,0 +1,76 @@
+//! Handling of soft-masked (lowercase) sequence, as repeat masking leaves it
+//! (`--masked-segments`, `--uppercase`).
+
+use crate::ncontent::drop_segments;
+use crate::trim::Trimmed;
+
+/// Whether a sequence is soft-masked completely, i.e. has lowercase but no uppercase letters
+pub fn is_soft_masked(sequence: &str) -> bool {
+    sequence.bytes().any(|b| b.is_ascii_lowercase())
+        && !sequence.bytes().any(|b| b.is_ascii_uppercase())
+}
+
+fn sequence(segment: &str) -> &str {
+    segment
+        .split('\t')
+        .nth(2)
+        .filter(|s| *s != "*")
+        .unwrap_or("")
+}
+
+/// Number of soft-masked bp and of completely soft-masked segments
+pub fn masked_bp(trimmed: &Trimmed) -> (usize, usize) {
+    let sequences = trimmed.segments.iter().map(|s| sequence(s));
+    sequences.fold((0, 0), |(bp, segments), sequence| {
+        (
+            bp + sequence.bytes().filter(|b| b.is_ascii_lowercase()).count(),
+            segments + is_soft_masked(sequence) as usize,
+        )
+    })
+}
+
+/// Drops the completely soft-masked segments like [`crate::ncontent::drop_n_segments`], returns
+/// the number of dropped segments
+pub fn drop_soft_masked(trimmed: &mut Trimmed) -> Result<usize, String> {
+    drop_segments(trimmed, is_soft_masked)
+}
+
+/// Segment lines with uppercase sequences, tags are left as they are
+pub fn uppercase_segments(segments: &[&str]) -> Vec<String> {
+    segments
+        .iter()
+        .map(|line| {
+            let mut fields = line.split('\t').map(|f| f.to_string()).collect::<Vec<_>>();
+            if let Some(sequence) = fields.get_mut(2) {
+                sequence.make_ascii_uppercase();
+            }
+            fields.join("\t")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_soft_masked() {
+        let content = "S\t1\tACgt\tLN:i:4\nS\t2\tacgt\nS\t3\tCC\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\n\
+                       P\tref\t1+,2+,3+\t*\n";
+        let mut trimmed =
+            trim_gfa(GfaLines::classify(content), None, &TrimOptions::default()).unwrap();
+        assert_eq!(masked_bp(&trimmed), (6, 1));
+        assert_eq!(drop_soft_masked(&mut trimmed), Ok(1));
+        assert_eq!(masked_bp(&trimmed), (2, 0));
+        assert_eq!(
+            trimmed.paths,
+            vec!["P\tref:0-4\t1+\t*", "P\tref:8-10\t3+\t*"]
+        );
+        assert_eq!(
+            uppercase_segments(&trimmed.segments),
+            vec!["S\t1\tACGT\tLN:i:4", "S\t3\tCC"]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Ordering and renumbering of segments (`--sort topological`, `--compact-ids`).
+//!
+//! The topological order follows the links and jumps, with the segments of the reference path
+//! placed in the order the reference visits them and the other segments right after the last
+//! reference segment before them, like `odgi sort -O` does. An edge `a- b-` counts as `b+ a+`.
+//! Cycles are broken at the next reference segment, or the next segment in input order.
+//! Compacting renames the segments to `1..n` in their order.
+
+use crate::collapse::redirect_steps;
+use crate::graph::{path_line_steps, walk_line_steps};
+use crate::trim::{walk_name, Trimmed};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Segment indices in topological order, guided by `reference` (a path name or the PanSN name
+/// of a walk), the first path if it is `None`. Fails if the reference has invalid steps.
+pub fn topological_order(trimmed: &Trimmed, reference: Option<&str>) -> Result<Vec<usize>, String> {
+    let ranks = trimmed
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.split('\t').nth(1).expect("All nodes should have ids"), i))
+        .collect::<HashMap<_, _>>();
+    let n = ranks.len();
+    let mut successors = vec![Vec::new(); n];
+    let mut in_degree = vec![0; n];
+    for line in trimmed.links.iter().chain(&trimmed.jumps) {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        let (Some(from), Some(to)) = (
+            fields.get(1).and_then(|n| ranks.get(n)),
+            fields.get(3).and_then(|n| ranks.get(n)),
+        ) else {
+            continue;
+        };
+        let (from, to) = match (fields[2], fields[4]) {
+            ("-", "-") => (*to, *from),
+            _ => (*from, *to),
+        };
+        if from != to {
+            successors[from].push(to);
+            in_degree[to] += 1;
+        }
+    }
+
+    let path = match reference {
+        Some(reference) => trimmed
+            .paths
+            .iter()
+            .find(|p| p.split('\t').nth(1) == Some(reference)),
+        None => trimmed.paths.first(),
+    };
+    let walk =
+        reference.and_then(|reference| trimmed.walks.iter().find(|w| walk_name(w) == reference));
+    let steps = match (path, walk) {
+        (Some(path), _) => path_line_steps(path)?,
+        (None, Some(walk)) => walk_line_steps(walk)?,
+        (None, None) => Vec::new(),
+    };
+    let mut reference_order = Vec::new();
+    let mut anchor = vec![0; n];
+    let mut on_reference = vec![false; n];
+    for (node, _) in steps {
+        if let Some(&rank) = ranks.get(node) {
+            if !on_reference[rank] {
+                on_reference[rank] = true;
+                anchor[rank] = reference_order.len();
+                reference_order.push(rank);
+            }
+        }
+    }
+
+    // Ready segments by (anchor, not on the reference, input order)
+    let key = |anchor: &[usize], rank: usize| Reverse((anchor[rank], !on_reference[rank], rank));
+    let mut ready = (0..n)
+        .filter(|r| in_degree[*r] == 0)
+        .map(|r| key(&anchor, r))
+        .collect::<BinaryHeap<_>>();
+    let mut placed = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let (mut next_reference, mut next_input) = (0, 0);
+    while order.len() < n {
+        let rank = match ready.pop() {
+            Some(Reverse((_, _, rank))) if placed[rank] => continue,
+            Some(Reverse((_, _, rank))) => rank,
+            None => {
+                while next_reference < reference_order.len()
+                    && placed[reference_order[next_reference]]
+                {
+                    next_reference += 1;
+                }
+                while placed[next_input] {
+                    next_input += 1;
+                }
+                match reference_order.get(next_reference) {
+                    Some(rank) => *rank,
+                    None => next_input,
+                }
+            }
+        };
+        placed[rank] = true;
+        order.push(rank);
+        for &next in &successors[rank] {
+            if placed[next] {
+                continue;
+            }
+            if !on_reference[next] {
+                anchor[next] = anchor[next].max(anchor[rank]);
+            }
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                ready.push(key(&anchor, next));
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Puts the segment lines in topological order, fails if the reference has invalid steps
+pub fn sort_segments(trimmed: &mut Trimmed, reference: Option<&str>) -> Result<(), String> {
+    let order = topological_order(trimmed, reference)?;
+    trimmed.segments = order.into_iter().map(|i| trimmed.segments[i]).collect();
+    Ok(())
+}
+
+/// Segment, link and jump lines renamed by [`compact_ids`]
+#[derive(Debug, Clone, Default)]
+pub struct Renamed {
+    pub segments: Vec<String>,
+    pub links: Vec<String>,
+    pub jumps: Vec<String>,
+}
+
+/// Renames the segments to `1..n` in their order, the paths and walks are renamed in place,
+/// the renamed other lines are returned. Fails on a path or walk with invalid steps.
+pub fn compact_ids(trimmed: &mut Trimmed) -> Result<Renamed, String> {
+    let ids = trimmed
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let name = s.split('\t').nth(1).expect("All nodes should have ids");
+            (name.to_string(), (i + 1).to_string())
+        })
+        .collect::<HashMap<_, _>>();
+    let rename = |name: &str| ids.get(name).cloned().unwrap_or_else(|| name.to_string());
+    let rename_fields = |line: &str, columns: &[usize]| {
+        let mut fields = line.split('\t').map(|f| f.to_string()).collect::<Vec<_>>();
+        for column in columns {
+            if let Some(field) = fields.get_mut(*column) {
+                *field = rename(field);
+            }
+        }
+        fields.join("\t")
+    };
+    redirect_steps(trimmed, |name, forward| (rename(name), forward))?;
+    Ok(Renamed {
+        segments: trimmed
+            .segments
+            .iter()
+            .map(|s| rename_fields(s, &[1]))
+            .collect(),
+        links: trimmed
+            .links
+            .iter()
+            .map(|l| rename_fields(l, &[1, 3]))
+            .collect(),
+        jumps: trimmed
+            .jumps
+            .iter()
+            .map(|j| rename_fields(j, &[1, 3]))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+
+    #[test]
+    fn test_sort_and_compact() {
+        // The reference runs a -> c -> e, b is a bubble branch between a and c, d a reverse
+        // insertion between c and e
+        let content = "S\te\tA\nS\tc\tC\nS\tb\tG\nS\ta\tT\nS\td\tA\n\
+                       L\tc\t+\te\t+\t0M\nL\tb\t+\tc\t+\t0M\nL\ta\t+\tb\t+\t0M\n\
+                       L\ta\t+\tc\t+\t0M\nL\te\t-\td\t-\t0M\nL\td\t-\tc\t-\t0M\n\
+                       P\tref\ta+,c+,e+\t*\nW\ts\t1\tchr\t0\t5\t>a>b>c>d>e\n";
+        let lines = GfaLines::classify(content);
+        let mut trimmed = Trimmed {
+            segments: lines.segments,
+            links: lines.links,
+            paths: lines.paths.iter().map(|p| p.to_string()).collect(),
+            walks: lines.walks.iter().map(|w| w.to_string()).collect(),
+            ..Trimmed::default()
+        };
+        sort_segments(&mut trimmed, Some("ref")).unwrap();
+        let names = trimmed
+            .segments
+            .iter()
+            .map(|s| s.split('\t').nth(1).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+
+        let renamed = compact_ids(&mut trimmed).unwrap();
+        assert_eq!(renamed.segments[..2], ["S\t1\tT", "S\t2\tG"]);
+        assert_eq!(renamed.links[0], "L\t3\t+\t5\t+\t0M");
+        assert_eq!(trimmed.paths, vec!["P\tref\t1+,3+,5+\t*"]);
+        assert_eq!(trimmed.walks, vec!["W\ts\t1\tchr\t0\t5\t>1>2>3>4>5"]);
+    }
+
+    #[test]
+    fn test_compact_ids_spaced_steps() {
+        // Steps after a space were left with their old names
+        let content = "S\ta\tACGT\nS\tb\tGG\nS\tc\tT\nL\ta\t+\tb\t+\t0M\n\
+                       L\tb\t+\tc\t+\t0M\nP\tp\ta+, b+, c+\t*\n";
+        let mut trimmed =
+            trim_gfa(GfaLines::classify(content), None, &TrimOptions::default()).unwrap();
+        compact_ids(&mut trimmed).unwrap();
+        assert_eq!(trimmed.paths, ["P\tp\t1+,2+,3+\t*"]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Summary statistics of a graph (`trim-graph stats`), on their own or side by side for two
+//! versions of a graph with `--compare`.
+
+use crate::components::split_components;
+use crate::graph::segment_length;
+use crate::trim::GfaLines;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphStats {
+    pub nodes: usize,
+    /// Links and jumps
+    pub edges: usize,
+    pub bp: usize,
+    /// Paths and walks
+    pub paths: usize,
+    pub components: usize,
+    /// N50 of the segment lengths
+    pub n50: usize,
+}
+
+/// Length such that the segments at least as long cover half of the bp
+fn n50(mut lengths: Vec<usize>) -> usize {
+    lengths.sort_unstable_by(|a, b| b.cmp(a));
+    let total = lengths.iter().sum::<usize>();
+    let mut covered = 0;
+    for length in lengths {
+        covered += length;
+        if 2 * covered >= total {
+            return length;
+        }
+    }
+    0
+}
+
+impl GraphStats {
+    /// Statistics of the graph, fails on a path or walk with invalid steps
+    pub fn of(lines: &GfaLines) -> Result<Self, String> {
+        let lengths = lines
+            .segments
+            .iter()
+            .map(|s| segment_length(s))
+            .collect::<Vec<_>>();
+        Ok(GraphStats {
+            nodes: lines.segments.len(),
+            edges: lines.links.len() + lines.jumps.len(),
+            bp: lengths.iter().sum(),
+            paths: lines.paths.len() + lines.walks.len(),
+            components: split_components(lines)?.len(),
+            n50: n50(lengths),
+        })
+    }
+
+    pub(crate) fn rows(&self) -> [(&'static str, usize); 6] {
+        [
+            ("nodes", self.nodes),
+            ("edges", self.edges),
+            ("bp", self.bp),
+            ("paths", self.paths),
+            ("components", self.components),
+            ("N50", self.n50),
+        ]
+    }
+
+    /// Writes one line `statistic value` per statistic
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (name, value) in self.rows() {
+            writeln!(out, "{}\t{}", name, value)?;
+        }
+        Ok(())
+    }
+
+    /// Writes one line `statistic before after delta` per statistic, with a header
+    pub fn write_comparison<W: Write>(&self, after: &GraphStats, out: &mut W) -> io::Result<()> {
+        writeln!(out, "statistic\tbefore\tafter\tdelta")?;
+        for ((name, before), (_, after)) in self.rows().into_iter().zip(after.rows()) {
+            let delta = after as i64 - before as i64;
+            writeln!(out, "{}\t{}\t{}\t{:+}", name, before, after, delta)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_stats() {
+        let before = "S\t1\tACGT\nS\t2\tAA\nS\t3\tC\nS\t4\tGGGGGG\n\
+                      L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t1\t+\t3\t+\t0M\n\
+                      P\tref\t1+,3+\t*\nW\ts\t1\tc\t0\t7\t>1>2>3\n";
+        let after = "S\t1\tACGT\nS\t3\tC\nL\t1\t+\t3\t+\t0M\nP\tref\t1+,3+\t*\n";
+        let before = GraphStats::of(&GfaLines::classify(before)).unwrap();
+        let after = GraphStats::of(&GfaLines::classify(after)).unwrap();
+        assert_eq!(
+            before,
+            GraphStats {
+                nodes: 4,
+                edges: 3,
+                bp: 13,
+                paths: 2,
+                components: 2,
+                n50: 4
+            }
+        );
+        assert_eq!(after.n50, 4);
+        let mut out = Vec::new();
+        before.write_comparison(&after, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "statistic\tbefore\tafter\tdelta\nnodes\t4\t2\t-2\nedges\t3\t1\t-2\nbp\t13\t5\t-8\n\
+             paths\t2\t1\t-1\ncomponents\t2\t1\t-1\nN50\t4\t4\t+0\n"
+        );
+    }
+}
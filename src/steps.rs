@@ -0,0 +1,72 @@
+//! Table of the steps of the kept paths and walks (`--path-steps`), for coordinate arithmetic
+//! on the trimmed graph.
+//!
+//! Each step is listed with its index on the path or walk (from 0), its segment and orientation
+//! and the bp offset of the segment's start on the path, i.e. the total length of the steps
+//! before it. Walks are named in PanSN form and their offsets start at 0 as well, not at their
+//! start column. Segments without sequence (`*`) count as 0 bp.
+
+use crate::graph::{path_line_steps, segment_length, walk_line_steps};
+use crate::trim::{walk_name, Trimmed};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Writes one line `path step node orientation offset` per step of the paths, then the walks,
+/// with a header. Fails with [`io::ErrorKind::InvalidData`] on a path or walk with invalid
+/// steps.
+pub fn write_path_steps<W: Write>(trimmed: &Trimmed, out: &mut W) -> io::Result<()> {
+    let lengths = trimmed
+        .segments
+        .iter()
+        .map(|s| {
+            let name = s.split('\t').nth(1).expect("All nodes should have ids");
+            (name, segment_length(s))
+        })
+        .collect::<HashMap<_, _>>();
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+    let mut paths = Vec::new();
+    for p in &trimmed.paths {
+        let name = p.split('\t').nth(1).unwrap_or("").to_string();
+        paths.push((name, path_line_steps(p).map_err(invalid)?));
+    }
+    for w in &trimmed.walks {
+        paths.push((walk_name(w), walk_line_steps(w).map_err(invalid)?));
+    }
+    writeln!(out, "path\tstep\tnode\torientation\toffset")?;
+    for (name, steps) in paths {
+        let mut offset = 0;
+        for (i, (node, forward)) in steps.iter().enumerate() {
+            let orientation = if *forward { '+' } else { '-' };
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}",
+                name, i, node, orientation, offset
+            )?;
+            offset += lengths.get(node).copied().unwrap_or(0);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_path_steps() {
+        let trimmed = Trimmed {
+            segments: vec!["S\t1\tACGT", "S\t2\tGG", "S\t3\t*"],
+            paths: vec!["P\tref\t1+,2-,3+\t*".to_string()],
+            walks: vec!["W\ts\t1\tchr\t10\t16\t<2<1".to_string()],
+            ..Trimmed::default()
+        };
+        let mut out = Vec::new();
+        write_path_steps(&trimmed, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "path\tstep\tnode\torientation\toffset\n\
+             ref\t0\t1\t+\t0\nref\t1\t2\t-\t4\nref\t2\t3\t+\t6\n\
+             s#1#chr\t0\t2\t-\t0\ns#1#chr\t1\t1\t-\t2\n"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Stitching of path and walk fragments, as written by [`crate::extract`], back together.
+//!
+//! Fragments of the same original path (`name:start-end`) or walk (same sample, haplotype and
+//! sequence id) are merged where one ends at the coordinate the next one starts at. Fragments
+//! that overlap or leave a gap stay separate, a path joined into a single fragment from its
+//! start gets its original name back. Extraction drops the links leaving a region, so
+//! the link between the last step of a fragment and the first step of the next one is added
+//! if no input has it.
+
+use crate::edge::Edge;
+use crate::extract::{split_fragment, Region};
+use crate::graph::{Graph, Link, Path, Walk};
+use std::collections::{HashMap, HashSet};
+
+type Steps = Vec<(String, bool)>;
+type Junction = ((String, bool), (String, bool));
+
+/// Merges the fragments, given as (start, end, steps), whose coordinates are adjacent. The
+/// steps at which two fragments were joined are added to `junctions`.
+fn merge_adjacent(
+    mut fragments: Vec<(usize, usize, Steps)>,
+    junctions: &mut Vec<Junction>,
+) -> Vec<(usize, usize, Steps)> {
+    fragments.sort();
+    fragments.dedup();
+    let mut merged: Vec<(usize, usize, Steps)> = Vec::new();
+    for (start, end, steps) in fragments {
+        match merged.last_mut() {
+            Some((_, last_end, last_steps)) if *last_end == start => {
+                if let (Some(last), Some(first)) = (last_steps.last(), steps.first()) {
+                    junctions.push((last.clone(), first.clone()));
+                }
+                *last_end = end;
+                last_steps.extend(steps);
+            }
+            _ => merged.push((start, end, steps)),
+        }
+    }
+    merged
+}
+
+/// Groups the items by key, in the order in which the keys first appear
+fn group_by_key<K: Eq + std::hash::Hash + Clone, T>(
+    items: impl Iterator<Item = (K, T)>,
+) -> Vec<(K, Vec<T>)> {
+    let mut groups: Vec<(K, Vec<T>)> = Vec::new();
+    let mut positions = HashMap::new();
+    for (key, item) in items {
+        let position = *positions.entry(key.clone()).or_insert_with(|| {
+            groups.push((key, Vec::new()));
+            groups.len() - 1
+        });
+        groups[position].1.push(item);
+    }
+    groups
+}
+
+/// Paths named after their fragments, a single fragment from the start of the path gets the
+/// name of the original path back unless another input has a complete path of that name
+fn stitch_paths(paths: Vec<Path>, junctions: &mut Vec<Junction>) -> Vec<Path> {
+    let grouped = group_by_key(paths.into_iter().map(|p| {
+        let original = split_fragment(&p.name).0.to_string();
+        (original, (p.name.parse::<Region>().ok(), p))
+    }));
+    let mut stitched = Vec::new();
+    let mut names = HashSet::new();
+    let mut pieces = Vec::new();
+    for (original, paths) in grouped {
+        let mut fragments = Vec::new();
+        for (region, path) in paths {
+            match region {
+                Some(region) => fragments.push((region.start, region.end, path.steps)),
+                // Complete paths are only kept once
+                None if names.insert(path.name.clone()) => stitched.push(path),
+                None => {}
+            }
+        }
+        pieces.push((original, merge_adjacent(fragments, junctions)));
+    }
+    for (original, merged) in pieces {
+        let complete = matches!(merged[..], [(0, _, _)]) && !names.contains(&original);
+        stitched.extend(merged.into_iter().map(|(start, end, steps)| Path {
+            name: match complete {
+                true => original.clone(),
+                false => format!("{}:{}-{}", original, start, end),
+            },
+            steps,
+        }));
+    }
+    stitched
+}
+
+fn stitch_walks(walks: Vec<Walk>, junctions: &mut Vec<Junction>) -> Vec<Walk> {
+    let grouped = group_by_key(
+        walks
+            .into_iter()
+            .map(|w| ((w.sample.clone(), w.hap_index.clone(), w.seq_id.clone()), w)),
+    );
+    let mut stitched = Vec::new();
+    let mut names = HashSet::new();
+    for (_, walks) in grouped {
+        let template = walks[0].clone();
+        let mut fragments = Vec::new();
+        for walk in walks {
+            match (walk.seq_start.parse(), walk.seq_end.parse()) {
+                (Ok(start), Ok(end)) => fragments.push((start, end, walk.steps)),
+                // Walks without coordinates are only kept once
+                _ if names.insert(walk.name()) => stitched.push(walk),
+                _ => {}
+            }
+        }
+        stitched.extend(merge_adjacent(fragments, junctions).into_iter().map(
+            |(start, end, steps)| Walk {
+                seq_start: start.to_string(),
+                seq_end: end.to_string(),
+                steps,
+                ..template.clone()
+            },
+        ));
+    }
+    stitched
+}
+
+/// Union of the graphs with their path and walk fragments stitched together. Segments with
+/// the same name are expected to have the same sequence, the first one is kept.
+pub fn stitch(graphs: Vec<Graph>) -> Graph {
+    let mut stitched = Graph::default();
+    let mut segments = HashSet::new();
+    let mut links = HashSet::new();
+    let mut jumps = HashSet::new();
+    let mut paths = Vec::new();
+    let mut walks = Vec::new();
+    for graph in graphs {
+        for segment in graph.segments {
+            if segments.insert(segment.name.clone()) {
+                stitched.segments.push(segment);
+            }
+        }
+        for link in graph.links {
+            if links.insert(Edge::from(&link)) {
+                stitched.links.push(link);
+            }
+        }
+        for jump in graph.jumps {
+            if jumps.insert(Edge::from(&jump)) {
+                stitched.jumps.push(jump);
+            }
+        }
+        paths.extend(graph.paths);
+        walks.extend(graph.walks);
+    }
+    let mut junctions = Vec::new();
+    stitched.paths = stitch_paths(paths, &mut junctions);
+    stitched.walks = stitch_walks(walks, &mut junctions);
+    for ((from, from_forward), (to, to_forward)) in junctions {
+        // Links match in either direction, like in trimming
+        if links.insert(Edge::new(
+            from.clone(),
+            from_forward,
+            to.clone(),
+            to_forward,
+        )) {
+            stitched
+                .links
+                .push(Link::new(from, from_forward, to, to_forward));
+        }
+    }
+    stitched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::Extractor;
+
+    #[test]
+    fn test_stitch() {
+        let gfa = "S\t1\tAAAA\nS\t2\tCC\nS\t3\tGGGG\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\n\
+                   P\tref\t1+,2+,3+\t*\nW\ts\t1\tc\t10\t20\t>1>2>3\n";
+        let extractor = Extractor::new(Graph::from_gfa(gfa).unwrap());
+        let parts = ["ref:0-5", "ref:6-10"]
+            .iter()
+            .map(|r| extractor.extract(&r.parse::<Region>().unwrap(), None))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(parts[0].paths[0].name, "ref:0-6");
+
+        let stitched = stitch(parts);
+        assert_eq!(stitched.segments.len(), 3);
+        assert_eq!(stitched.links.len(), 2);
+        assert_eq!(stitched.paths.len(), 1);
+        assert_eq!(stitched.paths[0].name, "ref");
+        assert_eq!(stitched.paths[0].steps.len(), 3);
+        assert_eq!(stitched.walks.len(), 1);
+        assert_eq!(stitched.walks[0].seq_start, "10");
+        assert_eq!(stitched.walks[0].seq_end, "20");
+
+        // Fragments with a gap keep their names, so does a fragment from the start if the
+        // complete path is there as well
+        let gapped = ["ref:6-10", "ref:0-4"]
+            .iter()
+            .map(|r| extractor.extract(&r.parse::<Region>().unwrap(), None))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let names = |graph: Graph| graph.paths.into_iter().map(|p| p.name).collect::<Vec<_>>();
+        assert_eq!(names(stitch(gapped)), ["ref:0-4", "ref:6-10"]);
+        let fragment = gfa.replace("P\tref\t", "P\tref:0-10\t");
+        let with_complete = [gfa, &fragment]
+            .iter()
+            .map(|g| Graph::from_gfa(g).unwrap())
+            .collect();
+        assert_eq!(names(stitch(with_complete)), ["ref", "ref:0-10"]);
+    }
+
+    #[test]
+    fn test_merge_adjacent() {
+        let step = |n: &str| vec![(n.to_string(), true)];
+        let mut junctions = Vec::new();
+        let merged = merge_adjacent(
+            vec![
+                (5, 8, step("b")),
+                (0, 5, step("a")),
+                (9, 10, step("c")),
+                (0, 5, step("a")),
+            ],
+            &mut junctions,
+        );
+        assert_eq!(
+            merged,
+            vec![
+                (0, 8, vec![("a".to_string(), true), ("b".to_string(), true)]),
+                (9, 10, step("c"))
+            ]
+        );
+        assert_eq!(
+            junctions,
+            vec![(("a".to_string(), true), ("b".to_string(), true))]
+        );
+    }
+}
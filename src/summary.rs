@@ -0,0 +1,204 @@
+//! Summary of the lines and bp removed by trimming (`--removed-summary`).
+//!
+//! Line counts alone hide what matters, dropping a single 5 Mb segment removes more than a
+//! thousand SNP segments. The summary therefore weights every record type, sample and connected
+//! component by its sequence: the length of the segments, and of paths and walks the summed
+//! length of the segments they step through. Links and jumps have no sequence of their own.
+//! Removed amounts are the difference between the input and the trimmed graph, so fragments of
+//! paths that are kept count with the part they still cover.
+
+use crate::components::split_components;
+use crate::graph::{path_line_steps, segment_length, split_pansn, walk_line_steps};
+use crate::trim::{GfaLines, Trimmed};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Lines and bp of one record type, sample or component before and after trimming
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SummaryRow {
+    /// `record`, `sample` or `component`
+    pub scope: &'static str,
+    pub name: String,
+    pub lines: usize,
+    pub bp: usize,
+    pub kept_lines: usize,
+    pub kept_bp: usize,
+}
+
+impl SummaryRow {
+    fn new(scope: &'static str, name: &str) -> Self {
+        SummaryRow {
+            scope,
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn removed_lines(&self) -> usize {
+        self.lines.saturating_sub(self.kept_lines)
+    }
+
+    pub fn removed_bp(&self) -> usize {
+        self.bp.saturating_sub(self.kept_bp)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemovedSummary {
+    /// The record types S, L, J, P and W, the samples by name and the components of the input
+    /// by removed bp, most first
+    pub rows: Vec<SummaryRow>,
+}
+
+fn segment_name(line: &str) -> &str {
+    line.split('\t').nth(1).expect("All nodes should have ids")
+}
+
+/// Sample and bp of a P or W line, P lines without a PanSN name are a sample of their own
+fn path_sample(line: &str, lengths: &HashMap<&str, usize>) -> Result<(String, usize), String> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    let name = fields.get(1).copied().unwrap_or_default();
+    let (sample, steps) = match fields[0] {
+        "W" => (name, walk_line_steps(line)?),
+        _ => (
+            split_pansn(name).map_or(name, |(sample, _, _)| sample),
+            path_line_steps(line)?,
+        ),
+    };
+    let bp = steps
+        .iter()
+        .map(|(node, _)| lengths.get(node).copied().unwrap_or(0))
+        .sum();
+    Ok((sample.to_string(), bp))
+}
+
+impl RemovedSummary {
+    /// Summary of the records of `lines` removed in `trimmed`, fails on a path or walk with
+    /// invalid steps
+    pub fn new(lines: &GfaLines, trimmed: &Trimmed) -> Result<Self, String> {
+        let lengths = lines
+            .segments
+            .iter()
+            .map(|s| (segment_name(s), segment_length(s)))
+            .collect::<HashMap<_, _>>();
+        let kept = trimmed
+            .segments
+            .iter()
+            .map(|s| segment_name(s))
+            .collect::<HashSet<_>>();
+
+        let mut records = ["S", "L", "J", "P", "W"].map(|r| SummaryRow::new("record", r));
+        records[0].lines = lines.segments.len();
+        records[0].bp = lengths.values().sum();
+        records[0].kept_lines = trimmed.segments.len();
+        records[0].kept_bp = trimmed.segments.iter().map(|s| segment_length(s)).sum();
+        records[1].lines = lines.links.len();
+        records[1].kept_lines = trimmed.links.len();
+        records[2].lines = lines.jumps.len();
+        records[2].kept_lines = trimmed.jumps.len();
+
+        let mut samples = BTreeMap::<String, SummaryRow>::new();
+        let before = lines.paths.iter().chain(&lines.walks).map(|l| (*l, false));
+        let after = trimmed.paths.iter().chain(&trimmed.walks);
+        for (line, is_kept) in before.chain(after.map(|l| (l.as_str(), true))) {
+            let (sample, bp) = path_sample(line, &lengths)?;
+            let sample = samples
+                .entry(sample)
+                .or_insert_with_key(|s| SummaryRow::new("sample", s));
+            let record = &mut records[if line.starts_with('W') { 4 } else { 3 }];
+            for row in [sample, record] {
+                match is_kept {
+                    false => (row.lines, row.bp) = (row.lines + 1, row.bp + bp),
+                    true => (row.kept_lines, row.kept_bp) = (row.kept_lines + 1, row.kept_bp + bp),
+                }
+            }
+        }
+
+        let mut components = split_components(lines)?
+            .iter()
+            .filter(|component| !component.segments.is_empty())
+            .map(|component| {
+                let mut row = SummaryRow::new("component", segment_name(component.segments[0]));
+                for segment in &component.segments {
+                    let (name, bp) = (segment_name(segment), segment_length(segment));
+                    row.lines += 1;
+                    row.bp += bp;
+                    if kept.contains(name) {
+                        row.kept_lines += 1;
+                        row.kept_bp += bp;
+                    }
+                }
+                row
+            })
+            .collect::<Vec<_>>();
+        components.sort_by_key(|row| std::cmp::Reverse(row.removed_bp()));
+
+        Ok(RemovedSummary {
+            rows: records
+                .into_iter()
+                .chain(samples.into_values())
+                .chain(components)
+                .collect(),
+        })
+    }
+
+    /// Bp of the removed segments
+    pub fn removed_bp(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.removed_bp())
+    }
+
+    /// Writes one line `scope name lines removed_lines bp removed_bp` per row, with a header.
+    /// Components are named by their first segment.
+    pub fn write_tsv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "scope\tname\tlines\tremoved_lines\tbp\tremoved_bp")?;
+        for row in &self.rows {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                row.scope,
+                row.name,
+                row.lines,
+                row.removed_lines(),
+                row.bp,
+                row.removed_bp()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trim::{trim_gfa, TrimOptions};
+
+    #[test]
+    fn test_removed_summary() {
+        let content = "S\t1\tACGT\nS\t2\tAAAAAAAAAA\nS\t3\tC\nS\t4\tGG\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t1\t+\t3\t+\t0M\n\
+                       P\tref#0#chr1\t1+,3+\t*\nP\talt#1#chr1\t1+,2+,3+\t*\n\
+                       W\talt\t2\tchr1\t0\t15\t>1>2>3\n";
+        let lines = GfaLines::classify(content);
+        let trimmed = trim_gfa(
+            lines.clone(),
+            Some(vec!["ref#0#chr1".to_string()]),
+            &TrimOptions {
+                walks_to_keep: Some(Default::default()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let summary = RemovedSummary::new(&lines, &trimmed).unwrap();
+        assert_eq!(summary.removed_bp(), 12);
+        let mut out = Vec::new();
+        summary.write_tsv(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "scope\tname\tlines\tremoved_lines\tbp\tremoved_bp\n\
+             record\tS\t4\t2\t17\t12\nrecord\tL\t3\t2\t0\t0\nrecord\tJ\t0\t0\t0\t0\n\
+             record\tP\t2\t1\t20\t15\nrecord\tW\t1\t1\t15\t15\n\
+             sample\talt\t2\t2\t30\t30\nsample\tref\t1\t0\t5\t0\n\
+             component\t1\t3\t1\t15\t10\ncomponent\t4\t1\t1\t2\t2\n"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Structural variant events to extract one subgraph each from (`trim-graph sv`).
+//!
+//! Events are read from a TSV of breakpoint pairs (`contig start end [name]`, 0-based) or from
+//! a VCF. In a VCF, symbolic ALTs (`<DEL>`, `<INV>`, `<INS>`, ...) span from POS to the END
+//! tag (or POS + |SVLEN|), sequence-resolved ALTs span their REF allele. Breakends (`N[chr2:5[`)
+//! may join two contigs and are skipped. Every ALT of a multi-allelic record is an event of its
+//! own, named with the number of the ALT appended to the ID.
+
+use crate::extract::Region;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub name: String,
+    pub contig: String,
+    /// First base affected by the event, 0-based
+    pub start: usize,
+    /// End of the event, exclusive (equal to `start` for insertions)
+    pub end: usize,
+}
+
+impl Event {
+    /// Region of the event with `flank` bp on both sides on the reference `prefix + contig`
+    pub fn region(&self, prefix: &str, flank: usize) -> Region {
+        let start = self.start.saturating_sub(flank);
+        Region {
+            path: format!("{}{}", prefix, self.contig),
+            start,
+            end: (self.end + flank).max(start + 1),
+        }
+    }
+
+    /// Name usable as a file name, `/` is replaced
+    pub fn file_name(&self) -> String {
+        format!("{}.gfa", self.name.replace('/', "_"))
+    }
+}
+
+/// Value of a key of the INFO column of a VCF record
+fn info<'a>(info: &'a str, key: &str) -> Option<&'a str> {
+    info.split(';')
+        .find_map(|entry| entry.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// Events of the ALTs of a VCF record, without the breakends
+fn parse_vcf_record(line: &str) -> Result<Vec<Event>, String> {
+    let invalid = |what: &str| format!("Invalid {} in VCF record {}", what, line);
+    let fields = line.split('\t').collect::<Vec<_>>();
+    if fields.len() < 8 {
+        return Err(invalid("number of columns"));
+    }
+    let alts = fields[4].split(',').collect::<Vec<_>>();
+    let mut events = Vec::new();
+    for (i, alt) in alts.iter().enumerate() {
+        let name = match fields[2] {
+            "." => format!(
+                "{}_{}_{}",
+                fields[0],
+                fields[1],
+                alt.trim_matches(['<', '>'])
+            ),
+            id if alts.len() > 1 => format!("{}_{}", id, i + 1),
+            id => id.to_string(),
+        };
+        events.extend(parse_vcf_alt(&fields, alt, name).map_err(invalid)?);
+    }
+    Ok(events)
+}
+
+/// Event of one ALT of a VCF record, `None` for a breakend. Fails with the invalid column.
+fn parse_vcf_alt(fields: &[&str], alt: &str, name: String) -> Result<Option<Event>, &'static str> {
+    if alt.contains(['[', ']']) {
+        log::warn!(
+            "Skipping breakend {} at {}:{}",
+            fields[2],
+            fields[0],
+            fields[1]
+        );
+        return Ok(None);
+    }
+    // POS 0 only marks telomeric breakends
+    let position = fields[1]
+        .parse::<usize>()
+        .ok()
+        .filter(|p| *p > 0)
+        .ok_or("POS")?;
+    let reference = fields[3];
+    let end = if alt.starts_with('<') {
+        let svlen = || {
+            info(fields[7], "SVLEN")
+                .and_then(|l| l.trim_start_matches('-').parse::<usize>().ok())
+                .map(|l| position + l)
+        };
+        match info(fields[7], "END") {
+            Some(end) => end.parse::<usize>().map_err(|_| "END")?,
+            None if alt == "<INS>" => position,
+            None => svlen().ok_or("END or SVLEN")?,
+        }
+    } else {
+        position - 1 + reference.len()
+    };
+    // POS is the padding base before the event for symbolic and indel ALTs
+    let start = if alt.starts_with('<') || alt.len() != reference.len() {
+        position
+    } else {
+        position - 1
+    };
+    Ok(Some(Event {
+        name,
+        contig: fields[0].to_string(),
+        start,
+        end: end.max(start),
+    }))
+}
+
+fn parse_tsv_record(line: &str) -> Result<Event, String> {
+    let invalid = || format!("Invalid breakpoints {}, expected contig, start, end", line);
+    let fields = line.split('\t').collect::<Vec<_>>();
+    if fields.len() < 3 {
+        return Err(invalid());
+    }
+    let start = fields[1].parse::<usize>().map_err(|_| invalid())?;
+    let end = fields[2].parse::<usize>().map_err(|_| invalid())?;
+    if end < start {
+        return Err(invalid());
+    }
+    let name = match fields.get(3) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => format!("{}_{}_{}", fields[0], start, end),
+    };
+    Ok(Event {
+        name,
+        contig: fields[0].to_string(),
+        start,
+        end,
+    })
+}
+
+/// Events of a breakpoint TSV or a VCF (recognized by its `##fileformat=VCF` line)
+pub fn parse_events(content: &str) -> Result<Vec<Event>, String> {
+    let is_vcf = content.starts_with("##fileformat=VCF");
+    let mut events = Vec::new();
+    for line in content.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        match is_vcf {
+            true => events.extend(parse_vcf_record(line)?),
+            false => events.push(parse_tsv_record(line)?),
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let tsv = "#contig\tstart\tend\nchr1\t100\t250\tdel1\nchr2\t10\t10\n";
+        let events = parse_events(tsv).unwrap();
+        assert_eq!(events[0].name, "del1");
+        assert_eq!(events[1].name, "chr2_10_10");
+        assert_eq!(
+            events[1].region("GRCh38#0#", 5),
+            Region {
+                path: "GRCh38#0#chr2".to_string(),
+                start: 5,
+                end: 15
+            }
+        );
+        assert!(parse_events("chr1\t100\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_vcf() {
+        let vcf = "##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n\
+                   chr1\t100\tsv1\tA\t<DEL>\t.\tPASS\tSVTYPE=DEL;END=150\n\
+                   chr1\t200\t.\tC\t<INS>\t.\tPASS\tSVTYPE=INS;SVLEN=300\n\
+                   chr1\t300\t.\tG\t<INV>\t.\tPASS\tSVLEN=-40\n\
+                   chr1\t400\tbnd\tT\tT[chr2:5[\t.\tPASS\tSVTYPE=BND\n\
+                   chr1\t500\tdel2\tACGT\tA\t.\tPASS\t.\n";
+        let events = parse_events(vcf).unwrap();
+        let spans = events
+            .iter()
+            .map(|e| (e.name.as_str(), e.start, e.end))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                ("sv1", 100, 150),
+                ("chr1_200_INS", 200, 200),
+                ("chr1_300_INV", 300, 340),
+                ("del2", 500, 503)
+            ]
+        );
+
+        let vcf = "##fileformat=VCFv4.2\n\
+                   chr1\t100\tsv1\tAC\tA,<DEL>\t.\tPASS\tEND=150\n\
+                   chr1\t0\tbnd\tN\t.[chr2:5[\t.\tPASS\tSVTYPE=BND\n";
+        let events = parse_events(vcf).unwrap();
+        let spans = events
+            .iter()
+            .map(|e| (e.name.as_str(), e.start, e.end))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![("sv1_1", 100, 101), ("sv1_2", 100, 150)]);
+        assert_eq!(
+            parse_events("##fileformat=VCFv4.2\nchr1\t0\t.\tA\tT\t.\tPASS\t.\n").unwrap_err(),
+            "Invalid POS in VCF record chr1\t0\t.\tA\tT\t.\tPASS\t."
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Breadth- or depth-first traversal of a graph from seed segments (`trim-graph traverse`).
+//!
+//! Edges are followed on both sides of a segment regardless of orientation, every segment is
+//! visited once. Breadth-first traversal starts from all seeds at once, depth-first traversal
+//! from one seed after the other and lists the segments in preorder. The depth of a segment is
+//! its distance to the seed it was reached from in the traversal tree.
+
+use crate::handle::{Handle, HandleGraph};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    BreadthFirst,
+    DepthFirst,
+}
+
+/// Segment reached by a traversal, identified by its rank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visit {
+    pub node: usize,
+    pub depth: usize,
+    /// Segment it was reached from, `None` for seeds
+    pub parent: Option<usize>,
+}
+
+/// Segments adjacent to the segment on either side, in edge order
+fn neighbours(graph: &HandleGraph, rank: usize) -> impl Iterator<Item = usize> + '_ {
+    let handle = Handle::new(rank, false);
+    graph
+        .follow_edges(handle, false)
+        .chain(graph.follow_edges(handle, true))
+        .map(|h| h.rank())
+}
+
+/// Visits the segments reachable from the seeds (segment names) up to `max_depth` in the
+/// given order
+pub fn traverse(
+    graph: &HandleGraph,
+    seeds: &[String],
+    order: Order,
+    max_depth: Option<usize>,
+) -> Result<Vec<Visit>, String> {
+    let seeds = seeds
+        .iter()
+        .map(|seed| {
+            graph
+                .get_handle(seed, false)
+                .map(|h| h.rank())
+                .ok_or_else(|| format!("Seed {} is not a segment of the graph", seed))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let expand = |depth: usize| max_depth.is_none_or(|max| depth < max);
+    let mut visited = vec![false; graph.node_count()];
+    let mut visits = Vec::new();
+    match order {
+        Order::BreadthFirst => {
+            let mut queue = VecDeque::new();
+            for seed in seeds {
+                if !visited[seed] {
+                    visited[seed] = true;
+                    queue.push_back((seed, 0, None));
+                }
+            }
+            while let Some((node, depth, parent)) = queue.pop_front() {
+                visits.push(Visit {
+                    node,
+                    depth,
+                    parent,
+                });
+                if !expand(depth) {
+                    continue;
+                }
+                for next in neighbours(graph, node) {
+                    if !visited[next] {
+                        visited[next] = true;
+                        queue.push_back((next, depth + 1, Some(node)));
+                    }
+                }
+            }
+        }
+        Order::DepthFirst => {
+            // Segments are marked when they are taken from the stack, so that they are reached
+            // as deep as a recursive traversal would reach them
+            let mut stack = seeds
+                .into_iter()
+                .rev()
+                .map(|seed| (seed, 0, None))
+                .collect::<Vec<_>>();
+            while let Some((node, depth, parent)) = stack.pop() {
+                if visited[node] {
+                    continue;
+                }
+                visited[node] = true;
+                visits.push(Visit {
+                    node,
+                    depth,
+                    parent,
+                });
+                if !expand(depth) {
+                    continue;
+                }
+                let next = neighbours(graph, node)
+                    .filter(|n| !visited[*n])
+                    .collect::<Vec<_>>();
+                stack.extend(next.into_iter().rev().map(|n| (n, depth + 1, Some(node))));
+            }
+        }
+    }
+    Ok(visits)
+}
+
+/// Writes one line `node depth parent` per visit in traversal order, with a header and `*` as
+/// the parent of seeds
+pub fn write_traversal<W: Write>(
+    graph: &HandleGraph,
+    visits: &[Visit],
+    out: &mut W,
+) -> io::Result<()> {
+    writeln!(out, "node\tdepth\tparent")?;
+    for visit in visits {
+        let parent = visit
+            .parent
+            .map_or("*", |p| graph.get_name(Handle::new(p, false)));
+        let node = graph.get_name(Handle::new(visit.node, false));
+        writeln!(out, "{}\t{}\t{}", node, visit.depth, parent)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    #[test]
+    fn test_traverse() {
+        // Bubble 2/3 between 1 and 4, 5 hangs off 2 in reverse, 6 is disconnected
+        let graph = HandleGraph::new(
+            Graph::from_gfa(
+                "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nS\t5\tA\nS\t6\tC\n\
+             L\t1\t+\t2\t+\t0M\nL\t1\t+\t3\t+\t0M\nL\t2\t+\t4\t+\t0M\n\
+             L\t3\t+\t4\t+\t0M\nL\t5\t+\t2\t-\t0M\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let names = |visits: &[Visit]| {
+            visits
+                .iter()
+                .map(|v| (graph.get_name(Handle::new(v.node, false)), v.depth))
+                .collect::<Vec<_>>()
+        };
+        let seeds = ["1".to_string()];
+        let bfs = traverse(&graph, &seeds, Order::BreadthFirst, None).unwrap();
+        assert_eq!(
+            names(&bfs),
+            vec![("1", 0), ("2", 1), ("3", 1), ("4", 2), ("5", 2)]
+        );
+        let dfs = traverse(&graph, &seeds, Order::DepthFirst, None).unwrap();
+        assert_eq!(
+            names(&dfs),
+            vec![("1", 0), ("2", 1), ("4", 2), ("3", 3), ("5", 2)]
+        );
+        let near = traverse(&graph, &seeds, Order::BreadthFirst, Some(1)).unwrap();
+        assert_eq!(near.len(), 3);
+        assert!(traverse(&graph, &["7".to_string()], Order::DepthFirst, None).is_err());
+
+        let mut out = Vec::new();
+        write_traversal(&graph, &near, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "node\tdepth\tparent\n1\t0\t*\n2\t1\t1\n3\t1\t1\n"
+        );
+    }
+}
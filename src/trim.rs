@@ -0,0 +1,1424 @@
+use crate::bloom::BloomFilter;
+use crate::edge::Edge;
+use crate::extract::Region;
+use crate::graph::{
+    path_line_steps, segment_length, split_pansn, tokenize_path, tokenize_walk, walk_line_steps,
+    EdgeRecord, Graph, StepError, WalkRecord,
+};
+use crate::index::{GraphIndex, KeepSet, Span};
+use crate::metrics::RecordCounts;
+use crate::par::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use crate::scan;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::io::{self, Write};
+
+pub fn get_paths(paths: Vec<&str>, paths_to_keep: Vec<String>) -> Vec<String> {
+    log::info!("Filtering paths");
+    let paths = paths
+        .into_par_iter()
+        .filter(|l| {
+            paths_to_keep.contains(
+                &scan::field(l, 1)
+                    .expect("All paths should have names")
+                    .to_string(),
+            )
+        })
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    paths
+}
+
+pub type SortedNodes = Vec<String>;
+pub type SortedEdges = Vec<Edge>;
+pub type Nodes = HashSet<String>;
+/// Edges match in either direction, see [`Edge`]
+pub type Edges = HashSet<Edge>;
+
+/// Union of all rows. Each thread extends a set of its own with the rows it takes and these
+/// sets are merged at the end, always into the larger one, instead of building a new set on
+/// every union.
+pub fn flatten_into_hashset<T: Eq + Hash + Send + Sync + Clone>(v: Vec<Vec<T>>) -> HashSet<T> {
+    #[cfg(feature = "parallel")]
+    return v
+        .into_par_iter()
+        .fold(HashSet::new, |mut set, row| {
+            set.extend(row);
+            set
+        })
+        .reduce(HashSet::new, |mut larger, mut smaller| {
+            if larger.len() < smaller.len() {
+                std::mem::swap(&mut larger, &mut smaller);
+            }
+            larger.extend(smaller);
+            larger
+        });
+    #[cfg(not(feature = "parallel"))]
+    return v.into_iter().flatten().collect();
+}
+
+/// Step of a path or walk, borrowed from its line
+type Step<'a> = (&'a str, bool);
+type StepEdges<'a> = Vec<Edge<&'a str>>;
+
+/// Steps, links and jumps of a path, as slices of it so that parsing does not allocate per
+/// step
+fn path_steps(path: &str) -> Result<(Vec<Step<'_>>, StepEdges<'_>, StepEdges<'_>), StepError> {
+    let tokens = tokenize_path(path)?;
+    let mut links = Vec::new();
+    let mut jumps = Vec::new();
+    for pair in tokens.windows(2) {
+        let edge = Edge::new(pair[0].name, pair[0].forward, pair[1].name, pair[1].forward);
+        match pair[0].jump {
+            Some(true) => jumps.push(edge),
+            _ => links.push(edge),
+        }
+    }
+    let steps = tokens.iter().map(|t| (t.name, t.forward)).collect();
+    Ok((steps, links, jumps))
+}
+
+/// Steps, links and jumps of a P line, errors name the path
+fn path_line_edges(line: &str) -> Result<(Vec<Step<'_>>, StepEdges<'_>, StepEdges<'_>), String> {
+    path_steps(scan::field(line, 2).unwrap_or("")).map_err(|e| {
+        let name = scan::field(line, 1).unwrap_or("");
+        format!("Invalid steps of path {}: {}", name, e)
+    })
+}
+
+/// Steps and links of a W line, as slices of it, after checking all of its fields
+fn walk_steps(line: &str) -> Result<(Vec<Step<'_>>, StepEdges<'_>), String> {
+    let steps = WalkRecord::parse(line)?.steps;
+    let links = steps
+        .iter()
+        .copied()
+        .tuple_windows()
+        .map(step_edge)
+        .collect();
+    Ok((steps, links))
+}
+
+/// Edge between two consecutive steps
+fn step_edge<'a>(((from, from_forward), (to, to_forward)): (Step<'a>, Step<'a>)) -> Edge<&'a str> {
+    Edge::new(from, from_forward, to, to_forward)
+}
+
+pub fn get_nodes_edges_from_path(
+    path: &str,
+) -> Result<(SortedNodes, SortedEdges, SortedEdges), StepError> {
+    let (nodes, links, jumps) = path_steps(path)?;
+    Ok((
+        nodes.into_iter().map(|(n, _)| n.to_string()).collect(),
+        links.into_iter().map(Edge::into_owned).collect(),
+        jumps.into_iter().map(Edge::into_owned).collect(),
+    ))
+}
+
+pub fn get_nodes_edges_from_walk(walk: &str) -> Result<(SortedNodes, SortedEdges), StepError> {
+    let nodes = tokenize_walk(walk)?;
+    let links = nodes
+        .iter()
+        .copied()
+        .tuple_windows()
+        .map(|pair| step_edge(pair).into_owned())
+        .collect();
+    Ok((
+        nodes.into_iter().map(|(n, _)| n.to_string()).collect(),
+        links,
+    ))
+}
+
+/// Parsed steps and edges of a path or walk, one JSON object per line of a
+/// `--trace-parsing` file
+#[derive(Serialize)]
+struct ParseTrace {
+    record: &'static str,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    steps: Vec<String>,
+    links: Vec<[String; 2]>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    jumps: Vec<[String; 2]>,
+}
+
+impl ParseTrace {
+    fn new(
+        record: &'static str,
+        name: String,
+        steps: &[Step],
+        links: &StepEdges,
+        jumps: &StepEdges,
+    ) -> Self {
+        let step = |(node, forward): Step| format!("{}{}", node, if forward { '+' } else { '-' });
+        let edges = |edges: &StepEdges| {
+            edges
+                .iter()
+                .map(|e| [step((e.from, e.from_forward)), step((e.to, e.to_forward))])
+                .collect()
+        };
+        ParseTrace {
+            record,
+            name,
+            error: None,
+            steps: steps.iter().map(|s| step(*s)).collect(),
+            links: edges(links),
+            jumps: edges(jumps),
+        }
+    }
+}
+
+/// Writes the steps, links and jumps parsed from every P and W line as JSON lines, e.g. to see
+/// why an edge is removed. Paths with invalid steps get the error instead.
+pub fn write_parse_trace<W: Write>(lines: &GfaLines, out: &mut W) -> io::Result<()> {
+    for path in &lines.paths {
+        let name = scan::field(path, 1).unwrap_or("").to_string();
+        let trace = match path_line_edges(path) {
+            Ok((steps, links, jumps)) => ParseTrace::new("P", name, &steps, &links, &jumps),
+            Err(e) => ParseTrace {
+                error: Some(e),
+                ..ParseTrace::new("P", name, &[], &Vec::new(), &Vec::new())
+            },
+        };
+        serde_json::to_writer(&mut *out, &trace)?;
+        writeln!(out)?;
+    }
+    for walk in &lines.walks {
+        let trace = match walk_steps(walk) {
+            Ok((steps, links)) => {
+                ParseTrace::new("W", walk_name(walk), &steps, &links, &Vec::new())
+            }
+            Err(e) => ParseTrace {
+                error: Some(e),
+                ..ParseTrace::new("W", walk_name(walk), &[], &Vec::new(), &Vec::new())
+            },
+        };
+        serde_json::to_writer(&mut *out, &trace)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Nodes, links and jumps of all paths and walks. Steps are parsed into slices of the lines
+/// and only the distinct nodes and edges are copied into the sets. Fails on the first path or
+/// walk that is invalid.
+pub fn get_nodes_edges(
+    paths: &Vec<String>,
+    walks: &Vec<String>,
+) -> Result<(Nodes, Edges, Edges), String> {
+    let parsed = paths
+        .par_iter()
+        .map(|p| {
+            let (steps, links, jumps) = path_line_edges(p)?;
+            let nodes = steps.into_iter().map(|(n, _)| n).collect::<Vec<_>>();
+            Ok((nodes, (links, jumps)))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let (nodes, (links, jumps)): (Vec<_>, (Vec<_>, Vec<_>)) = parsed.into_iter().unzip();
+    let mut nodes = flatten_into_hashset(nodes);
+    let mut links = flatten_into_hashset(links);
+    let jumps = flatten_into_hashset(jumps);
+    let parsed = walks
+        .par_iter()
+        .map(|w| {
+            let (steps, links) = walk_steps(w)?;
+            Ok((steps.into_iter().map(|(n, _)| n).collect::<Vec<_>>(), links))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let (walk_nodes, walk_links): (Vec<_>, Vec<_>) = parsed.into_iter().unzip();
+    let walk_nodes = flatten_into_hashset(walk_nodes);
+    let walk_links = flatten_into_hashset(walk_links);
+    nodes.extend(walk_nodes);
+    links.extend(walk_links);
+    Ok((
+        nodes.into_iter().map(|n| n.to_string()).collect(),
+        links.into_iter().map(Edge::into_owned).collect(),
+        jumps.into_iter().map(Edge::into_owned).collect(),
+    ))
+}
+
+pub fn filter_segments(segments: Vec<&str>, nodes_to_keep: HashSet<String>) -> Vec<&str> {
+    segments
+        .into_par_iter()
+        .filter(|n| nodes_to_keep.contains(scan::field(n, 1).expect("All nodes should have ids")))
+        .collect::<Vec<_>>()
+}
+
+/// Lines (with `record` as type) for the edges that have no line in `lines`, in either
+/// direction
+pub fn missing_edges(lines: &[&str], edges: &Edges, record: char) -> Vec<String> {
+    let present = lines
+        .iter()
+        .filter_map(|line| Edge::from_line(line).map(Edge::into_owned))
+        .collect::<HashSet<_>>();
+    let orientation = |forward: bool| if forward { '+' } else { '-' };
+    let overlap = if record == 'L' { "0M" } else { "*" };
+    edges
+        .iter()
+        .filter(|edge| !present.contains(*edge))
+        .map(|edge| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                record,
+                edge.from,
+                orientation(edge.from_forward),
+                edge.to,
+                orientation(edge.to_forward),
+                overlap
+            )
+        })
+        .sorted()
+        .collect()
+}
+
+/// L or J lines with their edges, fails on the first line without two oriented segments.
+/// Lines without overlap column are counted in a warning.
+fn parse_edges(lines: Vec<&str>) -> Result<Vec<(&str, Edge<&str>)>, String> {
+    let records = lines
+        .into_par_iter()
+        .map(|l| EdgeRecord::parse(l).map(|r| (l, r)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let without_overlap = records.iter().filter(|(_, r)| r.overlap.is_none()).count();
+    if without_overlap > 0 {
+        log::warn!(
+            "{} L/J lines without overlap column, they are written unchanged",
+            without_overlap
+        );
+    }
+    Ok(records.into_iter().map(|(l, r)| (l, r.edge)).collect())
+}
+
+/// L or J lines of the edges to keep, unchanged including their tags
+pub fn filter_edges(links: Vec<&str>, edges_to_keep: Edges) -> Result<Vec<&str>, String> {
+    Ok(parse_edges(links)?
+        .into_par_iter()
+        .filter(|(_, edge)| edges_to_keep.contains(&edge.into_owned()))
+        .map(|(l, _)| l)
+        .collect::<Vec<_>>())
+}
+/// Lines of a GFA file grouped by record type
+#[derive(Debug, Default, Clone)]
+pub struct GfaLines<'a> {
+    pub headers: Vec<&'a str>,
+    pub segments: Vec<&'a str>,
+    pub links: Vec<&'a str>,
+    pub jumps: Vec<&'a str>,
+    pub paths: Vec<&'a str>,
+    pub walks: Vec<&'a str>,
+    pub others: Vec<&'a str>,
+}
+
+impl<'a> GfaLines<'a> {
+    pub fn classify(content: &'a str) -> Self {
+        let mut lines = GfaLines::default();
+        for line in scan::lines(content) {
+            let records = match line.as_bytes().first() {
+                Some(b'S') => &mut lines.segments,
+                Some(b'L') => &mut lines.links,
+                Some(b'P') => &mut lines.paths,
+                Some(b'W') => &mut lines.walks,
+                Some(b'J') => &mut lines.jumps,
+                Some(b'H') => &mut lines.headers,
+                _ => &mut lines.others,
+            };
+            records.push(line);
+        }
+        lines
+    }
+
+    /// Number of lines, counted like the records of the metrics
+    pub fn line_count(&self) -> usize {
+        RecordCounts::of_lines(self).total()
+    }
+
+    /// Names of the paths (P lines) and walks (W lines) that traverse any of the given nodes,
+    /// fails on the first path or walk with invalid steps
+    pub fn paths_through(
+        &self,
+        nodes: &HashSet<String>,
+    ) -> Result<(Vec<String>, HashSet<String>), String> {
+        let paths = self
+            .paths
+            .par_iter()
+            .map(|l| {
+                let steps = path_line_steps(l)?;
+                Ok(steps
+                    .iter()
+                    .any(|(node, _)| nodes.contains(*node))
+                    .then(|| scan::field(l, 1).unwrap_or("").to_string()))
+            })
+            .collect::<Result<Vec<_>, String>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        let walks = self
+            .walks
+            .par_iter()
+            .map(|l| {
+                let steps = walk_line_steps(l)?;
+                Ok(steps
+                    .iter()
+                    .any(|(node, _)| nodes.contains(*node))
+                    .then(|| walk_name(l)))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((paths, walks.into_iter().flatten().collect()))
+    }
+
+    /// Reference samples declared by the `RS:Z:` tags of the header, separated by spaces
+    pub fn reference_samples(&self) -> Vec<String> {
+        self.headers
+            .iter()
+            .flat_map(|h| h.split('\t').skip(1))
+            .filter_map(|tag| tag.strip_prefix("RS:Z:"))
+            .flat_map(|samples| samples.split_whitespace())
+            .map(|sample| sample.to_string())
+            .collect()
+    }
+
+    /// Names of the paths (P lines) and walks (W lines) of the given samples, P lines that are
+    /// not named in PanSN form count as samples of their own
+    pub fn paths_of_samples(&self, samples: &HashSet<String>) -> (Vec<String>, HashSet<String>) {
+        let paths = self
+            .path_names()
+            .into_iter()
+            .filter(|name| samples.contains(split_pansn(name).map_or(name.as_str(), |p| p.0)))
+            .collect();
+        let walks = self
+            .walks
+            .iter()
+            .filter(|l| scan::field(l, 1).is_some_and(|s| samples.contains(s)))
+            .map(|l| walk_name(l))
+            .collect();
+        (paths, walks)
+    }
+
+    /// Segments visited by a reference, given by the name of a P line or the PanSN name of a
+    /// W line, fails if its steps are invalid
+    pub fn reference_segments(&self, reference: &str) -> Result<HashSet<String>, String> {
+        if let Some(line) = self
+            .paths
+            .iter()
+            .find(|l| scan::field(l, 1) == Some(reference))
+        {
+            let steps = path_line_steps(line)?;
+            return Ok(steps
+                .into_iter()
+                .map(|(node, _)| node.to_string())
+                .collect());
+        }
+        let walk = self
+            .walks
+            .iter()
+            .find(|l| walk_name(l) == reference)
+            .ok_or_else(|| format!("Reference {} is no path or walk of the graph", reference))?;
+        Ok(walk_line_steps(walk)?
+            .into_iter()
+            .map(|(node, _)| node.to_string())
+            .collect())
+    }
+
+    /// Paths (P lines) and walks (W lines) among the given ones (all if `None`), with their
+    /// lengths for [`longest_per_sample`] and their bp off the `reference` segments. Fails on
+    /// the first path or walk with invalid steps.
+    pub fn candidates(
+        &self,
+        paths_to_keep: Option<&[String]>,
+        walks_to_keep: Option<&HashSet<String>>,
+        reference: Option<&HashSet<String>>,
+    ) -> Result<Vec<Candidate>, String> {
+        let lengths = self
+            .segments
+            .iter()
+            .map(|s| {
+                let name = scan::field(s, 1).expect("All nodes should have ids");
+                (name, segment_length(s))
+            })
+            .collect::<HashMap<_, _>>();
+        let length = |steps: &[(&str, bool)]| {
+            steps
+                .iter()
+                .map(|(node, _)| lengths.get(node).copied().unwrap_or(0))
+                .sum()
+        };
+        let nonref = |steps: &[(&str, bool)]| {
+            reference.map_or(0, |reference| {
+                steps
+                    .iter()
+                    .filter(|(node, _)| !reference.contains(*node))
+                    .map(|(node, _)| lengths.get(node).copied().unwrap_or(0))
+                    .sum()
+            })
+        };
+        let paths_to_keep =
+            paths_to_keep.map(|keep| keep.iter().map(|p| p.as_str()).collect::<HashSet<_>>());
+        let mut candidates = Vec::new();
+        for l in &self.paths {
+            let name = scan::field(l, 1).unwrap_or("");
+            if paths_to_keep
+                .as_ref()
+                .is_some_and(|keep| !keep.contains(name))
+            {
+                continue;
+            }
+            let steps = path_line_steps(l)?;
+            candidates.push(Candidate {
+                sample: split_pansn(name).map_or(name, |p| p.0).to_string(),
+                name: name.to_string(),
+                is_walk: false,
+                length: length(&steps),
+                nonref: nonref(&steps),
+            });
+        }
+        for l in &self.walks {
+            let name = walk_name(l);
+            if walks_to_keep.is_some_and(|keep| !keep.contains(&name)) {
+                continue;
+            }
+            let steps = walk_line_steps(l)?;
+            candidates.push(Candidate {
+                sample: scan::field(l, 1).unwrap_or("").to_string(),
+                name,
+                is_walk: true,
+                length: length(&steps),
+                nonref: nonref(&steps),
+            });
+        }
+        Ok(candidates)
+    }
+
+    /// Names of the walks (W lines) whose start/end columns overlap any of the ranges on their
+    /// contig
+    pub fn walks_in(&self, ranges: &[Region]) -> HashSet<String> {
+        self.walks
+            .par_iter()
+            .filter(|l| {
+                let fields = scan::fields(l).collect::<Vec<_>>();
+                let (Some(Ok(start)), Some(Ok(end))) = (
+                    fields.get(4).map(|s| s.parse::<usize>()),
+                    fields.get(5).map(|s| s.parse::<usize>()),
+                ) else {
+                    return false;
+                };
+                ranges
+                    .iter()
+                    .any(|r| r.path == fields[3] && start < r.end && r.start < end)
+            })
+            .map(|l| walk_name(l))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Names of all paths (P lines)
+    pub fn path_names(&self) -> Vec<String> {
+        self.paths
+            .par_iter()
+            .map(|l| {
+                scan::field(l, 1)
+                    .expect("All paths should have names")
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+/// A path or walk competing for the `--max-paths-per-sample` places of its sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub sample: String,
+    pub name: String,
+    pub is_walk: bool,
+    /// Length in bp
+    pub length: usize,
+    /// Length in bp on segments the reference does not visit, 0 without a reference
+    pub nonref: usize,
+}
+
+/// Names of the `max` longest paths and walks of every sample (paths in input order), ties
+/// are broken by the input order
+pub fn longest_per_sample(
+    candidates: Vec<Candidate>,
+    max: usize,
+) -> (Vec<String>, HashSet<String>) {
+    let mut by_sample = HashMap::<&str, Vec<usize>>::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        by_sample.entry(&candidate.sample).or_default().push(i);
+    }
+    let mut kept = vec![false; candidates.len()];
+    for indices in by_sample.values_mut() {
+        indices.sort_by_key(|i| std::cmp::Reverse(candidates[*i].length));
+        for i in indices.iter().take(max) {
+            kept[*i] = true;
+        }
+    }
+    let (walks, paths): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .zip(kept)
+        .filter(|(_, kept)| *kept)
+        .map(|(c, _)| c)
+        .partition(|c| c.is_walk);
+    (
+        paths.into_iter().map(|c| c.name).collect(),
+        walks.into_iter().map(|c| c.name).collect(),
+    )
+}
+
+/// How to combine several lists of paths to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Combine {
+    /// Paths in any of the lists
+    #[default]
+    Union,
+    /// Paths in all of the lists
+    Intersection,
+    /// Paths in the first list but none of the others
+    Difference,
+}
+
+/// Combines the lists, keeping the order of first appearance
+pub fn combine_keep_lists(lists: Vec<Vec<String>>, combine: Combine) -> Vec<String> {
+    let sets = lists
+        .iter()
+        .map(|list| list.iter().collect::<HashSet<_>>())
+        .collect::<Vec<_>>();
+    let keep = |name: &String| match combine {
+        Combine::Union => true,
+        Combine::Intersection => sets.iter().all(|set| set.contains(name)),
+        Combine::Difference => !sets[1..].iter().any(|set| set.contains(name)),
+    };
+    let candidates = match combine {
+        Combine::Union => lists.concat(),
+        _ => lists.first().cloned().unwrap_or_default(),
+    };
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|name| keep(name) && seen.insert(name.clone()))
+        .collect()
+}
+
+/// PanSN name `sample#hap#contig` of a W line
+pub fn walk_name(walk: &str) -> String {
+    walk.split('\t').skip(1).take(3).join("#")
+}
+
+/// Record types that should be kept untouched, and the walks to keep
+#[derive(Debug, Default, Clone)]
+pub struct TrimOptions {
+    pub ignore_segments: bool,
+    pub ignore_links: bool,
+    pub ignore_jumps: bool,
+    /// PanSN names of the walks to keep, all walks are kept if `None`
+    pub walks_to_keep: Option<HashSet<String>>,
+    /// Segments and edges (in both orientations) supported by enough reads, kept besides those
+    /// of the kept paths and walks (`--assembly-mode`)
+    pub read_support: Option<(Nodes, Edges)>,
+}
+
+/// GFA record types, `Other` covers comments and all other lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordType {
+    Header,
+    Segment,
+    Link,
+    Jump,
+    Path,
+    Walk,
+    Other,
+}
+
+/// Records remaining after trimming
+#[derive(Debug, Default, Clone)]
+pub struct Trimmed<'a> {
+    pub headers: Vec<&'a str>,
+    pub segments: Vec<&'a str>,
+    pub paths: Vec<String>,
+    pub walks: Vec<String>,
+    pub links: Vec<&'a str>,
+    pub jumps: Vec<&'a str>,
+    pub others: Vec<&'a str>,
+}
+
+impl Trimmed<'_> {
+    /// Removes all records of the types for which `keep` is false
+    pub fn retain_records(&mut self, keep: impl Fn(RecordType) -> bool) {
+        let clear = |lines: &mut Vec<_>, record| {
+            if !keep(record) {
+                lines.clear();
+            }
+        };
+        clear(&mut self.headers, RecordType::Header);
+        clear(&mut self.segments, RecordType::Segment);
+        clear(&mut self.links, RecordType::Link);
+        clear(&mut self.jumps, RecordType::Jump);
+        clear(&mut self.others, RecordType::Other);
+        if !keep(RecordType::Path) {
+            self.paths.clear();
+        }
+        if !keep(RecordType::Walk) {
+            self.walks.clear();
+        }
+    }
+
+    /// L and J lines for consecutive steps of the kept paths and walks without a kept edge,
+    /// i.e. without an edge in the input. Fails on a path or walk with invalid steps.
+    pub fn missing_edges(&self) -> Result<Vec<String>, String> {
+        let (mut links, mut jumps) = (Edges::new(), Edges::new());
+        for path in &self.paths {
+            let (_, path_links, path_jumps) = path_line_edges(path)?;
+            links.extend(path_links.into_iter().map(Edge::into_owned));
+            jumps.extend(path_jumps.into_iter().map(Edge::into_owned));
+        }
+        for walk in &self.walks {
+            links.extend(walk_steps(walk)?.1.into_iter().map(Edge::into_owned));
+        }
+        let mut missing = missing_edges(&self.links, &links, 'L');
+        missing.extend(missing_edges(&self.jumps, &jumps, 'J'));
+        Ok(missing)
+    }
+
+    /// The kept records as a [`Graph`], fails on a path or walk with invalid steps
+    pub fn to_graph(&self) -> Result<Graph, String> {
+        Graph::from_lines(
+            &self.segments,
+            &self.links,
+            &self.jumps,
+            &self.paths,
+            &self.walks,
+        )
+    }
+
+    /// The kept lines, grouped by record type in the order they are written in
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.headers
+            .iter()
+            .chain(&self.segments)
+            .copied()
+            .chain(self.paths.iter().map(|p| p.as_str()))
+            .chain(self.walks.iter().map(|w| w.as_str()))
+            .chain(
+                self.links
+                    .iter()
+                    .chain(&self.jumps)
+                    .chain(&self.others)
+                    .copied(),
+            )
+    }
+
+    /// Writes the kept lines as GFA, grouped by record type
+    pub fn write_gfa<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for line in self.lines() {
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the given paths (all paths if `None`) and the walks selected in `options`, and removes the segments,
+/// links and jumps not covered by them
+pub fn trim_gfa<'a>(
+    lines: GfaLines<'a>,
+    paths_to_keep: Option<Vec<String>>,
+    options: &TrimOptions,
+) -> Result<Trimmed<'a>, String> {
+    trim_gfa_with(lines, paths_to_keep, options, |paths, walks| {
+        log::info!("Getting nodes/edges to keep");
+        get_nodes_edges(paths, walks)
+    })
+}
+
+/// P and W lines to keep, all paths if `paths_to_keep` is `None`
+fn kept_paths_walks(
+    lines: &GfaLines,
+    paths_to_keep: Option<Vec<String>>,
+    options: &TrimOptions,
+) -> (Vec<String>, Vec<String>) {
+    let paths_to_keep = paths_to_keep.unwrap_or_else(|| lines.path_names());
+    let paths = get_paths(lines.paths.clone(), paths_to_keep);
+    let walks = lines
+        .walks
+        .par_iter()
+        .filter(|w| {
+            options
+                .walks_to_keep
+                .as_ref()
+                .is_none_or(|keep| keep.contains(&walk_name(w)))
+        })
+        .map(|s| s.to_string())
+        .collect();
+    (paths, walks)
+}
+
+/// Same as [`trim_gfa`], but the nodes and edges covered by the kept paths and walks are
+/// obtained from `keep_set`, e.g. to restore them from a checkpoint
+pub fn trim_gfa_with<'a, E: From<String>>(
+    lines: GfaLines<'a>,
+    paths_to_keep: Option<Vec<String>>,
+    options: &TrimOptions,
+    keep_set: impl FnOnce(&Vec<String>, &Vec<String>) -> Result<(Nodes, Edges, Edges), E>,
+) -> Result<Trimmed<'a>, E> {
+    let (paths, walks) = kept_paths_walks(&lines, paths_to_keep, options);
+    let (mut nodes, mut links, jumps) = keep_set(&paths, &walks)?;
+    if let Some((supported_nodes, supported_edges)) = &options.read_support {
+        nodes.extend(supported_nodes.iter().cloned());
+        links.extend(
+            supported_edges
+                .iter()
+                .filter(|e| nodes.contains(&e.from) && nodes.contains(&e.to))
+                .cloned(),
+        );
+    }
+
+    let segments = match options.ignore_segments {
+        false => {
+            log::info!("Removing nodes");
+            filter_segments(lines.segments, nodes)
+        }
+        true => lines.segments,
+    };
+
+    let link_lines = match options.ignore_links {
+        false => {
+            log::info!("Removing links");
+            filter_edges(lines.links, links)?
+        }
+        true => lines.links,
+    };
+
+    let jump_lines = match options.ignore_jumps {
+        false => {
+            log::info!("Removing jumps");
+            filter_edges(lines.jumps, jumps)?
+        }
+        true => lines.jumps,
+    };
+
+    Ok(Trimmed {
+        headers: lines.headers,
+        segments,
+        paths,
+        walks,
+        links: link_lines,
+        jumps: jump_lines,
+        others: lines.others,
+    })
+}
+
+/// Same as [`trim_gfa`], but the nodes and edges of the kept paths and walks are first
+/// remembered in Bloom filters with the given false positive rate. The segments, links and
+/// jumps the filters report as covered are candidates, which a second pass over the kept paths
+/// and walks checks exactly, so the result is the same as the exact one. The sets of that pass
+/// only hold the candidates, borrowed from the input, instead of owned copies of all steps.
+pub fn trim_gfa_approximate<'a>(
+    mut lines: GfaLines<'a>,
+    paths_to_keep: Option<Vec<String>>,
+    options: &TrimOptions,
+    rate: f64,
+) -> Result<Trimmed<'a>, String> {
+    let (paths, walks) = kept_paths_walks(&lines, paths_to_keep, options);
+    let steps = paths
+        .iter()
+        .map(|p| scan::field(p, 2).unwrap_or("").split([',', ';']).count())
+        .chain(walks.iter().map(|w| {
+            let steps = scan::field(w, 6).unwrap_or("");
+            steps.matches(['>', '<']).count()
+        }))
+        .sum::<usize>();
+    let supported = options.read_support.as_ref();
+    let items = steps + supported.map_or(0, |(nodes, edges)| nodes.len() + edges.len());
+    let mut nodes = BloomFilter::new(items, rate);
+    let mut links = BloomFilter::new(items, rate);
+    let mut jumps = BloomFilter::new(items, rate);
+    log::info!(
+        "Getting nodes/edges to keep into Bloom filters of {} bytes each",
+        nodes.size()
+    );
+    // The hash of a borrowed step or edge is the same as of the owned one, as in `read_support`
+    for path in &paths {
+        let (path_nodes, path_links, path_jumps) = path_line_edges(path)?;
+        path_nodes.iter().for_each(|(n, _)| nodes.insert(n));
+        path_links.iter().for_each(|l| links.insert(l));
+        path_jumps.iter().for_each(|j| jumps.insert(j));
+    }
+    for walk in &walks {
+        let (walk_nodes, walk_links) = walk_steps(walk)?;
+        walk_nodes.iter().for_each(|(n, _)| nodes.insert(n));
+        walk_links.iter().for_each(|l| links.insert(l));
+    }
+    if let Some((supported_nodes, supported_edges)) = supported {
+        supported_nodes.iter().for_each(|n| nodes.insert(n));
+        for edge in supported_edges {
+            if nodes.contains(&edge.from) && nodes.contains(&edge.to) {
+                links.insert(edge);
+            }
+        }
+    }
+
+    let name = |s: &&'a str| scan::field(s, 1).expect("All nodes should have ids");
+    let segments = match options.ignore_segments {
+        false => lines
+            .segments
+            .into_par_iter()
+            .filter(|s| nodes.contains(name(s)))
+            .collect(),
+        true => lines.segments,
+    };
+    // Same matching as filter_edges, edges hash the same in both directions
+    type Candidates<'a> = Vec<(&'a str, Edge<&'a str>)>;
+    let candidates = |filter: &BloomFilter, lines: Vec<&'a str>| -> Result<Candidates, String> {
+        Ok(parse_edges(lines)?
+            .into_par_iter()
+            .filter(|(_, edge)| filter.contains(edge))
+            .collect())
+    };
+    let link_candidates = match options.ignore_links {
+        false => Some(candidates(&links, std::mem::take(&mut lines.links))?),
+        true => None,
+    };
+    let jump_candidates = match options.ignore_jumps {
+        false => Some(candidates(&jumps, std::mem::take(&mut lines.jumps))?),
+        true => None,
+    };
+
+    log::info!("Checking the candidates of the Bloom filters");
+    let candidate_names = segments.iter().map(name).collect::<HashSet<_>>();
+    let edge_set = |candidates: &Option<Candidates<'a>>| {
+        candidates
+            .iter()
+            .flatten()
+            .map(|(_, edge)| *edge)
+            .collect::<HashSet<_>>()
+    };
+    let (candidate_links, candidate_jumps) =
+        (edge_set(&link_candidates), edge_set(&jump_candidates));
+    let mut visited = HashSet::<&str>::new();
+    let (mut traversed_links, mut traversed_jumps) = (HashSet::new(), HashSet::new());
+    for path in &paths {
+        let (path_nodes, path_links, path_jumps) = path_line_edges(path)?;
+        visited.extend(
+            path_nodes
+                .iter()
+                .filter_map(|(n, _)| candidate_names.get(n).copied()),
+        );
+        traversed_links.extend(
+            path_links
+                .iter()
+                .filter_map(|l| candidate_links.get(l).copied()),
+        );
+        traversed_jumps.extend(
+            path_jumps
+                .iter()
+                .filter_map(|j| candidate_jumps.get(j).copied()),
+        );
+    }
+    for walk in &walks {
+        let (walk_nodes, walk_links) = walk_steps(walk)?;
+        visited.extend(
+            walk_nodes
+                .iter()
+                .filter_map(|(n, _)| candidate_names.get(n).copied()),
+        );
+        traversed_links.extend(
+            walk_links
+                .iter()
+                .filter_map(|l| candidate_links.get(l).copied()),
+        );
+    }
+    // Supported links need both segments kept, as in trim_gfa_with
+    let kept = |node: &str| {
+        visited.contains(node) || supported.is_some_and(|(nodes, _)| nodes.contains(node))
+    };
+    let supported_link = |edge: &Edge<&str>| {
+        supported.is_some_and(|(_, edges)| edges.contains(&edge.into_owned()))
+            && kept(edge.from)
+            && kept(edge.to)
+    };
+    let segments = match options.ignore_segments {
+        false => segments.into_iter().filter(|s| kept(name(s))).collect(),
+        true => segments,
+    };
+    let link_lines = match link_candidates {
+        Some(candidates) => candidates
+            .into_iter()
+            .filter(|(_, edge)| traversed_links.contains(edge) || supported_link(edge))
+            .map(|(l, _)| l)
+            .collect(),
+        None => lines.links,
+    };
+    let jump_lines = match jump_candidates {
+        Some(candidates) => candidates
+            .into_iter()
+            .filter(|(_, edge)| traversed_jumps.contains(edge))
+            .map(|(l, _)| l)
+            .collect(),
+        None => lines.jumps,
+    };
+    Ok(Trimmed {
+        headers: lines.headers,
+        segments,
+        paths,
+        walks,
+        links: link_lines,
+        jumps: jump_lines,
+        others: lines.others,
+    })
+}
+
+/// Records to keep according to the index, with the ignored record types kept completely
+pub fn index_keep_set(
+    index: &GraphIndex,
+    paths_to_keep: Option<Vec<String>>,
+    options: &TrimOptions,
+) -> KeepSet {
+    log::info!("Getting nodes/edges to keep from index");
+    let mut keep = index.keep_set(paths_to_keep.as_deref(), options.walks_to_keep.as_ref());
+    for (flags, ignore) in [
+        (&mut keep.segments, options.ignore_segments),
+        (&mut keep.links, options.ignore_links),
+        (&mut keep.jumps, options.ignore_jumps),
+    ] {
+        if ignore {
+            flags.fill(true);
+        }
+    }
+    keep
+}
+
+/// Same as [`trim_gfa`], but takes the records to keep from an index of the file. Neither
+/// the paths nor the other lines are parsed, kept lines are cut out of `content` by their
+/// position, so trimming the same graph with another selection is cheap. Fails if the
+/// content does not match the index.
+pub fn trim_gfa_with_index<'a>(
+    content: &'a str,
+    index: &GraphIndex,
+    paths_to_keep: Option<Vec<String>>,
+    options: &TrimOptions,
+) -> Result<Trimmed<'a>, String> {
+    if content.len() as u64 != index.content_size {
+        return Err("Index does not match the graph, rebuild it with trim-graph index".to_string());
+    }
+    let keep = index_keep_set(index, paths_to_keep, options);
+    cut_kept_lines(index, &keep, |span| span.slice(content))
+}
+
+/// Kept records of an indexed graph, looked up by their span with `line` (e.g. in a partial
+/// copy of the file)
+pub fn cut_kept_lines<'a>(
+    index: &GraphIndex,
+    keep: &KeepSet,
+    line: impl Fn(&Span) -> Option<&'a str>,
+) -> Result<Trimmed<'a>, String> {
+    let cut = |spans: &mut dyn Iterator<Item = &Span>, flags: Option<&[bool]>| {
+        spans
+            .enumerate()
+            .filter(|(i, _)| flags.is_none_or(|flags| flags[*i]))
+            .map(|(_, span)| line(span).ok_or_else(|| "Index does not match the graph".to_string()))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let owned = |lines: Vec<&str>| lines.into_iter().map(|s| s.to_string()).collect();
+
+    Ok(Trimmed {
+        headers: cut(&mut index.headers.iter(), None)?,
+        segments: cut(
+            &mut index.segments.iter().map(|s| &s.span),
+            Some(&keep.segments),
+        )?,
+        paths: owned(cut(
+            &mut index.paths.iter().map(|p| &p.span),
+            Some(&keep.paths),
+        )?),
+        walks: owned(cut(
+            &mut index.walks.iter().map(|w| &w.span),
+            Some(&keep.walks),
+        )?),
+        links: cut(&mut index.links.iter().map(|l| &l.span), Some(&keep.links))?,
+        jumps: cut(&mut index.jumps.iter().map(|j| &j.span), Some(&keep.jumps))?,
+        others: cut(&mut index.others.iter(), None)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn edge(from: &str, from_forward: bool, to: &str, to_forward: bool) -> Edge {
+        Edge::new(from.to_string(), from_forward, to.to_string(), to_forward)
+    }
+
+    #[test]
+    fn test_get_paths() {
+        let paths = vec!["P\tp1\t1+, 2-, 3+", "P\tp2\t2+, 4-", "P\tp3\t5-, 3-, 1+"];
+        let paths_to_keep = vec!["p2".to_string(), "p3".to_string()];
+        let calculated = get_paths(paths, paths_to_keep);
+        let expected = vec!["P\tp2\t2+, 4-".to_string(), "P\tp3\t5-, 3-, 1+".to_string()];
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_retain_records() {
+        let content = "H\tVN:Z:1.0\nS\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nP\tp\t1+,2+\t*\n\
+                       W\ts\t1\tc\t0\t2\t>1>2\n# comment\n";
+        let mut trimmed =
+            trim_gfa(GfaLines::classify(content), None, &TrimOptions::default()).unwrap();
+        trimmed.retain_records(|r| !matches!(r, RecordType::Path | RecordType::Walk));
+        assert_eq!(trimmed.segments.len(), 2);
+        assert_eq!(trimmed.links.len(), 1);
+        assert_eq!(trimmed.others, vec!["# comment"]);
+        assert!(trimmed.paths.is_empty() && trimmed.walks.is_empty());
+    }
+
+    #[test]
+    fn test_missing_edges() {
+        // p traverses the link 1+ 2+ in reverse, 2 and 3 are not linked
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nL\t1\t+\t2\t+\t0M\n\
+                       P\tp\t2-,1-;3+\t*\nW\ts\t1\tc\t0\t2\t>2>3\n";
+        let options = TrimOptions::default();
+        let trimmed = trim_gfa(GfaLines::classify(content), None, &options).unwrap();
+        let missing = vec!["L\t2\t+\t3\t+\t0M", "J\t1\t-\t3\t+\t*"];
+        assert_eq!(trimmed.missing_edges().unwrap(), missing);
+        let approximate =
+            trim_gfa_approximate(GfaLines::classify(content), None, &options, 0.01).unwrap();
+        assert_eq!(approximate.missing_edges().unwrap(), missing);
+    }
+
+    #[test]
+    fn test_read_support() {
+        // Reads support 3 and 4 and the link between them, the link 1+ 3+ has no support
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nS\t5\tT\nL\t1\t+\t2\t+\t0M\n\
+                       L\t1\t+\t3\t+\t0M\nL\t3\t+\t4\t+\t0M\nL\t4\t+\t5\t+\t0M\nP\tp\t1+,2+\t*\n";
+        let depth = crate::gaf::ReadDepth::from_gaf("r\t2\t0\t2\t+\t<4<3\t2\t0\t2\t2\t2\t60\n");
+        let options = TrimOptions {
+            read_support: Some(depth.unwrap().supported(1)),
+            ..TrimOptions::default()
+        };
+        let trimmed = trim_gfa(GfaLines::classify(content), None, &options).unwrap();
+        assert_eq!(trimmed.segments.len(), 4);
+        assert_eq!(
+            trimmed.links,
+            vec!["L\t1\t+\t2\t+\t0M", "L\t3\t+\t4\t+\t0M"]
+        );
+        let approximate =
+            trim_gfa_approximate(GfaLines::classify(content), None, &options, 0.5).unwrap();
+        assert_eq!(approximate.segments, trimmed.segments);
+        assert_eq!(approximate.links, trimmed.links);
+    }
+
+    #[test]
+    fn test_trim_gfa_approximate() {
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nS\t4\tT\nL\t1\t+\t2\t+\t0M\n\
+                       L\t2\t+\t3\t+\t0M\nL\t1\t+\t3\t+\t0M\nJ\t3\t+\t4\t+\t*\n\
+                       P\tp\t1+,2+;4+\t*\nW\ts\t1\tc\t0\t2\t>2>3\n";
+        let options = TrimOptions {
+            walks_to_keep: Some(HashSet::new()),
+            ..TrimOptions::default()
+        };
+        let exact = trim_gfa(GfaLines::classify(content), None, &options).unwrap();
+        // The false positives of even a bad filter are dropped by the exact pass
+        for rate in [1e-6, 0.9] {
+            let approximate =
+                trim_gfa_approximate(GfaLines::classify(content), None, &options, rate).unwrap();
+            assert_eq!(approximate.segments, exact.segments);
+            assert_eq!(approximate.links, exact.links);
+            assert_eq!(approximate.jumps, exact.jumps);
+            assert_eq!(approximate.paths, exact.paths);
+            assert!(approximate.walks.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_paths_through() {
+        let content = "S\t1\tA\nS\t2\tC\nS\t3\tG\nP\tp1\t1+,2-\t*\nP\tp2\t3+\t*\n\
+                       W\ts\t1\tc\t0\t2\t>3<2\nW\tt\t1\tc\t0\t1\t>1\n";
+        let lines = GfaLines::classify(content);
+        let (paths, walks) = lines
+            .paths_through(&HashSet::from(["2".to_string()]))
+            .unwrap();
+        assert_eq!(paths, vec!["p1"]);
+        assert_eq!(walks, HashSet::from(["s#1#c".to_string()]));
+
+        let options = TrimOptions {
+            walks_to_keep: Some(walks),
+            ..TrimOptions::default()
+        };
+        let trimmed = trim_gfa(lines, Some(paths), &options).unwrap();
+        assert_eq!(trimmed.walks, vec!["W\ts\t1\tc\t0\t2\t>3<2"]);
+        assert_eq!(trimmed.segments.len(), 3);
+    }
+
+    #[test]
+    fn test_paths_of_samples() {
+        let content = "H\tVN:Z:1.1\tRS:Z:GRCh38 HG002\n\
+                       P\tGRCh38#0#chr1\t1+\t*\nP\tHG002#1#chr1\t1+\t*\nP\tHG003#1#chr1\t1+\t*\n\
+                       P\tchrM\t1+\t*\nW\tHG002\t2\tchr1\t0\t1\t>1\nW\tHG004\t1\tchr1\t0\t1\t>1\n";
+        let lines = GfaLines::classify(content);
+        assert_eq!(lines.reference_samples(), vec!["GRCh38", "HG002"]);
+        let samples = lines.reference_samples().into_iter().collect();
+        let (paths, walks) = lines.paths_of_samples(&samples);
+        assert_eq!(paths, vec!["GRCh38#0#chr1", "HG002#1#chr1"]);
+        assert_eq!(walks, HashSet::from(["HG002#2#chr1".to_string()]));
+    }
+
+    #[test]
+    fn test_longest_per_sample() {
+        let content = "S\t1\tACGT\nS\t2\tA\nS\t3\tCC\n\
+                       P\tHG002#1#scaffold1\t2+\t*\nP\tHG002#1#scaffold2\t1+,3+\t*\n\
+                       P\tHG002#2#scaffold1\t3+\t*\nP\tGRCh38#0#chr1\t1+\t*\n\
+                       W\tHG002\t2\tscaffold3\t0\t5\t>1>2\nW\tHG003\t1\tchr1\t0\t1\t>2\n";
+        let lines = GfaLines::classify(content);
+        let (paths, walks) = longest_per_sample(lines.candidates(None, None, None).unwrap(), 2);
+        assert_eq!(paths, vec!["HG002#1#scaffold2", "GRCh38#0#chr1"]);
+        assert_eq!(
+            walks,
+            HashSet::from(["HG002#2#scaffold3".to_string(), "HG003#1#chr1".to_string()])
+        );
+
+        let keep = vec![
+            "HG002#1#scaffold1".to_string(),
+            "HG002#2#scaffold1".to_string(),
+        ];
+        let candidates = lines
+            .candidates(Some(&keep), Some(&HashSet::new()), None)
+            .unwrap();
+        let (paths, walks) = longest_per_sample(candidates, 1);
+        assert_eq!(paths, vec!["HG002#2#scaffold1"]);
+        assert!(walks.is_empty());
+
+        let reference = lines.reference_segments("GRCh38#0#chr1").unwrap();
+        let nonref = lines
+            .candidates(None, None, Some(&reference))
+            .unwrap()
+            .into_iter()
+            .map(|c| c.nonref)
+            .collect::<Vec<_>>();
+        assert_eq!(nonref, vec![1, 2, 2, 0, 1, 1]);
+        assert!(lines.reference_segments("chr1").is_err());
+    }
+
+    #[test]
+    fn test_combine_keep_lists() {
+        let list = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let lists = vec![
+            list(&["a", "b", "c"]),
+            list(&["d", "b"]),
+            list(&["c", "b", "a"]),
+        ];
+        let combine = |combine| combine_keep_lists(lists.clone(), combine);
+        assert_eq!(combine(Combine::Union), list(&["a", "b", "c", "d"]));
+        assert_eq!(combine(Combine::Intersection), list(&["b"]));
+        assert_eq!(combine(Combine::Difference), Vec::<String>::new());
+        assert_eq!(
+            combine_keep_lists(lists[..2].to_vec(), Combine::Difference),
+            list(&["a", "c"])
+        );
+    }
+
+    #[test]
+    fn test_walks_in() {
+        let content = "W\ts\t1\tchr1\t100\t200\t>1\nW\ts\t2\tchr1\t200\t300\t>1\n\
+                       W\tt\t1\tchr2\t100\t200\t>1\n";
+        let lines = GfaLines::classify(content);
+        let ranges = ["chr1:150-250".parse().unwrap()];
+        assert_eq!(lines.walks_in(&ranges).len(), 2);
+        let ranges = [
+            "chr1:0-100".parse().unwrap(),
+            "chr2:199-250".parse().unwrap(),
+        ];
+        assert_eq!(
+            lines.walks_in(&ranges),
+            HashSet::from(["t#1#chr2".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_get_nodes_edges_from_path_nodes() {
+        let path = "1+, 2-, 3+";
+        let mut expected = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+        let (mut calculated, _, _) = get_nodes_edges_from_path(path).unwrap();
+        calculated.sort();
+        expected.sort();
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_get_nodes_edges_from_path_links() {
+        let path = "1+, 2-; 3+, 2+";
+        let mut expected = vec![edge("1", true, "2", false), edge("3", true, "2", true)];
+        let (_, mut calculated, _) = get_nodes_edges_from_path(path).unwrap();
+        calculated.sort();
+        expected.sort();
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_get_nodes_edges_from_path_jumps() {
+        let path = "1+; 2-, 3+; 2+";
+        let mut expected = vec![edge("1", true, "2", false), edge("3", true, "2", true)];
+        let (_, _, mut calculated) = get_nodes_edges_from_path(path).unwrap();
+        calculated.sort();
+        expected.sort();
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_path_steps() {
+        // Steps are slices of the path, orientation characters inside names are kept
+        let path = "s-1+,s-2-;s-3+";
+        let (nodes, links, jumps) = path_steps(path).unwrap();
+        assert_eq!(nodes, vec![("s-1", true), ("s-2", false), ("s-3", true)]);
+        assert_eq!(links, vec![Edge::new("s-1", true, "s-2", false)]);
+        assert_eq!(jumps, vec![Edge::new("s-2", false, "s-3", true)]);
+        assert!(path
+            .as_bytes()
+            .as_ptr_range()
+            .contains(&nodes[0].0.as_ptr()));
+    }
+
+    #[test]
+    fn test_write_parse_trace() {
+        let lines = GfaLines::classify(
+            "P\tp\t1+,2-;3+\t*\nW\tHG002\t1\tchr1\t0\t2\t>1<2\nP\tq\t1+,,2+\t*\n",
+        );
+        let mut out = Vec::new();
+        write_parse_trace(&lines, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"record\":\"P\",\"name\":\"p\",\"steps\":[\"1+\",\"2-\",\"3+\"],\
+             \"links\":[[\"1+\",\"2-\"]],\"jumps\":[[\"2-\",\"3+\"]]}\n\
+             {\"record\":\"P\",\"name\":\"q\",\"error\":\"Invalid steps of path q: Empty step at position 3\",\
+             \"steps\":[],\"links\":[]}\n\
+             {\"record\":\"W\",\"name\":\"HG002#1#chr1\",\"steps\":[\"1+\",\"2-\"],\
+             \"links\":[[\"1+\",\"2-\"]]}\n"
+        );
+    }
+
+    #[test]
+    fn test_get_node_edges_for_paths() {
+        let paths = vec!["P\tp1\t1+, 2-; 3+".to_string(), "P\tp2\t2+, 4-".to_string()];
+        let expected = (
+            HashSet::from([
+                "1".to_string(),
+                "2".to_string(),
+                "3".to_string(),
+                "4".to_string(),
+            ]),
+            HashSet::from([edge("1", true, "2", false), edge("2", true, "4", false)]),
+            HashSet::from([edge("2", false, "3", true)]),
+        );
+        let calculated = get_nodes_edges(&paths, &Vec::new()).unwrap();
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_get_nodes_edges_from_walk_nodes() {
+        let walk = ">1<2>3";
+        let mut expected = vec!["1".to_string(), "2".to_string(), "3".to_string()];
+        let (mut calculated, _) = get_nodes_edges_from_walk(walk).unwrap();
+        expected.sort();
+        calculated.sort();
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_get_nodes_edges_from_walk_links() {
+        let walk = ">1<2>3";
+        let mut expected = vec![edge("1", true, "2", false), edge("2", false, "3", true)];
+        let (_, mut calculated) = get_nodes_edges_from_walk(walk).unwrap();
+        expected.sort();
+        calculated.sort();
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_get_nodes_edges_for_walks() {
+        let walks = vec![
+            "W\tNA12878\t1\tchr1\t0\t11\t>1<2>3".to_string(),
+            "W\tNA12878\t1\tchr1\t0\t11\t>2<4".to_string(),
+        ];
+        let expected = (
+            HashSet::from([
+                "1".to_string(),
+                "2".to_string(),
+                "3".to_string(),
+                "4".to_string(),
+            ]),
+            HashSet::from([
+                edge("1", true, "2", false),
+                edge("2", false, "3", true),
+                edge("2", true, "4", false),
+            ]),
+            HashSet::from([]),
+        );
+        let calculated = get_nodes_edges(&Vec::new(), &walks).unwrap();
+        assert_eq!(calculated, expected);
+        let empty = vec!["W\tNA12878\t2\tchr1\t*\t*\t*".to_string()];
+        assert!(get_nodes_edges(&Vec::new(), &empty).unwrap().0.is_empty());
+        let invalid = vec!["W\tNA12878\t1\tchr1\t0\t11\t>1 >2".to_string()];
+        assert_eq!(
+            get_nodes_edges(&Vec::new(), &invalid).unwrap_err(),
+            "Invalid steps of walk NA12878#1#chr1: Invalid character in segment name at position 2"
+        );
+    }
+
+    #[test]
+    fn test_flatten_into_hashset() {
+        let v = vec![vec![1, 2, 3], vec![2, 4]];
+        let expected = HashSet::from([1, 2, 3, 4]);
+        let calculated = flatten_into_hashset(v);
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_filter_segments() {
+        let segments = vec!["S\t1\tTCCGAT", "S\t2\tTA", "S\t3\tACG"];
+        let nodes = HashSet::from(["1".to_string(), "2".to_string()]);
+        let expected = vec!["S\t1\tTCCGAT", "S\t2\tTA"];
+        let calculated = filter_segments(segments, nodes);
+        assert_eq!(calculated, expected);
+    }
+
+    #[test]
+    fn test_filter_links() {
+        // 2- 1+ connects the starts of 1 and 2 and is not the edge 1+ 2- between their ends,
+        // 2- 1- is 1+ 2+ read from the other strand
+        let links = vec![
+            "L\t2\t-\t1\t+",
+            "L\t2\t-\t3\t+",
+            "L\t2\t-\t4\t+",
+            "L\t5\t-\t4\t+",
+            "L\t2\t-\t1\t-",
+        ];
+        let links_to_keep = HashSet::from([
+            edge("1", true, "2", false),
+            edge("2", false, "3", true),
+            edge("2", true, "4", false),
+            edge("5", false, "3", false),
+            edge("1", true, "2", true),
+        ]);
+        let expected = vec!["L\t2\t-\t3\t+", "L\t2\t-\t1\t-"];
+        let calculated = filter_edges(links, links_to_keep).unwrap();
+        assert_eq!(calculated, expected);
+        assert_eq!(
+            filter_edges(vec!["L\t1\t+\t2\t*\t0M"], HashSet::new()).unwrap_err(),
+            "Invalid to orientation of link 1 + 2 *: '*'"
+        );
+    }
+
+    #[test]
+    fn test_trim_gfa_with_index() {
+        let gfa = "S\t1\tA\nS\t2\tC\nS\t3\tG\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\n\
+                   P\tp1\t1+,2+\t*\nP\tp2\t2+,3+\t*\n";
+        let index = GraphIndex::build(gfa).unwrap();
+        let keep = Some(vec!["p1".to_string()]);
+        let options = TrimOptions::default();
+        let with_index = trim_gfa_with_index(gfa, &index, keep.clone(), &options).unwrap();
+        let without_index = trim_gfa(GfaLines::classify(gfa), keep, &options).unwrap();
+        assert_eq!(with_index.segments, without_index.segments);
+        assert_eq!(with_index.links, without_index.links);
+        assert_eq!(with_index.paths, without_index.paths);
+        assert!(trim_gfa_with_index("S\t1\tA\n", &index, None, &options).is_err());
+    }
+}
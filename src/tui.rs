@@ -0,0 +1,408 @@
+//! Interactive terminal browser of a graph (`trim-graph tui`, feature `tui`).
+//!
+//! Shows the statistics of the graph, its connected components by size and its paths and
+//! walks, which can be searched and selected. For a selection, the summary of what trimming to
+//! it would remove is computed on request, to explore a graph before committing to a trim.
+//!
+//! Keys: `Tab` switches the view, arrows and `PageUp`/`PageDown` move, `/` searches the paths,
+//! `Space` selects the path under the cursor, `a` selects all paths found, `c` clears the
+//! selection, `p` previews the trim and `q` quits.
+
+use crate::components::split_components;
+use crate::graph::{path_line_steps, segment_length, walk_line_steps};
+use crate::stats::GraphStats;
+use crate::summary::RemovedSummary;
+use crate::trim::{trim_gfa, walk_name, GfaLines, TrimOptions};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Row, Table, Tabs};
+use ratatui::Frame;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io;
+
+/// Rows moved by `PageUp` and `PageDown`
+const PAGE: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+    Stats,
+    Components,
+    Paths,
+}
+
+/// Path or walk of the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathEntry {
+    /// Name of a P line, PanSN name of a W line
+    pub name: String,
+    pub is_walk: bool,
+    pub steps: usize,
+    pub bp: usize,
+}
+
+/// Component of the graph, named by its first segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentEntry {
+    pub name: String,
+    pub segments: usize,
+    pub bp: usize,
+    pub paths: usize,
+}
+
+/// State of the browser, independent of the terminal
+pub struct Browser<'a> {
+    lines: GfaLines<'a>,
+    pub stats: GraphStats,
+    /// Components by bp, largest first
+    pub components: Vec<ComponentEntry>,
+    pub paths: Vec<PathEntry>,
+    pub view: View,
+    pub search: String,
+    pub searching: bool,
+    /// Indices into `paths`
+    pub selected: BTreeSet<usize>,
+    /// Row of the cursor in the current view
+    pub cursor: usize,
+    /// Summary of trimming to the selection, `None` until requested or after it changed
+    pub preview: Option<RemovedSummary>,
+}
+
+impl<'a> Browser<'a> {
+    /// Browser of the graph, fails on a path or walk with invalid steps
+    pub fn new(lines: GfaLines<'a>) -> Result<Self, String> {
+        let lengths = lines
+            .segments
+            .iter()
+            .map(|s| {
+                let name = s.split('\t').nth(1).expect("All nodes should have ids");
+                (name, segment_length(s))
+            })
+            .collect::<HashMap<_, _>>();
+        let bp = |steps: &[(&str, bool)]| {
+            let bp = steps
+                .iter()
+                .map(|(node, _)| lengths.get(node).copied().unwrap_or(0))
+                .sum();
+            (steps.len(), bp)
+        };
+        let mut paths = Vec::new();
+        for line in &lines.paths {
+            let (steps, bp) = bp(&path_line_steps(line)?);
+            let name = line.split('\t').nth(1).unwrap_or("").to_string();
+            paths.push(PathEntry {
+                name,
+                is_walk: false,
+                steps,
+                bp,
+            });
+        }
+        for line in &lines.walks {
+            let (steps, bp) = bp(&walk_line_steps(line)?);
+            paths.push(PathEntry {
+                name: walk_name(line),
+                is_walk: true,
+                steps,
+                bp,
+            });
+        }
+        let mut components = split_components(&lines)?
+            .iter()
+            .filter(|component| !component.segments.is_empty())
+            .map(|component| ComponentEntry {
+                name: component.segments[0]
+                    .split('\t')
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string(),
+                segments: component.segments.len(),
+                bp: component.segments.iter().map(|s| segment_length(s)).sum(),
+                paths: component.paths.len() + component.walks.len(),
+            })
+            .collect::<Vec<_>>();
+        components.sort_by_key(|component| std::cmp::Reverse(component.bp));
+        Ok(Browser {
+            stats: GraphStats::of(&lines)?,
+            lines,
+            components,
+            paths,
+            view: View::Stats,
+            search: String::new(),
+            searching: false,
+            selected: BTreeSet::new(),
+            cursor: 0,
+            preview: None,
+        })
+    }
+
+    /// Indices of the paths whose name contains the search
+    pub fn found(&self) -> Vec<usize> {
+        (0..self.paths.len())
+            .filter(|i| self.paths[*i].name.contains(&self.search))
+            .collect()
+    }
+
+    fn rows(&self) -> usize {
+        match self.view {
+            View::Stats => 0,
+            View::Components => self.components.len(),
+            View::Paths => self.found().len(),
+        }
+    }
+
+    fn select(&mut self, paths: impl IntoIterator<Item = usize>, select: bool) {
+        for path in paths {
+            match select {
+                true => self.selected.insert(path),
+                false => self.selected.remove(&path),
+            };
+        }
+        self.preview = None;
+    }
+
+    /// Trims a copy of the graph to the selected paths and walks and keeps the summary
+    pub fn compute_preview(&mut self) -> Result<(), String> {
+        let (walks, paths): (Vec<_>, Vec<_>) = self
+            .selected
+            .iter()
+            .map(|i| &self.paths[*i])
+            .partition(|path| path.is_walk);
+        let options = TrimOptions {
+            walks_to_keep: Some(walks.iter().map(|w| w.name.clone()).collect::<HashSet<_>>()),
+            ..Default::default()
+        };
+        let paths = paths.iter().map(|p| p.name.clone()).collect();
+        let trimmed = trim_gfa(self.lines.clone(), Some(paths), &options)?;
+        self.preview = Some(RemovedSummary::new(&self.lines, &trimmed)?);
+        Ok(())
+    }
+
+    /// Handles a key, returns false to quit
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        if self.searching {
+            match key {
+                KeyCode::Char(c) => self.search.push(c),
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Enter | KeyCode::Esc => self.searching = false,
+                _ => {}
+            }
+            self.cursor = 0;
+            return true;
+        }
+        let rows = self.rows();
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab => {
+                self.view = match self.view {
+                    View::Stats => View::Components,
+                    View::Components => View::Paths,
+                    View::Paths => View::Stats,
+                };
+                self.cursor = 0;
+            }
+            KeyCode::Down => self.cursor = (self.cursor + 1).min(rows.saturating_sub(1)),
+            KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::PageDown => self.cursor = (self.cursor + PAGE).min(rows.saturating_sub(1)),
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(PAGE),
+            KeyCode::Char('/') => {
+                self.view = View::Paths;
+                self.searching = true;
+            }
+            KeyCode::Char(' ') if self.view == View::Paths => {
+                if let Some(path) = self.found().get(self.cursor).copied() {
+                    let select = !self.selected.contains(&path);
+                    self.select([path], select);
+                }
+            }
+            KeyCode::Char('a') => self.select(self.found(), true),
+            KeyCode::Char('c') => {
+                self.selected.clear();
+                self.preview = None;
+            }
+            KeyCode::Char('p') => {
+                if let Err(error) = self.compute_preview() {
+                    log::error!("{}", error);
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [tabs, main, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let selected = match self.view {
+            View::Stats => 0,
+            View::Components => 1,
+            View::Paths => 2,
+        };
+        frame.render_widget(
+            Tabs::new(["Statistics", "Components", "Paths"])
+                .select(selected)
+                .block(Block::bordered().title("trim-graph")),
+            tabs,
+        );
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+        match self.view {
+            View::Stats => {
+                let rows = self
+                    .stats
+                    .rows()
+                    .map(|(name, value)| Row::new([name.to_string(), value.to_string()]));
+                let widths = [Constraint::Length(12), Constraint::Min(0)];
+                frame.render_widget(Table::new(rows, widths).block(Block::bordered()), main);
+            }
+            View::Components => {
+                let rows = self.components.iter().enumerate().map(|(i, c)| {
+                    let row = Row::new([
+                        c.name.clone(),
+                        c.segments.to_string(),
+                        c.bp.to_string(),
+                        c.paths.to_string(),
+                    ]);
+                    match i == self.cursor {
+                        true => row.style(highlight),
+                        false => row,
+                    }
+                });
+                let widths = [
+                    Constraint::Min(12),
+                    Constraint::Length(12),
+                    Constraint::Length(12),
+                    Constraint::Length(12),
+                ];
+                let header = Row::new(["first segment", "segments", "bp", "paths"])
+                    .style(Style::default().add_modifier(Modifier::BOLD));
+                frame.render_widget(
+                    Table::new(rows, widths)
+                        .header(header)
+                        .block(Block::bordered().title("Components by bp")),
+                    main,
+                );
+            }
+            View::Paths => {
+                let [list, preview] =
+                    Layout::vertical([Constraint::Min(0), Constraint::Length(8)]).areas(main);
+                let items = self.found().into_iter().map(|i| {
+                    let path = &self.paths[i];
+                    let mark = if self.selected.contains(&i) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+                    ListItem::new(format!(
+                        "{} {}  {} steps  {} bp",
+                        mark, path.name, path.steps, path.bp
+                    ))
+                });
+                let title = match self.searching {
+                    true => format!("Paths /{}_", self.search),
+                    false if !self.search.is_empty() => format!("Paths /{}", self.search),
+                    false => "Paths".to_string(),
+                };
+                let mut state = ListState::default().with_selected(Some(self.cursor));
+                frame.render_stateful_widget(
+                    List::new(items)
+                        .block(Block::bordered().title(title))
+                        .highlight_style(highlight),
+                    list,
+                    &mut state,
+                );
+                frame.render_widget(self.preview_text(), preview);
+            }
+        }
+        frame.render_widget(
+            Line::from(
+                "Tab view  \u{2191}\u{2193} move  / search  Space select  a all  c clear  \
+                 p preview  q quit",
+            ),
+            help,
+        );
+    }
+
+    fn preview_text(&self) -> Paragraph<'static> {
+        let title = format!("Trim to {} selected paths", self.selected.len());
+        let lines = match &self.preview {
+            None => vec![Line::from("Press p to preview what trimming would remove")],
+            Some(summary) => summary
+                .rows
+                .iter()
+                .filter(|row| row.scope == "record")
+                .map(|row| {
+                    Line::from(format!(
+                        "{}  {} of {} lines  {} of {} bp removed",
+                        row.name,
+                        row.removed_lines(),
+                        row.lines,
+                        row.removed_bp(),
+                        row.bp
+                    ))
+                })
+                .collect(),
+        };
+        Paragraph::new(lines).block(Block::bordered().title(title))
+    }
+}
+
+/// Browses the graph in the terminal until the user quits
+pub fn run(content: &str) -> io::Result<()> {
+    let mut browser = Browser::new(GfaLines::classify(content))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut terminal = ratatui::init();
+    let result = (|| loop {
+        terminal.draw(|frame| browser.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !browser.handle_key(key.code) {
+                return Ok(());
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_browser() {
+        let content = "S\t1\tACGT\nS\t2\tAA\nS\t3\tC\nS\t4\tGG\n\
+                       L\t1\t+\t2\t+\t0M\nL\t2\t+\t3\t+\t0M\nL\t1\t+\t3\t+\t0M\n\
+                       P\tref#0#chr1\t1+,3+\t*\nP\talt#1#chr1\t1+,2+,3+\t*\n\
+                       W\talt\t2\tchr1\t0\t7\t>1>2>3\n";
+        let mut browser = Browser::new(GfaLines::classify(content)).unwrap();
+        assert_eq!(browser.components[0].bp, 7);
+        assert_eq!(browser.paths[1].bp, 7);
+
+        for key in "/ref".chars().map(KeyCode::Char) {
+            browser.handle_key(key);
+        }
+        browser.handle_key(KeyCode::Enter);
+        assert_eq!(browser.found(), [0]);
+        browser.handle_key(KeyCode::Char(' '));
+        browser.handle_key(KeyCode::Char('p'));
+        let summary = browser.preview.as_ref().unwrap();
+        assert_eq!(summary.removed_bp(), 4);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| browser.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("[x] ref#0#chr1"));
+        assert!(screen.contains("S  2 of 4 lines  4 of 9 bp removed"));
+
+        browser.handle_key(KeyCode::Char('c'));
+        assert!(browser.selected.is_empty() && browser.preview.is_none());
+        assert!(!browser.handle_key(KeyCode::Char('q')));
+    }
+}
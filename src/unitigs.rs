@@ -0,0 +1,225 @@
+//! Export of the unitigs of a graph for assembly tools (`trim-graph unitigs`).
+//!
+//! A unitig is a maximal chain of segments in which every segment has a single successor and
+//! that successor has a single predecessor. Unitigs are numbered `utg1`, `utg2`, ... in the
+//! order of their earliest segment in the graph and spelled in the orientation in which that
+//! segment is forward, with the overlaps of the links (`nM` CIGARs) removed. The links between unitigs
+//! are written once each, as GFA with sequence-less segments that Bandage loads next to the
+//! FASTA.
+
+use crate::edge::Edge;
+use crate::handle::{Handle, HandleGraph};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// Overlap in bp of every link, only `nM` CIGARs (and `*`) are supported
+pub fn link_overlaps(links: &[&str]) -> Result<HashMap<Edge, usize>, String> {
+    let mut overlaps = HashMap::new();
+    for line in links {
+        let fields = line.split('\t').collect::<Vec<_>>();
+        if fields.len() < 6 {
+            return Err(format!("Invalid link {}", line));
+        }
+        let overlap = match fields[5] {
+            "*" => 0,
+            cigar => cigar
+                .strip_suffix('M')
+                .and_then(|n| n.parse::<usize>().ok())
+                .ok_or_else(|| format!("Only nM overlaps are supported, not {}", cigar))?,
+        };
+        let edge = Edge::new(fields[1], fields[2] == "+", fields[3], fields[4] == "+");
+        overlaps.insert(edge.into_owned(), overlap);
+    }
+    Ok(overlaps)
+}
+
+/// Distinct neighbours of a handle on one side
+fn neighbours(graph: &HandleGraph, handle: Handle, go_left: bool) -> Vec<Handle> {
+    let mut neighbours = graph.follow_edges(handle, go_left).collect::<Vec<_>>();
+    neighbours.sort_unstable();
+    neighbours.dedup();
+    neighbours
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unitig {
+    pub name: String,
+    pub handles: Vec<Handle>,
+    pub sequence: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unitigs {
+    pub unitigs: Vec<Unitig>,
+    /// Links between oriented unitigs with their overlap, each once
+    pub links: Vec<(usize, bool, usize, bool, usize)>,
+}
+
+impl Unitigs {
+    pub fn new(graph: &HandleGraph, overlaps: &HashMap<Edge, usize>) -> Result<Self, String> {
+        let overlap = |from: Handle, to: Handle| {
+            let edge = Edge::new(
+                graph.get_name(from).to_string(),
+                !from.is_reverse(),
+                graph.get_name(to).to_string(),
+                !to.is_reverse(),
+            );
+            overlaps.get(&edge).copied().unwrap_or(0)
+        };
+        // The single successor of the handle, if it has the handle as single predecessor
+        let next = |handle: Handle| match neighbours(graph, handle, false)[..] {
+            [next] if next.rank() != handle.rank() => {
+                (neighbours(graph, next, true) == [handle]).then_some(next)
+            }
+            _ => None,
+        };
+
+        let mut visited = HashSet::new();
+        let mut unitigs = Vec::new();
+        for start in graph.handles() {
+            if !visited.insert(start.rank()) {
+                continue;
+            }
+            let mut handles = vec![start];
+            while let Some(after) = next(handles[handles.len() - 1]) {
+                if !visited.insert(after.rank()) {
+                    break;
+                }
+                handles.push(after);
+            }
+            let mut before = Vec::new();
+            let mut first = start;
+            while let Some(previous) = next(first.flip()).map(|h| h.flip()) {
+                if !visited.insert(previous.rank()) {
+                    break;
+                }
+                before.push(previous);
+                first = previous;
+            }
+            before.reverse();
+            before.extend(handles);
+            let handles = before;
+
+            let mut sequence = String::new();
+            for (i, handle) in handles.iter().enumerate() {
+                let spelled = graph.get_sequence(*handle)?;
+                if spelled == "*" {
+                    return Err(format!(
+                        "Segment {} has no sequence (*), unitigs need sequences",
+                        graph.get_name(*handle)
+                    ));
+                }
+                let skip = match i {
+                    0 => 0,
+                    _ => overlap(handles[i - 1], *handle).min(spelled.len()),
+                };
+                sequence.push_str(&spelled[skip..]);
+            }
+            unitigs.push(Unitig {
+                name: format!("utg{}", unitigs.len() + 1),
+                handles,
+                sequence,
+            });
+        }
+
+        // Unitig and its orientation entered by a handle
+        let mut entered = HashMap::new();
+        for (i, unitig) in unitigs.iter().enumerate() {
+            entered.insert(unitig.handles[0], (i, true));
+            entered.insert(unitig.handles[unitig.handles.len() - 1].flip(), (i, false));
+        }
+        let mut seen = HashSet::new();
+        let mut links = Vec::new();
+        for (i, unitig) in unitigs.iter().enumerate() {
+            let ends = [
+                (unitig.handles[unitig.handles.len() - 1], true),
+                (unitig.handles[0].flip(), false),
+            ];
+            for (end, forward) in ends {
+                for to in neighbours(graph, end, false) {
+                    let Some(&(j, to_forward)) = entered.get(&to) else {
+                        continue;
+                    };
+                    if seen.insert(Edge::new(i, forward, j, to_forward)) {
+                        links.push((i, forward, j, to_forward, overlap(end, to)));
+                    }
+                }
+            }
+        }
+        Ok(Unitigs { unitigs, links })
+    }
+
+    /// Writes one record per unitig, with its segments in the description
+    pub fn write_fasta<W: Write>(&self, graph: &HandleGraph, out: &mut W) -> io::Result<()> {
+        for unitig in &self.unitigs {
+            let segments = unitig
+                .handles
+                .iter()
+                .map(|h| {
+                    let orientation = if h.is_reverse() { '-' } else { '+' };
+                    format!("{}{}", graph.get_name(*h), orientation)
+                })
+                .collect::<Vec<_>>();
+            writeln!(out, ">{} segments={}", unitig.name, segments.join(","))?;
+            writeln!(out, "{}", unitig.sequence)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the unitigs as sequence-less S lines with their length and the links between
+    /// them
+    pub fn write_links<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "H\tVN:Z:1.0")?;
+        for unitig in &self.unitigs {
+            writeln!(out, "S\t{}\t*\tLN:i:{}", unitig.name, unitig.sequence.len())?;
+        }
+        let orientation = |forward: bool| if forward { '+' } else { '-' };
+        for (from, from_forward, to, to_forward, overlap) in &self.links {
+            writeln!(
+                out,
+                "L\t{}\t{}\t{}\t{}\t{}M",
+                self.unitigs[*from].name,
+                orientation(*from_forward),
+                self.unitigs[*to].name,
+                orientation(*to_forward),
+                overlap
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    #[test]
+    fn test_unitigs() {
+        // 1+ 2- 3+ is a chain with a 1 bp overlap between 2- and 3+, then a bubble 4/5 that
+        // merges at 6
+        let content = "S\t1\tAC\nS\t2\tTA\nS\t3\tAG\nS\t4\tC\nS\t5\tG\nS\t6\tTT\n\
+                       L\t1\t+\t2\t-\t0M\nL\t2\t-\t3\t+\t1M\nL\t3\t+\t4\t+\t0M\n\
+                       L\t3\t+\t5\t+\t0M\nL\t4\t+\t6\t+\t0M\nL\t6\t-\t5\t-\t0M\n";
+        let links = content
+            .lines()
+            .filter(|l| l.starts_with('L'))
+            .collect::<Vec<_>>();
+        let graph = HandleGraph::new(Graph::from_gfa(content).unwrap()).unwrap();
+        let unitigs = Unitigs::new(&graph, &link_overlaps(&links).unwrap()).unwrap();
+        let mut fasta = Vec::new();
+        unitigs.write_fasta(&graph, &mut fasta).unwrap();
+        assert_eq!(
+            String::from_utf8(fasta).unwrap(),
+            ">utg1 segments=1+,2-,3+\nACTAG\n>utg2 segments=4+\nC\n>utg3 segments=5+\nG\n\
+             >utg4 segments=6+\nTT\n"
+        );
+        let mut gfa = Vec::new();
+        unitigs.write_links(&mut gfa).unwrap();
+        let gfa = String::from_utf8(gfa).unwrap();
+        assert!(gfa.contains("S\tutg1\t*\tLN:i:5\n"));
+        assert_eq!(gfa.lines().filter(|l| l.starts_with('L')).count(), 4);
+        assert!(gfa.contains("L\tutg3\t+\tutg4\t+\t0M\n"));
+        assert!(link_overlaps(&["L\t1\t+\t2\t+\t2M1I"]).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Segments of a node universe missing from a trimmed graph (`trim-graph missing`), to audit
+//! what a trim removed, also one done by another tool.
+//!
+//! The universe is the original graph, whose segments give the names and lengths, or a list of
+//! segment names with an optional length as second column. Segments with a sequence of `*`
+//! and no LN tag, and listed names without length, have no known length.
+
+use crate::graph::segment_length;
+use crate::scan;
+use crate::trim::GfaLines;
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+/// Segment of the universe with its length, if known
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniverseNode {
+    pub name: String,
+    pub length: Option<usize>,
+}
+
+/// The segments of a graph or the names (and lengths) of a list, in file order. Content with
+/// S lines is a graph.
+pub fn parse_universe(content: &str) -> Vec<UniverseNode> {
+    let lines = GfaLines::classify(content);
+    // Names of a list may start with S as well
+    if lines.segments.iter().any(|line| line.starts_with("S\t")) {
+        return lines
+            .segments
+            .iter()
+            .map(|line| {
+                let has_length =
+                    scan::field(line, 2).is_some_and(|s| s != "*") || line.contains("\tLN:i:");
+                UniverseNode {
+                    name: scan::field(line, 1).unwrap_or("").to_string(),
+                    length: has_length.then(|| segment_length(line)),
+                }
+            })
+            .collect();
+    }
+    scan::lines(content)
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = scan::fields(line);
+            UniverseNode {
+                name: fields.next().unwrap_or("").trim().to_string(),
+                length: fields.next().and_then(|l| l.trim().parse().ok()),
+            }
+        })
+        .collect()
+}
+
+/// The segments of the universe that are not segments of the graph, in universe order and
+/// without duplicates
+pub fn missing_nodes<'a>(universe: &'a [UniverseNode], graph: &GfaLines) -> Vec<&'a UniverseNode> {
+    let mut seen = graph
+        .segments
+        .iter()
+        .filter_map(|line| scan::field(line, 1))
+        .collect::<HashSet<_>>();
+    universe
+        .iter()
+        .filter(|node| seen.insert(node.name.as_str()))
+        .collect()
+}
+
+/// Writes one line `node length` per missing segment, with a header. `*` stands for an
+/// unknown length.
+pub fn write_missing<W: Write>(missing: &[&UniverseNode], out: &mut W) -> io::Result<()> {
+    writeln!(out, "node\tlength")?;
+    for node in missing {
+        match node.length {
+            Some(length) => writeln!(out, "{}\t{}", node.name, length)?,
+            None => writeln!(out, "{}\t*", node.name)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_nodes() {
+        let trimmed = GfaLines::classify("S\t1\tACGT\nS\t3\tA\n");
+        let universe =
+            parse_universe("H\tVN:Z:1.0\nS\t1\tACGT\nS\t2\t*\tLN:i:7\nS\t3\tA\nS\t4\t*\n");
+        let missing = missing_nodes(&universe, &trimmed);
+        let mut out = Vec::new();
+        write_missing(&missing, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "node\tlength\n2\t7\n4\t*\n"
+        );
+
+        let universe = parse_universe("1\n2\t5\n\n5\n5\nS6\n");
+        let missing = missing_nodes(&universe, &trimmed);
+        assert_eq!(
+            missing,
+            [
+                &UniverseNode {
+                    name: "2".to_string(),
+                    length: Some(5)
+                },
+                &UniverseNode {
+                    name: "5".to_string(),
+                    length: None
+                },
+                &UniverseNode {
+                    name: "S6".to_string(),
+                    length: None
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::bgzf::BgzfWriter;
+use crate::graph::Graph;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Maximum number of nodes, edges or path steps per Graph message, similar to vg's own chunking
+const CHUNK_SIZE: usize = 1000;
+
+/// Maximum number of messages per group of the type-tagged stream, as in libvgio
+const GROUP_SIZE: usize = 1000;
+
+/// Minimal protobuf encoder for the subset of vg.proto written by trim-graph
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn uint_field(&mut self, field: u64, value: u64) {
+        if value != 0 {
+            self.varint(field << 3);
+            self.varint(value);
+        }
+    }
+
+    fn bool_field(&mut self, field: u64, value: bool) {
+        self.uint_field(field, value as u64);
+    }
+
+    fn bytes_field(&mut self, field: u64, value: &[u8]) {
+        self.varint((field << 3) | 2);
+        self.varint(value.len() as u64);
+        self.0.extend_from_slice(value);
+    }
+
+    fn message_field(&mut self, field: u64, value: &Message) {
+        self.bytes_field(field, &value.0);
+    }
+}
+
+// Field numbers follow vg.proto: Graph{node=1, edge=2, path=3}, Node{sequence=1, id=3},
+// Edge{from=1, to=2, from_start=3, to_end=4}, Path{name=1, mapping=2},
+// Mapping{position=1, edit=2, rank=5}, Position{node_id=1, is_reverse=4},
+// Edit{from_length=1, to_length=2}
+
+fn encode_mapping(node_id: u64, forward: bool, length: u64, rank: u64) -> Message {
+    let mut position = Message::default();
+    position.uint_field(1, node_id);
+    position.bool_field(4, !forward);
+    let mut edit = Message::default();
+    edit.uint_field(1, length);
+    edit.uint_field(2, length);
+    let mut mapping = Message::default();
+    mapping.message_field(1, &position);
+    mapping.message_field(2, &edit);
+    mapping.uint_field(5, rank);
+    mapping
+}
+
+fn encode_graphs(graph: &Graph) -> io::Result<Vec<Message>> {
+    let ids = graph.numeric_ids();
+    let lengths = graph
+        .segments
+        .iter()
+        .map(|s| (s.name.as_str(), s.length as u64))
+        .collect::<HashMap<_, _>>();
+    let mut messages = Vec::new();
+
+    for chunk in graph.segments.chunks(CHUNK_SIZE) {
+        let mut message = Message::default();
+        for segment in chunk {
+            let mut node = Message::default();
+            let sequence = segment
+                .loaded_sequence()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            node.bytes_field(1, sequence.as_bytes());
+            node.uint_field(3, ids[segment.name.as_str()]);
+            message.message_field(1, &node);
+        }
+        messages.push(message);
+    }
+
+    if !graph.jumps.is_empty() {
+        log::info!(
+            "vg has no jumps, writing {} jumps as edges",
+            graph.jumps.len()
+        );
+    }
+    let edges = graph
+        .links
+        .iter()
+        .chain(graph.jumps.iter())
+        .filter(|l| ids.contains_key(l.from.as_str()) && ids.contains_key(l.to.as_str()))
+        .collect::<Vec<_>>();
+    for chunk in edges.chunks(CHUNK_SIZE) {
+        let mut message = Message::default();
+        for link in chunk {
+            let mut edge = Message::default();
+            edge.uint_field(1, ids[link.from.as_str()]);
+            edge.uint_field(2, ids[link.to.as_str()]);
+            edge.bool_field(3, !link.from_forward);
+            edge.bool_field(4, !link.to_forward);
+            message.message_field(2, &edge);
+        }
+        messages.push(message);
+    }
+
+    let paths = graph
+        .paths
+        .iter()
+        .map(|p| (p.name.clone(), &p.steps))
+        .chain(graph.walks.iter().map(|w| (w.name(), &w.steps)));
+    for (name, steps) in paths {
+        // Long paths are split over several messages, vg merges them by name and rank
+        for (chunk_index, chunk) in steps.chunks(CHUNK_SIZE).enumerate() {
+            let mut path = Message::default();
+            path.bytes_field(1, name.as_bytes());
+            for (i, (node, forward)) in chunk.iter().enumerate() {
+                let rank = (chunk_index * CHUNK_SIZE + i + 1) as u64;
+                let node_id = *ids.get(node.as_str()).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Path {name} visits segment {node} which is not in the graph"),
+                    )
+                })?;
+                let length = lengths.get(node.as_str()).copied().unwrap_or(0);
+                path.message_field(2, &encode_mapping(node_id, *forward, length, rank));
+            }
+            let mut message = Message::default();
+            message.message_field(3, &path);
+            messages.push(message);
+        }
+    }
+    Ok(messages)
+}
+
+/// Writes the graph as a vg Protobuf graph (.vg): a BGZF-compressed stream of "VG"-tagged
+/// groups of Graph messages, as read by `vg view -v` and other vg subcommands
+pub fn write_vg<W: Write>(out: W, graph: &Graph) -> io::Result<()> {
+    let mut writer = BgzfWriter::new(out);
+    for group in encode_graphs(graph)?.chunks(GROUP_SIZE) {
+        let mut header = Message::default();
+        // The count includes the tag message
+        header.varint(group.len() as u64 + 1);
+        header.varint(2);
+        header.0.extend_from_slice(b"VG");
+        writer.write_all(&header.0)?;
+        for message in group {
+            let mut length = Message::default();
+            length.varint(message.0.len() as u64);
+            writer.write_all(&length.0)?;
+            writer.write_all(&message.0)?;
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_varint() {
+        let mut message = Message::default();
+        message.varint(1);
+        message.varint(300);
+        assert_eq!(message.0, vec![0x01, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_encode_graphs() {
+        let graph = Graph::from_lines(
+            &["S\t1\tAC"],
+            &["L\t1\t+\t1\t-\t0M"],
+            &[],
+            &["P\tp\t1-\t*"],
+            &[],
+        )
+        .unwrap();
+        let messages = encode_graphs(&graph).unwrap();
+        let encoded = messages.iter().map(|m| m.0.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            encoded,
+            vec![
+                // node {sequence: "AC", id: 1}
+                vec![0x0a, 0x06, 0x0a, 0x02, b'A', b'C', 0x18, 0x01],
+                // edge {from: 1, to: 1, to_end: true}
+                vec![0x12, 0x06, 0x08, 0x01, 0x10, 0x01, 0x20, 0x01],
+                // path {name: "p", mapping {position {node_id: 1, is_reverse: true},
+                //   edit {from_length: 2, to_length: 2}, rank: 1}}
+                vec![
+                    0x1a, 0x13, 0x0a, 0x01, b'p', 0x12, 0x0e, 0x0a, 0x04, 0x08, 0x01, 0x20, 0x01,
+                    0x12, 0x04, 0x08, 0x02, 0x10, 0x02, 0x28, 0x01
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_vg() {
+        let graph = Graph::from_lines(&["S\t1\tAC"], &[], &[], &[] as &[&str], &[]).unwrap();
+        let mut out = Vec::new();
+        write_vg(&mut out, &graph).unwrap();
+        let mut decompressed = Vec::new();
+        MultiGzDecoder::new(&out[..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(
+            decompressed,
+            vec![0x02, 0x02, b'V', b'G', 0x08, 0x0a, 0x06, 0x0a, 0x02, b'A', b'C', 0x18, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_write_vg_missing_segment() {
+        let graph = Graph::from_lines(&["S\t1\tAC"], &[], &[], &["P\tp\t1+,2+\t*"], &[]).unwrap();
+        let error = write_vg(&mut Vec::new(), &graph).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("Path p visits segment 2"));
+    }
+}
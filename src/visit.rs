@@ -0,0 +1,139 @@
+//! Callbacks for the records of a graph read line by line, to compute custom statistics in one
+//! pass without holding the graph in memory.
+//!
+//! [`visit`] parses every line of the input on its own and calls the method of the
+//! [`Visitor`] for its record type; the arguments borrow from the current line and are only
+//! valid during the call. Paths and walks are announced with [`Visitor::on_path`] (walks by
+//! their PanSN name) followed by one [`Visitor::on_path_step`] per step. The steps of a line
+//! are tokenized completely before the first call, so that a line with invalid steps fails
+//! without being announced; they borrow from the line and are dropped with it. Lines of other
+//! types are skipped.
+
+use crate::graph::{tokenize_path, tokenize_walk, EdgeRecord};
+use crate::grep::record_type;
+use crate::scan;
+use crate::trim::{walk_name, RecordType};
+use std::io::{self, BufRead};
+
+/// Receiver of the records of a graph, every method does nothing unless implemented
+pub trait Visitor {
+    /// H line
+    fn on_header(&mut self, _line: &str) {}
+
+    /// S line, with its name and sequence (`*` if not stored)
+    fn on_segment(&mut self, _name: &str, _sequence: &str, _line: &str) {}
+
+    /// L line
+    fn on_link(&mut self, _link: &EdgeRecord) {}
+
+    /// J line
+    fn on_jump(&mut self, _jump: &EdgeRecord) {}
+
+    /// P or W line, before its steps, with the path name or the PanSN name of the walk
+    fn on_path(&mut self, _name: &str, _line: &str) {}
+
+    /// Step of the path or walk announced last, `rank` counts the steps from 0
+    fn on_path_step(&mut self, _path: &str, _rank: usize, _node: &str, _forward: bool) {}
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads the graph line by line and calls the visitor for every record, returns the number of
+/// lines. Links, jumps, paths and walks that cannot be parsed are errors.
+pub fn visit<R: BufRead, V: Visitor + ?Sized>(mut input: R, visitor: &mut V) -> io::Result<usize> {
+    let mut lines = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(lines);
+        }
+        lines += 1;
+        let record = line.trim_end_matches(['\n', '\r']);
+        match record_type(record) {
+            RecordType::Header => visitor.on_header(record),
+            RecordType::Segment => {
+                let mut fields = scan::fields(record).skip(1);
+                let name = fields.next().unwrap_or("");
+                visitor.on_segment(name, fields.next().unwrap_or("*"), record);
+            }
+            RecordType::Link => visitor.on_link(&EdgeRecord::parse(record).map_err(invalid)?),
+            RecordType::Jump => visitor.on_jump(&EdgeRecord::parse(record).map_err(invalid)?),
+            RecordType::Path => {
+                let name = scan::field(record, 1).unwrap_or("");
+                let steps = tokenize_path(scan::field(record, 2).unwrap_or(""))
+                    .map_err(|e| invalid(format!("Invalid steps of path {}: {}", name, e)))?;
+                visitor.on_path(name, record);
+                for (rank, step) in steps.into_iter().enumerate() {
+                    visitor.on_path_step(name, rank, step.name, step.forward);
+                }
+            }
+            RecordType::Walk => {
+                let name = walk_name(record);
+                let steps = tokenize_walk(scan::field(record, 6).unwrap_or(""))
+                    .map_err(|e| invalid(format!("Invalid steps of walk {}: {}", name, e)))?;
+                visitor.on_path(&name, record);
+                for (rank, (node, forward)) in steps.into_iter().enumerate() {
+                    visitor.on_path_step(&name, rank, node, forward);
+                }
+            }
+            RecordType::Other => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::segment_length;
+    use std::collections::HashMap;
+
+    /// bp per path and the number of self-loops
+    #[derive(Default)]
+    struct Lengths {
+        segments: HashMap<String, usize>,
+        paths: Vec<(String, usize)>,
+        loops: usize,
+    }
+
+    impl Visitor for Lengths {
+        fn on_segment(&mut self, name: &str, _sequence: &str, line: &str) {
+            self.segments.insert(name.to_string(), segment_length(line));
+        }
+
+        fn on_link(&mut self, link: &EdgeRecord) {
+            self.loops += (link.edge.from == link.edge.to) as usize;
+        }
+
+        fn on_path(&mut self, name: &str, _line: &str) {
+            self.paths.push((name.to_string(), 0));
+        }
+
+        fn on_path_step(&mut self, _path: &str, _rank: usize, node: &str, _forward: bool) {
+            self.paths.last_mut().unwrap().1 += self.segments[node];
+        }
+    }
+
+    #[test]
+    fn test_visit() {
+        let content = "H\tVN:Z:1.0\r\nS\t1\tACG\r\nS\t2\t*\tLN:i:5\nL\t1\t+\t1\t-\t0M\n\
+                       L\t1\t+\t2\t+\t0M\nP\tp\t1+,2-,1+\t*\nW\ts\t1\tc\t0\t8\t>1<2\n# x\n";
+        let mut lengths = Lengths::default();
+        assert_eq!(visit(content.as_bytes(), &mut lengths).unwrap(), 8);
+        assert_eq!(
+            lengths.paths,
+            [("p".to_string(), 11), ("s#1#c".to_string(), 8)]
+        );
+        assert_eq!(lengths.loops, 1);
+        for invalid in [
+            "W\ts\t1\tc\t0\t8\t1\n",
+            "P\tp\t1+,,2+\t*\n",
+            "P\tp\t1é\t*\n",
+        ] {
+            let error = visit(invalid.as_bytes(), &mut lengths).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
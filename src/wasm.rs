@@ -0,0 +1,66 @@
+//! JavaScript API (feature `wasm`), e.g. for trimming small graphs in the browser.
+//!
+//! Build for the web with `wasm-pack build --target web -- --no-default-features --features wasm`.
+
+use crate::json::write_json;
+use crate::trim::{trim_gfa, GfaLines, TrimOptions};
+use wasm_bindgen::prelude::*;
+
+fn trim_with_keep_list(
+    gfa: &str,
+    paths_to_keep: Option<String>,
+) -> Result<crate::trim::Trimmed<'_>, String> {
+    let paths_to_keep =
+        paths_to_keep.map(|names| names.lines().map(|s| s.to_string()).collect::<Vec<_>>());
+    trim_gfa(
+        GfaLines::classify(gfa),
+        paths_to_keep,
+        &TrimOptions::default(),
+    )
+}
+
+/// Names of all paths (P lines) of a GFA, one per line
+#[wasm_bindgen(js_name = pathNames)]
+pub fn path_names(gfa: &str) -> String {
+    GfaLines::classify(gfa).path_names().join("\n")
+}
+
+/// Trims a GFA to the given paths (one name per line, all paths if undefined) and returns the
+/// trimmed GFA, throws if a path has invalid steps
+#[wasm_bindgen]
+pub fn trim(gfa: &str, paths_to_keep: Option<String>) -> Result<String, String> {
+    let mut out = Vec::new();
+    trim_with_keep_list(gfa, paths_to_keep)?
+        .write_gfa(&mut out)
+        .expect("Writing to memory should not fail");
+    Ok(String::from_utf8(out).expect("Trimmed GFA should be UTF-8"))
+}
+
+/// Like `trim`, but returns the trimmed graph as vg-style JSON
+#[wasm_bindgen(js_name = trimToJson)]
+pub fn trim_to_json(gfa: &str, paths_to_keep: Option<String>) -> Result<String, String> {
+    let mut out = Vec::new();
+    write_json(
+        &mut out,
+        &trim_with_keep_list(gfa, paths_to_keep)?.to_graph()?,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(String::from_utf8(out).expect("JSON should be UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim() {
+        let gfa = "S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nP\tp1\t1+\t*\nP\tp2\t1+,2+\t*\n";
+        assert_eq!(path_names(gfa), "p1\np2");
+        assert_eq!(
+            trim(gfa, Some("p1".to_string())).unwrap(),
+            "S\t1\tA\nP\tp1\t1+\t*\n"
+        );
+        assert_eq!(trim(gfa, None).unwrap().lines().count(), 5);
+        assert!(trim("P\tp\t1+,,2+\t*\n", None).is_err());
+    }
+}